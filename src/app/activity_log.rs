@@ -0,0 +1,190 @@
+//! Activity log of the TUI, kept between runs
+//!
+//! Without the daemon, the TUI's embedded watcher handles files itself and
+//! its activity would only live in memory. The files it handles are appended
+//! to a JSONL file in the data directory, in the daemon's log entry format,
+//! and the newest ones are loaded back on startup. UI messages aren't saved.
+
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::state::LogEntry;
+use crate::config::Config;
+
+/// Rough size of one entry on disk, used to decide when to trim the file
+const ESTIMATED_ENTRY_BYTES: u64 = 256;
+
+/// Append-only JSONL file of activity log entries, trimmed to the newest
+/// `max_entries` once it holds about twice that many
+#[derive(Debug, Clone)]
+pub struct ActivityLog {
+    path: PathBuf,
+    max_entries: usize,
+}
+
+impl ActivityLog {
+    /// Activity log stored at `path`, keeping `max_entries` entries
+    pub fn new(path: impl Into<PathBuf>, max_entries: usize) -> Self {
+        Self {
+            path: path.into(),
+            max_entries: max_entries.max(1),
+        }
+    }
+
+    /// Default activity log path (~/.local/share/hazelnut/activity.jsonl)
+    pub fn default_path() -> Option<PathBuf> {
+        Config::data_dir().map(|d| d.join("activity.jsonl"))
+    }
+
+    /// Path of the activity log file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append entries in one write, trimming old entries once the file
+    /// grows too large
+    pub fn append(&self, entries: &[LogEntry]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        let mut lines = String::new();
+        for entry in entries {
+            lines.push_str(&serde_json::to_string(&crate::ipc::LogEntry::from(entry))?);
+            lines.push('\n');
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open activity log {}", self.path.display()))?;
+        file.write_all(lines.as_bytes())?;
+
+        let limit = 2 * self.max_entries as u64 * ESTIMATED_ENTRY_BYTES;
+        if file.metadata()?.len() > limit {
+            self.save(&self.read_entries()?)?;
+        }
+        Ok(())
+    }
+
+    /// Load the newest `max_entries` entries, oldest first, skipping lines
+    /// that don't parse
+    pub fn load(&self) -> Result<Vec<LogEntry>> {
+        Ok(self
+            .read_entries()?
+            .into_iter()
+            .map(LogEntry::from)
+            .collect())
+    }
+
+    /// The newest `max_entries` entries as stored
+    fn read_entries(&self) -> Result<Vec<crate::ipc::LogEntry>> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Failed to read activity log {}", self.path.display())
+                });
+            }
+        };
+        let mut entries: Vec<crate::ipc::LogEntry> = content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        let excess = entries.len().saturating_sub(self.max_entries);
+        entries.drain(..excess);
+        Ok(entries)
+    }
+
+    /// Replace the file with `entries`, through a temporary file so a crash
+    /// mid-write can't leave it truncated
+    fn save(&self, entries: &[crate::ipc::LogEntry]) -> Result<()> {
+        let mut content = String::new();
+        for entry in entries {
+            content.push_str(&serde_json::to_string(entry)?);
+            content.push('\n');
+        }
+        let tmp = self.path.with_extension("jsonl.tmp");
+        std::fs::write(&tmp, content)
+            .with_context(|| format!("Failed to write activity log {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path)
+            .with_context(|| format!("Failed to replace activity log {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::state::LogLevel;
+
+    fn entry(message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: chrono::Local::now(),
+            level: LogLevel::Success,
+            message: message.to_string(),
+            file: Some(PathBuf::from("/tmp/a.pdf")),
+            rule: Some("PDFs".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_append_and_load_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = ActivityLog::new(dir.path().join("nested").join("activity.jsonl"), 10);
+        assert!(log.load().unwrap().is_empty());
+
+        log.append(&[entry("first"), entry("second")]).unwrap();
+        // A corrupt line doesn't lose the rest
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(log.path())
+            .unwrap();
+        writeln!(file, "not json").unwrap();
+        log.append(&[entry("third")]).unwrap();
+
+        let loaded = log.load().unwrap();
+        let messages: Vec<&str> = loaded.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, ["first", "second", "third"]);
+        assert_eq!(loaded[0].level, LogLevel::Success);
+        assert_eq!(loaded[0].file.as_deref(), Some(Path::new("/tmp/a.pdf")));
+        assert_eq!(loaded[0].rule.as_deref(), Some("PDFs"));
+    }
+
+    #[test]
+    fn test_old_entries_are_trimmed() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = ActivityLog::new(dir.path().join("activity.jsonl"), 3);
+        for i in 0..50 {
+            log.append(&[entry(&format!("entry {}", i))]).unwrap();
+        }
+
+        let messages: Vec<String> = log.load().unwrap().into_iter().map(|e| e.message).collect();
+        assert_eq!(messages, ["entry 47", "entry 48", "entry 49"]);
+        // The file itself is rotated instead of growing forever
+        let size = std::fs::metadata(log.path()).unwrap().len();
+        assert!(size <= 2 * 3 * ESTIMATED_ENTRY_BYTES, "{} bytes", size);
+        assert!(!log.path().with_extension("jsonl.tmp").exists());
+    }
+
+    #[test]
+    fn test_only_rule_activity_is_saved() {
+        use crate::app::state::AppState;
+        use crate::theme::Theme;
+
+        let dir = tempfile::tempdir().unwrap();
+        let log = ActivityLog::new(dir.path().join("activity.jsonl"), 10);
+        let mut state = AppState::new(Config::default(), Theme::default());
+        state.restore_activity_log(log.clone());
+
+        state.log(LogLevel::Info, "Theme changed to Dracula");
+        state.set_status("Press ? for help");
+        state.record_activity(vec![entry("first"), entry("second")]);
+        state.record_activity(Vec::new());
+
+        let messages: Vec<String> = log.load().unwrap().into_iter().map(|e| e.message).collect();
+        assert_eq!(messages, ["first", "second"]);
+        assert_eq!(state.log_entries.back().unwrap().message, "second");
+    }
+}
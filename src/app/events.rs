@@ -1,12 +1,13 @@
 //! Event handling for the TUI
 
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
 
 use super::state::{
     AppState, Mode, RuleEditorField, RuleEditorState, SettingsItem, View, WatchEditorField,
     WatchEditorState,
 };
-#[cfg(unix)]
+#[cfg(any(unix, windows))]
 use crate::autostart;
 use crate::theme::Theme;
 
@@ -69,6 +70,10 @@ pub fn handle_key(state: &mut AppState, key: KeyEvent) {
             // No input during update
             return;
         }
+        Mode::TestFile => {
+            handle_test_file_key(state, key);
+            return;
+        }
         Mode::Normal => {}
     }
 
@@ -143,6 +148,11 @@ pub fn handle_key(state: &mut AppState, key: KeyEvent) {
             state.mode = Mode::About;
             return;
         }
+        // Reload config from disk
+        (_, KeyCode::Char('R')) => {
+            state.reload_config();
+            return;
+        }
         _ => {}
     }
 
@@ -155,6 +165,49 @@ pub fn handle_key(state: &mut AppState, key: KeyEvent) {
     }
 }
 
+/// Handle a mouse event; `area` is the full terminal area
+pub fn handle_mouse(state: &mut AppState, mouse: MouseEvent, area: Rect) {
+    // Popups and editors are keyboard-driven
+    if state.mode != Mode::Normal || state.show_help {
+        return;
+    }
+
+    let [tabs_area, main_area, _] = super::ui::main_layout(area);
+
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            if let Some(view) = super::ui::tab_at(tabs_area, mouse.column, mouse.row) {
+                state.view = view;
+                return;
+            }
+
+            match state.view {
+                View::Rules => {
+                    let len = state.config.rules.len();
+                    let offset = state.rules_list_state.offset();
+                    if let Some(index) = super::ui::list_index_at(main_area, mouse.row, offset, len)
+                    {
+                        state.selected_rule = Some(index);
+                    }
+                }
+                View::Watches => {
+                    let len = state.config.watches.len();
+                    let offset = state.watches_list_state.offset();
+                    if let Some(index) = super::ui::list_index_at(main_area, mouse.row, offset, len)
+                    {
+                        state.selected_watch = Some(index);
+                    }
+                }
+                View::Dashboard | View::Log => {}
+            }
+        }
+        // Scrolling behaves like the arrow keys in the current view
+        MouseEventKind::ScrollDown => handle_key(state, KeyEvent::from(KeyCode::Down)),
+        MouseEventKind::ScrollUp => handle_key(state, KeyEvent::from(KeyCode::Up)),
+        _ => {}
+    }
+}
+
 fn handle_theme_picker_key(state: &mut AppState, key: KeyEvent) {
     let themes = Theme::all();
     let len = themes.len();
@@ -219,6 +272,26 @@ fn handle_dashboard_key(state: &mut AppState, key: KeyEvent) {
 fn handle_rules_key(state: &mut AppState, key: KeyEvent) {
     let len = state.config.rules.len();
 
+    // While a tested file has matches, n/N step through them
+    if state.rule_test.is_some() {
+        match key.code {
+            KeyCode::Char('n') => return state.step_rule_test(true),
+            KeyCode::Char('N') => return state.step_rule_test(false),
+            KeyCode::Esc => {
+                state.rule_test = None;
+                state.clear_status();
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    if key.code == KeyCode::Char('f') {
+        state.test_file_cursor = state.test_file_input.chars().count();
+        state.mode = Mode::TestFile;
+        return;
+    }
+
     // Allow adding new rules even if list is empty
     if key.code == KeyCode::Char('n') {
         state.rule_editor = Some(RuleEditorState::new_rule());
@@ -255,7 +328,9 @@ fn handle_rules_key(state: &mut AppState, key: KeyEvent) {
         }
         KeyCode::Enter | KeyCode::Char(' ') => {
             // Toggle rule enabled status
-            if let Some(rule) = state.current_rule_mut() {
+            if let Some(status) = included_rule_status(state) {
+                state.set_status(status);
+            } else if let Some(rule) = state.current_rule_mut() {
                 rule.enabled = !rule.enabled;
                 let name = rule.name.clone();
                 let status = if rule.enabled { "enabled" } else { "disabled" };
@@ -264,9 +339,20 @@ fn handle_rules_key(state: &mut AppState, key: KeyEvent) {
                 save_config(state);
             }
         }
+        KeyCode::Char('D') => {
+            // Disable all rules, or restore their previous states
+            if state.toggle_all_rules() {
+                state.set_status("All rules disabled (press D again to restore)");
+            } else {
+                state.set_status("Restored previous rule states");
+            }
+            save_config(state);
+        }
         KeyCode::Char('e') => {
             // Edit selected rule
-            if let Some(idx) = state.selected_rule {
+            if let Some(status) = included_rule_status(state) {
+                state.set_status(status);
+            } else if let Some(idx) = state.selected_rule {
                 if let Some(rule) = state.config.rules.get(idx) {
                     state.rule_editor = Some(RuleEditorState::from_rule(idx, rule));
                     state.mode = Mode::EditRule;
@@ -277,10 +363,13 @@ fn handle_rules_key(state: &mut AppState, key: KeyEvent) {
         }
         KeyCode::Char('d') | KeyCode::Delete => {
             // Delete selected rule
-            if let Some(idx) = state.selected_rule {
+            if let Some(status) = included_rule_status(state) {
+                state.set_status(status);
+            } else if let Some(idx) = state.selected_rule {
                 if idx < state.config.rules.len() {
                     let rule_name = state.config.rules[idx].name.clone();
                     state.config.rules.remove(idx);
+                    state.rule_test = None;
 
                     // Update selection
                     if state.config.rules.is_empty() {
@@ -301,6 +390,32 @@ fn handle_rules_key(state: &mut AppState, key: KeyEvent) {
     }
 }
 
+/// Status explaining that the selected rule came from an included file and
+/// can only be changed there, if it did
+fn included_rule_status(state: &AppState) -> Option<String> {
+    let rule = state.current_rule()?;
+    let source = state.config.included_rule_source(rule)?;
+    Some(format!(
+        "Rule '{}' comes from {}; edit it there",
+        rule.name,
+        source.display()
+    ))
+}
+
+/// Status explaining that the selected watch came from an included file and
+/// can only be changed there, if it did
+fn included_watch_status(state: &AppState) -> Option<String> {
+    let watch = state
+        .selected_watch
+        .and_then(|i| state.config.watches.get(i))?;
+    let source = state.config.included_watch_source(watch)?;
+    Some(format!(
+        "Watch '{}' comes from {}; edit it there",
+        watch.path.display(),
+        source.display()
+    ))
+}
+
 fn handle_watches_key(state: &mut AppState, key: KeyEvent) {
     use super::state::WatchEditorState;
 
@@ -345,7 +460,9 @@ fn handle_watches_key(state: &mut AppState, key: KeyEvent) {
         }
         KeyCode::Char('e') => {
             // Edit selected watch
-            if let Some(idx) = state.selected_watch {
+            if let Some(status) = included_watch_status(state) {
+                state.set_status(status);
+            } else if let Some(idx) = state.selected_watch {
                 if let Some(watch) = state.config.watches.get(idx) {
                     state.watch_editor =
                         Some(WatchEditorState::from_watch(idx, watch, available_rules));
@@ -357,7 +474,9 @@ fn handle_watches_key(state: &mut AppState, key: KeyEvent) {
         }
         KeyCode::Char('d') | KeyCode::Delete => {
             // Delete selected watch
-            if let Some(idx) = state.selected_watch {
+            if let Some(status) = included_watch_status(state) {
+                state.set_status(status);
+            } else if let Some(idx) = state.selected_watch {
                 if idx < state.config.watches.len() {
                     let watch_path = state.config.watches[idx].path.display().to_string();
                     state.config.watches.remove(idx);
@@ -382,14 +501,35 @@ fn handle_watches_key(state: &mut AppState, key: KeyEvent) {
             if let Some(i) = state.selected_watch
                 && let Some(watch) = state.config.watches.get(i)
             {
-                let path = watch.path.display().to_string();
-                state.set_status(format!("Would open: {}", path));
+                let path = watch_open_path(watch);
+                match open_in_file_manager(&path) {
+                    Ok(()) => state.set_status(format!("Opened {}", path.display())),
+                    Err(e) => state.set_status(format!("Failed to open {}: {}", path.display(), e)),
+                }
             }
         }
         _ => {}
     }
 }
 
+/// Resolve the folder to open for a watch, expanding `~` and environment variables
+fn watch_open_path(watch: &crate::config::WatchConfig) -> std::path::PathBuf {
+    crate::expand_path(&watch.path)
+}
+
+/// Launch the system file manager on a folder, without waiting for it, so
+/// the TUI keeps running while it's open
+#[cfg(not(test))]
+fn open_in_file_manager(path: &std::path::Path) -> std::io::Result<()> {
+    open::that_detached(path)
+}
+
+/// Tests never launch an external file manager
+#[cfg(test)]
+fn open_in_file_manager(_path: &std::path::Path) -> std::io::Result<()> {
+    Ok(())
+}
+
 fn handle_log_key(state: &mut AppState, key: KeyEvent) {
     let len = state.log_entries.len();
 
@@ -399,6 +539,7 @@ fn handle_log_key(state: &mut AppState, key: KeyEvent) {
             state.log_scroll = 0;
             state.set_status("Log cleared");
         }
+        KeyCode::Char('u') => state.undo_last_operation(),
         KeyCode::Up | KeyCode::Char('k') => {
             state.log_scroll = state.log_scroll.saturating_sub(1);
         }
@@ -527,7 +668,7 @@ fn handle_settings_action(state: &mut AppState) {
             ));
             save_config(state);
         }
-        #[cfg(unix)]
+        #[cfg(any(unix, windows))]
         SettingsItem::AutoStartOnBoot => match autostart::toggle() {
             Ok(enabled) => {
                 let status = if enabled { "enabled" } else { "disabled" };
@@ -559,6 +700,26 @@ fn handle_settings_action(state: &mut AppState) {
             state.set_status(format!("Notifications: {}", status));
             save_config(state);
         }
+        SettingsItem::SimulateMode => {
+            state.config.general.dry_run = !state.config.general.dry_run;
+            let status = if state.config.general.dry_run {
+                "on — actions are only logged"
+            } else {
+                "off"
+            };
+            state.set_status(format!("Simulate mode: {}", status));
+            save_config(state);
+        }
+        SettingsItem::HighContrast => {
+            state.config.general.high_contrast = !state.config.general.high_contrast;
+            let status = if state.config.general.high_contrast {
+                "on"
+            } else {
+                "off"
+            };
+            state.set_status(format!("High contrast: {}", status));
+            save_config(state);
+        }
     }
 }
 
@@ -654,6 +815,7 @@ fn save_config(state: &mut AppState) {
     if let Err(e) = state.config.save(None) {
         state.set_status(format!("Failed to save config: {}", e));
     }
+    state.remember_config_mtime();
     // Restart embedded watcher when daemon is not running so it picks up changes
     if !state.daemon_running {
         state.watcher_needs_restart = true;
@@ -666,7 +828,14 @@ fn handle_rule_editor_key(state: &mut AppState, key: KeyEvent) {
         return;
     };
 
+    // Any key other than a second Esc keeps editing
+    let confirming = std::mem::take(&mut editor.confirm_discard);
+
     match key.code {
+        KeyCode::Esc if !confirming && editor.is_dirty() => {
+            editor.confirm_discard = true;
+            state.set_status("Unsaved changes - press Esc again to discard");
+        }
         KeyCode::Esc => {
             // Cancel editing
             state.rule_editor = None;
@@ -675,11 +844,18 @@ fn handle_rule_editor_key(state: &mut AppState, key: KeyEvent) {
         }
         KeyCode::Tab => {
             // Move to next field
-            editor.field = editor.field.next();
+            editor.focus_next();
         }
         KeyCode::BackTab => {
             // Move to previous field
-            editor.field = editor.field.prev();
+            editor.focus_prev();
+        }
+        // Jump straight to the most-edited fields
+        KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            editor.field = RuleEditorField::Name;
+        }
+        KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            editor.field = RuleEditorField::ActionType;
         }
         KeyCode::Enter => {
             // Save the rule
@@ -728,6 +904,14 @@ fn handle_rule_editor_field_input(editor: &mut RuleEditorState, key: KeyEvent) {
                 editor.enabled = !editor.enabled;
             }
         }
+        RuleEditorField::StopProcessing => {
+            if matches!(
+                key.code,
+                KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right
+            ) {
+                editor.stop_processing = !editor.stop_processing;
+            }
+        }
         RuleEditorField::Extension => {
             handle_text_input(&mut editor.extension, &mut editor.cursor_extension, key)
         }
@@ -789,6 +973,22 @@ fn handle_rule_editor_field_input(editor: &mut RuleEditorState, key: KeyEvent) {
             &mut editor.cursor_action_destination,
             key,
         ),
+        RuleEditorField::ActionOverwrite => {
+            if matches!(
+                key.code,
+                KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right
+            ) {
+                editor.action_overwrite = !editor.action_overwrite;
+            }
+        }
+        RuleEditorField::ActionDeleteOriginal => {
+            if matches!(
+                key.code,
+                KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right
+            ) {
+                editor.action_delete_original = !editor.action_delete_original;
+            }
+        }
         RuleEditorField::ActionPattern => handle_text_input(
             &mut editor.action_pattern,
             &mut editor.cursor_action_pattern,
@@ -805,6 +1005,21 @@ fn handle_rule_editor_field_input(editor: &mut RuleEditorState, key: KeyEvent) {
     }
 }
 
+fn handle_test_file_key(state: &mut AppState, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc => state.mode = Mode::Normal,
+        KeyCode::Enter => {
+            state.mode = Mode::Normal;
+            let input = state.test_file_input.trim();
+            if !input.is_empty() {
+                let path = crate::expand_path(std::path::Path::new(input));
+                state.start_rule_test(path);
+            }
+        }
+        _ => handle_text_input(&mut state.test_file_input, &mut state.test_file_cursor, key),
+    }
+}
+
 fn handle_text_input(input: &mut String, cursor: &mut usize, key: KeyEvent) {
     match key.code {
         KeyCode::Char(c) => {
@@ -1012,3 +1227,186 @@ fn handle_watch_editor_field_input(editor: &mut WatchEditorState, key: KeyEvent)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WatchConfig;
+    use std::path::PathBuf;
+
+    fn watch(path: &str) -> WatchConfig {
+        WatchConfig {
+            path: PathBuf::from(path),
+            recursive: false,
+            rules: Vec::new(),
+            follow_symlinks: false,
+        }
+    }
+
+    #[test]
+    fn test_watch_open_path_expands_home_and_env() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(
+            watch_open_path(&watch("~/Downloads")),
+            home.join("Downloads")
+        );
+        assert_eq!(
+            watch_open_path(&watch("$HOME/Desktop")),
+            home.join("Desktop")
+        );
+        assert_eq!(
+            watch_open_path(&watch("/srv/incoming")),
+            PathBuf::from("/srv/incoming")
+        );
+    }
+
+    #[test]
+    fn test_rule_editor_jump_keys_focus_name_and_action_type() {
+        let mut state = AppState::new(crate::Config::default(), crate::Theme::default());
+        state.mode = Mode::AddRule;
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+
+        let mut field = RuleEditorField::Name;
+        loop {
+            state.rule_editor = Some(RuleEditorState {
+                field,
+                ..RuleEditorState::new_rule()
+            });
+            handle_rule_editor_key(&mut state, ctrl('a'));
+            let editor = state.rule_editor.as_ref().unwrap();
+            assert_eq!(
+                editor.field,
+                RuleEditorField::ActionType,
+                "from {:?}",
+                field
+            );
+            // The shortcut isn't typed into text fields
+            assert!(editor.name.is_empty());
+
+            handle_rule_editor_key(&mut state, ctrl('n'));
+            assert_eq!(
+                state.rule_editor.as_ref().unwrap().field,
+                RuleEditorField::Name
+            );
+
+            field = field.next();
+            if field == RuleEditorField::Name {
+                break;
+            }
+        }
+
+        // Plain letters are still typed into the focused field
+        handle_rule_editor_key(&mut state, KeyEvent::from(KeyCode::Char('a')));
+        assert_eq!(state.rule_editor.as_ref().unwrap().name, "a");
+    }
+
+    #[test]
+    fn test_space_toggles_rule_editor_checkboxes() {
+        let mut state = AppState::new(crate::Config::default(), crate::Theme::default());
+        state.mode = Mode::AddRule;
+        state.rule_editor = Some(RuleEditorState {
+            name: "Docs".to_string(),
+            ..RuleEditorState::new_rule()
+        });
+        let space = KeyEvent::from(KeyCode::Char(' '));
+
+        for field in [
+            RuleEditorField::StopProcessing,
+            RuleEditorField::ActionOverwrite,
+        ] {
+            state.rule_editor.as_mut().unwrap().field = field;
+            handle_rule_editor_key(&mut state, space);
+        }
+
+        let rule = state.rule_editor.as_ref().unwrap().to_rule();
+        assert!(rule.stop_processing);
+        assert!(matches!(
+            rule.action,
+            crate::Action::Move {
+                overwrite: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_rule_editor_esc_confirms_discarding_changes() {
+        let mut state = AppState::new(crate::Config::default(), crate::Theme::default());
+        state.mode = Mode::AddRule;
+        state.rule_editor = Some(RuleEditorState::new_rule());
+        handle_rule_editor_key(&mut state, KeyEvent::from(KeyCode::Char('x')));
+
+        // First Esc only asks
+        handle_rule_editor_key(&mut state, KeyEvent::from(KeyCode::Esc));
+        assert!(state.rule_editor.as_ref().unwrap().confirm_discard);
+
+        // Any other key keeps editing and clears the prompt
+        handle_rule_editor_key(&mut state, KeyEvent::from(KeyCode::Tab));
+        assert!(!state.rule_editor.as_ref().unwrap().confirm_discard);
+
+        handle_rule_editor_key(&mut state, KeyEvent::from(KeyCode::Esc));
+        handle_rule_editor_key(&mut state, KeyEvent::from(KeyCode::Esc));
+        assert!(state.rule_editor.is_none());
+        assert_eq!(state.mode, Mode::Normal);
+
+        // Without changes one Esc closes the editor
+        state.rule_editor = Some(RuleEditorState::new_rule());
+        handle_rule_editor_key(&mut state, KeyEvent::from(KeyCode::Esc));
+        assert!(state.rule_editor.is_none());
+    }
+
+    #[test]
+    fn test_n_steps_through_rules_matching_a_tested_file() {
+        use crate::rules::{Action, Condition, Rule};
+
+        let pdf_rule = |name: &str| {
+            Rule::new(
+                name,
+                Condition {
+                    extension: Some("pdf".to_string()),
+                    ..Default::default()
+                },
+                Action::Nothing,
+            )
+        };
+        let mut config = crate::Config::default();
+        config.rules = vec![
+            pdf_rule("First"),
+            Rule::new(
+                "Text",
+                Condition {
+                    extension: Some("txt".to_string()),
+                    ..Default::default()
+                },
+                Action::Nothing,
+            ),
+            pdf_rule("Second"),
+        ];
+        let mut state = AppState::new(config, Theme::default());
+        state.view = View::Rules;
+        let press = |state: &mut AppState, code| handle_key(state, KeyEvent::from(code));
+
+        press(&mut state, KeyCode::Char('f'));
+        assert_eq!(state.mode, Mode::TestFile);
+        for c in "/tmp/report.pdf".chars() {
+            press(&mut state, KeyCode::Char(c));
+        }
+        press(&mut state, KeyCode::Enter);
+        assert_eq!(state.mode, Mode::Normal);
+        assert_eq!(state.selected_rule, Some(0));
+
+        press(&mut state, KeyCode::Char('n'));
+        assert_eq!(state.selected_rule, Some(2));
+        press(&mut state, KeyCode::Char('n'));
+        assert_eq!(state.selected_rule, Some(0));
+        press(&mut state, KeyCode::Char('N'));
+        assert_eq!(state.selected_rule, Some(2));
+        assert!(state.rule_editor.is_none());
+
+        // Once cleared, n creates a rule again
+        press(&mut state, KeyCode::Esc);
+        assert!(state.rule_test.is_none());
+        press(&mut state, KeyCode::Char('n'));
+        assert_eq!(state.mode, Mode::AddRule);
+    }
+}
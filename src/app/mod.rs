@@ -1,14 +1,17 @@
 //! TUI Application module
 
+mod activity_log;
 mod events;
+mod session;
 mod state;
 mod ui;
+mod worker;
 
 pub use state::AppState;
 
 use anyhow::Result;
 use crossterm::{
-    event::{self, Event},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
@@ -19,8 +22,9 @@ use std::time::Duration;
 
 use crate::config::Config;
 use crate::theme::Theme;
+use crate::watcher::ProcessorLock;
 
-use std::sync::mpsc;
+use std::sync::{Arc, mpsc};
 
 /// Messages from background tasks
 enum BackgroundMsg {
@@ -29,22 +33,40 @@ enum BackgroundMsg {
 
 /// Run the TUI application
 pub async fn run(config_path: Option<PathBuf>) -> Result<()> {
-    // Load config from specified path or default (~/.config/hazelnut/config.toml)
-    let config = Config::load(config_path.as_deref())?;
-
-    // Load theme from config or use default
-    let theme = Theme::load(&config);
-
     // Initialize terminal
     enable_raw_mode()?;
     let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
 
-    // Create app state
+    // Load config from specified path or default (~/.config/hazelnut/config.toml),
+    // showing parse errors in the TUI instead of exiting straight away
+    let config = match load_config(&mut terminal, config_path.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            restore_terminal(&mut terminal)?;
+            return Err(e);
+        }
+    };
+
+    // Load theme from config or use default
+    let theme = Theme::load(&config);
+
+    // Create app state, restoring the last view and selections
     let mut state = AppState::new(config.clone(), theme);
+    state.watch_config_file(config_path.or_else(Config::default_path));
+    let session_path = session::Session::default_path();
+    if let Some(ref path) = session_path {
+        session::Session::load(path).restore(&mut state);
+    }
+    if let Some(path) = activity_log::ActivityLog::default_path() {
+        state.restore_activity_log(activity_log::ActivityLog::new(
+            path,
+            config.general.log_retention,
+        ));
+    }
 
     // Start daemon on launch if configured (Unix only)
     #[cfg(unix)]
@@ -66,7 +88,7 @@ pub async fn run(config_path: Option<PathBuf>) -> Result<()> {
         {
             Ok(_child) => {
                 state.daemon_running = true;
-                state.status_message = Some("Daemon started automatically".to_string());
+                state.set_status("Daemon started automatically");
             }
             Err(_) => {
                 // Silently fail - daemon might already be running
@@ -83,38 +105,107 @@ pub async fn run(config_path: Option<PathBuf>) -> Result<()> {
         }
     });
 
-    // Start embedded watcher when daemon is not running
-    let mut embedded_watcher = if !state.daemon_running {
-        match create_embedded_watcher(&config) {
-            Ok(w) => {
-                state.status_message = Some("Watching files (embedded)".to_string());
-                Some(w)
-            }
-            Err(e) => {
-                tracing::error!("Failed to start embedded watcher: {}", e);
-                None
+    // Start embedded watcher when daemon is not running and no other
+    // instance is processing files
+    let mut processor_lock = None;
+    let mut embedded_watcher = None;
+    if !state.daemon_running {
+        if acquire_processor_lock(&mut processor_lock) {
+            match create_embedded_watcher(&config) {
+                Ok(w) => {
+                    state.set_status("Watching files (embedded)");
+                    embedded_watcher =
+                        Some(worker::WatcherWorker::spawn(w, processor_lock.clone()));
+                }
+                Err(e) => {
+                    tracing::error!("Failed to start embedded watcher: {}", e);
+                }
             }
+        } else {
+            // Keep retrying so we take over once the other instance exits
+            state.set_status("Another Hazelnut instance is processing files");
+            state.watcher_needs_restart = true;
         }
-    } else {
-        None
-    };
+    }
 
     // Main loop
-    let result = run_app(&mut terminal, &mut state, rx, &mut embedded_watcher);
+    let result = run_app(
+        &mut terminal,
+        &mut state,
+        rx,
+        &mut embedded_watcher,
+        &mut processor_lock,
+    );
 
-    // Restore terminal
+    if let Some(ref path) = session_path
+        && let Err(e) = session::Session::capture(&state).save(path)
+    {
+        tracing::warn!("Failed to save session: {}", e);
+    }
+
+    restore_terminal(&mut terminal)?;
+
+    result
+}
+
+/// Leave the alternate screen and give the terminal back to the shell
+fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>) -> Result<()> {
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
     terminal.show_cursor()?;
+    Ok(())
+}
 
-    result
+/// Load the config, keeping a parse error on screen until the user quits or
+/// fixes the file and retries. Other load errors are returned as-is.
+fn load_config(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    config_path: Option<&std::path::Path>,
+) -> Result<Config> {
+    let Some(path) = config_path.map(PathBuf::from).or_else(Config::default_path) else {
+        return Config::load(config_path);
+    };
+    let mut note = None;
+
+    loop {
+        let err = match Config::load(Some(&path)) {
+            Ok(config) => return Ok(config),
+            Err(err) => err,
+        };
+        let Some(report) = crate::config::ConfigParseError::from_file(&path) else {
+            return Err(err);
+        };
+
+        terminal.draw(|frame| ui::render_config_error(frame, &path, &report, note.as_deref()))?;
+
+        if let Event::Key(key) = event::read()?
+            && key.kind == crossterm::event::KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Err(err),
+                KeyCode::Char('o') => {
+                    note = Some(match open::that(&path) {
+                        Ok(()) => "Opened config — press r after saving your fix".to_string(),
+                        Err(e) => format!("Failed to open config: {}", e),
+                    });
+                }
+                KeyCode::Char('r') => note = Some("Still failing to parse".to_string()),
+                _ => {}
+            }
+        }
+    }
 }
 
 fn run_app(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
     state: &mut AppState,
     bg_rx: mpsc::Receiver<BackgroundMsg>,
-    embedded_watcher: &mut Option<crate::Watcher>,
+    embedded_watcher: &mut Option<worker::WatcherWorker>,
+    processor_lock: &mut Option<Arc<ProcessorLock>>,
 ) -> Result<()> {
     loop {
         // Check for background messages (non-blocking)
@@ -137,24 +228,53 @@ fn run_app(
         }
 
         // Handle events
-        if event::poll(Duration::from_millis(100))?
-            && let Event::Key(key) = event::read()?
-            && key.kind == crossterm::event::KeyEventKind::Press
+        if event::poll(Duration::from_millis(100))? {
+            match event::read()? {
+                Event::Key(key) if key.kind == crossterm::event::KeyEventKind::Press => {
+                    events::handle_key(state, key);
+                }
+                Event::Mouse(mouse) => {
+                    let size = terminal.size()?;
+                    events::handle_mouse(state, mouse, Rect::new(0, 0, size.width, size.height));
+                }
+                _ => {}
+            }
+        }
+
+        // Notice a daemon started outside the TUI (it waits for our lock)
+        if embedded_watcher.is_some()
+            && state.frame.is_multiple_of(10)
+            && state::is_daemon_running()
         {
-            events::handle_key(state, key);
+            state.daemon_running = true;
+            state.set_status("Daemon started, handing off file processing");
         }
 
-        // Stop embedded watcher if daemon was started
-        if state.daemon_running && embedded_watcher.is_some() {
+        // Stop embedded watcher if daemon was started. The worker keeps its
+        // share of the lock until its current pass is done, then the daemon
+        // picks it up.
+        if state.daemon_running && (embedded_watcher.is_some() || processor_lock.is_some()) {
             *embedded_watcher = None;
+            *processor_lock = None;
+            state.watch_stats.clear();
         }
 
-        // Restart embedded watcher if daemon was stopped
-        if state.watcher_needs_restart {
+        // Restart embedded watcher if daemon was stopped, once it has let go
+        // of the processor lock
+        if state.watcher_needs_restart && state.daemon_running {
+            state.watcher_needs_restart = false;
+        }
+        if state.watcher_needs_restart
+            && (processor_lock.is_some() || state.frame.is_multiple_of(10))
+            && acquire_processor_lock(processor_lock)
+        {
             state.watcher_needs_restart = false;
+            // Drop the old worker first; it shares our lock, so there is no gap
+            *embedded_watcher = None;
             match create_embedded_watcher(&state.config) {
                 Ok(w) => {
-                    *embedded_watcher = Some(w);
+                    *embedded_watcher =
+                        Some(worker::WatcherWorker::spawn(w, processor_lock.clone()));
                     state.set_status("Embedded watcher started (daemon stopped)");
                 }
                 Err(e) => {
@@ -164,25 +284,29 @@ fn run_app(
             }
         }
 
-        // Process embedded watcher events in a background thread to avoid blocking the UI
-        if let Some(watcher) = embedded_watcher {
-            // Only poll events (non-blocking) and spawn processing if there are events
-            let events = match watcher.poll() {
-                Ok(events) => events,
-                Err(e) => {
-                    tracing::error!("Watcher poll error: {}", e);
-                    vec![]
-                }
-            };
-            if !events.is_empty() {
-                match watcher.process_polled_events(events) {
-                    Ok(count) if count > 0 => {
-                        tracing::info!("Processed {} files", count);
+        // The embedded watcher runs on its own thread; only collect its results here
+        if let Some(worker) = embedded_watcher {
+            for msg in worker.drain() {
+                match msg {
+                    worker::WorkerMsg::Processed {
+                        count,
+                        last_error,
+                        last_event_at,
+                        watch_stats,
+                        log,
+                    } => {
+                        if count > 0 {
+                            tracing::info!("Processed {} files", count);
+                        }
+                        state.toast_organized(count);
+                        state.last_error = last_error;
+                        state.last_event_at = last_event_at;
+                        state.watch_stats = watch_stats;
+                        state.record_activity(log.into_iter().map(Into::into).collect());
                     }
-                    Err(e) => {
+                    worker::WorkerMsg::Failed(e) => {
                         tracing::error!("Watcher error: {}", e);
                     }
-                    _ => {}
                 }
             }
         }
@@ -198,21 +322,48 @@ fn run_app(
     Ok(())
 }
 
+/// Take the processor lock for the embedded watcher unless we already hold it.
+///
+/// Returns `false` while the daemon or another TUI holds the lock. If the lock
+/// file can't be used at all, the watcher runs without it rather than not at all.
+fn acquire_processor_lock(lock: &mut Option<Arc<ProcessorLock>>) -> bool {
+    if lock.is_some() {
+        return true;
+    }
+    match ProcessorLock::try_acquire(&ProcessorLock::default_path()) {
+        Ok(Some(acquired)) => {
+            *lock = Some(Arc::new(acquired));
+            true
+        }
+        Ok(None) => false,
+        Err(e) => {
+            tracing::warn!("Processor lock unavailable, watching without it: {}", e);
+            true
+        }
+    }
+}
+
 /// Create an embedded file watcher for use when the daemon is not running.
 /// This enables file watching on all platforms (including Windows).
 fn create_embedded_watcher(config: &crate::Config) -> Result<crate::Watcher> {
-    let engine = crate::RuleEngine::new(config.rules.clone());
+    let engine = crate::RuleEngine::from_config(config)
+        .with_journal(Some(crate::rules::UndoJournal::open_default()));
     let mut watcher = crate::Watcher::new(
         engine,
         config.general.polling_interval_secs,
         config.general.debounce_seconds,
-    )?;
+    )?
+    .with_scan_on_start(config.general.scan_on_start)
+    .with_scan_max_depth(config.general.scan_max_depth)
+    .with_max_events_per_poll(config.general.max_events_per_poll)
+    .with_quarantine(
+        config.general.quarantine_after,
+        config.general.quarantine_dir.clone(),
+    );
 
     for watch in &config.watches {
         let expanded_path = crate::expand_path(&watch.path);
-        if let Err(e) =
-            watcher.watch_with_rules(&expanded_path, watch.recursive, watch.rules.clone())
-        {
+        if let Err(e) = watcher.watch_with_options(&expanded_path, watch.options()) {
             tracing::error!("Failed to watch {}: {}", expanded_path.display(), e);
         }
     }
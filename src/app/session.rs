@@ -0,0 +1,129 @@
+//! Persisted UI session (last view and selections), restored on startup
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::state::{AppState, View};
+use crate::config::Config;
+
+/// UI state remembered between TUI sessions
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// Last active view
+    #[serde(default)]
+    pub view: View,
+
+    /// Last selected rule index
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selected_rule: Option<usize>,
+
+    /// Last selected watch index
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selected_watch: Option<usize>,
+}
+
+impl Session {
+    /// Default session file path (~/.local/share/hazelnut/session.toml)
+    pub fn default_path() -> Option<PathBuf> {
+        Config::data_dir().map(|d| d.join("session.toml"))
+    }
+
+    /// Load a session, falling back to defaults if the file is missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the session to disk
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string_pretty(self).context("Failed to serialize session")?;
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write session file: {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Capture the current session from the app state
+    pub fn capture(state: &AppState) -> Self {
+        Self {
+            view: state.view,
+            selected_rule: state.selected_rule,
+            selected_watch: state.selected_watch,
+        }
+    }
+
+    /// Restore this session into the app state, dropping selections that no longer exist
+    pub fn restore(&self, state: &mut AppState) {
+        state.view = self.view;
+        state.selected_rule = self.selected_rule.filter(|&i| i < state.config.rules.len());
+        state.selected_watch = self
+            .selected_watch
+            .filter(|&i| i < state.config.watches.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::Theme;
+
+    #[test]
+    fn test_session_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("session.toml");
+
+        let session = Session {
+            view: View::Watches,
+            selected_rule: Some(3),
+            selected_watch: None,
+        };
+        session.save(&path).unwrap();
+
+        assert_eq!(Session::load(&path), session);
+    }
+
+    #[test]
+    fn test_missing_or_corrupt_session_uses_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.toml");
+        assert_eq!(Session::load(&path), Session::default());
+
+        std::fs::write(&path, "view = [not valid").unwrap();
+        assert_eq!(Session::load(&path), Session::default());
+    }
+
+    #[test]
+    fn test_restore_drops_out_of_range_selections() {
+        let mut config = Config::default();
+        config.rules.push(crate::Rule::new(
+            "Only rule",
+            crate::Condition::default(),
+            crate::Action::Nothing,
+        ));
+        let mut state = AppState::new(config, Theme::default());
+
+        Session {
+            view: View::Rules,
+            selected_rule: Some(5),
+            selected_watch: Some(0),
+        }
+        .restore(&mut state);
+
+        assert_eq!(state.view, View::Rules);
+        assert_eq!(state.selected_rule, None);
+        assert_eq!(state.selected_watch, None);
+
+        Session {
+            view: View::Rules,
+            selected_rule: Some(0),
+            selected_watch: None,
+        }
+        .restore(&mut state);
+        assert_eq!(state.selected_rule, Some(0));
+    }
+}
@@ -1,14 +1,43 @@
 //! Application state management
 
 use crate::config::Config;
-use crate::rules::{Action, Condition, Rule};
+use crate::rules::{Action, Rule};
 use crate::theme::Theme;
+use ratatui::widgets::ListState;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::path::PathBuf;
 
+/// Frames per second of the main loop's 100ms tick
+pub const TICKS_PER_SECOND: u64 = 10;
+
+/// Frames the "files organized" toast stays up while it fades
+pub const TOAST_TICKS: u64 = 30;
+
+/// Brief status bar confirmation that files were organized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Toast {
+    /// Files organized since the toast appeared
+    pub count: usize,
+    /// Frame the toast was last (re)started at
+    pub started: u64,
+}
+
+impl Toast {
+    /// How far the toast has faded at `frame`, from 0.0 (fresh) to 1.0 (gone)
+    pub fn fade(&self, frame: u64) -> f64 {
+        (frame.wrapping_sub(self.started) as f64 / TOAST_TICKS as f64).min(1.0)
+    }
+}
+
+/// Modification time of a file, or None if it can't be read
+fn file_mtime(path: &std::path::Path) -> Option<std::time::SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
 /// Check if the daemon is currently running by checking the PID file
 #[cfg(unix)]
-fn is_daemon_running() -> bool {
+pub(crate) fn is_daemon_running() -> bool {
     let pid_file = dirs::state_dir()
         .unwrap_or_else(|| {
             dirs::home_dir()
@@ -28,7 +57,7 @@ fn is_daemon_running() -> bool {
 }
 
 #[cfg(not(unix))]
-fn is_daemon_running() -> bool {
+pub(crate) fn is_daemon_running() -> bool {
     false
 }
 
@@ -58,58 +87,68 @@ pub enum Mode {
     UpdateConfirm,
     /// Update in progress
     Updating,
+    /// Entering a file path to test the rules against
+    TestFile,
 }
 
 /// Settings menu items
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SettingsItem {
     DaemonControl,
-    #[cfg(unix)]
+    #[cfg(any(unix, windows))]
     AutoStartOnBoot,
     ThemeSelection,
     PollingInterval,
     LogRetention,
     StartupBehavior,
     Notifications,
+    SimulateMode,
+    HighContrast,
 }
 
 impl SettingsItem {
     pub fn all() -> &'static [SettingsItem] {
         &[
             SettingsItem::DaemonControl,
-            #[cfg(unix)]
+            #[cfg(any(unix, windows))]
             SettingsItem::AutoStartOnBoot,
             SettingsItem::ThemeSelection,
             SettingsItem::PollingInterval,
             SettingsItem::LogRetention,
             SettingsItem::StartupBehavior,
             SettingsItem::Notifications,
+            SettingsItem::SimulateMode,
+            SettingsItem::HighContrast,
         ]
     }
 
     pub fn label(&self) -> &'static str {
         match self {
             SettingsItem::DaemonControl => "Start/Stop Daemon",
-            #[cfg(unix)]
+            #[cfg(any(unix, windows))]
             SettingsItem::AutoStartOnBoot => "Auto-start on Boot",
             SettingsItem::ThemeSelection => "Theme",
             SettingsItem::PollingInterval => "Polling Interval",
             SettingsItem::LogRetention => "Log Retention",
             SettingsItem::StartupBehavior => "Start Daemon on Launch",
             SettingsItem::Notifications => "Notifications",
+            SettingsItem::SimulateMode => "Simulate (no changes)",
+            SettingsItem::HighContrast => "High Contrast",
         }
     }
 
     pub fn icon(&self) -> &'static str {
         match self {
             SettingsItem::DaemonControl => "🔌",
-            #[cfg(unix)]
+            #[cfg(any(unix, windows))]
             SettingsItem::AutoStartOnBoot => "🖥️",
             SettingsItem::ThemeSelection => "🎨",
             SettingsItem::PollingInterval => "⏱",
             SettingsItem::LogRetention => "📋",
             SettingsItem::StartupBehavior => "🚀",
             SettingsItem::Notifications => "🔔",
+            SettingsItem::SimulateMode => "🧪",
+            SettingsItem::HighContrast => "🔆",
         }
     }
 }
@@ -144,6 +183,12 @@ pub struct AppState {
     /// Status message to display
     pub status_message: Option<String>,
 
+    /// Frame at which `status_message` was set
+    pub status_set_at: u64,
+
+    /// "Files organized" confirmation shown in the status bar
+    pub toast: Option<Toast>,
+
     /// Scroll offset for log view
     pub log_scroll: usize,
 
@@ -168,6 +213,15 @@ pub struct AppState {
     /// Watch editor state
     pub watch_editor: Option<WatchEditorState>,
 
+    /// File path typed into the rule tester prompt
+    pub test_file_input: String,
+
+    /// Cursor position in `test_file_input`
+    pub test_file_cursor: usize,
+
+    /// Rules matching the last tested file, stepped through with n/N
+    pub rule_test: Option<RuleTest>,
+
     /// Update available notification
     pub update_available: Option<String>,
 
@@ -186,12 +240,54 @@ pub struct AppState {
     /// Cached file position for daemon log reading
     pub log_file_position: u64,
 
+    /// Id of the last daemon log entry received over IPC
+    pub daemon_log_cursor: u64,
+
+    /// Daemon run `daemon_log_cursor` belongs to
+    pub daemon_log_epoch: u64,
+
     /// Flag: watcher needs restart (set when daemon is stopped from settings)
     pub watcher_needs_restart: bool,
+
+    /// Scroll state for the rules list
+    pub rules_list_state: ListState,
+
+    /// Scroll state for the watches list
+    pub watches_list_state: ListState,
+
+    /// First activity log row drawn (newest first); the window only moves
+    /// when `log_scroll` reaches one of its edges
+    pub log_offset: usize,
+
+    /// Most recent processing error reported by the watcher or daemon
+    pub last_error: Option<String>,
+
+    /// Unix timestamp of the last file a rule was applied to
+    pub last_event_at: Option<u64>,
+
+    /// Files handled per watch folder by the embedded watcher, keyed by
+    /// expanded path (empty while the daemon does the processing)
+    pub watch_stats: std::collections::HashMap<PathBuf, crate::watcher::WatchStats>,
+
+    /// Per-rule enabled states saved by `toggle_all_rules`, restored on the next toggle
+    pub rules_enabled_before_toggle: Option<Vec<(String, bool)>>,
+
+    /// Config file watched for external edits
+    pub config_path: Option<PathBuf>,
+
+    /// Last seen modification time of `config_path`
+    pub config_mtime: Option<std::time::SystemTime>,
+
+    /// Journal of reversible file operations, for undo
+    pub undo_journal: crate::rules::UndoJournal,
+
+    /// File the activity log is saved to, so it survives restarts
+    pub activity_log: Option<super::activity_log::ActivityLog>,
 }
 
 /// Available views in the TUI
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum View {
     #[default]
     Dashboard,
@@ -210,12 +306,37 @@ pub struct LogEntry {
     pub rule: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum LogLevel {
-    Info,
-    Success,
-    Warning,
-    Error,
+pub use crate::ipc::LogLevel;
+
+impl From<crate::ipc::LogEntry> for LogEntry {
+    fn from(entry: crate::ipc::LogEntry) -> Self {
+        let timestamp =
+            chrono::NaiveDateTime::parse_from_str(&entry.timestamp, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .and_then(|t| t.and_local_timezone(chrono::Local).earliest())
+                .unwrap_or_else(chrono::Local::now);
+        Self {
+            timestamp,
+            level: entry.level,
+            message: entry.message,
+            file: entry.file,
+            rule: entry.rule,
+        }
+    }
+}
+
+impl From<&LogEntry> for crate::ipc::LogEntry {
+    fn from(entry: &LogEntry) -> Self {
+        Self {
+            id: 0,
+            level: entry.level,
+            timestamp: entry.timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+            message: entry.message.clone(),
+            file: entry.file.clone(),
+            rule: entry.rule.clone(),
+            action: None,
+        }
+    }
 }
 
 impl AppState {
@@ -237,6 +358,8 @@ impl AppState {
             log_entries: VecDeque::new(),
             should_quit: false,
             status_message: None,
+            status_set_at: 0,
+            toast: None,
             log_scroll: 0,
             show_help: false,
             frame: 0,
@@ -245,13 +368,29 @@ impl AppState {
             daemon_running: is_daemon_running(),
             rule_editor: None,
             watch_editor: None,
+            test_file_input: String::new(),
+            test_file_cursor: 0,
+            rule_test: None,
             update_available: None,
             package_manager: crate::detect_package_manager(),
             update_status: None,
             original_theme: None,
             pending_update: false,
             log_file_position: 0,
+            daemon_log_cursor: 0,
+            daemon_log_epoch: 0,
             watcher_needs_restart: false,
+            rules_list_state: ListState::default(),
+            watches_list_state: ListState::default(),
+            log_offset: 0,
+            last_error: None,
+            last_event_at: None,
+            watch_stats: std::collections::HashMap::new(),
+            rules_enabled_before_toggle: None,
+            config_path: None,
+            config_mtime: None,
+            undo_journal: crate::rules::UndoJournal::open_default(),
+            activity_log: None,
         };
 
         // Add welcome log entries
@@ -268,6 +407,23 @@ impl AppState {
         state
     }
 
+    /// Colors for the current theme, with the high-contrast override applied
+    /// when it's enabled in the config
+    pub fn colors(&self) -> crate::theme::ThemeColors {
+        let colors = self.theme.colors();
+        if self.config.general.high_contrast {
+            colors.high_contrast()
+        } else {
+            colors
+        }
+    }
+
+    /// Theme highlighted in the theme picker, used for its preview pane
+    pub fn theme_picker_selection(&self) -> Theme {
+        let themes = Theme::all();
+        Theme::from(themes[self.theme_picker_index.min(themes.len() - 1)])
+    }
+
     /// Set update available (called from background task)
     pub fn set_update_available(&mut self, version: String) {
         self.update_available = Some(version.clone());
@@ -292,15 +448,67 @@ impl AppState {
             .and_then(|i| self.config.rules.get_mut(i))
     }
 
+    /// Disable every rule, or restore the states saved by the previous call.
+    /// Rules from included files are left alone, since they can't be saved.
+    ///
+    /// Returns true if the rules were disabled, false if their earlier states were restored.
+    pub fn toggle_all_rules(&mut self) -> bool {
+        let restore = self.rules_enabled_before_toggle.take();
+        if restore.is_none() {
+            self.rules_enabled_before_toggle = Some(
+                self.config
+                    .rules
+                    .iter()
+                    .map(|r| (r.name.clone(), r.enabled))
+                    .collect(),
+            );
+        }
+        let included: Vec<bool> = self
+            .config
+            .rules
+            .iter()
+            .map(|r| self.config.included_rule_source(r).is_some())
+            .collect();
+        let own_rules = self
+            .config
+            .rules
+            .iter_mut()
+            .zip(included)
+            .filter(|(_, included)| !included)
+            .map(|(rule, _)| rule);
+        match restore {
+            Some(saved) => {
+                for rule in own_rules {
+                    // Rules added since the toggle keep whatever state they have now
+                    if let Some((_, enabled)) = saved.iter().find(|(name, _)| *name == rule.name) {
+                        rule.enabled = *enabled;
+                    }
+                }
+                false
+            }
+            None => {
+                for rule in own_rules {
+                    rule.enabled = false;
+                }
+                true
+            }
+        }
+    }
+
     /// Add a log entry
     pub fn log(&mut self, level: LogLevel, message: impl Into<String>) {
-        self.log_entries.push_back(LogEntry {
+        self.push_log(LogEntry {
             timestamp: chrono::Local::now(),
             level,
             message: message.into(),
             file: None,
             rule: None,
         });
+    }
+
+    /// Add an entry to the activity log
+    pub fn push_log(&mut self, entry: LogEntry) {
+        self.log_entries.push_back(entry);
 
         // Keep log bounded
         if self.log_entries.len() > 1000 {
@@ -308,20 +516,83 @@ impl AppState {
         }
     }
 
-    /// Load daemon log entries from the log file (incremental)
+    /// Add the files the embedded watcher handled in one tick to the activity
+    /// log, saving them to the activity file in one write if one is attached.
+    /// Status and help messages go through `log` and are never saved.
+    pub fn record_activity(&mut self, entries: Vec<LogEntry>) {
+        if entries.is_empty() {
+            return;
+        }
+        if let Some(ref activity_log) = self.activity_log
+            && let Err(e) = activity_log.append(&entries)
+        {
+            tracing::debug!("Failed to save activity log entries: {:#}", e);
+        }
+        for entry in entries {
+            self.push_log(entry);
+        }
+    }
+
+    /// Load the rule activity saved by earlier runs in front of the current
+    /// entries, and save new rule activity to `activity_log` from now on
+    pub fn restore_activity_log(&mut self, activity_log: super::activity_log::ActivityLog) {
+        match activity_log.load() {
+            Ok(history) => {
+                for entry in history.into_iter().rev() {
+                    self.log_entries.push_front(entry);
+                }
+                while self.log_entries.len() > 1000 {
+                    self.log_entries.pop_front();
+                }
+            }
+            Err(e) => tracing::warn!("Failed to load activity log: {:#}", e),
+        }
+        self.activity_log = Some(activity_log);
+    }
+
+    /// Rows of the newest-first activity log that fit in `height` lines,
+    /// keeping `log_scroll` in view. Only these rows need to be rendered.
+    pub fn visible_log_range(&mut self, height: usize) -> std::ops::Range<usize> {
+        let len = self.log_entries.len();
+        if height == 0 || len == 0 {
+            return 0..0;
+        }
+        let scroll = self.log_scroll.min(len - 1);
+        let mut start = self.log_offset.min(scroll);
+        if scroll >= start + height {
+            start = scroll + 1 - height;
+        }
+        // Fill the viewport when the log shrinks or the terminal grows
+        start = start.min(len.saturating_sub(height));
+        self.log_offset = start;
+        start..(start + height).min(len)
+    }
+
+    /// Load new daemon log entries (incremental).
+    ///
+    /// While the daemon is running they're streamed from its in-memory log
+    /// over IPC; otherwise (or with a daemon too old for `TailLog`) the log
+    /// file is tailed instead.
     pub fn load_daemon_logs(&mut self) {
         use std::io::{Read, Seek, SeekFrom};
 
-        // Use ~/.local/state/hazelnut/ on all platforms for consistency
-        // This matches the path used by the daemon
-        let log_path = dirs::state_dir()
-            .unwrap_or_else(|| {
-                dirs::home_dir()
-                    .map(|h| h.join(".local").join("state"))
-                    .unwrap_or_else(|| PathBuf::from("/tmp"))
+        let log_path = crate::ipc::daemon_log_path();
+
+        if self.daemon_running
+            && let Ok(crate::ipc::DaemonResponse::LogTail {
+                entries,
+                missed,
+                epoch,
+            }) = crate::ipc::send_command(&crate::ipc::DaemonCommand::TailLog {
+                since: self.daemon_log_cursor,
+                epoch: self.daemon_log_epoch,
             })
-            .join("hazelnut")
-            .join("hazelnutd.log");
+        {
+            self.append_daemon_log(entries, missed, epoch);
+            // Skip what the daemon wrote to the file meanwhile; we already have it
+            self.log_file_position = std::fs::metadata(&log_path).map(|m| m.len()).unwrap_or(0);
+            return;
+        }
 
         let Ok(mut file) = std::fs::File::open(&log_path) else {
             return;
@@ -365,9 +636,117 @@ impl AppState {
         }
     }
 
-    /// Set a temporary status message
+    /// Add entries streamed from the daemon's log and advance the cursor past
+    /// them. A new `epoch` means the daemon restarted and its ids start over.
+    pub fn append_daemon_log(
+        &mut self,
+        entries: Vec<crate::ipc::LogEntry>,
+        missed: u64,
+        epoch: u64,
+    ) {
+        if epoch != self.daemon_log_epoch {
+            self.daemon_log_epoch = epoch;
+            self.daemon_log_cursor = 0;
+        }
+        if missed > 0 {
+            self.log_entries.push_back(LogEntry {
+                timestamp: chrono::Local::now(),
+                level: LogLevel::Warning,
+                message: format!(
+                    "{} daemon log entries were dropped before they could be shown",
+                    missed
+                ),
+                file: None,
+                rule: None,
+            });
+        }
+        // The first batch is history from before the TUI started; don't toast it
+        let live = self.daemon_log_cursor > 0;
+        let organized = entries
+            .iter()
+            .filter(|e| e.level == LogLevel::Success)
+            .count();
+        for entry in entries {
+            self.daemon_log_cursor = self.daemon_log_cursor.max(entry.id);
+            self.log_entries.push_back(entry.into());
+        }
+        if live {
+            self.toast_organized(organized);
+        }
+
+        while self.log_entries.len() > self.config.general.log_retention {
+            self.log_entries.pop_front();
+        }
+    }
+
+    /// Reverse the most recent journaled move, copy or rename
+    pub fn undo_last_operation(&mut self) {
+        match self.undo_journal.undo_last(1) {
+            Ok(undone) if undone.is_empty() => self.set_status("Nothing to undo"),
+            Ok(undone) => {
+                for entry in &undone {
+                    self.log(LogLevel::Info, format!("↶ Undid {}", entry.describe()));
+                }
+                self.set_status("Undid last operation");
+            }
+            Err(e) => {
+                self.log(LogLevel::Error, format!("Undo failed: {:#}", e));
+                self.set_status("Undo failed, see log");
+            }
+        }
+    }
+
+    /// Test every rule against `path`, selecting the first one that matches
+    pub fn start_rule_test(&mut self, path: PathBuf) {
+        let matches = matching_rule_indices(&self.config.rules, &path);
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        match matches.first() {
+            None => {
+                self.rule_test = None;
+                self.set_status(format!("No rules match {}", name));
+            }
+            Some(&first) => {
+                self.selected_rule = Some(first);
+                let count = matches.len();
+                self.rule_test = Some(RuleTest {
+                    path,
+                    matches,
+                    current: 0,
+                });
+                let rules = if count == 1 {
+                    "rule matches"
+                } else {
+                    "rules match"
+                };
+                self.set_status(format!(
+                    "{} {} {} (n/N to step, Esc to clear)",
+                    count, rules, name
+                ));
+            }
+        }
+    }
+
+    /// Select the next (or previous) rule matching the tested file, wrapping around
+    pub fn step_rule_test(&mut self, forward: bool) {
+        let Some(test) = self.rule_test.as_mut() else {
+            return;
+        };
+        let len = test.matches.len();
+        test.current = if forward {
+            (test.current + 1) % len
+        } else {
+            (test.current + len - 1) % len
+        };
+        self.selected_rule = Some(test.matches[test.current]);
+    }
+
+    /// Set a temporary status message, cleared after `status_timeout_secs`
     pub fn set_status(&mut self, message: impl Into<String>) {
         self.status_message = Some(message.into());
+        self.status_set_at = self.frame;
     }
 
     /// Clear the status message
@@ -375,6 +754,29 @@ impl AppState {
         self.status_message = None;
     }
 
+    /// Whether the status message has been up for `status_timeout_secs`
+    fn status_expired(&self) -> bool {
+        let timeout = self.config.general.status_timeout_secs;
+        timeout > 0
+            && self.frame.wrapping_sub(self.status_set_at)
+                >= timeout.saturating_mul(TICKS_PER_SECOND)
+    }
+
+    /// Show (or add to) the "files organized" toast
+    pub fn toast_organized(&mut self, count: usize) {
+        if count == 0 {
+            return;
+        }
+        let count = match self.toast {
+            Some(toast) => toast.count + count,
+            None => count,
+        };
+        self.toast = Some(Toast {
+            count,
+            started: self.frame,
+        });
+    }
+
     /// Navigate to the next view
     pub fn next_view(&mut self) {
         self.view = match self.view {
@@ -399,19 +801,178 @@ impl AppState {
     pub fn tick(&mut self) {
         self.frame = self.frame.wrapping_add(1);
 
+        if self.status_message.is_some() && self.status_expired() {
+            self.clear_status();
+        }
+        if self
+            .toast
+            .is_some_and(|toast| toast.fade(self.frame) >= 1.0)
+        {
+            self.toast = None;
+        }
+
+        // Pick up external config edits about once a second
+        if self.frame.is_multiple_of(10) {
+            self.reload_config_if_changed();
+        }
+
         // Refresh daemon logs every ~2 seconds (20 frames at 100ms poll)
         if self.frame.is_multiple_of(20) {
             self.load_daemon_logs();
+            if self.daemon_running {
+                self.refresh_daemon_health();
+            }
+        }
+    }
+
+    /// Start watching the config file at `path` for external edits
+    pub fn watch_config_file(&mut self, path: Option<PathBuf>) {
+        self.config_path = path;
+        self.remember_config_mtime();
+    }
+
+    /// Record the config file's current modification time, so our own saves
+    /// aren't mistaken for external edits
+    pub fn remember_config_mtime(&mut self) {
+        self.config_mtime = self.config_path.as_deref().and_then(file_mtime);
+    }
+
+    /// Reload the config if the file changed on disk.
+    ///
+    /// Waits while a dialog or editor is open so nothing changes underneath it.
+    pub fn reload_config_if_changed(&mut self) {
+        if self.mode != Mode::Normal {
+            return;
+        }
+        let Some(path) = self.config_path.clone() else {
+            return;
+        };
+        let mtime = file_mtime(&path);
+        if mtime == self.config_mtime {
+            return;
+        }
+        self.config_mtime = mtime;
+        // A missing file would load as the default config; keep what we have
+        if mtime.is_none() {
+            return;
+        }
+
+        match Config::load(Some(&path)) {
+            Ok(config) => {
+                self.apply_config(config);
+                self.set_status("Config reloaded");
+            }
+            Err(e) => self.set_status(format!("Config reload failed: {}", e)),
+        }
+    }
+
+    /// Reload the config from disk now, whether or not it changed
+    pub fn reload_config(&mut self) {
+        let Some(path) = self.config_path.clone() else {
+            self.set_status("No config file to reload");
+            return;
+        };
+        if file_mtime(&path).is_none() {
+            self.set_status(format!("Config file not found: {}", path.display()));
+            return;
+        }
+
+        match Config::load(Some(&path)) {
+            Ok(config) => {
+                self.remember_config_mtime();
+                self.apply_config(config);
+                self.set_status(format!("Config reloaded from {}", path.display()));
+            }
+            Err(e) => self.set_status(format!("Config reload failed: {}", e)),
+        }
+    }
+
+    /// Replace the config with one reloaded from disk, applying a changed theme right away
+    pub fn apply_config(&mut self, config: Config) {
+        let theme = Theme::load(&config);
+        if theme != self.theme {
+            self.theme = theme;
+            self.theme_picker_index = Theme::all()
+                .iter()
+                .position(|t| *t == theme.inner())
+                .unwrap_or(0);
+            self.log(LogLevel::Info, format!("Theme changed to {}", theme.name()));
+        }
+
+        self.config = config;
+        self.rules_enabled_before_toggle = None;
+        // Match indices refer to the old rule list
+        self.rule_test = None;
+        let rules = self.config.rules.len();
+        let watches = self.config.watches.len();
+        self.selected_rule = self
+            .selected_rule
+            .and_then(|i| rules.checked_sub(1).map(|last| i.min(last)));
+        self.selected_watch = self
+            .selected_watch
+            .and_then(|i| watches.checked_sub(1).map(|last| i.min(last)));
+
+        // Restart the embedded watcher so it picks up changed rules and watches
+        if !self.daemon_running {
+            self.watcher_needs_restart = true;
+        }
+    }
+
+    /// Files handled in a watch folder so far, if the embedded watcher is
+    /// running it
+    pub fn watch_stats_for(
+        &self,
+        watch: &crate::config::WatchConfig,
+    ) -> Option<crate::watcher::WatchStats> {
+        self.watch_stats
+            .get(&crate::expand_path(&watch.path))
+            .copied()
+    }
+
+    /// Fetch the last error and activity time from the daemon over IPC
+    pub fn refresh_daemon_health(&mut self) {
+        if let Ok(crate::ipc::DaemonResponse::Status {
+            last_error,
+            last_event_at,
+            ..
+        }) = crate::ipc::send_command(&crate::ipc::DaemonCommand::Status)
+        {
+            self.last_error = last_error;
+            self.last_event_at = last_event_at;
         }
     }
 }
 
+/// Result of testing the rules against a file from the Rules view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleTest {
+    /// File the rules were tested against
+    pub path: PathBuf,
+    /// Indices of the rules whose conditions match, in rule order (never empty)
+    pub matches: Vec<usize>,
+    /// Position in `matches` of the selected rule
+    pub current: usize,
+}
+
+/// Indices of every rule whose condition matches `path`, enabled or not, so
+/// rule precedence can be inspected. Conditions that fail to evaluate count
+/// as not matching.
+pub fn matching_rule_indices(rules: &[Rule], path: &std::path::Path) -> Vec<usize> {
+    rules
+        .iter()
+        .enumerate()
+        .filter(|(_, rule)| rule.condition.matches(path).unwrap_or(false))
+        .map(|(i, _)| i)
+        .collect()
+}
+
 /// Fields in the rule editor
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum RuleEditorField {
     #[default]
     Name,
     Enabled,
+    StopProcessing,
     // Conditions
     Extension,
     NameGlob,
@@ -425,6 +986,8 @@ pub enum RuleEditorField {
     // Action
     ActionType,
     ActionDestination,
+    ActionOverwrite,
+    ActionDeleteOriginal,
     ActionPattern,
     ActionCommand,
     ActionArgs,
@@ -435,7 +998,8 @@ impl RuleEditorField {
     pub fn next(self) -> Self {
         match self {
             Self::Name => Self::Enabled,
-            Self::Enabled => Self::Extension,
+            Self::Enabled => Self::StopProcessing,
+            Self::StopProcessing => Self::Extension,
             Self::Extension => Self::NameGlob,
             Self::NameGlob => Self::NameRegex,
             Self::NameRegex => Self::SizeGreater,
@@ -446,7 +1010,9 @@ impl RuleEditorField {
             Self::IsDirectory => Self::IsHidden,
             Self::IsHidden => Self::ActionType,
             Self::ActionType => Self::ActionDestination,
-            Self::ActionDestination => Self::ActionPattern,
+            Self::ActionDestination => Self::ActionOverwrite,
+            Self::ActionOverwrite => Self::ActionDeleteOriginal,
+            Self::ActionDeleteOriginal => Self::ActionPattern,
             Self::ActionPattern => Self::ActionCommand,
             Self::ActionCommand => Self::ActionArgs,
             Self::ActionArgs => Self::Name,
@@ -458,7 +1024,8 @@ impl RuleEditorField {
         match self {
             Self::Name => Self::ActionArgs,
             Self::Enabled => Self::Name,
-            Self::Extension => Self::Enabled,
+            Self::StopProcessing => Self::Enabled,
+            Self::Extension => Self::StopProcessing,
             Self::NameGlob => Self::Extension,
             Self::NameRegex => Self::NameGlob,
             Self::SizeGreater => Self::NameRegex,
@@ -469,7 +1036,9 @@ impl RuleEditorField {
             Self::IsHidden => Self::IsDirectory,
             Self::ActionType => Self::IsHidden,
             Self::ActionDestination => Self::ActionType,
-            Self::ActionPattern => Self::ActionDestination,
+            Self::ActionOverwrite => Self::ActionDestination,
+            Self::ActionDeleteOriginal => Self::ActionOverwrite,
+            Self::ActionPattern => Self::ActionDeleteOriginal,
             Self::ActionCommand => Self::ActionPattern,
             Self::ActionArgs => Self::ActionCommand,
         }
@@ -589,6 +1158,9 @@ pub struct WatchEditorState {
     /// Selected rule names (empty = all rules apply)
     pub rules_filter: Vec<String>,
 
+    /// Kept from the watch being edited; not shown in the editor
+    pub follow_symlinks: bool,
+
     /// All available rule names (for display in selector)
     pub available_rules: Vec<String>,
 
@@ -608,6 +1180,7 @@ impl WatchEditorState {
             path: String::new(),
             recursive: false,
             rules_filter: Vec::new(),
+            follow_symlinks: false,
             available_rules,
             rules_cursor: 0,
             cursor_path: 0,
@@ -629,6 +1202,7 @@ impl WatchEditorState {
             path,
             recursive: watch.recursive,
             rules_filter: watch.rules.clone(),
+            follow_symlinks: watch.follow_symlinks,
             available_rules,
             rules_cursor,
             cursor_path,
@@ -655,6 +1229,7 @@ impl WatchEditorState {
             path: std::path::PathBuf::from(&self.path),
             recursive: self.recursive,
             rules: self.rules_filter.clone(),
+            follow_symlinks: self.follow_symlinks,
         }
     }
 }
@@ -668,10 +1243,26 @@ pub struct RuleEditorState {
     /// Rule index being edited (None if adding new)
     pub editing_index: Option<usize>,
 
+    /// The rule as it was when the editor opened (None if adding new)
+    pub original: Option<Rule>,
+
+    /// Esc was pressed with unsaved changes; a second Esc discards them
+    pub confirm_discard: bool,
+
     // Basic fields
     pub name: String,
     pub enabled: bool,
     pub stop_processing: bool,
+    /// Kept from the rule being edited; not shown in the editor
+    pub cooldown_secs: Option<u64>,
+    /// Kept from the rule being edited; not shown in the editor
+    pub on_error: Option<crate::rules::OnError>,
+    /// Kept from the rule being edited; not shown in the editor
+    pub on_startup_scan: bool,
+    /// Kept from the rule being edited; not shown in the editor
+    pub budget: Option<crate::rules::FolderBudget>,
+    /// Kept from the rule being edited; not shown in the editor
+    pub quiet: bool,
 
     // Condition fields
     pub extension: String,
@@ -709,14 +1300,98 @@ pub struct RuleEditorState {
 }
 
 impl RuleEditorState {
+    /// Whether a field is shown for the current action type
+    pub fn shows_field(&self, field: RuleEditorField) -> bool {
+        match field {
+            RuleEditorField::ActionOverwrite => matches!(
+                self.action_type,
+                ActionTypeSelection::Move | ActionTypeSelection::Copy | ActionTypeSelection::Rename
+            ),
+            RuleEditorField::ActionDeleteOriginal => {
+                self.action_type == ActionTypeSelection::Archive
+            }
+            RuleEditorField::ActionArgs => self.action_type == ActionTypeSelection::Run,
+            _ => true,
+        }
+    }
+
+    /// Focus the next shown field in tab order
+    pub fn focus_next(&mut self) {
+        self.field = self.field.next();
+        while !self.shows_field(self.field) {
+            self.field = self.field.next();
+        }
+    }
+
+    /// Focus the previous shown field in tab order
+    pub fn focus_prev(&mut self) {
+        self.field = self.field.prev();
+        while !self.shows_field(self.field) {
+            self.field = self.field.prev();
+        }
+    }
+
     /// Create a new empty editor state for adding a rule
     pub fn new_rule() -> Self {
         Self {
             enabled: true,
+            on_startup_scan: true,
             ..Default::default()
         }
     }
 
+    /// Fields whose value differs from the saved rule (or from a blank rule
+    /// when adding one), in tab order
+    pub fn changed_fields(&self) -> Vec<RuleEditorField> {
+        let saved = match self.original {
+            Some(ref rule) => Self::from_rule(self.editing_index.unwrap_or_default(), rule),
+            None => Self::new_rule(),
+        };
+        let differs = |field: RuleEditorField| match field {
+            RuleEditorField::Name => self.name != saved.name,
+            RuleEditorField::Enabled => self.enabled != saved.enabled,
+            RuleEditorField::StopProcessing => self.stop_processing != saved.stop_processing,
+            RuleEditorField::Extension => self.extension != saved.extension,
+            RuleEditorField::NameGlob => self.name_glob != saved.name_glob,
+            RuleEditorField::NameRegex => self.name_regex != saved.name_regex,
+            RuleEditorField::SizeGreater => self.size_greater != saved.size_greater,
+            RuleEditorField::SizeLess => self.size_less != saved.size_less,
+            RuleEditorField::AgeGreater => self.age_greater != saved.age_greater,
+            RuleEditorField::AgeLess => self.age_less != saved.age_less,
+            RuleEditorField::IsDirectory => self.is_directory != saved.is_directory,
+            RuleEditorField::IsHidden => self.is_hidden != saved.is_hidden,
+            RuleEditorField::ActionType => self.action_type != saved.action_type,
+            RuleEditorField::ActionDestination => {
+                self.action_destination != saved.action_destination
+            }
+            RuleEditorField::ActionOverwrite => self.action_overwrite != saved.action_overwrite,
+            RuleEditorField::ActionDeleteOriginal => {
+                self.action_delete_original != saved.action_delete_original
+            }
+            RuleEditorField::ActionPattern => self.action_pattern != saved.action_pattern,
+            RuleEditorField::ActionCommand => self.action_command != saved.action_command,
+            RuleEditorField::ActionArgs => self.action_args != saved.action_args,
+        };
+
+        let mut changed = Vec::new();
+        let mut field = RuleEditorField::Name;
+        loop {
+            if self.shows_field(field) && differs(field) {
+                changed.push(field);
+            }
+            field = field.next();
+            if field == RuleEditorField::Name {
+                break;
+            }
+        }
+        changed
+    }
+
+    /// Whether anything differs from the saved rule
+    pub fn is_dirty(&self) -> bool {
+        !self.changed_fields().is_empty()
+    }
+
     /// Create editor state from an existing rule
     pub fn from_rule(index: usize, rule: &Rule) -> Self {
         let (
@@ -754,25 +1429,28 @@ impl RuleEditorState {
                 *overwrite,
                 false,
             ),
-            Action::Rename { pattern } => (
+            Action::Rename { pattern, overwrite } => (
                 ActionTypeSelection::Rename,
                 String::new(),
                 pattern.clone(),
                 String::new(),
                 String::new(),
-                false,
+                *overwrite,
                 false,
             ),
-            Action::Trash => (
+            Action::Trash { destination } => (
                 ActionTypeSelection::Trash,
-                String::new(),
+                destination
+                    .as_ref()
+                    .map(|d| d.display().to_string())
+                    .unwrap_or_default(),
                 String::new(),
                 String::new(),
                 String::new(),
                 false,
                 false,
             ),
-            Action::Delete => (
+            Action::Delete { .. } => (
                 ActionTypeSelection::Delete,
                 String::new(),
                 String::new(),
@@ -816,13 +1494,27 @@ impl RuleEditorState {
             ),
         };
 
+        // A list of extensions is shown comma-separated in the one field
+        let extension = match rule.condition.extension {
+            Some(ref ext) => ext.clone(),
+            None => rule.condition.extensions.join(","),
+        };
+
         Self {
             field: RuleEditorField::Name,
             editing_index: Some(index),
+            original: Some(rule.clone()),
+            confirm_discard: false,
             name: rule.name.clone(),
             enabled: rule.enabled,
             stop_processing: rule.stop_processing,
-            extension: rule.condition.extension.clone().unwrap_or_default(),
+            cooldown_secs: rule.cooldown_secs,
+            on_error: rule.on_error.clone(),
+            on_startup_scan: rule.on_startup_scan,
+            budget: rule.budget.clone(),
+            quiet: rule.quiet,
+            cursor_extension: extension.len(),
+            extension,
             name_glob: rule.condition.name_matches.clone().unwrap_or_default(),
             name_regex: rule.condition.name_regex.clone().unwrap_or_default(),
             size_greater: rule
@@ -856,12 +1548,6 @@ impl RuleEditorState {
             action_delete_original,
             // Set cursor positions to end of each field
             cursor_name: rule.name.len(),
-            cursor_extension: rule
-                .condition
-                .extension
-                .as_ref()
-                .map(|s| s.len())
-                .unwrap_or(0),
             cursor_name_glob: rule
                 .condition
                 .name_matches
@@ -903,47 +1589,125 @@ impl RuleEditorState {
 
     /// Build a Rule from the editor state
     pub fn to_rule(&self) -> Rule {
-        let condition = Condition {
-            extension: if self.extension.is_empty() {
-                None
-            } else {
-                Some(self.extension.clone())
-            },
-            extensions: Vec::new(),
-            name_matches: if self.name_glob.is_empty() {
-                None
-            } else {
-                Some(self.name_glob.clone())
-            },
-            name_regex: if self.name_regex.is_empty() {
-                None
-            } else {
-                Some(self.name_regex.clone())
-            },
-            size_greater_than: self.size_greater.parse().ok(),
-            size_less_than: self.size_less.parse().ok(),
-            age_days_greater_than: self.age_greater.parse().ok(),
-            age_days_less_than: self.age_less.parse().ok(),
-            is_directory: self.is_directory,
-            is_hidden: self.is_hidden,
+        // One extension sets `extension`, a comma-separated list `extensions`
+        let mut extensions: Vec<String> = self
+            .extension
+            .split(',')
+            .map(str::trim)
+            .filter(|ext| !ext.is_empty())
+            .map(String::from)
+            .collect();
+        let extension = if extensions.len() == 1 {
+            extensions.pop()
+        } else {
+            None
         };
+        // A rule with both shows only `extension` in the field; its list is
+        // kept unless the field now holds a list of its own
+        if let Some(ref original) = self.original
+            && original.condition.extension.is_some()
+            && extensions.is_empty()
+        {
+            extensions = original.condition.extensions.clone();
+        }
+
+        // Conditions the editor doesn't show are kept from the rule being edited
+        let mut condition = self
+            .original
+            .as_ref()
+            .map(|r| r.condition.clone())
+            .unwrap_or_default();
+        condition.extension = extension;
+        condition.extensions = extensions;
+        condition.name_matches = if self.name_glob.is_empty() {
+            None
+        } else {
+            Some(self.name_glob.clone())
+        };
+        condition.name_regex = if self.name_regex.is_empty() {
+            None
+        } else {
+            Some(self.name_regex.clone())
+        };
+        condition.size_greater_than = self.size_greater.parse().ok();
+        condition.size_less_than = self.size_less.parse().ok();
+        condition.age_days_greater_than = self.age_greater.parse().ok();
+        condition.age_days_less_than = self.age_less.parse().ok();
+        condition.is_directory = self.is_directory;
+        condition.is_hidden = self.is_hidden;
+
+        // Not shown in the editor; kept from the rule being edited
+        let (create_destination, preserve_structure, skip_if_open, skip_if_identical) =
+            match self.original.as_ref().map(|r| &r.action) {
+                Some(
+                    Action::Move {
+                        create_destination,
+                        preserve_structure,
+                        skip_if_open,
+                        skip_if_identical,
+                        ..
+                    }
+                    | Action::Copy {
+                        create_destination,
+                        preserve_structure,
+                        skip_if_open,
+                        skip_if_identical,
+                        ..
+                    },
+                ) => (
+                    *create_destination,
+                    *preserve_structure,
+                    *skip_if_open,
+                    *skip_if_identical,
+                ),
+                _ => (true, false, false, false),
+            };
+        let preserve_timestamps = matches!(
+            self.original.as_ref().map(|r| &r.action),
+            Some(Action::Copy {
+                preserve_timestamps: true,
+                ..
+            })
+        );
 
         let action = match self.action_type {
             ActionTypeSelection::Move => Action::Move {
                 destination: PathBuf::from(&self.action_destination),
-                create_destination: true,
+                create_destination,
                 overwrite: self.action_overwrite,
+                preserve_structure,
+                skip_if_open,
+                skip_if_identical,
             },
             ActionTypeSelection::Copy => Action::Copy {
                 destination: PathBuf::from(&self.action_destination),
-                create_destination: true,
+                create_destination,
                 overwrite: self.action_overwrite,
+                preserve_structure,
+                skip_if_open,
+                skip_if_identical,
+                preserve_timestamps,
             },
             ActionTypeSelection::Rename => Action::Rename {
                 pattern: self.action_pattern.clone(),
+                overwrite: self.action_overwrite,
+            },
+            ActionTypeSelection::Trash => Action::Trash {
+                destination: if self.action_destination.is_empty() {
+                    None
+                } else {
+                    Some(PathBuf::from(&self.action_destination))
+                },
+            },
+            // The acknowledgment is only ever set by hand in the config file
+            ActionTypeSelection::Delete => Action::Delete {
+                i_understand_delete_is_permanent: matches!(
+                    self.original.as_ref().map(|r| &r.action),
+                    Some(Action::Delete {
+                        i_understand_delete_is_permanent: true
+                    })
+                ),
             },
-            ActionTypeSelection::Trash => Action::Trash,
-            ActionTypeSelection::Delete => Action::Delete,
             ActionTypeSelection::Run => Action::Run {
                 command: self.action_command.clone(),
                 args: shlex::split(&self.action_args).unwrap_or_else(|| {
@@ -970,6 +1734,11 @@ impl RuleEditorState {
             condition,
             action,
             stop_processing: self.stop_processing,
+            cooldown_secs: self.cooldown_secs,
+            on_error: self.on_error.clone(),
+            on_startup_scan: self.on_startup_scan,
+            budget: self.budget.clone(),
+            quiet: self.quiet,
         }
     }
 }
@@ -981,6 +1750,15 @@ fn strip_ansi_codes(s: &str) -> String {
     ANSI_RE.replace_all(s, "").to_string()
 }
 
+/// A daemon log line written with `general.json_logs`
+#[derive(Deserialize)]
+struct JsonLogLine {
+    timestamp: String,
+    level: String,
+    #[serde(default)]
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
 /// Parse a daemon log line into a LogEntry
 fn parse_daemon_log_line(line: &str) -> Option<LogEntry> {
     // Format: 2026-02-04T20:12:37.235953Z  INFO message
@@ -988,6 +1766,9 @@ fn parse_daemon_log_line(line: &str) -> Option<LogEntry> {
     if line.is_empty() {
         return None;
     }
+    if line.starts_with('{') {
+        return parse_json_log_line(line);
+    }
 
     // Find timestamp and level using split_whitespace (handles multiple spaces
     // in tracing output like "2026-02-04T20:12:37Z  INFO message")
@@ -1018,3 +1799,766 @@ fn parse_daemon_log_line(line: &str) -> Option<LogEntry> {
         rule: None,
     })
 }
+
+/// Parse a JSON daemon log line (see `crate::logging`) into a LogEntry
+fn parse_json_log_line(line: &str) -> Option<LogEntry> {
+    let parsed: JsonLogLine = serde_json::from_str(line).ok()?;
+    let timestamp = chrono::DateTime::parse_from_rfc3339(&parsed.timestamp)
+        .map(|dt| dt.with_timezone(&chrono::Local))
+        .unwrap_or_else(|_| chrono::Local::now());
+    let level = match parsed.level.to_uppercase().as_str() {
+        "WARN" | "WARNING" => LogLevel::Warning,
+        "ERROR" => LogLevel::Error,
+        _ => LogLevel::Info,
+    };
+    let message = match parsed.fields.get("message") {
+        Some(serde_json::Value::String(message)) => message.clone(),
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+
+    Some(LogEntry {
+        timestamp,
+        level,
+        message,
+        file: None,
+        rule: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Condition;
+
+    #[test]
+    fn test_toggle_all_rules_restores_prior_states() {
+        let mut config = Config::default();
+        config.rules = ["A", "B", "C"]
+            .iter()
+            .map(|name| Rule::new(*name, Condition::default(), Action::Nothing))
+            .collect();
+        config.rules[1].enabled = false;
+        let mut state = AppState::new(config, Theme::default());
+
+        assert!(state.toggle_all_rules());
+        assert!(state.config.rules.iter().all(|r| !r.enabled));
+
+        // A rule added while everything is off keeps its own state
+        state
+            .config
+            .rules
+            .push(Rule::new("D", Condition::default(), Action::Nothing));
+
+        assert!(!state.toggle_all_rules());
+        let enabled: Vec<bool> = state.config.rules.iter().map(|r| r.enabled).collect();
+        assert_eq!(enabled, vec![true, false, true, true]);
+        assert!(state.rules_enabled_before_toggle.is_none());
+
+        // The next toggle disables everything again
+        assert!(state.toggle_all_rules());
+        assert!(state.config.rules.iter().all(|r| !r.enabled));
+    }
+
+    #[test]
+    fn test_changed_theme_slug_is_applied() {
+        let mut state = AppState::new(Config::default(), Theme::default());
+
+        let mut config = Config::default();
+        config.general.theme = Some("nord".to_string());
+        state.apply_config(config);
+        assert_eq!(state.theme, Theme::from(ratatui_themes::ThemeName::Nord));
+        assert_eq!(
+            Theme::all()[state.theme_picker_index],
+            ratatui_themes::ThemeName::Nord
+        );
+
+        let mut config = Config::default();
+        config.general.theme = Some("dracula".to_string());
+        state.apply_config(config);
+        assert_eq!(state.theme, Theme::from(ratatui_themes::ThemeName::Dracula));
+    }
+
+    #[test]
+    fn test_external_config_edit_reloads_theme() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[general]\ntheme = \"dracula\"\n").unwrap();
+
+        let mut state = AppState::new(Config::load(Some(&path)).unwrap(), Theme::default());
+        state.watch_config_file(Some(path.clone()));
+        state.reload_config_if_changed();
+        assert_eq!(state.theme, Theme::from(ratatui_themes::ThemeName::Dracula));
+
+        std::fs::write(&path, "[general]\ntheme = \"nord\"\n").unwrap();
+        // Make sure the edit is visible even on coarse-mtime filesystems
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+
+        // Not while a dialog is open
+        state.mode = Mode::Help;
+        state.reload_config_if_changed();
+        assert_eq!(state.theme, Theme::from(ratatui_themes::ThemeName::Dracula));
+
+        state.mode = Mode::Normal;
+        state.reload_config_if_changed();
+        assert_eq!(state.theme, Theme::from(ratatui_themes::ThemeName::Nord));
+        assert_eq!(state.config.general.theme.as_deref(), Some("nord"));
+    }
+
+    #[test]
+    fn test_action_args_shown_and_reachable_only_for_run() {
+        let mut editor = RuleEditorState {
+            field: RuleEditorField::ActionCommand,
+            ..RuleEditorState::new_rule()
+        };
+        assert!(!editor.shows_field(RuleEditorField::ActionArgs));
+        editor.focus_next();
+        assert_eq!(editor.field, RuleEditorField::Name);
+        editor.focus_prev();
+        assert_eq!(editor.field, RuleEditorField::ActionCommand);
+
+        editor.action_type = ActionTypeSelection::Run;
+        assert!(editor.shows_field(RuleEditorField::ActionArgs));
+        editor.focus_next();
+        assert_eq!(editor.field, RuleEditorField::ActionArgs);
+        editor.focus_next();
+        assert_eq!(editor.field, RuleEditorField::Name);
+        editor.focus_prev();
+        assert_eq!(editor.field, RuleEditorField::ActionArgs);
+    }
+
+    #[test]
+    fn test_action_args_round_trip_through_editor() {
+        let rule = Rule::new(
+            "Shrink",
+            Condition::default(),
+            Action::Run {
+                command: "convert".to_string(),
+                args: vec![
+                    "{path}".to_string(),
+                    "-resize".to_string(),
+                    "50%".to_string(),
+                    "my file.png".to_string(),
+                ],
+            },
+        );
+
+        let editor = RuleEditorState::from_rule(0, &rule);
+        assert_eq!(editor.action_type, ActionTypeSelection::Run);
+        assert!(editor.action_args.contains("-resize"));
+
+        match editor.to_rule().action {
+            Action::Run { command, args } => {
+                assert_eq!(command, "convert");
+                assert_eq!(
+                    args,
+                    vec!["{path}", "-resize", "50%", "my file.png"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect::<Vec<_>>()
+                );
+            }
+            other => panic!("expected a run action, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_editor_checkboxes_reach_the_rule() {
+        let mut editor = RuleEditorState {
+            name: "Docs".to_string(),
+            action_destination: "/tmp/docs".to_string(),
+            ..RuleEditorState::new_rule()
+        };
+        assert!(editor.shows_field(RuleEditorField::ActionOverwrite));
+        assert!(!editor.shows_field(RuleEditorField::ActionDeleteOriginal));
+
+        editor.stop_processing = true;
+        editor.action_overwrite = true;
+        let rule = editor.to_rule();
+        assert!(rule.stop_processing);
+        assert!(matches!(
+            rule.action,
+            Action::Move {
+                overwrite: true,
+                ..
+            }
+        ));
+
+        editor.action_type = ActionTypeSelection::Rename;
+        assert!(editor.shows_field(RuleEditorField::ActionOverwrite));
+        assert!(matches!(
+            editor.to_rule().action,
+            Action::Rename {
+                overwrite: true,
+                ..
+            }
+        ));
+
+        editor.action_type = ActionTypeSelection::Archive;
+        assert!(!editor.shows_field(RuleEditorField::ActionOverwrite));
+        assert!(editor.shows_field(RuleEditorField::ActionDeleteOriginal));
+        editor.action_delete_original = true;
+        assert!(matches!(
+            editor.to_rule().action,
+            Action::Archive {
+                delete_original: true,
+                ..
+            }
+        ));
+
+        // And back again when editing the saved rule
+        let reopened = RuleEditorState::from_rule(0, &editor.to_rule());
+        assert!(reopened.stop_processing);
+        assert!(reopened.action_delete_original);
+    }
+
+    #[test]
+    fn test_colors_follow_high_contrast_setting() {
+        let mut state = AppState::new(Config::default(), Theme::default());
+        assert_eq!(
+            state.colors().text_muted(),
+            state.theme.colors().text_muted()
+        );
+
+        state.config.general.high_contrast = true;
+        let contrast = state.theme.colors().high_contrast();
+        assert_eq!(state.colors().text_muted(), contrast.text_muted());
+        assert_ne!(state.colors().block(), state.theme.colors().block());
+    }
+
+    #[test]
+    fn test_daemon_log_stream_resumes_from_cursor() {
+        use crate::ipc::{DaemonResponse, LogBuffer};
+
+        let mut state = AppState::new(Config::default(), Theme::default());
+        let before = state.log_entries.len();
+        let mut buffer = LogBuffer::new(3, 1);
+        let poll = |state: &mut AppState, buffer: &LogBuffer| match buffer
+            .since(state.daemon_log_cursor, state.daemon_log_epoch)
+        {
+            DaemonResponse::LogTail {
+                entries,
+                missed,
+                epoch,
+            } => state.append_daemon_log(entries, missed, epoch),
+            other => panic!("unexpected response: {:?}", other),
+        };
+
+        buffer.push(crate::ipc::LogEntry::message("one"));
+        buffer.push(crate::ipc::LogEntry::error("two"));
+        poll(&mut state, &buffer);
+        poll(&mut state, &buffer);
+        assert_eq!(state.daemon_log_cursor, 2);
+        let messages: Vec<_> = state
+            .log_entries
+            .iter()
+            .skip(before)
+            .map(|e| e.message.as_str())
+            .collect();
+        assert_eq!(messages, vec!["one", "two"]);
+        assert_eq!(state.log_entries.back().unwrap().level, LogLevel::Error);
+
+        // Four more arrive between polls; one falls out of the buffer first
+        for msg in ["three", "four", "five", "six"] {
+            buffer.push(crate::ipc::LogEntry::message(msg));
+        }
+        poll(&mut state, &buffer);
+        let messages: Vec<_> = state
+            .log_entries
+            .iter()
+            .skip(before + 2)
+            .map(|e| e.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "1 daemon log entries were dropped before they could be shown",
+                "four",
+                "five",
+                "six"
+            ]
+        );
+        assert_eq!(state.daemon_log_cursor, 6);
+
+        // The daemon restarts and logs more than the old cursor before the next poll
+        let mut buffer = LogBuffer::new(10, 2);
+        for msg in ["a", "b", "c", "d", "e", "f", "g"] {
+            buffer.push(crate::ipc::LogEntry::message(msg));
+        }
+        let len = state.log_entries.len();
+        poll(&mut state, &buffer);
+        assert_eq!(state.log_entries.len(), len + 7);
+        assert_eq!(state.log_entries.back().unwrap().message, "g");
+        assert_eq!((state.daemon_log_cursor, state.daemon_log_epoch), (7, 2));
+    }
+
+    #[test]
+    fn test_undo_last_operation_restores_file() {
+        use crate::rules::{JournalEntry, JournalOp, UndoJournal};
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = AppState::new(Config::default(), Theme::default());
+        state.undo_journal = UndoJournal::new(dir.path().join("undo.jsonl"));
+
+        state.undo_last_operation();
+        assert_eq!(state.status_message.as_deref(), Some("Nothing to undo"));
+
+        let original = dir.path().join("report.pdf");
+        let moved = dir.path().join("Documents").join("report.pdf");
+        std::fs::create_dir_all(moved.parent().unwrap()).unwrap();
+        std::fs::write(&moved, "pdf").unwrap();
+        state
+            .undo_journal
+            .append(&JournalEntry::new(
+                "PDFs",
+                JournalOp::Move,
+                original.clone(),
+                moved.clone(),
+            ))
+            .unwrap();
+
+        state.undo_last_operation();
+        assert!(original.exists());
+        assert!(!moved.exists());
+        assert!(
+            state
+                .log_entries
+                .back()
+                .unwrap()
+                .message
+                .starts_with("↶ Undid")
+        );
+    }
+
+    #[test]
+    fn test_status_and_toast_expire_with_frames() {
+        let mut state = AppState::new(Config::default(), Theme::default());
+        for _ in 0..5 {
+            state.tick();
+        }
+        state.set_status("Cancelled");
+        for _ in 0..4 * TICKS_PER_SECOND - 1 {
+            state.tick();
+        }
+        assert_eq!(state.status_message.as_deref(), Some("Cancelled"));
+        state.tick();
+        assert!(state.status_message.is_none());
+
+        // A toast keeps counting while it's up and restarts its fade
+        state.toast_organized(2);
+        for _ in 0..10 {
+            state.tick();
+        }
+        let toast = state.toast.unwrap();
+        assert!(toast.fade(state.frame) > 0.0 && toast.fade(state.frame) < 1.0);
+        state.toast_organized(3);
+        let toast = state.toast.unwrap();
+        assert_eq!(toast.count, 5);
+        assert_eq!(toast.fade(state.frame), 0.0);
+        for _ in 0..TOAST_TICKS {
+            state.tick();
+        }
+        assert!(state.toast.is_none());
+        state.toast_organized(0);
+        assert!(state.toast.is_none());
+    }
+
+    #[test]
+    fn test_status_timeout_is_configurable() {
+        let mut config = Config::default();
+        config.general.status_timeout_secs = 1;
+        let mut state = AppState::new(config, Theme::default());
+        for _ in 0..7 {
+            state.tick();
+        }
+        state.set_status("Saved");
+        assert_eq!(state.status_set_at, 7);
+        for _ in 0..TICKS_PER_SECOND - 1 {
+            state.tick();
+        }
+        assert!(state.status_message.is_some());
+        state.tick();
+        assert!(state.status_message.is_none());
+
+        // 0 keeps the message until something replaces it
+        state.config.general.status_timeout_secs = 0;
+        state.set_status("Sticky");
+        for _ in 0..100 {
+            state.tick();
+        }
+        assert_eq!(state.status_message.as_deref(), Some("Sticky"));
+    }
+
+    #[test]
+    fn test_visible_log_range_follows_scroll() {
+        let mut state = AppState::new(Config::default(), Theme::default());
+        state.log_entries.clear();
+        assert_eq!(state.visible_log_range(10), 0..0);
+        for i in 0..100 {
+            state.log(LogLevel::Info, format!("entry {}", i));
+        }
+
+        assert_eq!(state.visible_log_range(10), 0..10);
+        assert_eq!(state.visible_log_range(0), 0..0);
+
+        // Scrolling past the bottom edge moves the window just enough
+        state.log_scroll = 15;
+        assert_eq!(state.visible_log_range(10), 6..16);
+        // Moving within the window leaves it in place
+        state.log_scroll = 8;
+        assert_eq!(state.visible_log_range(10), 6..16);
+        // Scrolling above the top edge moves it back up
+        state.log_scroll = 3;
+        assert_eq!(state.visible_log_range(10), 3..13);
+
+        state.log_scroll = 99;
+        assert_eq!(state.visible_log_range(10), 90..100);
+        // A taller viewport shows more rows instead of blank space
+        assert_eq!(state.visible_log_range(40), 60..100);
+
+        // A shrunken log keeps the window inside it
+        state.log_entries.truncate(5);
+        assert_eq!(state.visible_log_range(10), 0..5);
+    }
+
+    #[test]
+    fn test_manual_reload_reads_changed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[general]\nlog_level = \"info\"\n").unwrap();
+
+        let mut state = AppState::new(Config::load(Some(&path)).unwrap(), Theme::default());
+        state.reload_config();
+        assert_eq!(
+            state.status_message.as_deref(),
+            Some("No config file to reload")
+        );
+
+        state.watch_config_file(Some(path.clone()));
+        std::fs::write(
+            &path,
+            "[general]\ntheme = \"nord\"\n\n[[watch]]\npath = \"/tmp/in\"\n",
+        )
+        .unwrap();
+        state.watcher_needs_restart = false;
+        state.reload_config();
+
+        assert_eq!(state.config.watches.len(), 1);
+        assert_eq!(state.theme, Theme::from(ratatui_themes::ThemeName::Nord));
+        assert!(state.watcher_needs_restart || state.daemon_running);
+        assert!(
+            state
+                .status_message
+                .as_deref()
+                .is_some_and(|m| m.starts_with("Config reloaded"))
+        );
+        // The automatic check doesn't reload the same edit again
+        state.clear_status();
+        state.reload_config_if_changed();
+        assert!(state.status_message.is_none());
+
+        std::fs::write(&path, "not = [valid").unwrap();
+        state.reload_config();
+        assert_eq!(state.config.watches.len(), 1);
+        assert!(
+            state
+                .status_message
+                .as_deref()
+                .is_some_and(|m| m.starts_with("Config reload failed"))
+        );
+    }
+
+    #[test]
+    fn test_parse_json_daemon_log_line() {
+        let line = r#"{"timestamp":"2026-02-04T20:12:37.235953Z","level":"ERROR","target":"hazelnutd","fields":{"message":"Failed on 2026-01-01T00:00:00Z  WARN {odd} name.txt","path":"/x"}}"#;
+        let entry = parse_daemon_log_line(line).unwrap();
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(
+            entry.message,
+            "Failed on 2026-01-01T00:00:00Z  WARN {odd} name.txt"
+        );
+        assert_eq!(
+            entry.timestamp,
+            chrono::DateTime::parse_from_rfc3339("2026-02-04T20:12:37.235953Z").unwrap()
+        );
+
+        let warn = parse_daemon_log_line(r#"{"timestamp":"x","level":"WARN","fields":{}}"#);
+        assert_eq!(warn.unwrap().level, LogLevel::Warning);
+        assert!(parse_daemon_log_line("{not json").is_none());
+
+        // Plain text lines still parse as before
+        let text = parse_daemon_log_line("2026-02-04T20:12:37Z  INFO Daemon running").unwrap();
+        assert_eq!(text.level, LogLevel::Info);
+        assert_eq!(text.message, "Daemon running");
+    }
+
+    #[test]
+    fn test_rule_editor_changed_fields() {
+        let rule = Rule::new(
+            "PDFs",
+            Condition {
+                extension: Some("pdf".to_string()),
+                ..Default::default()
+            },
+            Action::Move {
+                destination: PathBuf::from("/tmp/pdfs"),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+            },
+        );
+        let mut editor = RuleEditorState::from_rule(0, &rule);
+        assert_eq!(editor.original.as_ref().unwrap().name, "PDFs");
+        assert!(!editor.is_dirty());
+
+        editor.name.push('!');
+        editor.enabled = false;
+        editor.action_type = ActionTypeSelection::Copy;
+        assert_eq!(
+            editor.changed_fields(),
+            vec![
+                RuleEditorField::Name,
+                RuleEditorField::Enabled,
+                RuleEditorField::ActionType
+            ]
+        );
+
+        // Putting the values back clears the changes
+        editor.name = "PDFs".to_string();
+        editor.enabled = true;
+        editor.action_type = ActionTypeSelection::Move;
+        assert!(!editor.is_dirty());
+
+        // Fields hidden for the action type don't count
+        editor.action_args = "--flag".to_string();
+        assert!(!editor.is_dirty());
+
+        // A new rule is compared against a blank one
+        let mut editor = RuleEditorState::new_rule();
+        assert!(!editor.is_dirty());
+        editor.extension = "txt".to_string();
+        assert_eq!(editor.changed_fields(), vec![RuleEditorField::Extension]);
+    }
+
+    #[test]
+    fn test_rule_editor_extension_list_round_trip() {
+        let mut editor = RuleEditorState {
+            name: "Images".to_string(),
+            extension: "jpg, png,gif,".to_string(),
+            ..RuleEditorState::new_rule()
+        };
+        let rule = editor.to_rule();
+        assert_eq!(rule.condition.extension, None);
+        assert_eq!(rule.condition.extensions, vec!["jpg", "png", "gif"]);
+        let reopened = RuleEditorState::from_rule(0, &rule);
+        assert_eq!(reopened.extension, "jpg,png,gif");
+        assert_eq!(reopened.cursor_extension, reopened.extension.len());
+
+        // A single extension stays a single extension
+        editor.extension = "pdf".to_string();
+        let rule = editor.to_rule();
+        assert_eq!(rule.condition.extension.as_deref(), Some("pdf"));
+        assert!(rule.condition.extensions.is_empty());
+        assert_eq!(RuleEditorState::from_rule(0, &rule).extension, "pdf");
+
+        editor.extension = " , ".to_string();
+        let rule = editor.to_rule();
+        assert_eq!(rule.condition.extension, None);
+        assert!(rule.condition.extensions.is_empty());
+    }
+
+    #[test]
+    fn test_watch_stats_for_matches_expanded_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.watches.push(crate::config::WatchConfig {
+            path: dir.path().to_path_buf(),
+            recursive: false,
+            rules: Vec::new(),
+            follow_symlinks: false,
+        });
+        let mut state = AppState::new(config, Theme::default());
+        let watch = state.config.watches[0].clone();
+        assert_eq!(state.watch_stats_for(&watch), None);
+
+        let stats = crate::watcher::WatchStats {
+            files_processed: 3,
+            last_event_at: Some(1_700_000_000),
+        };
+        state.watch_stats.insert(dir.path().to_path_buf(), stats);
+        assert_eq!(state.watch_stats_for(&watch), Some(stats));
+    }
+
+    #[test]
+    fn test_matching_rule_indices_for_sample_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("report.pdf");
+        std::fs::write(&file, "pdf").unwrap();
+
+        let rule = |name: &str, condition: Condition| Rule::new(name, condition, Action::Nothing);
+        let mut disabled = rule(
+            "Disabled PDFs",
+            Condition {
+                extension: Some("pdf".to_string()),
+                ..Default::default()
+            },
+        );
+        disabled.enabled = false;
+        let rules = vec![
+            rule(
+                "Text",
+                Condition {
+                    extension: Some("txt".to_string()),
+                    ..Default::default()
+                },
+            ),
+            rule(
+                "PDFs",
+                Condition {
+                    extension: Some("pdf".to_string()),
+                    ..Default::default()
+                },
+            ),
+            rule("Everything", Condition::default()),
+            rule(
+                "Reports",
+                Condition {
+                    name_matches: Some("report*".to_string()),
+                    ..Default::default()
+                },
+            ),
+            disabled,
+            rule(
+                "Big files",
+                Condition {
+                    size_greater_than: Some(1000),
+                    ..Default::default()
+                },
+            ),
+        ];
+
+        assert_eq!(matching_rule_indices(&rules, &file), vec![1, 2, 3, 4]);
+        assert_eq!(
+            matching_rule_indices(&rules, &dir.path().join("notes.txt")),
+            vec![0, 2]
+        );
+
+        // Stepping wraps around in both directions
+        let mut config = Config::default();
+        config.rules = rules;
+        let mut state = AppState::new(config, Theme::default());
+        state.start_rule_test(file);
+        assert_eq!(state.selected_rule, Some(1));
+        state.step_rule_test(true);
+        assert_eq!(state.selected_rule, Some(2));
+        state.step_rule_test(false);
+        state.step_rule_test(false);
+        assert_eq!(state.selected_rule, Some(4));
+        assert_eq!(state.rule_test.as_ref().unwrap().current, 3);
+
+        state.start_rule_test(dir.path().join("photo.png"));
+        assert!(state.rule_test.is_some_and(|t| t.matches == vec![2]));
+    }
+
+    #[test]
+    fn test_rule_editor_keeps_conditions_it_does_not_show() {
+        let config: Config = toml::from_str(
+            r#"
+            [[rule]]
+            name = "Old logs"
+
+            [rule.condition]
+            extension = "log"
+            name_matches_any = ["app-*", "web-*"]
+            under_any = ["/var/tmp/logs"]
+            depth_less_than = 3
+            accessed_days_greater_than = 30
+            mtime_weekday = ["sat", "sun"]
+            newer_than_file = "stamp"
+            is_broken_symlink = false
+            contains_text = "DEBUG"
+
+            [rule.action]
+            type = "trash"
+            "#,
+        )
+        .unwrap();
+        let rule = &config.rules[0];
+
+        // Saving without touching anything gives back the same rule
+        let editor = RuleEditorState::from_rule(0, rule);
+        assert_eq!(editor.to_rule().condition, rule.condition);
+
+        // Editing a shown field leaves the hidden ones alone
+        let mut editor = RuleEditorState::from_rule(0, rule);
+        editor.size_greater = "1024".to_string();
+        let saved = editor.to_rule();
+        assert_eq!(saved.condition.size_greater_than, Some(1024));
+        assert_eq!(
+            Condition {
+                size_greater_than: None,
+                ..saved.condition
+            },
+            rule.condition
+        );
+    }
+
+    #[test]
+    fn test_rule_editor_keeps_move_options() {
+        let rule = Rule::new(
+            "Mirror",
+            Condition::default(),
+            Action::Move {
+                destination: PathBuf::from("/tmp/mirror"),
+                create_destination: false,
+                overwrite: false,
+                preserve_structure: true,
+                skip_if_open: true,
+                skip_if_identical: false,
+            },
+        );
+        let mut editor = RuleEditorState::from_rule(0, &rule);
+        assert_eq!(editor.to_rule().action, rule.action);
+
+        // Switching to Copy carries them over too
+        editor.action_type = ActionTypeSelection::Copy;
+        match editor.to_rule().action {
+            Action::Copy {
+                create_destination,
+                preserve_structure,
+                ..
+            } => assert!(!create_destination && preserve_structure),
+            other => panic!("expected a copy, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rule_editor_keeps_extension_list_beside_single_extension() {
+        let rule = Rule::new(
+            "PDFs",
+            Condition {
+                extension: Some("pdf".to_string()),
+                extensions: vec!["pdf".to_string(), "txt".to_string()],
+                ..Default::default()
+            },
+            Action::Nothing,
+        );
+        let mut editor = RuleEditorState::from_rule(0, &rule);
+        assert_eq!(editor.extension, "pdf");
+        assert_eq!(editor.to_rule().condition, rule.condition);
+
+        // Clearing the field only clears `extension`
+        editor.extension.clear();
+        let saved = editor.to_rule();
+        assert_eq!(saved.condition.extension, None);
+        assert_eq!(saved.condition.extensions, vec!["pdf", "txt"]);
+    }
+}
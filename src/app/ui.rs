@@ -9,9 +9,10 @@ use ratatui::{
 };
 
 use super::state::{
-    AppState, LogLevel, Mode, RuleEditorField, SettingsItem, View, WatchEditorField,
+    AppState, LogLevel, Mode, RuleEditorField, RuleEditorState, SettingsItem, View,
+    WatchEditorField,
 };
-#[cfg(unix)]
+#[cfg(any(unix, windows))]
 use crate::autostart;
 use crate::theme::Theme;
 
@@ -29,22 +30,15 @@ const LOGO: &str = r#"
 const ICON: &str = "🌰";
 
 /// Render the entire UI
-pub fn render(frame: &mut Frame, state: &AppState) {
-    let colors = state.theme.colors();
+pub fn render(frame: &mut Frame, state: &mut AppState) {
+    let colors = state.colors();
 
     // Set background
     let area = frame.area();
     let bg_block = Block::default().style(Style::default().bg(colors.bg));
     frame.render_widget(bg_block, area);
 
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(3), // Tabs
-            Constraint::Min(0),    // Main content
-            Constraint::Length(1), // Status bar
-        ])
-        .split(area);
+    let chunks = main_layout(area);
 
     render_tabs(frame, state, chunks[0]);
     render_main(frame, state, chunks[1]);
@@ -85,6 +79,11 @@ pub fn render(frame: &mut Frame, state: &AppState) {
         render_update_confirm_dialog(frame, state);
     }
 
+    // Render rule tester prompt
+    if state.mode == Mode::TestFile {
+        render_test_file_prompt(frame, state);
+    }
+
     // Render updating overlay (while update is in progress)
     if state.mode == Mode::Updating {
         render_updating_overlay(frame, state);
@@ -98,53 +97,71 @@ pub fn render(frame: &mut Frame, state: &AppState) {
     }
 }
 
+/// Split the screen into tabs, main content and status bar
+pub(super) fn main_layout(area: Rect) -> [Rect; 3] {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Tabs
+            Constraint::Min(0),    // Main content
+            Constraint::Length(1), // Status bar
+        ])
+        .split(area);
+    [chunks[0], chunks[1], chunks[2]]
+}
+
+/// Tab order as rendered in the tab bar
+const TAB_VIEWS: [(View, &str); 4] = [
+    (View::Dashboard, "Dashboard"),
+    (View::Rules, "Rules"),
+    (View::Watches, "Watches"),
+    (View::Log, "Log"),
+];
+
+/// Find the tab under a mouse position in the tab bar area
+pub(super) fn tab_at(area: Rect, column: u16, row: u16) -> Option<View> {
+    if row != area.y + 1 || column <= area.x {
+        return None;
+    }
+
+    // Each title is " ●  Name " followed by a " │ " divider
+    let mut x = area.x + 1;
+    for (view, name) in TAB_VIEWS {
+        let width = 1 + 3 + name.chars().count() as u16 + 1;
+        if column >= x && column < x + width {
+            return Some(view);
+        }
+        x += width + 3;
+    }
+    None
+}
+
+/// Map a mouse row to an item index in a bordered single-line list
+pub(super) fn list_index_at(area: Rect, row: u16, offset: usize, len: usize) -> Option<usize> {
+    let top = area.y + 1;
+    let bottom = area.y + area.height.saturating_sub(1);
+    if row < top || row >= bottom {
+        return None;
+    }
+    let index = offset + (row - top) as usize;
+    (index < len).then_some(index)
+}
+
 fn render_tabs(frame: &mut Frame, state: &AppState, area: Rect) {
-    let colors = state.theme.colors();
+    let colors = state.colors();
 
-    let titles: Vec<Line> = vec![
-        format!(
-            "{}  Dashboard",
-            if state.view == View::Dashboard {
-                "●"
-            } else {
-                "○"
-            }
-        ),
-        format!(
-            "{}  Rules",
-            if state.view == View::Rules {
-                "●"
-            } else {
-                "○"
-            }
-        ),
-        format!(
-            "{}  Watches",
-            if state.view == View::Watches {
-                "●"
-            } else {
-                "○"
-            }
-        ),
-        format!(
-            "{}  Log",
-            if state.view == View::Log {
-                "●"
-            } else {
-                "○"
-            }
-        ),
-    ]
-    .into_iter()
-    .map(Line::from)
-    .collect();
-
-    let selected = match state.view {
-        View::Dashboard => 0,
-        View::Rules => 1,
-        View::Watches => 2,
-        View::Log => 3,
-    };
+    let titles: Vec<Line> = TAB_VIEWS
+        .iter()
+        .map(|(view, name)| {
+            let marker = if state.view == *view { "●" } else { "○" };
+            Line::from(format!("{}  {}", marker, name))
+        })
+        .collect();
+
+    let selected = TAB_VIEWS
+        .iter()
+        .position(|(view, _)| *view == state.view)
+        .unwrap_or(0);
 
     let tabs = Tabs::new(titles)
         .block(
@@ -162,7 +179,7 @@ fn render_tabs(frame: &mut Frame, state: &AppState, area: Rect) {
     frame.render_widget(tabs, area);
 }
 
-fn render_main(frame: &mut Frame, state: &AppState, area: Rect) {
+fn render_main(frame: &mut Frame, state: &mut AppState, area: Rect) {
     match state.view {
         View::Dashboard => render_dashboard(frame, state, area),
         View::Rules => render_rules(frame, state, area),
@@ -172,7 +189,7 @@ fn render_main(frame: &mut Frame, state: &AppState, area: Rect) {
 }
 
 fn render_dashboard(frame: &mut Frame, state: &AppState, area: Rect) {
-    let colors = state.theme.colors();
+    let colors = state.colors();
 
     // Check if we need to show update banner
     let has_update = state.update_available.is_some();
@@ -270,7 +287,7 @@ fn render_dashboard(frame: &mut Frame, state: &AppState, area: Rect) {
     let total_rules = state.config.rules.len();
     let watch_count = state.config.watches.len();
 
-    let stats_content = vec![
+    let mut stats_content = vec![
         Line::from(""),
         Line::from(vec![
             Span::styled("  📁 Watch Folders:  ", colors.text_dim()),
@@ -304,12 +321,26 @@ fn render_dashboard(frame: &mut Frame, state: &AppState, area: Rect) {
                 Span::styled("Not connected", colors.text_error())
             },
         ]),
+        Line::from(vec![
+            Span::styled("  ⏱  Last Activity:  ", colors.text_dim()),
+            match state.last_event_at {
+                Some(at) => Span::styled(format_ago(at), colors.text_secondary()),
+                None => Span::styled("None yet", colors.text_muted()),
+            },
+        ]),
         Line::from(""),
         Line::from(vec![
             Span::styled("  🎨 Theme:          ", colors.text_dim()),
             Span::styled(state.theme.name(), colors.text_secondary()),
         ]),
     ];
+    if let Some(ref error) = state.last_error {
+        stats_content.push(Line::from(""));
+        stats_content.push(Line::from(vec![
+            Span::styled("  ⚠  Last Error:     ", colors.text_dim()),
+            Span::styled(error.clone(), colors.text_error()),
+        ]));
+    }
 
     let stats = Paragraph::new(stats_content).block(
         Block::default()
@@ -376,8 +407,26 @@ fn render_dashboard(frame: &mut Frame, state: &AppState, area: Rect) {
     frame.render_widget(actions, content_chunks[1]);
 }
 
-fn render_rules(frame: &mut Frame, state: &AppState, area: Rect) {
-    let colors = state.theme.colors();
+/// Describe how long ago a Unix timestamp was, e.g. "3m ago"
+fn format_ago(unix_secs: u64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let elapsed = now.saturating_sub(unix_secs);
+    if elapsed < 60 {
+        "just now".to_string()
+    } else if elapsed < 3600 {
+        format!("{}m ago", elapsed / 60)
+    } else if elapsed < 86400 {
+        format!("{}h ago", elapsed / 3600)
+    } else {
+        format!("{}d ago", elapsed / 86400)
+    }
+}
+
+fn render_rules(frame: &mut Frame, state: &mut AppState, area: Rect) {
+    let colors = state.colors();
 
     if state.config.rules.is_empty() {
         let empty = Paragraph::new(vec![
@@ -428,27 +477,26 @@ fn render_rules(frame: &mut Frame, state: &AppState, area: Rect) {
             };
 
             // Build the rule line
-            let action_preview = match &rule.action {
-                crate::rules::Action::Move { destination, .. } => {
-                    format!("→ {}", destination.display())
-                }
-                crate::rules::Action::Copy { destination, .. } => {
-                    format!("⇒ {}", destination.display())
-                }
-                crate::rules::Action::Rename { pattern } => format!("✎ {}", pattern),
-                crate::rules::Action::Trash => "🗑 Trash".to_string(),
-                crate::rules::Action::Delete => "⚠ Delete".to_string(),
-                crate::rules::Action::Run { command, .. } => format!("$ {}", command),
-                crate::rules::Action::Archive { .. } => "📦 Archive".to_string(),
-                crate::rules::Action::Nothing => "∅ Nothing".to_string(),
-            };
+            let action_preview = rule.action.preview();
 
-            ListItem::new(Line::from(vec![
+            let mut spans = vec![
                 Span::styled(format!(" {} ", status_icon), status_style),
                 Span::styled(&rule.name, base_style.add_modifier(Modifier::BOLD)),
                 Span::styled(format!("  {}", action_preview), colors.text_dim()),
-            ]))
-            .style(base_style)
+            ];
+            // Rules matching the tested file, numbered in evaluation order
+            if let Some(position) = state
+                .rule_test
+                .as_ref()
+                .and_then(|test| test.matches.iter().position(|&m| m == i))
+            {
+                spans.push(Span::styled(
+                    format!("  ◆ match {}", position + 1),
+                    colors.text_success(),
+                ));
+            }
+
+            ListItem::new(Line::from(spans)).style(base_style)
         })
         .collect();
 
@@ -461,16 +509,26 @@ fn render_rules(frame: &mut Frame, state: &AppState, area: Rect) {
                 } else {
                     colors.block()
                 })
-                .title(format!(" Rules ({}) ", state.config.rules.len()))
+                .title(match state.rule_test {
+                    Some(ref test) => format!(
+                        " Rules ({}) · match {}/{} for {} ",
+                        state.config.rules.len(),
+                        test.current + 1,
+                        test.matches.len(),
+                        test.path.display()
+                    ),
+                    None => format!(" Rules ({}) ", state.config.rules.len()),
+                })
                 .title_style(colors.text_primary()),
         )
         .highlight_style(colors.selected());
 
-    frame.render_widget(rules_list, area);
+    state.rules_list_state.select(state.selected_rule);
+    frame.render_stateful_widget(rules_list, area, &mut state.rules_list_state);
 }
 
-fn render_watches(frame: &mut Frame, state: &AppState, area: Rect) {
-    let colors = state.theme.colors();
+fn render_watches(frame: &mut Frame, state: &mut AppState, area: Rect) {
+    let colors = state.colors();
 
     if state.config.watches.is_empty() {
         let empty = Paragraph::new(vec![
@@ -518,12 +576,26 @@ fn render_watches(frame: &mut Frame, state: &AppState, area: Rect) {
                 ("⚠", colors.text_warning())
             };
 
-            ListItem::new(Line::from(vec![
+            let mut spans = vec![
                 Span::styled(format!(" {} ", icon), base_style),
                 Span::styled(path_str, path_style),
                 Span::styled(recursive_indicator, colors.text_muted()),
-            ]))
-            .style(base_style)
+            ];
+            if let Some(stats) = state.watch_stats_for(watch) {
+                let plural = if stats.files_processed == 1 { "" } else { "s" };
+                spans.push(Span::styled(
+                    format!("  {} file{}", stats.files_processed, plural),
+                    colors.text_secondary(),
+                ));
+                if let Some(at) = stats.last_event_at {
+                    spans.push(Span::styled(
+                        format!(" · last {}", format_ago(at)),
+                        colors.text_muted(),
+                    ));
+                }
+            }
+
+            ListItem::new(Line::from(spans)).style(base_style)
         })
         .collect();
 
@@ -539,11 +611,12 @@ fn render_watches(frame: &mut Frame, state: &AppState, area: Rect) {
             .title_style(colors.text_primary()),
     );
 
-    frame.render_widget(list, area);
+    state.watches_list_state.select(state.selected_watch);
+    frame.render_stateful_widget(list, area, &mut state.watches_list_state);
 }
 
-fn render_log(frame: &mut Frame, state: &AppState, area: Rect) {
-    let colors = state.theme.colors();
+fn render_log(frame: &mut Frame, state: &mut AppState, area: Rect) {
+    let colors = state.colors();
 
     if state.log_entries.is_empty() {
         let empty = Paragraph::new(vec![
@@ -564,10 +637,14 @@ fn render_log(frame: &mut Frame, state: &AppState, area: Rect) {
         return;
     }
 
+    // Only build items for the rows that fit between the borders
+    let visible = state.visible_log_range(area.height.saturating_sub(2) as usize);
     let items: Vec<ListItem> = state
         .log_entries
         .iter()
         .rev()
+        .skip(visible.start)
+        .take(visible.len())
         .map(|entry| {
             let (icon, level_style) = match entry.level {
                 LogLevel::Info => ("ℹ", colors.text_info()),
@@ -595,7 +672,7 @@ fn render_log(frame: &mut Frame, state: &AppState, area: Rect) {
                 colors.block()
             })
             .title(format!(
-                " Activity Log ({}) [c: clear] ",
+                " Activity Log ({}) [c: clear, u: undo] ",
                 state.log_entries.len()
             ))
             .title_style(colors.text_primary()),
@@ -605,7 +682,7 @@ fn render_log(frame: &mut Frame, state: &AppState, area: Rect) {
 }
 
 fn render_status_bar(frame: &mut Frame, state: &AppState, area: Rect) {
-    let colors = state.theme.colors();
+    let colors = state.colors();
 
     let content = if let Some(ref msg) = state.status_message {
         vec![
@@ -633,21 +710,38 @@ fn render_status_bar(frame: &mut Frame, state: &AppState, area: Rect) {
     let status =
         Paragraph::new(Line::from(content)).style(Style::default().bg(colors.bg_secondary));
     frame.render_widget(status, area);
+
+    if let Some(toast) = state.toast {
+        // Fade from bold green through dim to muted before it disappears
+        let fade = toast.fade(state.frame);
+        let style = if fade < 0.5 {
+            colors.text_success().add_modifier(Modifier::BOLD)
+        } else if fade < 0.8 {
+            colors.text_success()
+        } else {
+            colors.text_muted()
+        };
+        let files = if toast.count == 1 { "file" } else { "files" };
+        let toast = Paragraph::new(Line::styled(
+            format!("✓ {} {} organized ", toast.count, files),
+            style,
+        ))
+        .alignment(Alignment::Right);
+        frame.render_widget(toast, area);
+    }
 }
 
 fn render_help_popup(frame: &mut Frame, state: &AppState) {
-    let colors = state.theme.colors();
+    let colors = state.colors();
     let area = frame.area();
 
     // Calculate popup size
     let popup_width = 60u16.min(area.width.saturating_sub(4));
-    let popup_height = 32u16.min(area.height.saturating_sub(4));
+    let popup_height = 40u16.min(area.height.saturating_sub(4));
 
-    let popup_area = Rect {
-        x: (area.width - popup_width) / 2,
-        y: (area.height - popup_height) / 2,
-        width: popup_width,
-        height: popup_height,
+    let Some(popup_area) = fit_popup(popup_width, popup_height, HELP_MIN_SIZE, area) else {
+        render_too_small(frame, state, HELP_MIN_SIZE);
+        return;
     };
 
     // Clear the area
@@ -684,6 +778,10 @@ fn render_help_popup(frame: &mut Frame, state: &AppState) {
             Span::styled("  Enter/Space        ", colors.key_hint()),
             Span::styled("Toggle rule on/off", colors.text()),
         ]),
+        Line::from(vec![
+            Span::styled("  D                  ", colors.key_hint()),
+            Span::styled("Disable all / restore rules", colors.text()),
+        ]),
         Line::from(vec![
             Span::styled("  e                  ", colors.key_hint()),
             Span::styled("Edit selected rule", colors.text()),
@@ -696,6 +794,14 @@ fn render_help_popup(frame: &mut Frame, state: &AppState) {
             Span::styled("  d                  ", colors.key_hint()),
             Span::styled("Delete selected rule", colors.text()),
         ]),
+        Line::from(vec![
+            Span::styled("  f                  ", colors.key_hint()),
+            Span::styled("Test rules against a file", colors.text()),
+        ]),
+        Line::from(vec![
+            Span::styled("  n/N                ", colors.key_hint()),
+            Span::styled("Next/previous matching rule", colors.text()),
+        ]),
         Line::from(""),
         Line::from(vec![Span::styled(
             "  Watches View",
@@ -714,6 +820,19 @@ fn render_help_popup(frame: &mut Frame, state: &AppState) {
             Span::styled("Delete selected watch", colors.text()),
         ]),
         Line::from(""),
+        Line::from(vec![Span::styled(
+            "  Log View",
+            colors.text_primary().add_modifier(Modifier::BOLD),
+        )]),
+        Line::from(vec![
+            Span::styled("  u                  ", colors.key_hint()),
+            Span::styled("Undo last move/copy/rename", colors.text()),
+        ]),
+        Line::from(vec![
+            Span::styled("  c                  ", colors.key_hint()),
+            Span::styled("Clear the log", colors.text()),
+        ]),
+        Line::from(""),
         Line::from(vec![Span::styled(
             "  Dashboard",
             colors.text_primary().add_modifier(Modifier::BOLD),
@@ -743,6 +862,10 @@ fn render_help_popup(frame: &mut Frame, state: &AppState) {
             Span::styled("  A                  ", colors.key_hint()),
             Span::styled("About Hazelnut", colors.text()),
         ]),
+        Line::from(vec![
+            Span::styled("  R                  ", colors.key_hint()),
+            Span::styled("Reload config from disk", colors.text()),
+        ]),
         Line::from(vec![
             Span::styled("  ?                  ", colors.key_hint()),
             Span::styled("Toggle this help", colors.text()),
@@ -775,10 +898,22 @@ fn render_help_popup(frame: &mut Frame, state: &AppState) {
     frame.render_widget(help, popup_area);
 }
 
+/// Smallest popup sizes (width, height) each dialog can be drawn in; below
+/// them a "terminal too small" message is shown instead
+const HELP_MIN_SIZE: (u16, u16) = (40, 10);
+const SETTINGS_MIN_SIZE: (u16, u16) = (40, 10);
+const RULE_EDITOR_MIN_SIZE: (u16, u16) = (50, 16);
+const WATCH_EDITOR_MIN_SIZE: (u16, u16) = (40, 12);
+const THEME_PICKER_MIN_SIZE: (u16, u16) = (40, 10);
+const ABOUT_MIN_SIZE: (u16, u16) = (30, 10);
+const CONFIRM_MIN_SIZE: (u16, u16) = (30, 7);
+const PROMPT_MIN_SIZE: (u16, u16) = (20, 3);
+
 /// Helper to create a centered rect
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_width = r.width * percent_x / 100;
-    let popup_height = r.height * percent_y / 100;
+    // Widen before multiplying so large terminals can't overflow
+    let popup_width = (u32::from(r.width) * u32::from(percent_x.min(100)) / 100) as u16;
+    let popup_height = (u32::from(r.height) * u32::from(percent_y.min(100)) / 100) as u16;
     Rect {
         x: r.x + (r.width - popup_width) / 2,
         y: r.y + (r.height - popup_height) / 2,
@@ -787,13 +922,70 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     }
 }
 
+/// A `width` x `height` popup centered in `area`, shrunk to fit inside it.
+/// `None` if that leaves it smaller than `min` (width, height).
+fn fit_popup(width: u16, height: u16, min: (u16, u16), area: Rect) -> Option<Rect> {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    if width < min.0 || height < min.1 {
+        return None;
+    }
+    Some(Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    })
+}
+
+/// Tell the user the terminal is too small for a dialog that needs a popup
+/// of at least `min` (width, height)
+fn render_too_small(frame: &mut Frame, state: &AppState, min: (u16, u16)) {
+    let colors = state.colors();
+    let area = frame.area();
+    let message = format!(
+        "Terminal too small for this dialog. Enlarge it to at least {}x{} (now {}x{}).",
+        // The dialogs keep a 2-cell margin around the popup
+        min.0 + 4,
+        min.1 + 4,
+        area.width,
+        area.height
+    );
+    // Roughly how many rows the wrapped message takes, plus one for word breaks
+    let chars = u16::try_from(message.chars().count()).unwrap_or(u16::MAX);
+    let lines = chars.div_ceil(area.width.max(1)) + 1;
+    let message_area = Rect {
+        x: area.x,
+        y: area.y + area.height.saturating_sub(lines) / 2,
+        width: area.width,
+        height: lines.min(area.height),
+    };
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(message)
+            .style(Style::default().fg(colors.warning).bg(colors.bg))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true }),
+        message_area,
+    );
+}
+
 fn render_theme_picker(frame: &mut Frame, state: &AppState) {
-    let colors = state.theme.colors();
+    let colors = state.colors();
     let area = frame.area();
 
-    let popup_area = centered_rect(50, 70, area);
+    let popup_area = centered_rect(80, 70, area);
+    if popup_area.width < THEME_PICKER_MIN_SIZE.0 || popup_area.height < THEME_PICKER_MIN_SIZE.1 {
+        render_too_small(frame, state, THEME_PICKER_MIN_SIZE);
+        return;
+    }
     frame.render_widget(Clear, popup_area);
 
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(popup_area);
+
     let themes = Theme::all();
     let items: Vec<ListItem> = themes
         .iter()
@@ -842,22 +1034,190 @@ fn render_theme_picker(frame: &mut Frame, state: &AppState) {
             .title_bottom(Line::from(" ↑↓ navigate │ ↵ apply │ Esc cancel ").centered()),
     );
 
-    frame.render_widget(theme_list, popup_area);
+    frame.render_widget(theme_list, chunks[0]);
+    render_theme_preview(frame, state.theme_picker_selection(), chunks[1]);
+}
+
+/// Full-screen report for a config file that failed to parse
+pub(super) fn render_config_error(
+    frame: &mut Frame,
+    path: &std::path::Path,
+    error: &crate::config::ConfigParseError,
+    note: Option<&str>,
+) {
+    let colors = Theme::default().colors();
+    let area = frame.area();
+    frame.render_widget(Block::default().style(Style::default().bg(colors.bg)), area);
+
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  ✗ ", colors.text_error()),
+            Span::styled(
+                "Your config file could not be parsed",
+                colors.text_error().add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  File:     ", colors.text_dim()),
+            Span::styled(path.display().to_string(), colors.text_primary()),
+        ]),
+    ];
+    if let Some((line, column)) = error.location {
+        lines.push(Line::from(vec![
+            Span::styled("  Location: ", colors.text_dim()),
+            Span::styled(
+                format!("line {}, column {}", line, column),
+                colors.text_secondary(),
+            ),
+        ]));
+    }
+    lines.push(Line::from(vec![
+        Span::styled("  Problem:  ", colors.text_dim()),
+        Span::styled(error.message.clone(), colors.text()),
+    ]));
+    lines.push(Line::from(""));
+
+    let gutter = error
+        .snippet
+        .last()
+        .map(|(n, _)| n.to_string().len())
+        .unwrap_or(1);
+    for (number, text) in &error.snippet {
+        let is_error_line = error.location.is_some_and(|(line, _)| line == *number);
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {:>width$} │ ", number, width = gutter),
+                colors.text_muted(),
+            ),
+            Span::styled(
+                text.clone(),
+                if is_error_line {
+                    colors.text_warning()
+                } else {
+                    colors.text()
+                },
+            ),
+        ]));
+        if is_error_line && let Some((_, column)) = error.location {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  {:>width$} │ ", "", width = gutter),
+                    colors.text_muted(),
+                ),
+                Span::styled(format!("{}^", " ".repeat(column - 1)), colors.text_error()),
+            ]));
+        }
+    }
+
+    lines.push(Line::from(""));
+    if let Some(note) = note {
+        lines.push(Line::styled(format!("  {}", note), colors.text_secondary()));
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(vec![
+        Span::styled("  [o]", colors.key_hint()),
+        Span::styled(" Open config   ", colors.text_muted()),
+        Span::styled("[r]", colors.key_hint()),
+        Span::styled(" Retry   ", colors.text_muted()),
+        Span::styled("[q]", colors.key_hint()),
+        Span::styled(" Quit", colors.text_muted()),
+    ]));
+
+    let report = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(colors.error))
+            .title(format!(" {} Hazelnut ", ICON))
+            .title_style(colors.text_primary()),
+    );
+    frame.render_widget(report, area);
+}
+
+/// Mini mock of the main UI (tabs, a list with a selection, status bar)
+/// drawn with the given theme so it can be judged before applying
+fn render_theme_preview(frame: &mut Frame, theme: Theme, area: Rect) {
+    let colors = theme.colors();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(colors.block_focus())
+        .style(Style::default().bg(colors.bg))
+        .title(format!(" Preview: {} ", theme.name()))
+        .title_style(colors.text_primary());
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(inner);
+
+    let content = vec![
+        Line::from(vec![
+            Span::styled(" ●  Dashboard ", colors.tab_active()),
+            Span::styled("│", colors.block()),
+            Span::styled("  Rules ", colors.tab()),
+            Span::styled("│", colors.block()),
+            Span::styled("  Watches ", colors.tab()),
+        ]),
+        Line::styled("─".repeat(inner.width as usize), colors.block()),
+        Line::from(vec![
+            Span::styled(" ✓ ", colors.text_success()),
+            Span::styled("Screenshots", colors.text()),
+            Span::styled("  → ~/Pictures", colors.text_muted()),
+        ]),
+        Line::from(vec![
+            Span::styled(" ▸ ", colors.selected()),
+            Span::styled("PDFs to Documents", colors.selected()),
+            Span::styled("  → ~/Documents ", colors.selected()),
+        ]),
+        Line::from(vec![
+            Span::styled(" ✗ ", colors.text_muted()),
+            Span::styled("Old downloads", colors.text_muted()),
+            Span::styled("  🗑 Trash", colors.text_muted()),
+        ]),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(" 12:04:31 ", colors.text_dim()),
+            Span::styled("Moved report.pdf", colors.text_success()),
+        ]),
+        Line::from(vec![
+            Span::styled(" 12:04:35 ", colors.text_dim()),
+            Span::styled("Watch folder missing", colors.text_warning()),
+        ]),
+        Line::from(vec![
+            Span::styled(" 12:04:40 ", colors.text_dim()),
+            Span::styled("Rule processing failed", colors.text_error()),
+        ]),
+    ];
+    frame.render_widget(Paragraph::new(content), rows[0]);
+
+    let status = Paragraph::new(Line::from(vec![
+        Span::styled(" ", Style::default()),
+        Span::styled("Tab", colors.key_hint()),
+        Span::styled(": views  ", colors.text_muted()),
+        Span::styled("q", colors.key_hint()),
+        Span::styled(": quit", colors.text_muted()),
+    ]))
+    .style(Style::default().bg(colors.bg_secondary));
+    frame.render_widget(status, rows[1]);
 }
 
 fn render_settings_dialog(frame: &mut Frame, state: &AppState) {
-    let colors = state.theme.colors();
+    let colors = state.colors();
     let area = frame.area();
 
     // Calculate popup size - a bit wider for settings
     let popup_width = 60u16.min(area.width.saturating_sub(4));
     let popup_height = 18u16.min(area.height.saturating_sub(4));
 
-    let popup_area = Rect {
-        x: (area.width - popup_width) / 2,
-        y: (area.height - popup_height) / 2,
-        width: popup_width,
-        height: popup_height,
+    let Some(popup_area) = fit_popup(popup_width, popup_height, SETTINGS_MIN_SIZE, area) else {
+        render_too_small(frame, state, SETTINGS_MIN_SIZE);
+        return;
     };
 
     // Clear the area
@@ -923,7 +1283,7 @@ fn get_settings_value_display(state: &AppState, item: SettingsItem) -> String {
                 "○ Stopped".to_string()
             }
         }
-        #[cfg(unix)]
+        #[cfg(any(unix, windows))]
         SettingsItem::AutoStartOnBoot => {
             if autostart::is_enabled() {
                 "✓ Enabled".to_string()
@@ -953,11 +1313,25 @@ fn get_settings_value_display(state: &AppState, item: SettingsItem) -> String {
             };
             status.to_string()
         }
+        SettingsItem::SimulateMode => {
+            if state.config.general.dry_run {
+                "✓ Enabled".to_string()
+            } else {
+                "✗ Disabled".to_string()
+            }
+        }
+        SettingsItem::HighContrast => {
+            if state.config.general.high_contrast {
+                "✓ Enabled".to_string()
+            } else {
+                "✗ Disabled".to_string()
+            }
+        }
     }
 }
 
 fn render_rule_editor(frame: &mut Frame, state: &AppState) {
-    let colors = state.theme.colors();
+    let colors = state.colors();
     let area = frame.area();
 
     let Some(ref editor) = state.rule_editor else {
@@ -968,11 +1342,9 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
     let popup_width = 70u16.min(area.width.saturating_sub(4));
     let popup_height = 28u16.min(area.height.saturating_sub(4));
 
-    let popup_area = Rect {
-        x: (area.width - popup_width) / 2,
-        y: (area.height - popup_height) / 2,
-        width: popup_width,
-        height: popup_height,
+    let Some(popup_area) = fit_popup(popup_width, popup_height, RULE_EDITOR_MIN_SIZE, area) else {
+        render_too_small(frame, state, RULE_EDITOR_MIN_SIZE);
+        return;
     };
 
     // Clear the area
@@ -997,6 +1369,10 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
 
     let cursor = |f: RuleEditorField| if editor.field == f { "▸" } else { " " };
 
+    // Fields that differ from the saved rule get an asterisk after the cursor
+    let changed = editor.changed_fields();
+    let changed_mark = |f: RuleEditorField| if changed.contains(&f) { "*" } else { " " };
+
     let tri_state_display = |v: Option<bool>| match v {
         None => "Any",
         Some(true) => "Yes",
@@ -1009,7 +1385,7 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
         " ✚ New Rule ".to_string()
     };
 
-    let content = vec![
+    let mut content = vec![
         Line::from(""),
         // Basic Info Section
         Line::from(vec![Span::styled(
@@ -1018,7 +1394,11 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
         )]),
         Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::Name)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::Name),
+                    changed_mark(RuleEditorField::Name)
+                ),
                 field_style(RuleEditorField::Name),
             ),
             Span::styled("Name:        ", label_style(RuleEditorField::Name)),
@@ -1026,7 +1406,11 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
         ]),
         Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::Enabled)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::Enabled),
+                    changed_mark(RuleEditorField::Enabled)
+                ),
                 field_style(RuleEditorField::Enabled),
             ),
             Span::styled("Enabled:     ", label_style(RuleEditorField::Enabled)),
@@ -1035,6 +1419,28 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
                 field_style(RuleEditorField::Enabled),
             ),
         ]),
+        Line::from(vec![
+            Span::styled(
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::StopProcessing),
+                    changed_mark(RuleEditorField::StopProcessing)
+                ),
+                field_style(RuleEditorField::StopProcessing),
+            ),
+            Span::styled(
+                "Stop Here:   ",
+                label_style(RuleEditorField::StopProcessing),
+            ),
+            Span::styled(
+                if editor.stop_processing {
+                    "✓ Yes"
+                } else {
+                    "✗ No"
+                },
+                field_style(RuleEditorField::StopProcessing),
+            ),
+        ]),
         Line::from(""),
         // Conditions Section
         Line::from(vec![Span::styled(
@@ -1043,7 +1449,11 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
         )]),
         Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::Extension)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::Extension),
+                    changed_mark(RuleEditorField::Extension)
+                ),
                 field_style(RuleEditorField::Extension),
             ),
             Span::styled("Extension:   ", label_style(RuleEditorField::Extension)),
@@ -1058,7 +1468,11 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
         ]),
         Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::NameGlob)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::NameGlob),
+                    changed_mark(RuleEditorField::NameGlob)
+                ),
                 field_style(RuleEditorField::NameGlob),
             ),
             Span::styled("Name Glob:   ", label_style(RuleEditorField::NameGlob)),
@@ -1073,7 +1487,11 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
         ]),
         Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::NameRegex)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::NameRegex),
+                    changed_mark(RuleEditorField::NameRegex)
+                ),
                 field_style(RuleEditorField::NameRegex),
             ),
             Span::styled("Name Regex:  ", label_style(RuleEditorField::NameRegex)),
@@ -1088,7 +1506,11 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
         ]),
         Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::SizeGreater)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::SizeGreater),
+                    changed_mark(RuleEditorField::SizeGreater)
+                ),
                 field_style(RuleEditorField::SizeGreater),
             ),
             Span::styled("Size >:      ", label_style(RuleEditorField::SizeGreater)),
@@ -1104,7 +1526,11 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
         ]),
         Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::SizeLess)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::SizeLess),
+                    changed_mark(RuleEditorField::SizeLess)
+                ),
                 field_style(RuleEditorField::SizeLess),
             ),
             Span::styled("Size <:      ", label_style(RuleEditorField::SizeLess)),
@@ -1120,7 +1546,11 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
         ]),
         Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::AgeGreater)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::AgeGreater),
+                    changed_mark(RuleEditorField::AgeGreater)
+                ),
                 field_style(RuleEditorField::AgeGreater),
             ),
             Span::styled("Age > days:  ", label_style(RuleEditorField::AgeGreater)),
@@ -1135,7 +1565,11 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
         ]),
         Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::AgeLess)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::AgeLess),
+                    changed_mark(RuleEditorField::AgeLess)
+                ),
                 field_style(RuleEditorField::AgeLess),
             ),
             Span::styled("Age < days:  ", label_style(RuleEditorField::AgeLess)),
@@ -1150,7 +1584,11 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
         ]),
         Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::IsDirectory)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::IsDirectory),
+                    changed_mark(RuleEditorField::IsDirectory)
+                ),
                 field_style(RuleEditorField::IsDirectory),
             ),
             Span::styled("Is Dir:      ", label_style(RuleEditorField::IsDirectory)),
@@ -1161,7 +1599,11 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
         ]),
         Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::IsHidden)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::IsHidden),
+                    changed_mark(RuleEditorField::IsHidden)
+                ),
                 field_style(RuleEditorField::IsHidden),
             ),
             Span::styled("Is Hidden:   ", label_style(RuleEditorField::IsHidden)),
@@ -1178,7 +1620,11 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
         )]),
         Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::ActionType)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::ActionType),
+                    changed_mark(RuleEditorField::ActionType)
+                ),
                 field_style(RuleEditorField::ActionType),
             ),
             Span::styled("Type:        ", label_style(RuleEditorField::ActionType)),
@@ -1189,7 +1635,11 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
         ]),
         Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::ActionDestination)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::ActionDestination),
+                    changed_mark(RuleEditorField::ActionDestination)
+                ),
                 field_style(RuleEditorField::ActionDestination),
             ),
             Span::styled(
@@ -1205,9 +1655,63 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
                 field_style(RuleEditorField::ActionDestination),
             ),
         ]),
+    ];
+    if editor.shows_field(RuleEditorField::ActionOverwrite) {
+        content.push(Line::from(vec![
+            Span::styled(
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::ActionOverwrite),
+                    changed_mark(RuleEditorField::ActionOverwrite)
+                ),
+                field_style(RuleEditorField::ActionOverwrite),
+            ),
+            Span::styled(
+                "Overwrite:   ",
+                label_style(RuleEditorField::ActionOverwrite),
+            ),
+            Span::styled(
+                if editor.action_overwrite {
+                    "✓ Yes"
+                } else {
+                    "✗ No"
+                },
+                field_style(RuleEditorField::ActionOverwrite),
+            ),
+        ]));
+    }
+    if editor.shows_field(RuleEditorField::ActionDeleteOriginal) {
+        content.push(Line::from(vec![
+            Span::styled(
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::ActionDeleteOriginal),
+                    changed_mark(RuleEditorField::ActionDeleteOriginal)
+                ),
+                field_style(RuleEditorField::ActionDeleteOriginal),
+            ),
+            Span::styled(
+                "Delete Orig: ",
+                label_style(RuleEditorField::ActionDeleteOriginal),
+            ),
+            Span::styled(
+                if editor.action_delete_original {
+                    "✓ Yes"
+                } else {
+                    "✗ No"
+                },
+                field_style(RuleEditorField::ActionDeleteOriginal),
+            ),
+        ]));
+    }
+    content.extend([
         Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::ActionPattern)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::ActionPattern),
+                    changed_mark(RuleEditorField::ActionPattern)
+                ),
                 field_style(RuleEditorField::ActionPattern),
             ),
             Span::styled("Pattern:     ", label_style(RuleEditorField::ActionPattern)),
@@ -1222,7 +1726,11 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
         ]),
         Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::ActionCommand)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::ActionCommand),
+                    changed_mark(RuleEditorField::ActionCommand)
+                ),
                 field_style(RuleEditorField::ActionCommand),
             ),
             Span::styled("Command:     ", label_style(RuleEditorField::ActionCommand)),
@@ -1235,9 +1743,16 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
                 field_style(RuleEditorField::ActionCommand),
             ),
         ]),
-        Line::from(vec![
+    ]);
+    // Arguments only apply to commands
+    if editor.shows_field(RuleEditorField::ActionArgs) {
+        content.push(Line::from(vec![
             Span::styled(
-                format!(" {} ", cursor(RuleEditorField::ActionArgs)),
+                format!(
+                    " {}{}",
+                    cursor(RuleEditorField::ActionArgs),
+                    changed_mark(RuleEditorField::ActionArgs)
+                ),
                 field_style(RuleEditorField::ActionArgs),
             ),
             Span::styled("Args:        ", label_style(RuleEditorField::ActionArgs)),
@@ -1249,7 +1764,9 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
                 },
                 field_style(RuleEditorField::ActionArgs),
             ),
-        ]),
+        ]));
+    }
+    content.extend([
         Line::from(""),
         // Contextual help line
         Line::from(vec![
@@ -1259,7 +1776,18 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
                 colors.text_muted().add_modifier(Modifier::ITALIC),
             ),
         ]),
-    ];
+    ]);
+
+    let footer = if editor.confirm_discard {
+        " Unsaved changes │ Esc: discard │ any other key: keep editing ".to_string()
+    } else if changed.is_empty() {
+        " Tab: next │ ^N: name │ ^A: action │ Enter: save │ Esc: cancel ".to_string()
+    } else {
+        format!(
+            " Tab: next │ ^N: name │ ^A: action │ Enter: save │ Esc: cancel │ *{} changed ",
+            changed.len()
+        )
+    };
 
     let editor_widget = Paragraph::new(content)
         .block(
@@ -1270,59 +1798,80 @@ fn render_rule_editor(frame: &mut Frame, state: &AppState) {
                 .style(Style::default().bg(colors.bg))
                 .title(title)
                 .title_style(colors.text_primary())
-                .title_bottom(
-                    Line::from(" Tab: next field │ Enter: save │ Esc: cancel ").centered(),
-                ),
+                .title_bottom(Line::from(footer).centered()),
         )
         .wrap(Wrap { trim: false });
 
     frame.render_widget(editor_widget, popup_area);
 
     // Set cursor position for text fields
-    // Calculate cursor position based on field type and cursor offset
     // Field layout: border (1) + " ▸ " (4) + "Label:       " (13) = 18 chars before value
     let prefix_len = 18u16;
-    // Row = line_index + 1 (for border)
-    // Line indices:
-    //  0: empty, 1: header, 2: Name, 3: Enabled, 4: empty, 5: header
-    //  6: Extension, 7: NameGlob, 8: NameRegex, 9: SizeGreater, 10: SizeLess
-    // 11: AgeGreater, 12: AgeLess, 13: IsDirectory, 14: IsHidden, 15: empty
-    // 16: header, 17: ActionType, 18: ActionDestination, 19: ActionPattern, 20: ActionCommand, 21: ActionArgs
-    let (field_row, cursor_offset) = match editor.field {
-        RuleEditorField::Name => (3, editor.cursor_name), // line 2 + 1
-        RuleEditorField::Extension => (7, editor.cursor_extension), // line 6 + 1
-        RuleEditorField::NameGlob => (8, editor.cursor_name_glob), // line 7 + 1
-        RuleEditorField::NameRegex => (9, editor.cursor_name_regex), // line 8 + 1
-        RuleEditorField::SizeGreater => (10, editor.cursor_size_greater), // line 9 + 1
-        RuleEditorField::SizeLess => (11, editor.cursor_size_less), // line 10 + 1
-        RuleEditorField::AgeGreater => (12, editor.cursor_age_greater), // line 11 + 1
-        RuleEditorField::AgeLess => (13, editor.cursor_age_less), // line 12 + 1
-        RuleEditorField::ActionDestination => (19, editor.cursor_action_destination), // line 18 + 1
-        RuleEditorField::ActionPattern => (20, editor.cursor_action_pattern), // line 19 + 1
-        RuleEditorField::ActionCommand => (21, editor.cursor_action_command), // line 20 + 1
-        RuleEditorField::ActionArgs => (22, editor.cursor_action_args), // line 21 + 1
+    let cursor_offset = match editor.field {
+        RuleEditorField::Name => Some(editor.cursor_name),
+        RuleEditorField::Extension => Some(editor.cursor_extension),
+        RuleEditorField::NameGlob => Some(editor.cursor_name_glob),
+        RuleEditorField::NameRegex => Some(editor.cursor_name_regex),
+        RuleEditorField::SizeGreater => Some(editor.cursor_size_greater),
+        RuleEditorField::SizeLess => Some(editor.cursor_size_less),
+        RuleEditorField::AgeGreater => Some(editor.cursor_age_greater),
+        RuleEditorField::AgeLess => Some(editor.cursor_age_less),
+        RuleEditorField::ActionDestination => Some(editor.cursor_action_destination),
+        RuleEditorField::ActionPattern => Some(editor.cursor_action_pattern),
+        RuleEditorField::ActionCommand => Some(editor.cursor_action_command),
+        RuleEditorField::ActionArgs => Some(editor.cursor_action_args),
         // Non-text fields don't need cursor
+        _ => None,
+    };
+    // Row = line index + 1 (for border)
+    let (field_row, cursor_offset) = match (cursor_offset, rule_editor_line(editor, editor.field)) {
+        (Some(offset), Some(line)) => (line + 1, offset),
         _ => (0, 0),
     };
 
     if field_row > 0 {
         let cursor_x = popup_area.x + prefix_len + cursor_offset as u16;
         let cursor_y = popup_area.y + field_row;
-        if cursor_x < popup_area.x + popup_area.width - 1
-            && cursor_y < popup_area.y + popup_area.height - 1
+        if cursor_x < popup_area.right().saturating_sub(1)
+            && cursor_y < popup_area.bottom().saturating_sub(1)
         {
             frame.set_cursor_position((cursor_x, cursor_y));
         }
     }
 }
 
+/// Line index of a field in the rule editor content, accounting for section
+/// headers and fields hidden for the current action type
+fn rule_editor_line(editor: &RuleEditorState, field: RuleEditorField) -> Option<u16> {
+    // Line 0 is blank, line 1 the "Basic Info" header
+    let mut line = 1u16;
+    let mut f = RuleEditorField::Name;
+    loop {
+        // Blank line + header before the Conditions and Action sections
+        if matches!(f, RuleEditorField::Extension | RuleEditorField::ActionType) {
+            line += 2;
+        }
+        if editor.shows_field(f) {
+            line += 1;
+            if f == field {
+                return Some(line);
+            }
+        }
+        f = f.next();
+        if f == RuleEditorField::Name {
+            return None;
+        }
+    }
+}
+
 /// Returns contextual help text for each rule editor field
 fn field_help(field: RuleEditorField) -> &'static str {
     use RuleEditorField::*;
     match field {
         Name => "Type a descriptive name for this rule",
         Enabled => "Space/←→ to toggle on/off",
-        Extension => "e.g. 'pdf', 'jpg' — leave empty for any",
+        StopProcessing => "Space/←→ to toggle: skip later rules once this one matches",
+        Extension => "e.g. 'pdf', or 'jpg,png,gif' for any of several — leave empty for any",
         NameGlob => "Glob pattern, e.g. 'Screenshot*.png' or '*.tmp'",
         NameRegex => "Regex pattern, e.g. '^invoice_\\d+\\.pdf$'",
         SizeGreater => "Type bytes (e.g. 1048576 = 1MB) — files larger than this",
@@ -1333,6 +1882,8 @@ fn field_help(field: RuleEditorField) -> &'static str {
         IsHidden => "Space/←→ to cycle: Any → Yes → No",
         ActionType => "←→ or Space to change action type",
         ActionDestination => "Target folder path, e.g. ~/Documents/PDFs",
+        ActionOverwrite => "Space/←→ to toggle: replace a file of the same name",
+        ActionDeleteOriginal => "Space/←→ to toggle: remove the file once archived",
         ActionPattern => "Rename pattern, e.g. '{name}_{date}.{ext}'",
         ActionCommand => "Command to run, e.g. 'convert' or '/usr/bin/script.sh'",
         ActionArgs => "Arguments for the command, e.g. '-resize 50% {file}'",
@@ -1340,7 +1891,7 @@ fn field_help(field: RuleEditorField) -> &'static str {
 }
 
 fn render_watch_editor(frame: &mut Frame, state: &AppState) {
-    let colors = state.theme.colors();
+    let colors = state.colors();
     let area = frame.area();
 
     let Some(ref editor) = state.watch_editor else {
@@ -1354,11 +1905,9 @@ fn render_watch_editor(frame: &mut Frame, state: &AppState) {
     let popup_height = (base_height + rules_height).min(area.height.saturating_sub(4));
     let popup_width = 65u16.min(area.width.saturating_sub(4));
 
-    let popup_area = Rect {
-        x: (area.width - popup_width) / 2,
-        y: (area.height - popup_height) / 2,
-        width: popup_width,
-        height: popup_height,
+    let Some(popup_area) = fit_popup(popup_width, popup_height, WATCH_EDITOR_MIN_SIZE, area) else {
+        render_too_small(frame, state, WATCH_EDITOR_MIN_SIZE);
+        return;
     };
 
     // Clear the area
@@ -1497,7 +2046,7 @@ fn render_watch_editor(frame: &mut Frame, state: &AppState) {
         let cursor_x = popup_area.x + prefix_len + editor.cursor_path as u16;
         // Row: border (1) + empty line (1) + path line (1) = row 2 from popup top
         let cursor_y = popup_area.y + 2;
-        if cursor_x < popup_area.x + popup_area.width - 1 {
+        if cursor_x < popup_area.right().saturating_sub(1) {
             frame.set_cursor_position((cursor_x, cursor_y));
         }
     }
@@ -1513,10 +2062,14 @@ fn watch_field_help(field: WatchEditorField) -> &'static str {
 }
 
 fn render_about_dialog(frame: &mut Frame, state: &AppState) {
-    let colors = state.theme.colors();
+    let colors = state.colors();
     let area = frame.area();
 
     let popup_area = centered_rect(80, 60, area);
+    if popup_area.width < ABOUT_MIN_SIZE.0 || popup_area.height < ABOUT_MIN_SIZE.1 {
+        render_too_small(frame, state, ABOUT_MIN_SIZE);
+        return;
+    }
     frame.render_widget(Clear, popup_area);
 
     let version = env!("CARGO_PKG_VERSION");
@@ -1605,17 +2158,13 @@ fn render_about_dialog(frame: &mut Frame, state: &AppState) {
 }
 
 fn render_update_confirm_dialog(frame: &mut Frame, state: &AppState) {
-    let colors = state.theme.colors();
+    let colors = state.colors();
     let area = frame.area();
 
     // Center popup
-    let popup_width = 50;
-    let popup_height = 9;
-    let popup_area = Rect {
-        x: area.width.saturating_sub(popup_width) / 2,
-        y: area.height.saturating_sub(popup_height) / 2,
-        width: popup_width.min(area.width),
-        height: popup_height.min(area.height),
+    let Some(popup_area) = fit_popup(50, 9, CONFIRM_MIN_SIZE, area) else {
+        render_too_small(frame, state, CONFIRM_MIN_SIZE);
+        return;
     };
 
     // Clear area behind popup
@@ -1674,8 +2223,51 @@ fn render_update_confirm_dialog(frame: &mut Frame, state: &AppState) {
     frame.render_widget(paragraph, popup_area);
 }
 
+fn render_test_file_prompt(frame: &mut Frame, state: &AppState) {
+    let colors = state.colors();
+    let area = frame.area();
+
+    let Some(popup_area) = fit_popup(60, 5, PROMPT_MIN_SIZE, area) else {
+        render_too_small(frame, state, PROMPT_MIN_SIZE);
+        return;
+    };
+
+    frame.render_widget(Clear, popup_area);
+
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(" Path: ", colors.text_muted()),
+            Span::styled(&state.test_file_input, colors.text()),
+        ]),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(" Enter", colors.key_hint()),
+            Span::styled(": test  ", colors.text_muted()),
+            Span::styled("Esc", colors.key_hint()),
+            Span::styled(": cancel", colors.text_muted()),
+        ]),
+    ];
+
+    let prompt = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(colors.block_focus())
+            .style(Style::default().bg(colors.bg_secondary))
+            .title(" Which rules match this file? ")
+            .title_style(colors.text_primary()),
+    );
+    frame.render_widget(prompt, popup_area);
+
+    // Border (1) + " Path: " (7)
+    let cursor_x = popup_area.x + 8 + state.test_file_cursor as u16;
+    if cursor_x < popup_area.x + popup_area.width.saturating_sub(1) {
+        frame.set_cursor_position((cursor_x, popup_area.y + 1));
+    }
+}
+
 fn render_update_status(frame: &mut Frame, state: &AppState, status: &str) {
-    let colors = state.theme.colors();
+    let colors = state.colors();
     let area = frame.area();
 
     // Bottom banner
@@ -1708,7 +2300,7 @@ fn render_update_status(frame: &mut Frame, state: &AppState, status: &str) {
 }
 
 fn render_updating_overlay(frame: &mut Frame, state: &AppState) {
-    let colors = state.theme.colors();
+    let colors = state.colors();
     let area = frame.area();
 
     // Dim the background with semi-transparent overlay
@@ -1716,18 +2308,9 @@ fn render_updating_overlay(frame: &mut Frame, state: &AppState) {
     frame.render_widget(overlay, area);
 
     // Centered modal - use percentage-based sizing like Feedo
-    let popup_width = 40u16;
-    let popup_height = 5u16;
-
-    // Calculate centered position
-    let x = area.width.saturating_sub(popup_width) / 2;
-    let y = area.height.saturating_sub(popup_height) / 2;
-
-    let popup_area = Rect {
-        x,
-        y,
-        width: popup_width.min(area.width.saturating_sub(x)),
-        height: popup_height.min(area.height.saturating_sub(y)),
+    let Some(popup_area) = fit_popup(40, 5, PROMPT_MIN_SIZE, area) else {
+        render_too_small(frame, state, PROMPT_MIN_SIZE);
+        return;
     };
 
     // Clear the popup area first to ensure clean rendering
@@ -1756,3 +2339,228 @@ fn render_updating_overlay(frame: &mut Frame, state: &AppState) {
 
     frame.render_widget(paragraph, popup_area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tab_at_maps_columns_to_views() {
+        let area = Rect::new(0, 0, 80, 3);
+
+        // " ●  Dashboard " starts right after the left border
+        assert_eq!(tab_at(area, 1, 1), Some(View::Dashboard));
+        assert_eq!(tab_at(area, 13, 1), Some(View::Dashboard));
+        // Divider between Dashboard and Rules
+        assert_eq!(tab_at(area, 15, 1), None);
+        assert_eq!(tab_at(area, 18, 1), Some(View::Rules));
+        assert_eq!(tab_at(area, 31, 1), Some(View::Watches));
+        assert_eq!(tab_at(area, 46, 1), Some(View::Log));
+        assert_eq!(tab_at(area, 60, 1), None);
+
+        // Border rows and column are not tabs
+        assert_eq!(tab_at(area, 5, 0), None);
+        assert_eq!(tab_at(area, 0, 1), None);
+    }
+
+    fn state_with_rules(count: usize) -> AppState {
+        let mut config = crate::Config::default();
+        config.rules = (0..count)
+            .map(|i| {
+                crate::Rule::new(
+                    format!("Rule {}", i),
+                    crate::Condition::default(),
+                    crate::Action::Nothing,
+                )
+            })
+            .collect();
+        let mut state = AppState::new(config, Theme::default());
+        state.view = View::Rules;
+        state
+    }
+
+    #[test]
+    fn test_rules_list_scrolls_with_selection() {
+        let mut state = state_with_rules(50);
+        let backend = ratatui::backend::TestBackend::new(80, 20);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+
+        state.selected_rule = Some(0);
+        terminal.draw(|frame| render(frame, &mut state)).unwrap();
+        assert_eq!(state.rules_list_state.offset(), 0);
+
+        // Moving past the viewport advances the offset so the selection stays visible
+        state.selected_rule = Some(40);
+        terminal.draw(|frame| render(frame, &mut state)).unwrap();
+        let offset = state.rules_list_state.offset();
+        assert!(offset > 0);
+        // 20 rows minus tabs (3), status bar (1) and list borders (2)
+        assert!(40 >= offset && 40 < offset + 14);
+
+        // Moving back up scrolls back
+        state.selected_rule = Some(2);
+        terminal.draw(|frame| render(frame, &mut state)).unwrap();
+        assert!(state.rules_list_state.offset() <= 2);
+    }
+
+    #[test]
+    fn test_list_index_at_accounts_for_border_and_offset() {
+        let area = Rect::new(0, 3, 80, 10);
+
+        assert_eq!(list_index_at(area, 3, 0, 5), None); // top border
+        assert_eq!(list_index_at(area, 4, 0, 5), Some(0));
+        assert_eq!(list_index_at(area, 8, 0, 5), Some(4));
+        assert_eq!(list_index_at(area, 9, 0, 5), None); // past last item
+        assert_eq!(list_index_at(area, 12, 0, 20), None); // bottom border
+        assert_eq!(list_index_at(area, 4, 7, 20), Some(7));
+    }
+
+    #[test]
+    fn test_format_ago() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert_eq!(format_ago(now), "just now");
+        assert_eq!(format_ago(now - 180), "3m ago");
+        assert_eq!(format_ago(now - 7200), "2h ago");
+        assert_eq!(format_ago(now - 3 * 86400), "3d ago");
+        // Clock skew shouldn't underflow
+        assert_eq!(format_ago(now + 60), "just now");
+    }
+
+    #[test]
+    fn test_theme_preview_uses_highlighted_palette() {
+        let mut state = AppState::new(crate::Config::default(), Theme::default());
+        state.mode = Mode::ThemePicker;
+        let backend = ratatui::backend::TestBackend::new(100, 30);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+
+        for (i, name) in Theme::all().iter().enumerate() {
+            state.theme_picker_index = i;
+            assert_eq!(state.theme_picker_selection(), Theme::from(*name));
+
+            terminal.draw(|frame| render(frame, &mut state)).unwrap();
+            let popup = centered_rect(80, 70, Rect::new(0, 0, 100, 30));
+            // First cell inside the preview pane's border
+            let cell = terminal
+                .backend()
+                .buffer()
+                .cell((popup.x + popup.width / 2 + 1, popup.y + 1))
+                .unwrap()
+                .clone();
+            assert_eq!(cell.bg, name.palette().bg);
+        }
+    }
+
+    #[test]
+    fn test_config_error_screen_shows_location_and_snippet() {
+        let content = "[general]\ndebounce_seconds = \"soon\"\n";
+        let err = toml::from_str::<crate::Config>(content).unwrap_err();
+        let report = crate::config::ConfigParseError::new(content, &err);
+
+        let backend = ratatui::backend::TestBackend::new(80, 20);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+        terminal
+            .draw(|frame| {
+                render_config_error(
+                    frame,
+                    std::path::Path::new("/tmp/config.toml"),
+                    &report,
+                    None,
+                )
+            })
+            .unwrap();
+
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(text.contains("/tmp/config.toml"));
+        assert!(text.contains("line 2, column 20"));
+        assert!(text.contains("2 │ debounce_seconds = \"soon\""));
+        assert!(text.contains("[q] Quit"));
+    }
+
+    #[test]
+    fn test_rule_editor_cursor_follows_contextual_rows() {
+        use crate::app::state::{ActionTypeSelection, RuleEditorState};
+
+        let backend = ratatui::backend::TestBackend::new(100, 40);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+        let mut state = AppState::new(crate::Config::default(), Theme::default());
+        state.mode = Mode::AddRule;
+
+        for action_type in [
+            ActionTypeSelection::Move,
+            ActionTypeSelection::Archive,
+            ActionTypeSelection::Run,
+        ] {
+            state.rule_editor = Some(RuleEditorState {
+                field: RuleEditorField::ActionCommand,
+                action_type,
+                ..RuleEditorState::new_rule()
+            });
+            terminal.draw(|frame| render(frame, &mut state)).unwrap();
+            let cursor = terminal.get_cursor_position().unwrap();
+
+            let buffer = terminal.backend().buffer();
+            let row: String = (0..buffer.area.width)
+                .map(|x| buffer[(x, cursor.y)].symbol().to_string())
+                .collect();
+            assert!(row.contains("Command:"), "{:?}: {}", action_type, row);
+        }
+    }
+
+    #[test]
+    fn test_popup_centering_in_tiny_areas() {
+        // Fits: centered, offset by the area's origin
+        assert_eq!(
+            fit_popup(20, 6, (10, 4), Rect::new(5, 2, 40, 10)),
+            Some(Rect::new(15, 4, 20, 6))
+        );
+        // Shrunk to the area, as long as the minimum still fits
+        assert_eq!(
+            fit_popup(70, 28, (10, 4), Rect::new(0, 0, 12, 5)),
+            Some(Rect::new(0, 0, 12, 5))
+        );
+        for area in [
+            Rect::new(0, 0, 0, 0),
+            Rect::new(0, 0, 1, 1),
+            Rect::new(3, 3, 9, 3),
+        ] {
+            assert_eq!(fit_popup(70, 28, (10, 4), area), None);
+        }
+
+        for area in [Rect::new(0, 0, 0, 0), Rect::new(2, 2, 1, 1)] {
+            let rect = centered_rect(80, 70, area);
+            assert!(rect.width <= area.width && rect.height <= area.height);
+            assert!(rect.x >= area.x && rect.y >= area.y);
+        }
+        // Percentages of a very wide terminal don't overflow
+        assert_eq!(centered_rect(80, 50, Rect::new(0, 0, 1000, 10)).width, 800);
+    }
+
+    #[test]
+    fn test_dialogs_in_a_tiny_terminal_show_a_message() {
+        use crate::app::state::RuleEditorState;
+
+        for (width, height) in [(1, 1), (20, 6), (60, 12)] {
+            let backend = ratatui::backend::TestBackend::new(width, height);
+            let mut terminal = ratatui::Terminal::new(backend).unwrap();
+            let mut state = AppState::new(crate::Config::default(), Theme::default());
+            state.mode = Mode::AddRule;
+            state.rule_editor = Some(RuleEditorState::new_rule());
+            terminal.draw(|frame| render(frame, &mut state)).unwrap();
+
+            let buffer = terminal.backend().buffer();
+            let text: String = buffer.content().iter().map(|c| c.symbol()).collect();
+            if width >= 20 {
+                assert!(text.contains("Terminal too small"), "{}", text);
+            }
+        }
+    }
+}
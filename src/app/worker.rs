@@ -0,0 +1,300 @@
+//! Background worker that runs the embedded watcher off the UI thread
+//!
+//! Rule actions can take a while (copying a large file, running a command),
+//! so the embedded watcher polls and processes events on its own thread and
+//! reports each pass back to the UI over a channel.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::Watcher;
+use crate::watcher::{ProcessorLock, WatchStats};
+
+/// How long the worker sleeps between polls when there is nothing to do
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Outcome of one processing pass, sent from the worker to the UI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkerMsg {
+    /// A batch of events was processed
+    Processed {
+        /// Number of files a rule was applied to
+        count: usize,
+        /// Most recent rule processing error, if any
+        last_error: Option<String>,
+        /// Unix timestamp of the last file a rule was applied to
+        last_event_at: Option<u64>,
+        /// Files handled per watch folder, keyed by expanded path
+        watch_stats: HashMap<PathBuf, WatchStats>,
+        /// Activity log entries for what the rules did
+        log: Vec<crate::ipc::LogEntry>,
+    },
+    /// Polling or processing failed as a whole
+    Failed(String),
+}
+
+/// Handle to the embedded watcher's worker thread.
+///
+/// Dropping the handle asks the thread to stop after its current pass; it is
+/// not joined, so the UI never waits on a slow action. The thread keeps its
+/// share of the processor lock until it has stopped, so a daemon waiting for
+/// the lock never overlaps with an action still in flight.
+pub struct WatcherWorker {
+    rx: mpsc::Receiver<WorkerMsg>,
+    stop: Arc<AtomicBool>,
+}
+
+impl WatcherWorker {
+    /// Move the watcher onto a new thread and start processing its events
+    pub fn spawn(mut watcher: Watcher, lock: Option<Arc<ProcessorLock>>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+
+        thread::spawn(move || {
+            let _lock = lock;
+            while !thread_stop.load(Ordering::Relaxed) {
+                let events = match watcher.poll() {
+                    Ok(events) => events,
+                    Err(e) => {
+                        if tx.send(WorkerMsg::Failed(e.to_string())).is_err() {
+                            break;
+                        }
+                        Vec::new()
+                    }
+                };
+                if events.is_empty() && !watcher.has_pending_work() {
+                    thread::sleep(IDLE_POLL_INTERVAL);
+                    continue;
+                }
+
+                let msg = match watcher.process_polled_events(events) {
+                    Ok(count) => WorkerMsg::Processed {
+                        count,
+                        last_error: watcher.last_error().map(str::to_string),
+                        last_event_at: watcher.last_event_at(),
+                        watch_stats: watcher.watch_stats(),
+                        log: activity_entries(&mut watcher),
+                    },
+                    Err(e) => WorkerMsg::Failed(e.to_string()),
+                };
+                // The UI went away; nothing left to report to
+                if tx.send(msg).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { rx, stop }
+    }
+
+    /// Collect every result reported since the last call, without blocking
+    pub fn drain(&self) -> Vec<WorkerMsg> {
+        self.rx.try_iter().collect()
+    }
+}
+
+impl Drop for WatcherWorker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Log entries for the files the watcher handled since the last call, with
+/// bursts summarized the way the daemon logs them
+fn activity_entries(watcher: &mut Watcher) -> Vec<crate::ipc::LogEntry> {
+    let mut entries = Vec::new();
+    for group in crate::watcher::coalesce_activity(watcher.take_activity()) {
+        if group.is_burst() {
+            entries.push(crate::ipc::LogEntry::batch(&group));
+            continue;
+        }
+        for path in group.files {
+            entries.push(crate::ipc::LogEntry::processed(
+                path,
+                group.rule_name.clone(),
+                &group.action,
+            ));
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{Action, Condition, Rule};
+    use crate::{RuleEngine, Watcher};
+    use std::time::Instant;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ui_keeps_ticking_while_processing_is_in_flight() {
+        let dir = tempfile::tempdir().unwrap();
+        // A deliberately slow action (`sleep` only exists on Unix)
+        let engine = RuleEngine::new(vec![Rule::new(
+            "Slow",
+            Condition {
+                extension: Some("txt".to_string()),
+                ..Default::default()
+            },
+            Action::Run {
+                command: "sleep".to_string(),
+                args: vec!["1".to_string()],
+            },
+        )]);
+        let mut watcher = Watcher::new(engine, 1, 0).unwrap();
+        watcher.watch(dir.path(), false).unwrap();
+        let worker = WatcherWorker::spawn(watcher, None);
+
+        std::fs::write(dir.path().join("big.txt"), "data").unwrap();
+
+        let start = Instant::now();
+        let mut ticks = 0;
+        let mut results = Vec::new();
+        while start.elapsed() < Duration::from_secs(10) {
+            let tick = Instant::now();
+            results.extend(worker.drain());
+            // Draining never blocks on the worker
+            assert!(tick.elapsed() < Duration::from_millis(50));
+            if results
+                .iter()
+                .any(|m| matches!(m, WorkerMsg::Processed { count, .. } if *count > 0))
+            {
+                break;
+            }
+            ticks += 1;
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        // The UI loop kept running for the second the action took
+        assert!(ticks > 20, "only {} ticks while processing", ticks);
+        match results
+            .iter()
+            .find(|m| matches!(m, WorkerMsg::Processed { count, .. } if *count > 0))
+        {
+            Some(WorkerMsg::Processed {
+                count,
+                last_error,
+                last_event_at,
+                watch_stats,
+                log,
+            }) => {
+                assert_eq!(*count, 1);
+                assert!(last_error.is_none());
+                assert!(last_event_at.is_some());
+                assert_eq!(log.len(), 1);
+                assert_eq!(log[0].rule.as_deref(), Some("Slow"));
+                // The initial scan may have run the rule on the file too
+                assert!(watch_stats[dir.path()].files_processed >= 1);
+            }
+            other => panic!("expected a processed result, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_worker_reports_processing_errors() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        std::fs::write(dest.path().join("clash.txt"), "old").unwrap();
+
+        let engine = RuleEngine::new(vec![Rule::new(
+            "Move text",
+            Condition {
+                extension: Some("txt".to_string()),
+                ..Default::default()
+            },
+            Action::Move {
+                destination: dest.path().to_path_buf(),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+            },
+        )]);
+        let mut watcher = Watcher::new(engine, 1, 0).unwrap();
+        watcher.watch(src.path(), false).unwrap();
+        let worker = WatcherWorker::spawn(watcher, None);
+
+        std::fs::write(src.path().join("clash.txt"), "new").unwrap();
+
+        let start = Instant::now();
+        let error = loop {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "no error reported"
+            );
+            let reported = worker.drain().into_iter().find_map(|msg| match msg {
+                WorkerMsg::Processed { last_error, .. } => last_error,
+                WorkerMsg::Failed(e) => Some(e),
+            });
+            if let Some(error) = reported {
+                break error;
+            }
+            thread::sleep(Duration::from_millis(20));
+        };
+        assert!(error.contains("clash.txt"));
+    }
+
+    #[test]
+    fn test_worker_releases_lock_after_stopping() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock_path = dir.path().join("processor.lock");
+        let lock = Arc::new(ProcessorLock::try_acquire(&lock_path).unwrap().unwrap());
+
+        let watcher = Watcher::new(RuleEngine::new(Vec::new()), 1, 0).unwrap();
+        let worker = WatcherWorker::spawn(watcher, Some(Arc::clone(&lock)));
+
+        // The UI lets go of its share; the worker still holds the lock
+        drop(lock);
+        assert!(ProcessorLock::try_acquire(&lock_path).unwrap().is_none());
+
+        // Handing off: the lock frees up once the thread finishes its pass
+        drop(worker);
+        let handed_off =
+            ProcessorLock::acquire_timeout(&lock_path, Duration::from_secs(5)).unwrap();
+        assert!(handed_off.is_some());
+    }
+
+    #[test]
+    fn test_quiet_rules_stay_out_of_the_activity_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let rule = |name: &str, extension: &str, quiet| {
+            let mut rule = Rule::new(
+                name,
+                Condition {
+                    extension: Some(extension.to_string()),
+                    ..Default::default()
+                },
+                Action::Nothing,
+            );
+            rule.quiet = quiet;
+            rule
+        };
+        let engine = RuleEngine::new(vec![
+            rule("Noisy", "txt", false),
+            rule("Quiet", "log", true),
+        ]);
+        let mut watcher = Watcher::new(engine, 1, 0).unwrap();
+
+        let events = ["a.txt", "b.log"]
+            .map(|name| {
+                let path = dir.path().join(name);
+                std::fs::write(&path, name).unwrap();
+                notify::Event::new(notify::EventKind::Modify(notify::event::ModifyKind::Any))
+                    .add_path(path)
+            })
+            .to_vec();
+        assert_eq!(watcher.process_polled_events(events).unwrap(), 2);
+
+        let log = activity_entries(&mut watcher);
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].rule.as_deref(), Some("Noisy"));
+    }
+}
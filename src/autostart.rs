@@ -3,17 +3,26 @@
 //! Supports:
 //! - macOS: LaunchAgent plist
 //! - Linux: systemd user service
+//! - Windows: `HKCU\...\Run` registry value. The daemon is Unix-only, so
+//!   Windows runs `hazelnut run --apply` at login to tidy the watch folders.
 
+#[cfg(not(windows))]
 use std::fs;
 use std::io;
+#[cfg(not(windows))]
 use std::path::PathBuf;
 
+#[cfg(windows)]
+pub use registry::{disable, enable, is_enabled};
+
 /// Check if auto-start is currently enabled
+#[cfg(not(windows))]
 pub fn is_enabled() -> bool {
     get_autostart_path().map(|p| p.exists()).unwrap_or(false)
 }
 
 /// Enable auto-start for the daemon
+#[cfg(not(windows))]
 pub fn enable() -> io::Result<()> {
     let path = get_autostart_path().ok_or_else(|| {
         io::Error::new(
@@ -30,6 +39,10 @@ pub fn enable() -> io::Result<()> {
     let content = get_autostart_content()?;
     fs::write(&path, content)?;
 
+    // On macOS, load the agent now so it doesn't wait for the next login
+    #[cfg(target_os = "macos")]
+    launchctl("load", &path)?;
+
     // On Linux with systemd, reload the daemon
     #[cfg(target_os = "linux")]
     {
@@ -42,6 +55,7 @@ pub fn enable() -> io::Result<()> {
 }
 
 /// Disable auto-start for the daemon
+#[cfg(not(windows))]
 pub fn disable() -> io::Result<()> {
     let path = get_autostart_path().ok_or_else(|| {
         io::Error::new(
@@ -51,6 +65,10 @@ pub fn disable() -> io::Result<()> {
     })?;
 
     if path.exists() {
+        // The agent may not be loaded (e.g. enabled before a reboot), so
+        // unloading is allowed to fail
+        #[cfg(target_os = "macos")]
+        let _ = launchctl("unload", &path);
         fs::remove_file(&path)?;
     }
 
@@ -77,13 +95,14 @@ pub fn toggle() -> io::Result<bool> {
 }
 
 /// Get the path to the autostart file for the current platform
+#[cfg(not(windows))]
 fn get_autostart_path() -> Option<PathBuf> {
     #[cfg(target_os = "macos")]
     {
         dirs::home_dir().map(|h| {
             h.join("Library")
                 .join("LaunchAgents")
-                .join("me.ricardodantas.hazelnutd.plist")
+                .join(format!("{}.plist", LAUNCH_AGENT_LABEL))
         })
     }
 
@@ -114,36 +133,13 @@ fn get_autostart_path() -> Option<PathBuf> {
 }
 
 /// Get the content for the autostart file
+#[cfg(not(windows))]
 fn get_autostart_content() -> io::Result<String> {
     let binary_path = get_daemon_binary_path()?;
 
     #[cfg(target_os = "macos")]
     {
-        Ok(format!(
-            r#"<?xml version="1.0" encoding="UTF-8"?>
-<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
-<plist version="1.0">
-<dict>
-    <key>Label</key>
-    <string>me.ricardodantas.hazelnutd</string>
-    <key>ProgramArguments</key>
-    <array>
-        <string>{}</string>
-        <string>run</string>
-    </array>
-    <key>RunAtLoad</key>
-    <true/>
-    <key>KeepAlive</key>
-    <false/>
-    <key>StandardOutPath</key>
-    <string>/tmp/hazelnutd.stdout.log</string>
-    <key>StandardErrorPath</key>
-    <string>/tmp/hazelnutd.stderr.log</string>
-</dict>
-</plist>
-"#,
-            binary_path.display()
-        ))
+        Ok(launch_agent_plist(&binary_path))
     }
 
     #[cfg(target_os = "linux")]
@@ -189,7 +185,71 @@ X-GNOME-Autostart-enabled=true
     }
 }
 
+/// Label of the macOS LaunchAgent, also its plist file name
+#[cfg(any(target_os = "macos", test))]
+const LAUNCH_AGENT_LABEL: &str = "me.ricardodantas.hazelnutd";
+
+/// LaunchAgent plist that starts the daemon at login
+#[cfg(any(target_os = "macos", test))]
+fn launch_agent_plist(binary_path: &std::path::Path) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>run</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <false/>
+    <key>StandardOutPath</key>
+    <string>/tmp/hazelnutd.stdout.log</string>
+    <key>StandardErrorPath</key>
+    <string>/tmp/hazelnutd.stderr.log</string>
+</dict>
+</plist>
+"#,
+        LAUNCH_AGENT_LABEL,
+        xml_escape(&binary_path.display().to_string())
+    )
+}
+
+/// Escape text for use inside an XML element
+#[cfg(any(target_os = "macos", test))]
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Run `launchctl <command> <plist>`, failing if launchctl reports an error
+#[cfg(target_os = "macos")]
+fn launchctl(command: &str, plist: &std::path::Path) -> io::Result<()> {
+    let output = std::process::Command::new("launchctl")
+        .arg(command)
+        .arg(plist)
+        .output()?;
+    // launchctl load/unload exits 0 even on some failures, reporting them on stderr
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.status.success() && stderr.trim().is_empty() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "launchctl {} failed: {}",
+            command,
+            stderr.trim()
+        )))
+    }
+}
+
 /// Find the daemon binary path
+#[cfg(not(windows))]
 fn get_daemon_binary_path() -> io::Result<PathBuf> {
     // First try to find hazelnutd in PATH
     if let Ok(output) = std::process::Command::new("which")
@@ -241,3 +301,128 @@ fn is_systemd_available() -> bool {
         .map(|o| o.status.success())
         .unwrap_or(false)
 }
+
+/// Auto-start through the current user's `Run` registry key, edited with
+/// `reg.exe` so no registry crate is needed
+#[cfg(windows)]
+mod registry {
+    use std::io;
+    use std::process::Command;
+
+    const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+
+    /// Name of the value under the `Run` key
+    const VALUE_NAME: &str = "Hazelnut";
+
+    /// Check if auto-start is currently enabled
+    pub fn is_enabled() -> bool {
+        query(RUN_KEY, VALUE_NAME).is_some()
+    }
+
+    /// Enable auto-start: run `hazelnut run --apply` at login
+    pub fn enable() -> io::Result<()> {
+        set(RUN_KEY, VALUE_NAME, &login_command()?)
+    }
+
+    /// Disable auto-start
+    pub fn disable() -> io::Result<()> {
+        if is_enabled() {
+            delete(RUN_KEY, VALUE_NAME)?;
+        }
+        Ok(())
+    }
+
+    /// The command line stored in the registry, for the running executable
+    fn login_command() -> io::Result<String> {
+        let exe = std::env::current_exe()?;
+        Ok(format!("\"{}\" run --apply", exe.display()))
+    }
+
+    /// Data of the value `name` under `key`, if it exists
+    pub(super) fn query(key: &str, name: &str) -> Option<String> {
+        let output = Command::new("reg")
+            .args(["query", key, "/v", name])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        parse_query(&String::from_utf8_lossy(&output.stdout), name)
+    }
+
+    /// Create or replace the value `name` under `key`, creating the key if needed
+    pub(super) fn set(key: &str, name: &str, command: &str) -> io::Result<()> {
+        run_reg(&["add", key, "/v", name, "/t", "REG_SZ", "/d", command, "/f"])
+    }
+
+    /// Remove the value `name` under `key`
+    pub(super) fn delete(key: &str, name: &str) -> io::Result<()> {
+        run_reg(&["delete", key, "/v", name, "/f"])
+    }
+
+    pub(super) fn run_reg(args: &[&str]) -> io::Result<()> {
+        let output = Command::new("reg").args(args).output()?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ))
+        }
+    }
+
+    /// Pick the data out of `reg query` output, whose value lines look like
+    /// `    Hazelnut    REG_SZ    "C:\...\hazelnut.exe" run --apply`
+    pub(super) fn parse_query(output: &str, name: &str) -> Option<String> {
+        output.lines().find_map(|line| {
+            let rest = line.trim_start().strip_prefix(name)?;
+            let (_, data) = rest.split_once("REG_SZ")?;
+            Some(data.trim().to_string())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_launch_agent_plist() {
+        let plist = launch_agent_plist(std::path::Path::new("/opt/R&D/bin/hazelnutd"));
+        assert!(plist.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+        assert!(
+            plist.contains("<key>Label</key>\n    <string>me.ricardodantas.hazelnutd</string>")
+        );
+        assert!(plist.contains(
+            "<array>\n        <string>/opt/R&amp;D/bin/hazelnutd</string>\n        <string>run</string>\n    </array>"
+        ));
+        assert!(plist.contains("<key>RunAtLoad</key>\n    <true/>"));
+        assert!(plist.trim_end().ends_with("</plist>"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_parse_reg_query_output() {
+        let output = "\r\nHKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Run\r\n    Hazelnut    REG_SZ    \"C:\\Tools\\hazelnut.exe\" run --apply\r\n\r\n";
+        assert_eq!(
+            registry::parse_query(output, "Hazelnut").as_deref(),
+            Some("\"C:\\Tools\\hazelnut.exe\" run --apply")
+        );
+        assert_eq!(registry::parse_query(output, "Other"), None);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_run_value_round_trip() {
+        // A scratch key of its own, so the real Run key is never written
+        let key = format!(r"HKCU\Software\HazelnutTest\{}", std::process::id());
+        let command = "\"C:\\Program Files\\hazelnut.exe\" run --apply";
+
+        registry::set(&key, "Hazelnut", command).unwrap();
+        assert_eq!(registry::query(&key, "Hazelnut").as_deref(), Some(command));
+
+        registry::delete(&key, "Hazelnut").unwrap();
+        assert_eq!(registry::query(&key, "Hazelnut"), None);
+        registry::run_reg(&["delete", &key, "/f"]).unwrap();
+    }
+}
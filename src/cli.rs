@@ -0,0 +1,462 @@
+//! Helpers behind the `hazelnut` command-line subcommands
+
+use anyhow::{Context, Result};
+use std::fmt;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::rules::{Action, Condition, FileInUse, Rule, RuleEngine};
+
+/// Tally of what a one-shot `hazelnut run` did
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RunSummary {
+    /// Actions executed successfully (or that would be, in dry-run)
+    pub applied: usize,
+    /// Actions not attempted because an earlier action moved the file or failed
+    pub skipped: usize,
+    /// Files whose action (or rule matching) returned an error, with the error
+    pub failures: Vec<(PathBuf, String)>,
+}
+
+impl RunSummary {
+    /// Number of actions that returned an error
+    pub fn failed(&self) -> usize {
+        self.failures.len()
+    }
+
+    /// Process exit code for this run: non-zero if any action failed
+    pub fn exit_code(&self) -> i32 {
+        if self.failures.is_empty() { 0 } else { 1 }
+    }
+
+    /// Count a failed file, printing it as it happens
+    fn fail(&mut self, path: &Path, error: &anyhow::Error) {
+        let error = format!("{:#}", error);
+        eprintln!("  ✗ {}: {}", path.display(), error);
+        self.failures.push((path.to_path_buf(), error));
+    }
+}
+
+/// The counts on one line, then each failure with its error
+impl fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Applied {} action{}, {} skipped, {} failed",
+            self.applied,
+            if self.applied == 1 { "" } else { "s" },
+            self.skipped,
+            self.failed()
+        )?;
+        for (path, error) in &self.failures {
+            write!(f, "\n  ✗ {}: {}", path.display(), error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run the rules once over the files inside `dir`, including subfolders
+/// when `recursive` is set.
+///
+/// Without `apply`, the actions `RuleEngine::dry_evaluate_dir` finds are
+/// only printed. Failures are counted rather than aborting the run so the
+/// summary covers every file.
+pub fn run_dir(
+    engine: &RuleEngine,
+    dir: &Path,
+    recursive: bool,
+    apply: bool,
+    summary: &mut RunSummary,
+) -> Result<()> {
+    println!("Processing: {}", dir.display());
+    if !apply {
+        for (path, matched) in engine.dry_evaluate_dir(dir, recursive)? {
+            if let Some((_, action)) = matched {
+                println!("  [dry-run] {} {}", path.display(), action.preview());
+                summary.applied += 1;
+            }
+        }
+        return Ok(());
+    }
+
+    // The full listing is taken up front, so files moved within the tree aren't seen twice
+    let entries: Box<dyn Iterator<Item = std::fs::DirEntry>> = if recursive {
+        let walk = crate::watcher::walkdir(dir)
+            .with_context(|| format!("Failed to read directory: {}", dir.display()))?;
+        for (path, e) in &walk.skipped {
+            eprintln!("  ⚠ Skipped {}: {}", path.display(), e);
+        }
+        if !walk.skipped.is_empty() {
+            eprintln!("  ⚠ {} unreadable path(s) skipped", walk.skipped.len());
+        }
+        Box::new(walk.entries.into_iter())
+    } else {
+        Box::new(
+            std::fs::read_dir(dir)
+                .with_context(|| format!("Failed to read directory: {}", dir.display()))?
+                .flatten(),
+        )
+    };
+
+    for entry in entries {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+
+        let rules = match engine.matching_rules(&path, None, Some(dir)) {
+            Ok(rules) => rules,
+            Err(e) => {
+                summary.fail(&path, &e);
+                continue;
+            }
+        };
+
+        let mut remaining = rules.iter();
+        for rule in remaining.by_ref() {
+            let action = &rule.action;
+            println!("  Applying: {} {}", path.display(), action.preview());
+            if let Err(e) = engine.execute_rule(rule, &path, Some(dir)) {
+                if e.downcast_ref::<FileInUse>().is_some() {
+                    println!("  Skipped (in use): {}", path.display());
+                    summary.skipped += 1;
+                    break;
+                }
+                summary.fail(&path, &e);
+                break;
+            }
+            summary.applied += 1;
+            if action.removes_source() {
+                break;
+            }
+        }
+        summary.skipped += remaining.count();
+    }
+
+    Ok(())
+}
+
+/// Listing printed by `hazelnut list`. With `verbose`, each rule is followed
+/// by its conditions and action.
+pub fn format_rules(rules: &[Rule], verbose: bool) -> String {
+    let mut out = String::from("Rules:\n");
+    for (i, rule) in rules.iter().enumerate() {
+        let status = if rule.enabled { "✓" } else { "✗" };
+        out.push_str(&format!("  {} [{}] {}\n", status, i + 1, rule.name));
+        // Shown even without --verbose: these rules destroy files
+        if rule.action.is_unacknowledged_delete() {
+            out.push_str(
+                "      ⚠ DELETE not acknowledged: won't run without i_understand_delete_is_permanent = true\n",
+            );
+        } else if matches!(rule.action, Action::Delete { .. }) {
+            out.push_str("      ⚠ PERMANENTLY deletes matching files\n");
+        }
+        if !verbose {
+            continue;
+        }
+        let conditions = condition_fields(&rule.condition);
+        if conditions.is_empty() {
+            out.push_str("      when: any file\n");
+        }
+        for field in conditions {
+            out.push_str(&format!("      when: {}\n", field));
+        }
+        if let Some(ref budget) = rule.budget {
+            let mut limits = Vec::new();
+            limits.extend(budget.max_bytes.map(|n| format!("{} bytes", n)));
+            limits.extend(budget.max_files.map(|n| format!("{} files", n)));
+            out.push_str(&format!(
+                "      when: {} is over {}, oldest files first\n",
+                budget.path.display(),
+                limits.join(" or ")
+            ));
+        }
+        out.push_str(&format!("      then: {}\n", rule.action.preview()));
+        if rule.stop_processing {
+            out.push_str("      stop processing further rules\n");
+        }
+    }
+    out
+}
+
+/// The condition fields a rule sets, as `key = value` in config syntax
+fn condition_fields(condition: &Condition) -> Vec<String> {
+    let Ok(toml::Value::Table(table)) = toml::Value::try_from(condition) else {
+        return Vec::new();
+    };
+    table
+        .into_iter()
+        .filter(|(_, value)| !matches!(value, toml::Value::Array(items) if items.is_empty()))
+        .map(|(key, value)| format!("{} = {}", key, value))
+        .collect()
+}
+
+/// How often `follow_log` checks the log file for new output
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The last `count` lines of a log file, oldest first, and the file length
+/// they end at. Reads backwards from the end, so large logs stay cheap.
+pub fn tail_lines(path: &Path, count: usize) -> Result<(Vec<String>, u64)> {
+    const BLOCK: u64 = 8192;
+
+    let mut file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open log {}", path.display()))?;
+    let len = file.metadata()?.len();
+    let mut start = len;
+    let mut buf = Vec::new();
+    // One extra newline: the first line read may be partial
+    while start > 0 && buf.iter().filter(|&&b| b == b'\n').count() <= count {
+        let step = BLOCK.min(start);
+        start -= step;
+        let mut block = vec![0; step as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut block)?;
+        block.extend_from_slice(&buf);
+        buf = block;
+    }
+
+    let text = String::from_utf8_lossy(&buf);
+    let lines: Vec<&str> = text.lines().collect();
+    let skip = lines.len().saturating_sub(count);
+    Ok((lines[skip..].iter().map(|l| l.to_string()).collect(), len))
+}
+
+/// Print lines appended to a log file after byte `position`, like `tail -f`.
+/// Starts over when the file is truncated or replaced. Runs until interrupted.
+pub fn follow_log(path: &Path, mut position: u64, out: &mut impl Write) -> Result<()> {
+    loop {
+        let len = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        if len < position {
+            position = 0;
+        }
+        if len > position {
+            let mut file = std::fs::File::open(path)
+                .with_context(|| format!("Failed to open log {}", path.display()))?;
+            file.seek(SeekFrom::Start(position))?;
+            let mut chunk = Vec::new();
+            file.take(len - position).read_to_end(&mut chunk)?;
+            position += chunk.len() as u64;
+            out.write_all(&chunk)?;
+            out.flush()?;
+        }
+        std::thread::sleep(FOLLOW_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn txt_rule(name: &str, action: Action) -> Rule {
+        Rule::new(
+            name,
+            Condition {
+                extension: Some("txt".to_string()),
+                ..Default::default()
+            },
+            action,
+        )
+    }
+
+    #[test]
+    fn test_run_dir_summary_counts() {
+        let src = tempfile::tempdir().unwrap();
+        let moved = tempfile::tempdir().unwrap();
+        let copied = tempfile::tempdir().unwrap();
+
+        std::fs::write(src.path().join("a.txt"), "a").unwrap();
+        std::fs::write(src.path().join("b.txt"), "b").unwrap();
+        std::fs::write(src.path().join("c.md"), "c").unwrap();
+        // b.txt collides at the destination, so its move fails
+        std::fs::write(moved.path().join("b.txt"), "existing").unwrap();
+
+        let engine = RuleEngine::new(vec![
+            txt_rule(
+                "Move text",
+                Action::Move {
+                    destination: moved.path().to_path_buf(),
+                    create_destination: true,
+                    overwrite: false,
+                    preserve_structure: false,
+                    skip_if_open: false,
+                    skip_if_identical: false,
+                },
+            ),
+            txt_rule(
+                "Copy text",
+                Action::Copy {
+                    destination: copied.path().to_path_buf(),
+                    create_destination: true,
+                    overwrite: false,
+                    preserve_structure: false,
+                    skip_if_open: false,
+                    skip_if_identical: false,
+                    preserve_timestamps: false,
+                },
+            ),
+        ]);
+
+        let mut summary = RunSummary::default();
+        run_dir(&engine, src.path(), false, true, &mut summary).unwrap();
+
+        assert_eq!(
+            (summary.applied, summary.skipped, summary.failed()),
+            (1, 2, 1)
+        );
+        assert_eq!(summary.failures[0].0, src.path().join("b.txt"));
+        assert_eq!(summary.exit_code(), 1);
+        assert_eq!(
+            summary.to_string(),
+            format!(
+                "Applied 1 action, 2 skipped, 1 failed\n  ✗ {}: {}",
+                src.path().join("b.txt").display(),
+                summary.failures[0].1
+            )
+        );
+        assert!(summary.failures[0].1.contains("exists"), "{}", summary);
+        assert!(moved.path().join("a.txt").exists());
+        assert!(src.path().join("b.txt").exists());
+    }
+
+    #[test]
+    fn test_run_dir_dry_run_changes_nothing() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        std::fs::write(src.path().join("a.txt"), "a").unwrap();
+
+        let engine = RuleEngine::new(vec![txt_rule(
+            "Move text",
+            Action::Move {
+                destination: dest.path().to_path_buf(),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+            },
+        )]);
+
+        let mut summary = RunSummary::default();
+        run_dir(&engine, src.path(), false, false, &mut summary).unwrap();
+
+        assert_eq!(summary.applied, 1);
+        assert_eq!(summary.exit_code(), 0);
+        assert!(src.path().join("a.txt").exists());
+    }
+
+    #[test]
+    fn test_run_dir_recursive_includes_subfolders() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let nested = src.path().join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(src.path().join("top.txt"), "top").unwrap();
+        std::fs::write(nested.join("deep.txt"), "deep").unwrap();
+
+        let engine = RuleEngine::new(vec![txt_rule(
+            "Move text",
+            Action::Move {
+                destination: dest.path().to_path_buf(),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+            },
+        )]);
+
+        // Without --recursive only the top level is processed
+        let mut summary = RunSummary::default();
+        run_dir(&engine, src.path(), false, true, &mut summary).unwrap();
+        assert_eq!(summary.applied, 1);
+        assert!(dest.path().join("top.txt").exists());
+        assert!(nested.join("deep.txt").exists());
+
+        let mut summary = RunSummary::default();
+        run_dir(&engine, src.path(), true, true, &mut summary).unwrap();
+        assert_eq!(summary.applied, 1);
+        assert!(dest.path().join("deep.txt").exists());
+        assert!(!nested.join("deep.txt").exists());
+    }
+
+    #[test]
+    fn test_format_rules_verbose() {
+        let mut rule = txt_rule(
+            "Big text",
+            Action::Move {
+                destination: "~/Documents/Text".into(),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+            },
+        );
+        rule.condition.size_greater_than = Some(1000);
+        rule.stop_processing = true;
+        let mut disabled = Rule::new("Everything", Condition::default(), Action::Nothing);
+        disabled.enabled = false;
+        let rules = vec![rule, disabled];
+
+        assert_eq!(
+            format_rules(&rules, false),
+            "Rules:\n  ✓ [1] Big text\n  ✗ [2] Everything\n"
+        );
+
+        let verbose = format_rules(&rules, true);
+        assert!(verbose.contains("      when: extension = \"txt\"\n"));
+        assert!(verbose.contains("      when: size_greater_than = 1000\n"));
+        assert!(verbose.contains("      then: → ~/Documents/Text\n"));
+        assert!(verbose.contains("      stop processing further rules\n"));
+        assert!(
+            verbose.contains("  ✗ [2] Everything\n      when: any file\n      then: ∅ Nothing\n")
+        );
+        // Unset list conditions aren't shown
+        assert!(!verbose.contains("extensions"));
+    }
+
+    #[test]
+    fn test_format_rules_flags_delete_rules() {
+        let rules = vec![
+            txt_rule(
+                "Purge",
+                Action::Delete {
+                    i_understand_delete_is_permanent: false,
+                },
+            ),
+            txt_rule(
+                "Shred",
+                Action::Delete {
+                    i_understand_delete_is_permanent: true,
+                },
+            ),
+        ];
+        let out = format_rules(&rules, false);
+        assert!(out.contains("  ✓ [1] Purge\n      ⚠ DELETE not acknowledged"));
+        assert!(out.contains("  ✓ [2] Shred\n      ⚠ PERMANENTLY deletes matching files\n"));
+    }
+
+    #[test]
+    fn test_tail_lines_returns_last_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hazelnutd.log");
+        let content: String = (1..=3000).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&path, &content).unwrap();
+
+        let (lines, end) = tail_lines(&path, 3).unwrap();
+        assert_eq!(lines, vec!["line 2998", "line 2999", "line 3000"]);
+        assert_eq!(end, content.len() as u64);
+
+        // More than one read block back
+        let (lines, _) = tail_lines(&path, 2500).unwrap();
+        assert_eq!(lines.len(), 2500);
+        assert_eq!(lines[0], "line 501");
+
+        // Fewer lines than asked for, and a last line without a newline
+        std::fs::write(&path, "a\nb").unwrap();
+        assert_eq!(tail_lines(&path, 10).unwrap().0, vec!["a", "b"]);
+        assert!(tail_lines(&path, 0).unwrap().0.is_empty());
+        assert!(tail_lines(&dir.path().join("missing.log"), 5).is_err());
+    }
+}
@@ -1,12 +1,23 @@
 //! Configuration management
 
+mod plan;
 mod schema;
+mod validate;
 
+pub use plan::PlanItem;
 pub use schema::{Config, WatchConfig};
+pub use validate::ValidationReport;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
+/// Commented starter config written by `hazelnut init`
+pub const STARTER_CONFIG: &str = include_str!("starter.toml");
+
+/// How many previous versions of the config `Config::save` keeps
+pub const BACKUP_GENERATIONS: usize = 3;
+
 impl Config {
     /// Load configuration from a file or default location
     pub fn load(path: Option<&Path>) -> Result<Self> {
@@ -16,12 +27,8 @@ impl Config {
             .context("Could not determine config path")?;
 
         if config_path.exists() {
-            let content = std::fs::read_to_string(&config_path)
-                .with_context(|| format!("Failed to read config from {}", config_path.display()))?;
-
-            let config: Config = toml::from_str(&content).with_context(|| {
-                format!("Failed to parse config from {}", config_path.display())
-            })?;
+            let mut config = Self::read_with_includes(&config_path, &mut Vec::new())?;
+            config.normalize();
 
             Ok(config)
         } else {
@@ -29,7 +36,158 @@ impl Config {
         }
     }
 
-    /// Save configuration to a file (with advisory file locking)
+    /// Parse the config at `path` and merge in the watches and rules of the
+    /// files it includes, recursively. `chain` holds the files being read
+    /// further up, so an include cycle is reported instead of looping.
+    fn read_with_includes(path: &Path, chain: &mut Vec<PathBuf>) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config from {}", path.display()))?;
+        let mut config: Config = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config from {}", path.display()))?;
+
+        chain.push(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()));
+        let base = path.parent().unwrap_or(Path::new("."));
+        for include in config.include.clone() {
+            let include_path = base.join(crate::expand_path(&include));
+            let canonical = include_path.canonicalize().with_context(|| {
+                format!("Failed to read included config {}", include_path.display())
+            })?;
+            if let Some(start) = chain.iter().position(|p| *p == canonical) {
+                let cycle: Vec<String> = chain[start..]
+                    .iter()
+                    .chain([&canonical])
+                    .map(|p| p.display().to_string())
+                    .collect();
+                bail!("Config include cycle: {}", cycle.join(" -> "));
+            }
+
+            let included = Self::read_with_includes(&include_path, chain)?;
+            // Entries the included file has from its own includes keep their source
+            let own = included.without_included()?;
+            config
+                .included
+                .rules
+                .extend(included.included.rules.clone());
+            config
+                .included
+                .watches
+                .extend(included.included.watches.clone());
+            config.included.rules.extend(
+                own.rules
+                    .into_iter()
+                    .map(|rule| (rule, include_path.clone())),
+            );
+            config.included.watches.extend(
+                own.watches
+                    .into_iter()
+                    .map(|watch| (watch, include_path.clone())),
+            );
+            config.rules.extend(included.rules);
+            config.watches.extend(included.watches);
+        }
+        chain.pop();
+
+        Ok(config)
+    }
+
+    /// This config without the rules and watches merged in from included files.
+    ///
+    /// Fails if one of those was changed or removed, since saving can't carry
+    /// that back to the file it came from.
+    fn without_included(&self) -> Result<Config> {
+        let mut own = self.clone();
+        for (rule, source) in &self.included.rules {
+            match own.rules.iter().position(|r| r == rule) {
+                Some(i) => {
+                    own.rules.remove(i);
+                }
+                None => bail!(
+                    "Rule '{}' comes from {}; change it there instead",
+                    rule.name,
+                    source.display()
+                ),
+            }
+        }
+        for (watch, source) in &self.included.watches {
+            match own.watches.iter().position(|w| w == watch) {
+                Some(i) => {
+                    own.watches.remove(i);
+                }
+                None => bail!(
+                    "Watch '{}' comes from {}; change it there instead",
+                    watch.path.display(),
+                    source.display()
+                ),
+            }
+        }
+        Ok(own)
+    }
+
+    /// The included file `rule` was loaded from, if it wasn't in the main file
+    pub fn included_rule_source(&self, rule: &crate::Rule) -> Option<&Path> {
+        self.included
+            .rules
+            .iter()
+            .find(|(included, _)| included == rule)
+            .map(|(_, source)| source.as_path())
+    }
+
+    /// The included file `watch` was loaded from, if it wasn't in the main file
+    pub fn included_watch_source(&self, watch: &WatchConfig) -> Option<&Path> {
+        self.included
+            .watches
+            .iter()
+            .find(|(included, _)| included == watch)
+            .map(|(_, source)| source.as_path())
+    }
+
+    /// Write the commented starter config to `path` (or the default location).
+    ///
+    /// Refuses to replace an existing file unless `force` is set. Returns the
+    /// path written.
+    pub fn write_starter(path: Option<&Path>, force: bool) -> Result<PathBuf> {
+        let config_path = path
+            .map(PathBuf::from)
+            .or_else(Self::default_path)
+            .context("Could not determine config path")?;
+        if config_path.exists() && !force {
+            bail!(
+                "{} already exists (use --force to overwrite it)",
+                config_path.display()
+            );
+        }
+        if let Some(parent) = config_path.parent() {
+            std::fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create config directory: {}", parent.display())
+            })?;
+        }
+        std::fs::write(&config_path, STARTER_CONFIG)
+            .with_context(|| format!("Failed to write config to {}", config_path.display()))?;
+        Ok(config_path)
+    }
+
+    /// Put configured extensions in canonical form (lowercase, no leading dot)
+    /// and resolve `category` conditions against `extension_groups`
+    pub fn normalize(&mut self) {
+        for ext in self.extension_groups.values_mut().flatten() {
+            *ext = crate::rules::normalize_extension(ext);
+        }
+        let included = self.included.rules.iter_mut().map(|(rule, _)| rule);
+        for rule in self.rules.iter_mut().chain(included) {
+            rule.condition.normalize_extensions();
+            rule.condition.resolve_category(&self.extension_groups);
+        }
+        for ext in &mut self.general.protected_extensions {
+            *ext = crate::rules::normalize_extension(ext);
+        }
+    }
+
+    /// Save configuration to a file (with advisory file locking). The file
+    /// being replaced is kept as the newest backup (see `backup_path`).
+    ///
+    /// Rules and watches that came from `include`d files aren't written;
+    /// they stay in the files they were loaded from. Saving fails if one of
+    /// them was changed or removed.
     pub fn save(&self, path: Option<&Path>) -> Result<()> {
         let config_path = path
             .map(PathBuf::from)
@@ -43,30 +201,49 @@ impl Config {
             })?;
         }
 
-        let content = toml::to_string_pretty(self).context("Failed to serialize config")?;
+        let content = toml::to_string_pretty(&self.without_included()?)
+            .context("Failed to serialize config")?;
 
-        // Use a lockfile to prevent concurrent writes
-        let lock_path = config_path.with_extension("toml.lock");
-        let lock_file = std::fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&lock_path)
-            .with_context(|| format!("Failed to create lock file: {}", lock_path.display()))?;
-
-        use fs2::FileExt;
-        lock_file
-            .lock_exclusive()
-            .with_context(|| "Failed to acquire config file lock")?;
+        with_lock(&config_path, || {
+            rotate_backups(&config_path)?;
+            write_atomic(&config_path, |file| file.write_all(content.as_bytes()))
+        })
+    }
 
-        let result = std::fs::write(&config_path, content)
-            .with_context(|| format!("Failed to write config to {}", config_path.display()));
+    /// Roll the config file back to its newest backup. Older backups move up a
+    /// generation, so restoring again goes further back. Returns the config path.
+    pub fn restore_backup(path: Option<&Path>) -> Result<PathBuf> {
+        let config_path = path
+            .map(PathBuf::from)
+            .or_else(Self::default_path)
+            .context("Could not determine config path")?;
 
-        let _ = lock_file.unlock();
-        // Clean up the lock file after successful write
-        let _ = std::fs::remove_file(&lock_path);
+        with_lock(&config_path, || {
+            let newest = backup_path(&config_path, 0);
+            let content = match std::fs::read(&newest) {
+                Ok(content) => content,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    bail!("No backup of {} to restore", config_path.display())
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to read {}", newest.display()));
+                }
+            };
+            write_atomic(&config_path, |file| file.write_all(&content))?;
 
-        result
+            for generation in 1..BACKUP_GENERATIONS {
+                let older = backup_path(&config_path, generation);
+                if older.exists() {
+                    std::fs::rename(&older, backup_path(&config_path, generation - 1))
+                        .with_context(|| format!("Failed to move {}", older.display()))?;
+                } else {
+                    let _ = std::fs::remove_file(backup_path(&config_path, generation - 1));
+                    break;
+                }
+            }
+            Ok(())
+        })?;
+        Ok(config_path)
     }
 
     /// Get the default config file path
@@ -81,3 +258,504 @@ impl Config {
         dirs::data_dir().map(|d| d.join("hazelnut"))
     }
 }
+
+/// Where `Config::save` keeps a previous version of the config:
+/// `config.toml.bak` for the newest, then `config.toml.bak.1` and so on
+pub fn backup_path(config_path: &Path, generation: usize) -> PathBuf {
+    let mut name = config_path.as_os_str().to_owned();
+    name.push(".bak");
+    if generation > 0 {
+        name.push(format!(".{}", generation));
+    }
+    PathBuf::from(name)
+}
+
+/// Copy the config file to the newest backup, shifting older backups back a
+/// generation and dropping the oldest. Nothing happens if there's no file yet.
+fn rotate_backups(config_path: &Path) -> Result<()> {
+    if !config_path.is_file() {
+        return Ok(());
+    }
+    for generation in (1..BACKUP_GENERATIONS).rev() {
+        let newer = backup_path(config_path, generation - 1);
+        if newer.exists() {
+            std::fs::rename(&newer, backup_path(config_path, generation))
+                .with_context(|| format!("Failed to move {}", newer.display()))?;
+        }
+    }
+    let newest = backup_path(config_path, 0);
+    std::fs::copy(config_path, &newest)
+        .with_context(|| format!("Failed to back up config to {}", newest.display()))?;
+    Ok(())
+}
+
+/// Run `f` while holding the advisory lock that coordinates config writes
+/// between processes
+fn with_lock<T>(config_path: &Path, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let lock_path = config_path.with_extension("toml.lock");
+    let lock_file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to create lock file: {}", lock_path.display()))?;
+
+    use fs2::FileExt;
+    lock_file
+        .lock_exclusive()
+        .with_context(|| "Failed to acquire config file lock")?;
+
+    let result = f();
+
+    let _ = lock_file.unlock();
+    // Clean up the lock file once the write is done
+    let _ = std::fs::remove_file(&lock_path);
+
+    result
+}
+
+/// Replace `path` with what `write` puts in a temporary file next to it.
+/// The rename is atomic, so a crash mid-write leaves the old file intact and
+/// readers never see a partial one. A symlinked config keeps its link.
+fn write_atomic(
+    path: &Path,
+    write: impl FnOnce(&mut std::fs::File) -> std::io::Result<()>,
+) -> Result<()> {
+    let target = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let file_name = target
+        .file_name()
+        .context("Config path has no file name")?
+        .to_string_lossy();
+    let temp_path = target.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&temp_path)?;
+        write(&mut file)?;
+        file.sync_all()?;
+        // Keep the permissions of the file being replaced
+        if let Ok(metadata) = std::fs::metadata(&target) {
+            std::fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+        std::fs::rename(&temp_path, &target)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result.with_context(|| format!("Failed to write config to {}", path.display()))
+}
+
+/// Number of lines shown before the offending line in a parse error snippet
+const SNIPPET_CONTEXT_LINES: usize = 2;
+
+/// A config file that failed to parse, with the location of the problem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigParseError {
+    /// Parser message, without location details
+    pub message: String,
+    /// 1-based line and column of the problem, if the parser reported one
+    pub location: Option<(usize, usize)>,
+    /// The offending line and a few lines before it, with 1-based line numbers
+    pub snippet: Vec<(usize, String)>,
+}
+
+impl ConfigParseError {
+    /// Describe a TOML error against the text that produced it
+    pub fn new(content: &str, err: &toml::de::Error) -> Self {
+        let Some(span) = err.span() else {
+            return Self {
+                message: err.message().to_string(),
+                location: None,
+                snippet: Vec::new(),
+            };
+        };
+
+        let offset = span.start.min(content.len());
+        let before = &content[..offset];
+        let line_idx = before.matches('\n').count();
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let column = before[line_start..].chars().count() + 1;
+
+        let snippet = content
+            .lines()
+            .enumerate()
+            .skip(line_idx.saturating_sub(SNIPPET_CONTEXT_LINES))
+            .take(line_idx.min(SNIPPET_CONTEXT_LINES) + 1)
+            .map(|(i, line)| (i + 1, line.to_string()))
+            .collect();
+
+        Self {
+            message: err.message().to_string(),
+            location: Some((line_idx + 1, column)),
+            snippet,
+        }
+    }
+
+    /// Re-read a config file and explain why it doesn't parse.
+    ///
+    /// Returns `None` if the file can't be read or parses fine.
+    pub fn from_file(path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        toml::from_str::<Config>(&content)
+            .err()
+            .map(|err| Self::new(&content, &err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error_points_at_offending_line() {
+        let content = "[general]\nlog_level = \"info\"\ndebounce_seconds = \"soon\"\n\n[[rule]]\n";
+        let err = toml::from_str::<Config>(content).unwrap_err();
+        let report = ConfigParseError::new(content, &err);
+
+        let (line, column) = report.location.unwrap();
+        assert_eq!(line, 3);
+        assert_eq!(column, 20);
+        assert_eq!(
+            report.snippet,
+            vec![
+                (1, "[general]".to_string()),
+                (2, "log_level = \"info\"".to_string()),
+                (3, "debounce_seconds = \"soon\"".to_string()),
+            ]
+        );
+        assert!(!report.message.is_empty());
+        assert!(!report.message.contains("line 3"));
+    }
+
+    #[test]
+    fn test_parse_error_on_first_line_and_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[general\ntheme = \"nord\"\n").unwrap();
+
+        let report = ConfigParseError::from_file(&path).unwrap();
+        assert_eq!(report.location.map(|(line, _)| line), Some(1));
+        assert_eq!(report.snippet, vec![(1, "[general".to_string())]);
+
+        std::fs::write(&path, "[general]\ntheme = \"nord\"\n").unwrap();
+        assert!(ConfigParseError::from_file(&path).is_none());
+    }
+
+    #[test]
+    fn test_load_normalizes_extensions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            r#"
+[general]
+protected_extensions = [".APP"]
+
+[[rule]]
+name = "PDFs"
+
+[rule.condition]
+extension = ".Pdf"
+extensions = [".PDF", "PDF", "pdf"]
+
+[rule.action]
+type = "nothing"
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(Some(&path)).unwrap();
+        let condition = &config.rules[0].condition;
+        assert_eq!(condition.extension.as_deref(), Some("pdf"));
+        assert_eq!(condition.extensions, vec!["pdf", "pdf", "pdf"]);
+        assert_eq!(config.general.protected_extensions, vec!["app"]);
+    }
+
+    #[test]
+    fn test_user_extension_groups_resolve_and_override_builtins() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            r#"
+[extension_groups]
+raw_photos = ["CR2", ".nef", "arw"]
+images = ["png"]
+
+[[rule]]
+name = "Raw"
+condition = { category = "raw_photos" }
+action = { type = "nothing" }
+
+[[rule]]
+name = "Images"
+condition = { category = "images" }
+action = { type = "nothing" }
+
+[[rule]]
+name = "Audio"
+condition = { category = "audio" }
+action = { type = "nothing" }
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(Some(&path)).unwrap();
+        assert!(config.validate().is_ok());
+        let matches = |rule: usize, name: &str| {
+            config.rules[rule]
+                .condition
+                .matches(&dir.path().join(name))
+                .unwrap()
+        };
+
+        // User group, with its extensions normalized
+        assert!(matches(0, "IMG_1.cr2"));
+        assert!(matches(0, "IMG_2.NEF"));
+        assert!(!matches(0, "IMG_3.jpg"));
+
+        // The user's "images" replaces the built-in one
+        assert!(matches(1, "shot.png"));
+        assert!(!matches(1, "photo.jpg"));
+
+        // Built-in groups still work
+        assert!(matches(2, "song.mp3"));
+        assert!(!matches(2, "song.txt"));
+
+        // Saving keeps the groups but not the resolved lists
+        config.save(Some(&path)).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("[extension_groups]"));
+        assert!(!saved.contains("category_extensions"));
+        assert!(
+            Config::load(Some(&path)).unwrap().rules[0]
+                .condition
+                .matches(&dir.path().join("x.arw"))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_starter_config_parses_and_validates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hazelnut").join("config.toml");
+
+        assert_eq!(Config::write_starter(Some(&path), false).unwrap(), path);
+        let config = Config::load(Some(&path)).unwrap();
+        assert!(!config.watches.is_empty());
+        assert!(config.rules.len() >= 2);
+        let report = config.validate();
+        assert!(report.is_ok(), "{:?}", report.errors);
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+
+        // An existing config is only replaced with force
+        std::fs::write(&path, "# mine\n").unwrap();
+        assert!(Config::write_starter(Some(&path), false).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# mine\n");
+        Config::write_starter(Some(&path), true).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), STARTER_CONFIG);
+    }
+
+    #[test]
+    fn test_interrupted_save_keeps_original_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut config = Config::default();
+        config.general.debounce_seconds = 7;
+        config.save(Some(&path)).unwrap();
+        let original = std::fs::read_to_string(&path).unwrap();
+
+        // Fail halfway through writing the replacement
+        let result = write_atomic(&path, |file| {
+            file.write_all(b"[general]\ndebounce_")?;
+            Err(std::io::Error::other("interrupted"))
+        });
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), original);
+        assert_eq!(
+            Config::load(Some(&path)).unwrap().general.debounce_seconds,
+            7
+        );
+
+        // No temporary or lock files are left behind
+        let names: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, vec!["config.toml"]);
+    }
+
+    #[test]
+    fn test_save_keeps_backup_generations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut config = Config::default();
+
+        // The first save has nothing to back up
+        config.general.debounce_seconds = 1;
+        config.save(Some(&path)).unwrap();
+        assert!(!backup_path(&path, 0).exists());
+
+        for seconds in 2..=6 {
+            config.general.debounce_seconds = seconds;
+            config.save(Some(&path)).unwrap();
+        }
+        let debounce = |p: &Path| Config::load(Some(p)).unwrap().general.debounce_seconds;
+        assert_eq!(debounce(&path), 6);
+        assert_eq!(debounce(&backup_path(&path, 0)), 5);
+        assert_eq!(debounce(&backup_path(&path, 1)), 4);
+        assert_eq!(debounce(&backup_path(&path, 2)), 3);
+        assert!(!backup_path(&path, BACKUP_GENERATIONS).exists());
+
+        // Restoring walks back one generation at a time
+        Config::restore_backup(Some(&path)).unwrap();
+        assert_eq!(debounce(&path), 5);
+        assert_eq!(debounce(&backup_path(&path, 0)), 4);
+        assert_eq!(debounce(&backup_path(&path, 1)), 3);
+        assert!(!backup_path(&path, 2).exists());
+
+        Config::restore_backup(Some(&path)).unwrap();
+        Config::restore_backup(Some(&path)).unwrap();
+        assert_eq!(debounce(&path), 3);
+        let err = Config::restore_backup(Some(&path)).unwrap_err();
+        assert!(err.to_string().contains("No backup"));
+        assert_eq!(debounce(&path), 3);
+    }
+
+    #[test]
+    fn test_load_merges_included_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::create_dir(dir.path().join("rules")).unwrap();
+        std::fs::write(
+            &path,
+            r#"
+include = ["rules/photos.toml", "rules/docs.toml"]
+
+[[rule]]
+name = "Main"
+[rule.action]
+type = "nothing"
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("rules/photos.toml"),
+            r#"
+[[watch]]
+path = "~/Pictures"
+
+[[rule]]
+name = "Photos"
+[rule.condition]
+category = "images"
+[rule.action]
+type = "nothing"
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("rules/docs.toml"),
+            r#"
+[[rule]]
+name = "Docs"
+[rule.action]
+type = "nothing"
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(Some(&path)).unwrap();
+        let names: Vec<_> = config.rules.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["Main", "Photos", "Docs"]);
+        assert_eq!(config.watches.len(), 1);
+        assert_eq!(config.watches[0].path, PathBuf::from("~/Pictures"));
+        // Included rules are normalized like the main file's
+        assert!(config.rules[1].condition.category_is_known());
+
+        // Saving keeps included rules in their own files
+        config.save(Some(&path)).unwrap();
+        let saved: Config = toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.rules.len(), 1);
+        assert!(saved.watches.is_empty());
+        assert_eq!(saved.include.len(), 2);
+        assert_eq!(Config::load(Some(&path)).unwrap().rules.len(), 3);
+    }
+
+    #[test]
+    fn test_save_tracks_where_included_entries_came_from() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let included = dir.path().join("more.toml");
+        let rule = |name: &str, ext: &str| {
+            format!(
+                "[[rule]]\nname = \"{}\"\n[rule.condition]\nextension = \"{}\"\n[rule.action]\ntype = \"nothing\"\n",
+                name, ext
+            )
+        };
+        std::fs::write(
+            &path,
+            format!("include = [\"more.toml\"]\n\n{}", rule("Docs", "pdf")),
+        )
+        .unwrap();
+        std::fs::write(
+            &included,
+            format!("[[watch]]\npath = \"~/Desktop\"\n\n{}", rule("Docs", "TXT")),
+        )
+        .unwrap();
+
+        // The main file's rule shares its name with the included one but is kept
+        let config = Config::load(Some(&path)).unwrap();
+        assert_eq!(config.rules.len(), 2);
+        assert_eq!(config.included_rule_source(&config.rules[0]), None);
+        assert_eq!(
+            config.included_rule_source(&config.rules[1]),
+            Some(included.as_path())
+        );
+        assert_eq!(
+            config.included_watch_source(&config.watches[0]),
+            Some(included.as_path())
+        );
+        config.save(Some(&path)).unwrap();
+        let saved: Config = toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.rules.len(), 1);
+        assert_eq!(saved.rules[0].condition.extension.as_deref(), Some("pdf"));
+
+        // Changing or removing an included entry can't be saved
+        let mut changed = Config::load(Some(&path)).unwrap();
+        changed.rules[1].enabled = false;
+        let err = changed.save(Some(&path)).unwrap_err();
+        assert!(
+            err.to_string().starts_with("Rule 'Docs' comes from"),
+            "{}",
+            err
+        );
+        let mut removed = Config::load(Some(&path)).unwrap();
+        removed.watches.clear();
+        assert!(removed.save(Some(&path)).is_err());
+        assert_eq!(Config::load(Some(&path)).unwrap().rules.len(), 2);
+    }
+
+    #[test]
+    fn test_include_cycle_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "include = [\"more.toml\"]\n").unwrap();
+        std::fs::write(
+            dir.path().join("more.toml"),
+            "include = [\"config.toml\"]\n",
+        )
+        .unwrap();
+
+        let err = Config::load(Some(&path)).unwrap_err();
+        assert!(
+            err.to_string().starts_with("Config include cycle:"),
+            "{}",
+            err
+        );
+
+        // A missing include is reported too
+        std::fs::write(&path, "include = [\"nope.toml\"]\n").unwrap();
+        let err = Config::load(Some(&path)).unwrap_err();
+        assert!(err.to_string().contains("nope.toml"), "{}", err);
+    }
+}
@@ -0,0 +1,133 @@
+//! Side-effect-free preview of what a config would do to a directory
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use super::Config;
+use crate::rules::{Action, RuleEngine};
+
+/// One action a config would take on a file
+#[derive(Debug, Clone)]
+pub struct PlanItem {
+    /// File the action applies to
+    pub path: PathBuf,
+    /// Name of the rule that matched
+    pub matched_rule: String,
+    /// The action that would run
+    pub action: Action,
+}
+
+impl Config {
+    /// Work out which rules would act on the files in `dir` (and its subfolders
+    /// when `recursive`), without touching anything.
+    ///
+    /// Items follow the same order and stopping rules as real processing: a file
+    /// gets one item per matching rule, up to the first `stop_processing` rule or
+    /// action that moves the file away (see `RuleEngine::dry_evaluate_dir`).
+    pub fn plan(&self, dir: &Path, recursive: bool) -> Result<Vec<PlanItem>> {
+        let engine = RuleEngine::from_config(self);
+        Ok(engine
+            .dry_evaluate_dir(dir, recursive)?
+            .into_iter()
+            .filter_map(|(path, matched)| {
+                matched.map(|(matched_rule, action)| PlanItem {
+                    path,
+                    matched_rule,
+                    action,
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{Condition, Rule};
+
+    #[test]
+    fn test_plan_matches_actual_processing() {
+        let src = tempfile::tempdir().unwrap();
+        let docs = tempfile::tempdir().unwrap();
+        let backup = tempfile::tempdir().unwrap();
+        std::fs::create_dir(src.path().join("sub")).unwrap();
+        for name in ["a.pdf", "b.txt", "c.md", "sub/d.pdf"] {
+            std::fs::write(src.path().join(name), name).unwrap();
+        }
+
+        let mut config = Config::default();
+        config.rules = vec![
+            Rule::new(
+                "Backup text",
+                Condition {
+                    extension: Some("txt".to_string()),
+                    ..Default::default()
+                },
+                Action::Copy {
+                    destination: backup.path().to_path_buf(),
+                    create_destination: true,
+                    overwrite: false,
+                    preserve_structure: false,
+                    skip_if_open: false,
+                    skip_if_identical: false,
+                    preserve_timestamps: false,
+                },
+            ),
+            Rule::new(
+                "Documents",
+                Condition {
+                    extensions: vec!["pdf".to_string(), "txt".to_string()],
+                    ..Default::default()
+                },
+                Action::Move {
+                    destination: docs.path().to_path_buf(),
+                    create_destination: true,
+                    overwrite: false,
+                    preserve_structure: false,
+                    skip_if_open: false,
+                    skip_if_identical: false,
+                },
+            ),
+            Rule::new(
+                "Never reached",
+                Condition {
+                    extension: Some("pdf".to_string()),
+                    ..Default::default()
+                },
+                Action::Delete {
+                    i_understand_delete_is_permanent: true,
+                },
+            ),
+        ];
+
+        let mut plan: Vec<(PathBuf, String)> = config
+            .plan(src.path(), true)
+            .unwrap()
+            .into_iter()
+            .map(|item| (item.path, item.matched_rule))
+            .collect();
+        plan.sort();
+
+        // Planning changed nothing
+        assert!(src.path().join("a.pdf").exists());
+        assert!(std::fs::read_dir(docs.path()).unwrap().next().is_none());
+
+        let engine = RuleEngine::from_config(&config);
+        let mut actual = Vec::new();
+        for name in ["a.pdf", "b.txt", "c.md", "sub/d.pdf"] {
+            let path = src.path().join(name);
+            for m in engine.process_filtered(&path, None).unwrap() {
+                actual.push((path.clone(), m.rule_name));
+            }
+        }
+        actual.sort();
+
+        assert_eq!(plan, actual);
+        assert_eq!(plan.len(), 4);
+        assert!(docs.path().join("d.pdf").exists());
+
+        // Without recursion the subfolder is left out
+        let flat = Config::default().plan(src.path(), false).unwrap();
+        assert!(flat.is_empty());
+    }
+}
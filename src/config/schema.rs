@@ -2,15 +2,28 @@
 
 use crate::rules::Rule;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
+    /// Other config files whose watches and rules are added to this one,
+    /// e.g. `include = ["rules/photos.toml"]`. Relative paths are resolved
+    /// against the directory of the including file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<PathBuf>,
+
     /// General settings
     #[serde(default)]
     pub general: GeneralConfig,
 
+    /// Named extension groups for the `category` condition, e.g.
+    /// `raw_photos = ["cr2", "nef", "arw"]`. A group named like a built-in
+    /// category replaces it.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub extension_groups: HashMap<String, Vec<String>>,
+
     /// Watched folders
     #[serde(default, rename = "watch")]
     pub watches: Vec<WatchConfig>,
@@ -18,6 +31,19 @@ pub struct Config {
     /// Organization rules
     #[serde(default, rename = "rule")]
     pub rules: Vec<Rule>,
+
+    /// What was merged in from `include`d files, which `Config::save` leaves
+    /// out so it stays in those files
+    #[serde(skip)]
+    pub(crate) included: Included,
+}
+
+/// The rules and watches that came from included files, as loaded, with the
+/// file each one came from
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Included {
+    pub rules: Vec<(Rule, PathBuf)>,
+    pub watches: Vec<(WatchConfig, PathBuf)>,
 }
 
 /// General application settings
@@ -54,6 +80,73 @@ pub struct GeneralConfig {
     /// Theme name
     #[serde(default)]
     pub theme: Option<String>,
+
+    /// Simulate mode: log what rules would do without changing any files
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// Folder that `trash` actions move files into instead of the system trash
+    #[serde(default)]
+    pub trash_dir: Option<PathBuf>,
+
+    /// Extensions (without dot) that no rule may ever act on, e.g. `app` or `lnk`
+    #[serde(default)]
+    pub protected_extensions: Vec<String>,
+
+    /// Draw muted text and borders closer to the foreground color, for
+    /// terminals where the theme's grays are hard to read
+    #[serde(default)]
+    pub high_contrast: bool,
+
+    /// Scan all watch folders on this interval (e.g. `1h`, `daily`) instead of
+    /// watching them continuously
+    #[serde(default)]
+    pub schedule: Option<String>,
+
+    /// Seconds a TUI status message stays in the status bar (0 = until replaced)
+    #[serde(default = "default_status_timeout")]
+    pub status_timeout_secs: u64,
+
+    /// Daemon writes its log as JSON lines instead of plain text
+    #[serde(default)]
+    pub json_logs: bool,
+
+    /// Process the files already in a watch folder when watching starts
+    #[serde(default = "default_scan_on_start")]
+    pub scan_on_start: bool,
+
+    /// How many folder levels the initial scan of a recursive watch goes
+    /// into (1 = only the watch folder itself); live events aren't limited
+    #[serde(default)]
+    pub scan_max_depth: Option<usize>,
+
+    /// Most file events handled in one pass; the rest wait for the next
+    /// pass so a huge drop doesn't stall everything (0 = no limit)
+    #[serde(default = "default_max_events_per_poll")]
+    pub max_events_per_poll: usize,
+
+    /// Stop retrying a file after this many failures in a row (0 = never)
+    #[serde(default = "default_quarantine_after")]
+    pub quarantine_after: u32,
+
+    /// Folder quarantined files are moved into (they stay put if unset)
+    #[serde(default)]
+    pub quarantine_dir: Option<PathBuf>,
+
+    /// Shell command that delivers notifications instead of the desktop,
+    /// with `{summary}` and `{message}` replaced (shell-escaped)
+    #[serde(default)]
+    pub notify_command: Option<String>,
+}
+
+impl GeneralConfig {
+    /// Time between scheduled scans, or `None` to watch continuously
+    pub fn schedule_interval(&self) -> anyhow::Result<Option<std::time::Duration>> {
+        self.schedule
+            .as_deref()
+            .map(crate::watcher::parse_schedule)
+            .transpose()
+    }
 }
 
 impl Default for GeneralConfig {
@@ -67,6 +160,19 @@ impl Default for GeneralConfig {
             start_daemon_on_launch: false,
             notifications_enabled: false,
             theme: None,
+            dry_run: false,
+            trash_dir: None,
+            protected_extensions: Vec::new(),
+            high_contrast: false,
+            schedule: None,
+            status_timeout_secs: default_status_timeout(),
+            json_logs: false,
+            scan_on_start: true,
+            scan_max_depth: None,
+            max_events_per_poll: default_max_events_per_poll(),
+            quarantine_after: default_quarantine_after(),
+            quarantine_dir: None,
+            notify_command: None,
         }
     }
 }
@@ -87,8 +193,24 @@ fn default_log_retention() -> usize {
     1000
 }
 
+fn default_scan_on_start() -> bool {
+    true
+}
+
+fn default_max_events_per_poll() -> usize {
+    1000
+}
+
+fn default_quarantine_after() -> u32 {
+    5
+}
+
+fn default_status_timeout() -> u64 {
+    4
+}
+
 /// Configuration for a watched folder
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WatchConfig {
     /// Path to watch
     pub path: PathBuf,
@@ -100,6 +222,21 @@ pub struct WatchConfig {
     /// Only apply rules with these names (empty = all rules)
     #[serde(default)]
     pub rules: Vec<String>,
+
+    /// Descend into symlinked folders when scanning a recursive watch
+    #[serde(default)]
+    pub follow_symlinks: bool,
+}
+
+impl WatchConfig {
+    /// The watcher settings for this folder (its path still needs expanding)
+    pub fn options(&self) -> crate::watcher::WatchOptions {
+        crate::watcher::WatchOptions {
+            recursive: self.recursive,
+            rules: self.rules.clone(),
+            follow_symlinks: self.follow_symlinks,
+        }
+    }
 }
 
 #[cfg(test)]
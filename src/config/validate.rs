@@ -0,0 +1,391 @@
+//! Config checks beyond parsing: broken patterns and rules that can never match
+
+use super::Config;
+use crate::rules::{Action, Condition, Rule};
+
+/// Problems found by `Config::validate`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Problems that stop a rule or setting from working at all
+    pub errors: Vec<String>,
+    /// Likely mistakes, such as rules whose conditions can never all match
+    pub warnings: Vec<String>,
+}
+
+impl ValidationReport {
+    /// Whether no errors were found (warnings are allowed)
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl Config {
+    /// Check the config for invalid patterns and settings (errors) and for
+    /// rules that look like they can never match (warnings)
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        if let Err(e) = self.general.schedule_interval() {
+            report.errors.push(format!("general.schedule: {}", e));
+        }
+
+        for rule in &self.rules {
+            check_patterns(rule, &mut report.errors);
+            if rule.action.is_unacknowledged_delete() {
+                report.errors.push(format!(
+                    "Rule '{}': delete action won't run without i_understand_delete_is_permanent = true",
+                    rule.name
+                ));
+            } else if rule.enabled && matches!(rule.action, Action::Delete { .. }) {
+                report.warnings.push(format!(
+                    "Rule '{}' PERMANENTLY deletes matching files; they can't be recovered",
+                    rule.name
+                ));
+            }
+            if let Some(ref budget) = rule.budget {
+                if budget.max_bytes.is_none() && budget.max_files.is_none() {
+                    report.errors.push(format!(
+                        "Rule '{}': budget needs max_bytes or max_files",
+                        rule.name
+                    ));
+                }
+                if !rule.action.leaves_folder() {
+                    report.errors.push(format!(
+                        "Rule '{}': its action ({}) doesn't take files out of the budget folder",
+                        rule.name,
+                        rule.action.preview()
+                    ));
+                }
+            }
+            for problem in dead_conditions(&rule.condition) {
+                report
+                    .warnings
+                    .push(format!("Rule '{}' can never match: {}", rule.name, problem));
+            }
+        }
+
+        for watch in &self.watches {
+            for name in &watch.rules {
+                if !self.rules.iter().any(|r| &r.name == name) {
+                    report.warnings.push(format!(
+                        "Watch '{}' lists unknown rule '{}'",
+                        watch.path.display(),
+                        name
+                    ));
+                }
+            }
+        }
+
+        report
+    }
+}
+
+/// Report glob and regex patterns (and weekday names) in a rule that don't parse
+fn check_patterns(rule: &Rule, errors: &mut Vec<String>) {
+    let c = &rule.condition;
+    let globs = c
+        .name_matches
+        .iter()
+        .chain(&c.name_matches_any)
+        .chain(&c.parent_name);
+    for pattern in globs {
+        if let Err(e) = glob::Pattern::new(pattern) {
+            errors.push(format!(
+                "Rule '{}': invalid glob '{}': {}",
+                rule.name, pattern, e
+            ));
+        }
+    }
+    if !c.category_is_known()
+        && let Some(ref category) = c.category
+    {
+        errors.push(format!(
+            "Rule '{}': unknown category '{}' (add it under [extension_groups])",
+            rule.name, category
+        ));
+    }
+    for day in c.mtime_weekday.iter().flatten() {
+        if day.trim().parse::<chrono::Weekday>().is_err() {
+            errors.push(format!("Rule '{}': unknown weekday '{}'", rule.name, day));
+        }
+    }
+    let action_pattern = match rule.action {
+        Action::Move {
+            ref destination, ..
+        }
+        | Action::Copy {
+            ref destination, ..
+        } => Some(destination.to_string_lossy().into_owned()),
+        Action::Rename { ref pattern, .. } => Some(pattern.clone()),
+        _ => None,
+    };
+    if let Some(format) = action_pattern
+        .as_deref()
+        .and_then(crate::rules::invalid_mtime_format)
+    {
+        errors.push(format!(
+            "Rule '{}': invalid {{mtime}} format '{}'",
+            rule.name, format
+        ));
+    }
+    for pattern in c.name_regex.iter().chain(&c.contains_regex) {
+        if let Err(e) = regex::Regex::new(pattern) {
+            errors.push(format!(
+                "Rule '{}': invalid regex '{}': {}",
+                rule.name, pattern, e
+            ));
+        }
+    }
+}
+
+/// Heuristic reasons a condition can never be satisfied
+fn dead_conditions(c: &Condition) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    // Sizes are whole bytes: turn the strict bounds into inclusive ones
+    let min_size = [
+        c.size_greater_than.map(|n| n.saturating_add(1)),
+        c.size_at_least,
+    ]
+    .into_iter()
+    .flatten()
+    .max();
+    let max_size = [
+        c.size_less_than.map(|n| n.checked_sub(1)),
+        c.size_at_most.map(Some),
+    ]
+    .into_iter()
+    .flatten()
+    .min();
+    match (min_size, max_size) {
+        (_, Some(None)) => problems.push("size_less_than = 0 excludes every file".to_string()),
+        (Some(min), Some(Some(max))) if min > max => problems.push(format!(
+            "size bounds leave no room (at least {} bytes but at most {})",
+            min, max
+        )),
+        _ => {}
+    }
+
+    // Ages are compared in whole days
+    for (label, greater, less) in [
+        ("age_days", c.age_days_greater_than, c.age_days_less_than),
+        (
+            "accessed_days",
+            c.accessed_days_greater_than,
+            c.accessed_days_less_than,
+        ),
+    ] {
+        if let (Some(greater), Some(less)) = (greater, less)
+            && less <= greater.saturating_add(1)
+        {
+            problems.push(format!(
+                "{}_greater_than = {} and {}_less_than = {} leave no whole day in between",
+                label, greater, label, less
+            ));
+        }
+    }
+
+    if c.is_directory == Some(true) {
+        if c.extension.is_some() || !c.extensions.is_empty() {
+            problems.push("is_directory = true is combined with an extension".to_string());
+        }
+        if c.contains_text.is_some() || c.contains_regex.is_some() {
+            problems.push("is_directory = true is combined with a content check".to_string());
+        }
+    }
+
+    if c.is_hidden == Some(false)
+        && let Some(ref pattern) = c.name_matches
+        && pattern.starts_with('.')
+    {
+        problems.push(format!(
+            "is_hidden = false but name_matches '{}' only matches hidden files",
+            pattern
+        ));
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with(condition: Condition) -> Config {
+        let mut config = Config::default();
+        config
+            .rules
+            .push(Rule::new("Test", condition, Action::Nothing));
+        config
+    }
+
+    #[test]
+    fn test_contradictory_size_bounds_warn() {
+        let report = config_with(Condition {
+            size_greater_than: Some(1000),
+            size_less_than: Some(500),
+            ..Default::default()
+        })
+        .validate();
+        assert!(report.is_ok());
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.warnings[0].contains("Rule 'Test' can never match"));
+
+        // Strict bounds one apart leave no whole byte in between
+        let report = config_with(Condition {
+            size_greater_than: Some(10),
+            size_less_than: Some(11),
+            ..Default::default()
+        })
+        .validate();
+        assert_eq!(report.warnings.len(), 1);
+
+        // A real range is fine, including mixed strict and inclusive bounds
+        let report = config_with(Condition {
+            size_greater_than: Some(10),
+            size_at_most: Some(11),
+            ..Default::default()
+        })
+        .validate();
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_directory_with_extension_warns() {
+        let report = config_with(Condition {
+            is_directory: Some(true),
+            extension: Some("pdf".to_string()),
+            ..Default::default()
+        })
+        .validate();
+        assert!(report.is_ok());
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.warnings[0].contains("is_directory"));
+
+        let report = config_with(Condition {
+            is_directory: Some(false),
+            extension: Some("pdf".to_string()),
+            ..Default::default()
+        })
+        .validate();
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_patterns_are_errors() {
+        let mut config = config_with(Condition {
+            name_regex: Some("([unclosed".to_string()),
+            name_matches: Some("[".to_string()),
+            ..Default::default()
+        });
+        config.general.schedule = Some("sometimes".to_string());
+        config.watches.push(super::super::WatchConfig {
+            path: "~/Downloads".into(),
+            recursive: false,
+            rules: vec!["Nope".to_string()],
+            follow_symlinks: false,
+        });
+
+        let report = config.validate();
+        assert!(!report.is_ok());
+        assert_eq!(report.errors.len(), 3);
+        assert_eq!(
+            report.warnings,
+            vec!["Watch '~/Downloads' lists unknown rule 'Nope'"]
+        );
+    }
+
+    #[test]
+    fn test_budget_rules_are_checked() {
+        let mut rule = Rule::new(
+            "Budget",
+            Condition::default(),
+            Action::Copy {
+                destination: "/tmp/x".into(),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+                preserve_timestamps: false,
+            },
+        );
+        rule.budget = Some(crate::rules::FolderBudget {
+            path: "~/Downloads".into(),
+            max_bytes: None,
+            max_files: None,
+        });
+        let mut config = Config::default();
+        config.rules.push(rule);
+
+        let report = config.validate();
+        assert_eq!(report.errors.len(), 2);
+        assert_eq!(
+            report.errors[0],
+            "Rule 'Budget': budget needs max_bytes or max_files"
+        );
+        assert!(report.errors[1].contains("doesn't take files out"));
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_delete_rules_need_acknowledgment() {
+        let mut config = Config::default();
+        config.rules.push(Rule::new(
+            "Purge",
+            Condition::default(),
+            Action::Delete {
+                i_understand_delete_is_permanent: false,
+            },
+        ));
+        let report = config.validate();
+        assert_eq!(
+            report.errors,
+            vec![
+                "Rule 'Purge': delete action won't run without i_understand_delete_is_permanent = true"
+            ]
+        );
+
+        // Acknowledged, it's allowed but still called out
+        config.rules[0].action = Action::Delete {
+            i_understand_delete_is_permanent: true,
+        };
+        let report = config.validate();
+        assert!(report.is_ok());
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.warnings[0].contains("PERMANENTLY"));
+    }
+
+    #[test]
+    fn test_unknown_category_is_an_error() {
+        let report = config_with(Condition {
+            category: Some("raw_photos".to_string()),
+            ..Default::default()
+        })
+        .validate();
+        assert_eq!(
+            report.errors,
+            vec!["Rule 'Test': unknown category 'raw_photos' (add it under [extension_groups])"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_mtime_format_is_an_error() {
+        let mut config = config_with(Condition::default());
+        config.rules[0].action = Action::Rename {
+            pattern: "{mtime:%Q}_{filename}".to_string(),
+            overwrite: false,
+        };
+        let report = config.validate();
+        assert_eq!(
+            report.errors,
+            vec!["Rule 'Test': invalid {mtime} format '%Q'"]
+        );
+
+        config.rules[0].action = Action::Rename {
+            pattern: "{mtime:%Y-%m}_{filename}".to_string(),
+            overwrite: false,
+        };
+        assert!(config.validate().is_ok());
+    }
+}
@@ -39,13 +39,21 @@ mod unix_daemon {
     #[derive(clap::Subcommand, Debug)]
     pub enum Commands {
         /// Start the daemon in background
-        Start,
+        Start {
+            /// Log what rules would do without changing any files
+            #[arg(long)]
+            dry_run: bool,
+        },
 
         /// Stop the running daemon
         Stop,
 
         /// Restart the daemon
-        Restart,
+        Restart {
+            /// Log what rules would do without changing any files
+            #[arg(long)]
+            dry_run: bool,
+        },
 
         /// Show daemon status
         Status,
@@ -54,7 +62,15 @@ mod unix_daemon {
         Reload,
 
         /// Run in foreground (for debugging)
-        Run,
+        Run {
+            /// Scan every watch folder once, then exit (for cron or systemd timers)
+            #[arg(long)]
+            once: bool,
+
+            /// Log what rules would do without changing any files
+            #[arg(long)]
+            dry_run: bool,
+        },
     }
 
     /// Get the PID file path
@@ -152,18 +168,18 @@ mod unix_daemon {
         (false, None)
     }
 
-    pub async fn run(cli: Cli) -> Result<()> {
+    pub async fn run(cli: Cli) -> Result<std::process::ExitCode> {
         match cli.command {
-            Commands::Start => {
-                start_daemon(cli.config)?;
+            Commands::Start { dry_run } => {
+                start_daemon(cli.config, dry_run)?;
             }
             Commands::Stop => {
                 stop_daemon()?;
             }
-            Commands::Restart => {
+            Commands::Restart { dry_run } => {
                 let _ = stop_daemon();
                 std::thread::sleep(std::time::Duration::from_millis(500));
-                start_daemon(cli.config)?;
+                start_daemon(cli.config, dry_run)?;
             }
             Commands::Status => {
                 show_status();
@@ -171,23 +187,36 @@ mod unix_daemon {
             Commands::Reload => {
                 reload_config()?;
             }
-            Commands::Run => {
+            Commands::Run { once, dry_run } => {
                 // Initialize logging for foreground mode
-                tracing_subscriber::registry()
-                    .with(tracing_subscriber::EnvFilter::new(
-                        std::env::var("HAZELNUT_LOG").unwrap_or_else(|_| "info".to_string()),
-                    ))
-                    .with(tracing_subscriber::fmt::layer().with_target(false))
-                    .init();
-
-                run_daemon(cli.config).await?;
+                let json_logs = hazelnut::Config::load(cli.config.as_deref())
+                    .is_ok_and(|config| config.general.json_logs);
+                let filter = tracing_subscriber::EnvFilter::new(
+                    std::env::var("HAZELNUT_LOG").unwrap_or_else(|_| "info".to_string()),
+                );
+                if json_logs {
+                    tracing_subscriber::registry()
+                        .with(filter)
+                        .with(hazelnut::logging::JsonLayer::new(std::io::stdout))
+                        .init();
+                } else {
+                    tracing_subscriber::registry()
+                        .with(filter)
+                        .with(tracing_subscriber::fmt::layer().with_target(false))
+                        .init();
+                }
+
+                if once {
+                    return run_once(cli.config, dry_run);
+                }
+                run_daemon(cli.config, dry_run).await?;
             }
         }
 
-        Ok(())
+        Ok(std::process::ExitCode::SUCCESS)
     }
 
-    fn start_daemon(config_path: Option<PathBuf>) -> Result<()> {
+    fn start_daemon(config_path: Option<PathBuf>, dry_run: bool) -> Result<()> {
         let (running, pid) = get_status();
         if running {
             println!("🌰 Daemon is already running (PID: {})", pid.unwrap());
@@ -202,6 +231,9 @@ mod unix_daemon {
         // Build command
         let mut cmd = Command::new(&exe);
         cmd.arg("run");
+        if dry_run {
+            cmd.arg("--dry-run");
+        }
 
         if let Some(ref config) = config_path {
             cmd.arg("--config").arg(config);
@@ -323,8 +355,92 @@ mod unix_daemon {
         Ok(())
     }
 
-    async fn run_daemon(config_path: Option<std::path::PathBuf>) -> Result<()> {
-        use std::collections::VecDeque;
+    /// Load the config, forcing simulate mode on when `--dry-run` was given
+    fn load_config(
+        config_path: Option<&std::path::Path>,
+        dry_run: bool,
+    ) -> Result<hazelnut::Config> {
+        let mut config = hazelnut::Config::load(config_path)?;
+        config.general.dry_run |= dry_run;
+        Ok(config)
+    }
+
+    /// The rule engine and watcher for `config`, as the daemon and `run --once` use them
+    fn build_watcher(config: &hazelnut::Config) -> Result<hazelnut::Watcher> {
+        let engine = hazelnut::RuleEngine::from_config(config)
+            .with_journal(Some(hazelnut::rules::UndoJournal::open_default()));
+        Ok(hazelnut::Watcher::new(
+            engine,
+            config.general.polling_interval_secs,
+            config.general.debounce_seconds,
+        )?
+        .with_scan_on_start(config.general.scan_on_start)
+        .with_scan_max_depth(config.general.scan_max_depth)
+        .with_max_events_per_poll(config.general.max_events_per_poll)
+        .with_quarantine(
+            config.general.quarantine_after,
+            config.general.quarantine_dir.clone(),
+        ))
+    }
+
+    /// Run `schedule`'s scan if it's due, recording it in the watcher's stats
+    /// and activity and requeueing the files it wants retried.
+    ///
+    /// Returns the number of files processed and the scan's errors, with
+    /// retried files listed too, or `None` if no scan was due.
+    fn run_scan(
+        watcher: &mut hazelnut::Watcher,
+        schedule: &mut hazelnut::watcher::ScheduledScan,
+        now: std::time::Instant,
+    ) -> Option<(usize, Vec<String>)> {
+        let report = schedule.run_if_due(watcher.engine(), now)?;
+        let mut errors = report.errors.clone();
+        errors.extend(
+            report
+                .retries
+                .iter()
+                .map(|(path, e)| format!("{}: {} (retrying later)", path.display(), e)),
+        );
+        Some((watcher.record_scan(report), errors))
+    }
+
+    /// Scan every watch folder a single time, the way a scheduled scan does.
+    /// The exit code is non-zero if any file failed.
+    fn run_once(config_path: Option<PathBuf>, dry_run: bool) -> Result<std::process::ExitCode> {
+        let lock_path = hazelnut::watcher::ProcessorLock::default_path();
+        let _processor_lock = hazelnut::watcher::ProcessorLock::try_acquire(&lock_path)?
+            .with_context(|| {
+                format!(
+                    "Another Hazelnut instance is processing files (lock held on {})",
+                    lock_path.display()
+                )
+            })?;
+
+        let config = load_config(config_path.as_deref(), dry_run)?;
+        let mut watcher = build_watcher(&config)?;
+        let mut schedule =
+            hazelnut::watcher::ScheduledScan::new(&config.watches, std::time::Duration::ZERO);
+        let (processed, errors) =
+            run_scan(&mut watcher, &mut schedule, std::time::Instant::now()).unwrap_or_default();
+
+        println!(
+            "✓ Scanned {} watch folders: {} files processed, {} errors",
+            config.watches.len(),
+            processed,
+            errors.len()
+        );
+        for error in &errors {
+            eprintln!("  ✗ {}", error);
+        }
+        Ok(if errors.is_empty() {
+            std::process::ExitCode::SUCCESS
+        } else {
+            std::process::ExitCode::FAILURE
+        })
+    }
+
+    async fn run_daemon(config_path: Option<std::path::PathBuf>, dry_run: bool) -> Result<()> {
+        use hazelnut::ipc::{LogBuffer, LogEntry};
         use std::sync::{Arc, Mutex};
         use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
         use tokio::signal::unix::{SignalKind, signal};
@@ -334,23 +450,58 @@ mod unix_daemon {
         /// Maximum number of log entries kept in the ring buffer.
         const MAX_LOG_ENTRIES: usize = 500;
 
-        // In-memory ring buffer for log entries returned by GetLog.
-        let log_buffer: Arc<Mutex<VecDeque<String>>> =
-            Arc::new(Mutex::new(VecDeque::with_capacity(MAX_LOG_ENTRIES)));
+        /// Upper bound on the final event pass during shutdown.
+        const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
 
-        /// Push a message into the ring buffer, evicting the oldest if full.
-        fn push_log(buf: &Mutex<VecDeque<String>>, msg: String, max: usize) {
+        /// How often watched directories are checked for availability.
+        const WATCH_CHECK_INTERVAL_SECS: u64 = 30;
+
+        // In-memory ring buffer for log entries returned by GetLog and TailLog.
+        // Tells this run's log ids apart from a previous run's
+        let log_epoch = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        let log_buffer: Arc<Mutex<LogBuffer>> =
+            Arc::new(Mutex::new(LogBuffer::new(MAX_LOG_ENTRIES, log_epoch)));
+
+        /// Push an entry into the ring buffer, evicting the oldest if full.
+        fn push_log(buf: &Mutex<LogBuffer>, msg: LogEntry) {
             if let Ok(mut ring) = buf.lock() {
-                if ring.len() >= max {
-                    ring.pop_front();
-                }
-                ring.push_back(msg);
+                ring.push(msg);
             }
         }
 
-        // Write PID file for foreground mode too
+        /// How long to wait for an embedded TUI watcher to hand over the processor lock.
+        const LOCK_WAIT: Duration = Duration::from_secs(10);
+
+        if let Some(pid) = read_pid()
+            && pid as u32 != std::process::id()
+            && is_process_running(pid)
+        {
+            anyhow::bail!("Daemon is already running (PID: {})", pid);
+        }
+
+        // Write PID file for foreground mode too. The TUI sees it and stops
+        // its embedded watcher, releasing the processor lock.
         write_pid(std::process::id())?;
 
+        let lock_path = hazelnut::watcher::ProcessorLock::default_path();
+        let _processor_lock =
+            match hazelnut::watcher::ProcessorLock::acquire_timeout(&lock_path, LOCK_WAIT) {
+                Ok(Some(lock)) => lock,
+                Ok(None) => {
+                    remove_pid_file();
+                    anyhow::bail!(
+                        "Another Hazelnut instance is processing files (lock held on {})",
+                        lock_path.display()
+                    );
+                }
+                Err(e) => {
+                    remove_pid_file();
+                    return Err(e);
+                }
+            };
+
         let start_time = std::time::Instant::now();
 
         // Set up IPC listener
@@ -367,30 +518,42 @@ mod unix_daemon {
         let mut sighup = signal(SignalKind::hangup())?;
 
         let config_path_clone = config_path.clone();
-        let mut config = hazelnut::Config::load(config_path.as_deref())?;
+        let mut config = load_config(config_path.as_deref(), dry_run)?;
 
         // Initialize notifications
-        hazelnut::notifications::init(config.general.notifications_enabled);
+        hazelnut::notifications::init(
+            config.general.notifications_enabled,
+            config.general.notify_command.clone(),
+        );
 
         info!(
             "Loaded config with {} watch paths and {} rules",
             config.watches.len(),
             config.rules.len()
         );
+        if config.general.dry_run {
+            info!("Dry run: actions are logged but no files are changed");
+        }
 
-        let engine = hazelnut::RuleEngine::new(config.rules.clone());
-        let mut watcher = hazelnut::Watcher::new(
-            engine,
-            config.general.polling_interval_secs,
-            config.general.debounce_seconds,
-        )?;
+        let mut watcher = build_watcher(&config)?;
+
+        // With a schedule, watch folders are scanned periodically instead of watched
+        let mut scheduled = config
+            .general
+            .schedule_interval()?
+            .map(|every| hazelnut::watcher::ScheduledScan::new(&config.watches, every));
+        if let Some(ref schedule) = scheduled {
+            info!(
+                "Scanning {} watch paths every {}s",
+                config.watches.len(),
+                schedule.interval().as_secs()
+            );
+        }
 
-        for watch in &config.watches {
+        for watch in config.watches.iter().filter(|_| scheduled.is_none()) {
             let expanded_path = hazelnut::expand_path(&watch.path);
             info!("Watching: {}", expanded_path.display());
-            if let Err(e) =
-                watcher.watch_with_rules(&expanded_path, watch.recursive, watch.rules.clone())
-            {
+            if let Err(e) = watcher.watch_with_options(&expanded_path, watch.options()) {
                 tracing::error!("Failed to watch {}: {}", expanded_path.display(), e);
                 hazelnut::notifications::notify_watch_error(
                     &expanded_path.display().to_string(),
@@ -402,12 +565,7 @@ mod unix_daemon {
         info!("Daemon running (PID: {})", std::process::id());
         push_log(
             &log_buffer,
-            format!(
-                "[{}] Daemon started (PID: {})",
-                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                std::process::id()
-            ),
-            MAX_LOG_ENTRIES,
+            LogEntry::message(format!("Daemon started (PID: {})", std::process::id())),
         );
 
         use std::sync::atomic::AtomicBool;
@@ -418,34 +576,49 @@ mod unix_daemon {
         // Poll for events periodically
         let mut poll_interval = interval(Duration::from_millis(500));
 
+        // Check that watched directories are still reachable
+        let mut availability_interval = interval(Duration::from_secs(WATCH_CHECK_INTERVAL_SECS));
+
+        // Set on SIGTERM/SIGINT so queued events get one last pass before exit
+        let mut drain_on_exit = false;
+
         loop {
             tokio::select! {
                 _ = sigterm.recv() => {
                     info!("Received SIGTERM, shutting down...");
+                    drain_on_exit = true;
                     break;
                 }
                 _ = sigint.recv() => {
                     info!("Received SIGINT, shutting down...");
+                    drain_on_exit = true;
                     break;
                 }
                 _ = sighup.recv() => {
                     info!("Received SIGHUP, reloading configuration...");
-                    match hazelnut::Config::load(config_path_clone.as_deref()) {
+                    match load_config(config_path_clone.as_deref(), dry_run) {
                         Ok(new_config) => {
+                            let new_schedule = match new_config.general.schedule_interval() {
+                                Ok(every) => every,
+                                Err(e) => {
+                                    tracing::error!("Failed to reload config: {}", e);
+                                    continue;
+                                }
+                            };
                             config = new_config;
+                            scheduled = new_schedule
+                                .map(|every| hazelnut::watcher::ScheduledScan::new(&config.watches, every));
                             // Update notification settings
-                            hazelnut::notifications::init(config.general.notifications_enabled);
+                            hazelnut::notifications::init(
+                                config.general.notifications_enabled,
+                                config.general.notify_command.clone(),
+                            );
                             // Recreate watcher with new rules, polling interval, and debounce
-                            let engine = hazelnut::RuleEngine::new(config.rules.clone());
-                            match hazelnut::Watcher::new(
-                                engine,
-                                config.general.polling_interval_secs,
-                                config.general.debounce_seconds,
-                            ) {
+                            match build_watcher(&config) {
                                 Ok(mut new_watcher) => {
-                                    for watch in &config.watches {
+                                    for watch in config.watches.iter().filter(|_| scheduled.is_none()) {
                                         let expanded_path = hazelnut::expand_path(&watch.path);
-                                        if let Err(e) = new_watcher.watch_with_rules(&expanded_path, watch.recursive, watch.rules.clone()) {
+                                        if let Err(e) = new_watcher.watch_with_options(&expanded_path, watch.options()) {
                                             tracing::error!("Failed to watch {}: {}", expanded_path.display(), e);
                                             hazelnut::notifications::notify_watch_error(
                                                 &expanded_path.display().to_string(),
@@ -474,20 +647,66 @@ mod unix_daemon {
                         info!("Stop flag set, shutting down...");
                         break;
                     }
-                    match watcher.process_events() {
+                    let result = match scheduled.as_mut() {
+                        Some(schedule) => {
+                            let scanned = run_scan(&mut watcher, schedule, std::time::Instant::now())
+                                .map_or(0, |(processed, _)| processed);
+                            // Files a scan failed on come back once their retry is due
+                            if watcher.has_due_requeues() {
+                                watcher.process_polled_events(Vec::new()).map(|n| n + scanned)
+                            } else {
+                                Ok(scanned)
+                            }
+                        }
+                        None => watcher.process_events(),
+                    };
+                    match result {
                         Ok(count) if count > 0 => {
-                            let msg = format!("[{}] Processed {} file(s)", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), count);
                             info!("Processed {} files", count);
-                            push_log(&log_buffer, msg, MAX_LOG_ENTRIES);
+                            // Summarize bulk drops so one burst doesn't flood the log
+                            for group in hazelnut::watcher::coalesce_activity(watcher.take_activity()) {
+                                if group.is_burst() {
+                                    info!("{} ({})", group.summary(), group.rule_name);
+                                    push_log(&log_buffer, LogEntry::batch(&group));
+                                    continue;
+                                }
+                                for path in group.files {
+                                    let entry = LogEntry::processed(path, group.rule_name.clone(), &group.action);
+                                    push_log(&log_buffer, entry);
+                                }
+                            }
                         }
                         Err(e) => {
-                            let msg = format!("[{}] Error: {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), e);
                             tracing::error!("Error processing events: {}", e);
-                            push_log(&log_buffer, msg, MAX_LOG_ENTRIES);
+                            push_log(&log_buffer, LogEntry::error(format!("Error: {}", e)));
                         }
                         _ => {}
                     }
                 }
+                _ = availability_interval.tick() => {
+                    for change in watcher.check_watches() {
+                        match change {
+                            hazelnut::watcher::WatchAvailability::Lost { path, error } => {
+                                tracing::warn!("Watch folder unavailable: {}: {}", path.display(), error);
+                                hazelnut::notifications::notify_watch_error(
+                                    &path.display().to_string(),
+                                    &format!("unavailable ({})", error),
+                                );
+                                push_log(
+                                    &log_buffer,
+                                    LogEntry::warning(format!("Watch folder unavailable: {}", path.display())),
+                                );
+                            }
+                            hazelnut::watcher::WatchAvailability::Restored { path } => {
+                                info!("Watch folder available again: {}", path.display());
+                                push_log(
+                                    &log_buffer,
+                                    LogEntry::message(format!("Watching again: {}", path.display())),
+                                );
+                            }
+                        }
+                    }
+                }
                 result = ipc_listener.accept() => {
                     if let Ok((stream, _)) = result {
                         let log_buf = Arc::clone(&log_buffer);
@@ -497,6 +716,9 @@ mod unix_daemon {
                         let num_watches = config.watches.len();
                         let num_rules = config.rules.len();
                         let files_count = watcher.files_processed();
+                        let last_error = watcher.last_error().map(str::to_string);
+                        let last_event_at = watcher.last_event_at();
+                        let quarantined = watcher.quarantined().to_vec();
                         let stop = Arc::clone(&stop_flag);
 
                         // Handle IPC synchronously to avoid race between stop flag
@@ -519,6 +741,9 @@ mod unix_daemon {
                                             watches: num_watches,
                                             rules: num_rules,
                                             files_processed: files_count,
+                                            last_error,
+                                            last_event_at,
+                                            quarantined,
                                         }
                                     }
                                     hazelnut::ipc::DaemonCommand::Stop => {
@@ -545,13 +770,28 @@ mod unix_daemon {
                                     }
                                     hazelnut::ipc::DaemonCommand::GetLog { limit } => {
                                         let entries = if let Ok(ring) = log_buf.lock() {
-                                            let skip = ring.len().saturating_sub(limit);
-                                            ring.iter().skip(skip).cloned().collect()
+                                            ring.recent(limit).iter().map(|e| e.to_line()).collect()
                                         } else {
                                             vec![]
                                         };
                                         hazelnut::ipc::DaemonResponse::Log { entries }
                                     }
+                                    hazelnut::ipc::DaemonCommand::GetLogEntries { limit } => {
+                                        let entries = if let Ok(ring) = log_buf.lock() {
+                                            ring.recent(limit)
+                                        } else {
+                                            vec![]
+                                        };
+                                        hazelnut::ipc::DaemonResponse::LogEntries { entries }
+                                    }
+                                    hazelnut::ipc::DaemonCommand::TailLog { since, epoch } => {
+                                        match log_buf.lock() {
+                                            Ok(ring) => ring.since(since, epoch),
+                                            Err(_) => hazelnut::ipc::DaemonResponse::Error {
+                                                message: "Log buffer unavailable".to_string(),
+                                            },
+                                        }
+                                    }
                                     hazelnut::ipc::DaemonCommand::GetStats => {
                                         hazelnut::ipc::DaemonResponse::Status {
                                             running: true,
@@ -559,6 +799,9 @@ mod unix_daemon {
                                             watches: num_watches,
                                             rules: num_rules,
                                             files_processed: files_count,
+                                            last_error,
+                                            last_event_at,
+                                            quarantined,
                                         }
                                     }
                                 },
@@ -582,6 +825,14 @@ mod unix_daemon {
             }
         }
 
+        if drain_on_exit {
+            match watcher.drain(DRAIN_TIMEOUT) {
+                Ok(count) if count > 0 => info!("Processed {} queued file(s) before exit", count),
+                Err(e) => tracing::error!("Error draining events: {}", e),
+                _ => {}
+            }
+        }
+
         remove_pid_file();
         let _ = std::fs::remove_file(&sock_path);
         info!("Daemon stopped");
@@ -591,7 +842,7 @@ mod unix_daemon {
 
 #[cfg(unix)]
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() -> anyhow::Result<std::process::ExitCode> {
     use clap::Parser;
     let cli = unix_daemon::Cli::parse();
     unix_daemon::run(cli).await
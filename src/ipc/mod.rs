@@ -5,7 +5,21 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+/// Daemon log file path.
+/// Uses ~/.local/state/hazelnut/ on all platforms, matching the daemon
+pub fn daemon_log_path() -> PathBuf {
+    dirs::state_dir()
+        .unwrap_or_else(|| {
+            dirs::home_dir()
+                .map(|h| h.join(".local").join("state"))
+                .unwrap_or_else(|| PathBuf::from("/tmp"))
+        })
+        .join("hazelnut")
+        .join("hazelnutd.log")
+}
 
 /// IPC socket path
 pub fn socket_path() -> PathBuf {
@@ -41,10 +55,188 @@ pub enum DaemonCommand {
     /// Get activity log
     GetLog { limit: usize },
 
+    /// Get activity log as structured entries, with the rule and action of each file
+    GetLogEntries { limit: usize },
+
+    /// Get log entries newer than the entry with id `since` (0 = from the start).
+    ///
+    /// `epoch` is the one from the previous `LogTail`; a cursor from another
+    /// daemon run starts over from the beginning.
+    TailLog {
+        since: u64,
+        #[serde(default)]
+        epoch: u64,
+    },
+
     /// Get statistics
     GetStats,
 }
 
+/// Severity of a log entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// A single entry in the daemon activity log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// Position in the daemon's log, assigned by `LogBuffer` (0 = not logged yet)
+    #[serde(default)]
+    pub id: u64,
+    /// Severity
+    #[serde(default)]
+    pub level: LogLevel,
+    /// Local time the entry was recorded (`%Y-%m-%d %H:%M:%S`)
+    pub timestamp: String,
+    /// Human-readable message
+    pub message: String,
+    /// File the entry is about, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// Name of the rule that matched, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// Short description of the action taken, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+}
+
+impl LogEntry {
+    /// Create a plain message entry stamped with the current time
+    pub fn message(message: impl Into<String>) -> Self {
+        Self {
+            id: 0,
+            level: LogLevel::Info,
+            timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            message: message.into(),
+            file: None,
+            rule: None,
+            action: None,
+        }
+    }
+
+    /// The entry as a `[timestamp] message` line, as `GetLog` returns it
+    pub fn to_line(&self) -> String {
+        match self.rule {
+            Some(ref rule) => format!("[{}] {} ({})", self.timestamp, self.message, rule),
+            None => format!("[{}] {}", self.timestamp, self.message),
+        }
+    }
+
+    /// Create a warning entry stamped with the current time
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            level: LogLevel::Warning,
+            ..Self::message(message)
+        }
+    }
+
+    /// Create an error entry stamped with the current time
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            level: LogLevel::Error,
+            ..Self::message(message)
+        }
+    }
+
+    /// Create an entry for a file handled by a rule
+    pub fn processed(
+        file: PathBuf,
+        rule: impl Into<String>,
+        action: &crate::rules::Action,
+    ) -> Self {
+        Self {
+            file: Some(file.clone()),
+            rule: Some(rule.into()),
+            action: Some(action.preview()),
+            level: LogLevel::Success,
+            ..Self::message(format!("Processed {}", file.display()))
+        }
+    }
+
+    /// Create a single entry summarizing a burst of files handled the same way
+    pub fn batch(group: &crate::watcher::ActivityGroup) -> Self {
+        Self {
+            rule: Some(group.rule_name.clone()),
+            action: Some(group.action.preview()),
+            level: LogLevel::Success,
+            ..Self::message(group.summary())
+        }
+    }
+}
+
+/// The daemon's in-memory activity log.
+///
+/// Keeps the most recent entries and numbers every entry with an increasing
+/// id, so a client can ask for everything after the last id it saw. Ids start
+/// over with each daemon run, which `epoch` tells apart.
+#[derive(Debug)]
+pub struct LogBuffer {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+    next_id: u64,
+    epoch: u64,
+}
+
+impl LogBuffer {
+    /// Create an empty buffer holding at most `capacity` entries, for the
+    /// daemon run identified by `epoch` (the daemon uses its start time)
+    pub fn new(capacity: usize, epoch: u64) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            next_id: 1,
+            epoch,
+        }
+    }
+
+    /// Add an entry, evicting the oldest if full. Returns the id assigned.
+    pub fn push(&mut self, mut entry: LogEntry) -> u64 {
+        entry.id = self.next_id;
+        self.next_id += 1;
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+        self.next_id - 1
+    }
+
+    /// The last `limit` entries, oldest first
+    pub fn recent(&self, limit: usize) -> Vec<LogEntry> {
+        let skip = self.entries.len().saturating_sub(limit);
+        self.entries.iter().skip(skip).cloned().collect()
+    }
+
+    /// Entries with an id greater than `since`, oldest first.
+    ///
+    /// `missed` counts entries after `since` that were already evicted. A
+    /// cursor from another daemon run (`epoch` differs) starts over from the
+    /// beginning.
+    pub fn since(&self, since: u64, epoch: u64) -> DaemonResponse {
+        let since = if epoch == self.epoch { since } else { 0 };
+        let entries: Vec<LogEntry> = self
+            .entries
+            .iter()
+            .filter(|e| e.id > since)
+            .cloned()
+            .collect();
+        let missed = entries
+            .first()
+            .map_or(0, |first| first.id.saturating_sub(since + 1));
+        DaemonResponse::LogTail {
+            entries,
+            missed,
+            epoch: self.epoch,
+        }
+    }
+}
+
 /// Messages from daemon to TUI
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -56,11 +248,33 @@ pub enum DaemonResponse {
         watches: usize,
         rules: usize,
         files_processed: u64,
+        /// Most recent rule processing error, if any
+        #[serde(default)]
+        last_error: Option<String>,
+        /// Unix timestamp of the last file a rule was applied to
+        #[serde(default)]
+        last_event_at: Option<u64>,
+        /// Files that failed too often in a row and are no longer retried
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        quarantined: Vec<PathBuf>,
     },
 
-    /// Log entries
+    /// Log entries as `[timestamp] message` lines
     Log { entries: Vec<String> },
 
+    /// Structured log entries
+    LogEntries { entries: Vec<LogEntry> },
+
+    /// Log entries after a `TailLog` cursor
+    LogTail {
+        entries: Vec<LogEntry>,
+        /// Entries after the cursor that were evicted before they could be sent
+        missed: u64,
+        /// Daemon run the entry ids belong to, to send back with the next `TailLog`
+        #[serde(default)]
+        epoch: u64,
+    },
+
     /// Acknowledgment
     Ok,
 
@@ -68,18 +282,63 @@ pub enum DaemonResponse {
     Error { message: String },
 }
 
+/// Daemon statistics, as printed by `hazelnut stats --json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonStats {
+    pub uptime_seconds: u64,
+    pub watches: usize,
+    pub rules: usize,
+    pub files_processed: u64,
+    pub last_error: Option<String>,
+    pub last_event_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quarantined: Vec<PathBuf>,
+}
+
+impl DaemonStats {
+    /// Ask the daemon listening on `socket` for its statistics
+    pub fn fetch(socket: &Path) -> Result<Self> {
+        match send_command_to(socket, &DaemonCommand::GetStats)? {
+            DaemonResponse::Status {
+                uptime_seconds,
+                watches,
+                rules,
+                files_processed,
+                last_error,
+                last_event_at,
+                quarantined,
+                ..
+            } => Ok(Self {
+                uptime_seconds,
+                watches,
+                rules,
+                files_processed,
+                last_error,
+                last_event_at,
+                quarantined,
+            }),
+            DaemonResponse::Error { message } => anyhow::bail!("Daemon error: {}", message),
+            other => anyhow::bail!("Unexpected daemon response: {:?}", other),
+        }
+    }
+}
+
 /// Send a command to the daemon and receive a response.
 ///
 /// Connects to the Unix socket, sends a JSON-encoded command,
 /// and reads back a JSON-encoded response.
-#[cfg(unix)]
 pub fn send_command(cmd: &DaemonCommand) -> Result<DaemonResponse> {
+    send_command_to(&socket_path(), cmd)
+}
+
+/// Like `send_command`, for the daemon listening on `path`
+#[cfg(unix)]
+pub fn send_command_to(path: &Path, cmd: &DaemonCommand) -> Result<DaemonResponse> {
     use std::io::{BufRead, BufReader, Write};
     use std::os::unix::net::UnixStream;
     use std::time::Duration;
 
-    let path = socket_path();
-    let stream = UnixStream::connect(&path)
+    let stream = UnixStream::connect(path)
         .with_context(|| format!("Failed to connect to daemon at {}", path.display()))?;
 
     stream.set_read_timeout(Some(Duration::from_secs(5)))?;
@@ -101,7 +360,7 @@ pub fn send_command(cmd: &DaemonCommand) -> Result<DaemonResponse> {
 }
 
 #[cfg(not(unix))]
-pub fn send_command(_cmd: &DaemonCommand) -> Result<DaemonResponse> {
+pub fn send_command_to(_path: &Path, _cmd: &DaemonCommand) -> Result<DaemonResponse> {
     anyhow::bail!("IPC is only supported on Unix platforms")
 }
 
@@ -116,3 +375,201 @@ pub fn is_daemon_running() -> bool {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Action;
+
+    #[test]
+    fn test_log_entry_serialization() {
+        let entry = LogEntry::processed(
+            PathBuf::from("/tmp/report.pdf"),
+            "PDFs",
+            &Action::Move {
+                destination: PathBuf::from("/tmp/docs"),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+            },
+        );
+        let response = DaemonResponse::LogEntries {
+            entries: vec![entry.clone(), LogEntry::message("Daemon started")],
+        };
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains(r#""type":"log_entries""#));
+        assert!(json.contains(r#""rule":"PDFs""#));
+        assert!(json.contains(r#""file":"/tmp/report.pdf""#));
+        assert!(json.contains(r#""action":"→ /tmp/docs""#));
+
+        let parsed: DaemonResponse = serde_json::from_str(&json).unwrap();
+        match parsed {
+            DaemonResponse::LogEntries { entries } => {
+                assert_eq!(entries[0], entry);
+                assert_eq!(entries[1].message, "Daemon started");
+                assert!(entries[1].rule.is_none());
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_get_log_keeps_plain_lines() {
+        let mut entry = LogEntry::message("Daemon started");
+        entry.timestamp = "2024-01-02 03:04:05".to_string();
+        let response = DaemonResponse::Log {
+            entries: vec![entry.to_line()],
+        };
+
+        // Clients built before structured entries still parse the reply
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"log","entries":["[2024-01-02 03:04:05] Daemon started"]}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&DaemonCommand::GetLogEntries { limit: 5 }).unwrap(),
+            r#"{"type":"get_log_entries","limit":5}"#
+        );
+    }
+
+    #[test]
+    fn test_status_health_fields_are_optional() {
+        // Older daemons don't send the health fields
+        let json = r#"{"type":"status","running":true,"uptime_seconds":5,"watches":1,"rules":2,"files_processed":3}"#;
+        match serde_json::from_str::<DaemonResponse>(json).unwrap() {
+            DaemonResponse::Status {
+                last_error,
+                last_event_at,
+                ..
+            } => {
+                assert!(last_error.is_none());
+                assert!(last_event_at.is_none());
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        let status = DaemonResponse::Status {
+            running: true,
+            uptime_seconds: 5,
+            watches: 1,
+            rules: 2,
+            files_processed: 3,
+            last_error: Some("/tmp/a.txt: Destination exists".to_string()),
+            last_event_at: Some(1_700_000_000),
+            quarantined: vec![PathBuf::from("/tmp/locked.pdf")],
+        };
+        let json = serde_json::to_string(&status).unwrap();
+        assert!(json.contains(r#""last_error":"/tmp/a.txt: Destination exists""#));
+        assert!(json.contains(r#""last_event_at":1700000000"#));
+        assert!(json.contains(r#""quarantined":["/tmp/locked.pdf"]"#));
+    }
+
+    fn tail(buffer: &LogBuffer, since: u64) -> (Vec<u64>, u64) {
+        match buffer.since(since, 7) {
+            DaemonResponse::LogTail {
+                entries, missed, ..
+            } => (entries.iter().map(|e| e.id).collect(), missed),
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_tail_log_cursor_has_no_duplicates_or_gaps() {
+        let mut buffer = LogBuffer::new(10, 7);
+        for i in 0..3 {
+            buffer.push(LogEntry::message(format!("entry {}", i)));
+        }
+
+        let (ids, missed) = tail(&buffer, 0);
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(missed, 0);
+
+        // Nothing new: nothing repeated
+        assert_eq!(tail(&buffer, 3), (vec![], 0));
+
+        buffer.push(LogEntry::error("boom"));
+        buffer.push(LogEntry::message("after"));
+        assert_eq!(tail(&buffer, 3), (vec![4, 5], 0));
+        assert_eq!(
+            buffer.recent(2).iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![4, 5]
+        );
+    }
+
+    #[test]
+    fn test_tail_log_reports_evicted_entries() {
+        let mut buffer = LogBuffer::new(3, 7);
+        for i in 0..5 {
+            buffer.push(LogEntry::message(format!("entry {}", i)));
+        }
+
+        // Entries 1 and 2 were evicted; the client is told instead of silently skipping
+        assert_eq!(tail(&buffer, 0), (vec![3, 4, 5], 2));
+        assert_eq!(tail(&buffer, 1), (vec![3, 4, 5], 1));
+        assert_eq!(tail(&buffer, 2), (vec![3, 4, 5], 0));
+        assert_eq!(tail(&buffer, 4), (vec![5], 0));
+    }
+
+    #[test]
+    fn test_tail_log_starts_over_after_a_restart() {
+        let mut restarted = LogBuffer::new(10, 8);
+        for i in 0..5 {
+            restarted.push(LogEntry::message(format!("entry {}", i)));
+        }
+
+        // A cursor from the previous run gets everything, even though the new
+        // run has already logged past it
+        match restarted.since(2, 7) {
+            DaemonResponse::LogTail {
+                entries,
+                missed,
+                epoch,
+            } => {
+                assert_eq!(entries.len(), 5);
+                assert_eq!(missed, 0);
+                assert_eq!(epoch, 8);
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+        match restarted.since(2, 8) {
+            DaemonResponse::LogTail { entries, .. } => assert_eq!(entries.len(), 3),
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_daemon_stats_json() {
+        let stats = DaemonStats {
+            uptime_seconds: 90,
+            watches: 2,
+            rules: 5,
+            files_processed: 42,
+            last_error: None,
+            last_event_at: Some(1_700_000_000),
+            quarantined: Vec::new(),
+        };
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "uptime_seconds": 90,
+                "watches": 2,
+                "rules": 5,
+                "files_processed": 42,
+                "last_error": null,
+                "last_event_at": 1_700_000_000u64,
+            })
+        );
+        assert_eq!(serde_json::from_value::<DaemonStats>(json).unwrap(), stats);
+    }
+
+    #[test]
+    fn test_daemon_stats_fetch_fails_without_daemon() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(DaemonStats::fetch(&dir.path().join("hazelnut.sock")).is_err());
+    }
+}
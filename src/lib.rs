@@ -5,14 +5,16 @@
 pub mod app;
 #[cfg(unix)]
 pub mod autostart;
+pub mod cli;
 pub mod config;
 pub mod ipc;
+pub mod logging;
 pub mod notifications;
 pub mod rules;
 pub mod theme;
 pub mod watcher;
 
-pub use config::Config;
+pub use config::{Config, PlanItem};
 pub use rules::{Action, Condition, Rule, RuleEngine};
 pub use theme::Theme;
 pub use watcher::Watcher;
@@ -108,7 +110,10 @@ fn version_is_newer(latest: &str, current: &str) -> bool {
     false
 }
 
-/// Expand ~ and environment variables ($VAR, ${VAR}) in a path
+/// Expand ~ and environment variables ($VAR, ${VAR}, ${VAR:-default}) in a path.
+///
+/// Unset variables are left as written, unless a `:-` default is given, which
+/// (as in the shell) is used when the variable is unset or empty.
 pub fn expand_path(path: &std::path::Path) -> std::path::PathBuf {
     let path_str = path.to_string_lossy();
 
@@ -129,19 +134,25 @@ pub fn expand_path(path: &std::path::Path) -> std::path::PathBuf {
         path_str.to_string()
     };
 
-    // Then expand $VAR and ${VAR} patterns
+    // Then expand $VAR, ${VAR} and ${VAR:-default} patterns
     use std::sync::LazyLock;
     static ENV_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
         regex::Regex::new(r"\$\{([^}]+)\}|\$([A-Za-z_][A-Za-z0-9_]*)").expect("invalid env regex")
     });
 
     let result = ENV_RE.replace_all(&expanded, |caps: &regex::Captures| {
-        let var_name = caps
+        let var = caps
             .get(1)
             .or_else(|| caps.get(2))
             .map(|m| m.as_str())
             .unwrap_or("");
-        std::env::var(var_name).unwrap_or_else(|_| caps[0].to_string())
+        match var.split_once(":-") {
+            Some((name, default)) => std::env::var(name)
+                .ok()
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| default.to_string()),
+            None => std::env::var(var).unwrap_or_else(|_| caps[0].to_string()),
+        }
     });
 
     std::path::PathBuf::from(result.as_ref())
@@ -303,3 +314,54 @@ pub fn check_for_updates_crates_io_timeout(timeout: std::time::Duration) -> Vers
         Err(e) => VersionCheck::CheckFailed(format!("Request failed: {}", e)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_expand_path_set_variable() {
+        // SAFETY: the variable name is unique to this test
+        unsafe { std::env::set_var("HAZELNUT_TEST_MEDIA_SET", "/srv/media") };
+
+        assert_eq!(
+            expand_path(Path::new("$HAZELNUT_TEST_MEDIA_SET/photos")),
+            PathBuf::from("/srv/media/photos")
+        );
+        assert_eq!(
+            expand_path(Path::new("${HAZELNUT_TEST_MEDIA_SET}/photos")),
+            PathBuf::from("/srv/media/photos")
+        );
+        // A default is ignored when the variable is set
+        assert_eq!(
+            expand_path(Path::new("${HAZELNUT_TEST_MEDIA_SET:-/mnt/media}/photos")),
+            PathBuf::from("/srv/media/photos")
+        );
+    }
+
+    #[test]
+    fn test_expand_path_unset_variable_with_default() {
+        assert_eq!(
+            expand_path(Path::new("${HAZELNUT_TEST_MEDIA_UNSET:-/mnt/media}/photos")),
+            PathBuf::from("/mnt/media/photos")
+        );
+        // An empty default is allowed
+        assert_eq!(
+            expand_path(Path::new("/data${HAZELNUT_TEST_MEDIA_UNSET:-}/photos")),
+            PathBuf::from("/data/photos")
+        );
+    }
+
+    #[test]
+    fn test_expand_path_unset_variable_without_default() {
+        assert_eq!(
+            expand_path(Path::new("$HAZELNUT_TEST_MEDIA_UNSET/photos")),
+            PathBuf::from("$HAZELNUT_TEST_MEDIA_UNSET/photos")
+        );
+        assert_eq!(
+            expand_path(Path::new("${HAZELNUT_TEST_MEDIA_UNSET}/photos")),
+            PathBuf::from("${HAZELNUT_TEST_MEDIA_UNSET}/photos")
+        );
+    }
+}
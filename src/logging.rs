@@ -0,0 +1,135 @@
+//! JSON-lines log output for the daemon
+//!
+//! With `general.json_logs`, every log event is written as one JSON object per
+//! line, in the same shape as `tracing-subscriber`'s JSON format:
+//!
+//! ```json
+//! {"timestamp":"2026-02-04T20:12:37.235953Z","level":"INFO","target":"hazelnutd","fields":{"message":"Daemon running"}}
+//! ```
+//!
+//! Unlike the text format, messages can contain anything without confusing
+//! the TUI, which reads the log back.
+
+use serde_json::{Map, Value};
+use std::fmt;
+use std::io::Write;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Layer that writes each event as a JSON line
+pub struct JsonLayer<W> {
+    make_writer: W,
+}
+
+impl<W> JsonLayer<W>
+where
+    W: for<'a> MakeWriter<'a> + 'static,
+{
+    /// Write JSON lines to the writers `make_writer` produces
+    pub fn new(make_writer: W) -> Self {
+        Self { make_writer }
+    }
+}
+
+/// Render an event as a single JSON line (without the newline)
+fn format_event(event: &Event<'_>) -> String {
+    let mut fields = Map::new();
+    event.record(&mut JsonVisitor(&mut fields));
+    let metadata = event.metadata();
+    serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+        "level": metadata.level().as_str(),
+        "target": metadata.target(),
+        "fields": fields,
+    })
+    .to_string()
+}
+
+impl<S, W> Layer<S> for JsonLayer<W>
+where
+    S: Subscriber,
+    W: for<'a> MakeWriter<'a> + 'static,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let line = format_event(event);
+        let mut writer = self.make_writer.make_writer();
+        let _ = writeln!(writer, "{}", line);
+    }
+}
+
+/// Collects an event's fields into a JSON object
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value).into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Writer that appends to a shared buffer
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_events_are_written_as_json_lines() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber =
+            tracing_subscriber::registry().with(JsonLayer::new(move || writer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(count = 3, "Moved \"a b\" to {}", "/tmp/x  INFO y");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let value: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(value["level"], "WARN");
+        assert_eq!(
+            value["fields"]["message"],
+            "Moved \"a b\" to /tmp/x  INFO y"
+        );
+        assert_eq!(value["fields"]["count"], 3);
+        assert!(chrono::DateTime::parse_from_rfc3339(value["timestamp"].as_str().unwrap()).is_ok());
+    }
+}
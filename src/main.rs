@@ -29,7 +29,21 @@ enum Commands {
     Ui,
 
     /// List all rules
-    List,
+    List {
+        /// Also show each rule's conditions and action
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Write a commented starter config to the config path
+    Init {
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Roll the config back to the version before the last save
+    Restore,
 
     /// Validate config file
     Check {
@@ -44,14 +58,58 @@ enum Commands {
         #[arg(long)]
         apply: bool,
 
-        /// Target directory to process
+        /// Target directory to process; repeat for several (default: the watch folders)
+        #[arg(short, long)]
+        dir: Vec<PathBuf>,
+
+        /// Also process files in subdirectories (watches marked recursive always are)
         #[arg(short, long)]
-        dir: Option<PathBuf>,
+        recursive: bool,
+    },
+
+    /// Check whether one rule matches a file; exits 0 if it does, 1 if not
+    TestRule {
+        /// Name of the rule to test
+        #[arg(long)]
+        rule_name: String,
+
+        /// File to test the rule against
+        #[arg(long)]
+        path: PathBuf,
+    },
+
+    /// Reverse the most recent moves, copies and renames
+    Undo {
+        /// Number of operations to undo
+        #[arg(default_value_t = 1)]
+        count: usize,
+
+        /// List the journaled operations instead of undoing them
+        #[arg(long)]
+        list: bool,
+    },
+
+    /// Print the daemon log
+    Logs {
+        /// Keep printing new lines as they're written, like `tail -f`
+        #[arg(short, long)]
+        follow: bool,
+
+        /// Number of lines to print from the end of the log
+        #[arg(short = 'n', long, default_value_t = 20)]
+        lines: usize,
     },
 
     /// Show daemon status
     Status,
 
+    /// Print statistics from the running daemon
+    Stats {
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Check for updates and install if available
     Update,
 }
@@ -122,23 +180,51 @@ async fn main() -> Result<()> {
         None | Some(Commands::Ui) => {
             hazelnut::app::run(cli.config).await?;
         }
-        Some(Commands::List) => {
+        Some(Commands::List { verbose }) => {
             let config = hazelnut::Config::load(cli.config.as_deref())?;
-            println!("Rules:");
-            for (i, rule) in config.rules.iter().enumerate() {
-                let status = if rule.enabled { "✓" } else { "✗" };
-                println!("  {} [{}] {}", status, i + 1, rule.name);
+            print!("{}", hazelnut::cli::format_rules(&config.rules, verbose));
+        }
+        Some(Commands::Init { force }) => {
+            match hazelnut::Config::write_starter(cli.config.as_deref(), force) {
+                Ok(path) => {
+                    println!("✓ Wrote starter config to {}", path.display());
+                    println!("  Edit it, then run `hazelnut check` to validate");
+                }
+                Err(e) => {
+                    eprintln!("✗ {:#}", e);
+                    std::process::exit(1);
+                }
             }
         }
+        Some(Commands::Restore) => match hazelnut::Config::restore_backup(cli.config.as_deref()) {
+            Ok(path) => println!("✓ Restored previous config to {}", path.display()),
+            Err(e) => {
+                eprintln!("✗ {:#}", e);
+                std::process::exit(1);
+            }
+        },
         Some(Commands::Check {
             config: config_path,
         }) => {
             let path = config_path.or(cli.config);
             match hazelnut::Config::load(path.as_deref()) {
                 Ok(config) => {
+                    let report = config.validate();
+                    for error in &report.errors {
+                        eprintln!("✗ {}", error);
+                    }
+                    for warning in &report.warnings {
+                        println!("⚠ {}", warning);
+                    }
+                    if !report.is_ok() {
+                        std::process::exit(1);
+                    }
                     println!("✓ Config is valid");
                     println!("  {} watch paths", config.watches.len());
                     println!("  {} rules", config.rules.len());
+                    if !report.warnings.is_empty() {
+                        println!("  {} warnings", report.warnings.len());
+                    }
                 }
                 Err(e) => {
                     eprintln!("✗ Config error: {}", e);
@@ -146,39 +232,162 @@ async fn main() -> Result<()> {
                 }
             }
         }
-        Some(Commands::Run { apply, dir }) => {
+        Some(Commands::Run {
+            apply,
+            dir,
+            recursive,
+        }) => {
             let config = hazelnut::Config::load(cli.config.as_deref())?;
-            let engine = hazelnut::RuleEngine::new(config.rules);
+            let engine = hazelnut::RuleEngine::from_config(&config)
+                .with_journal(Some(hazelnut::rules::UndoJournal::open_default()));
 
-            let dirs: Vec<_> = if let Some(d) = dir {
-                vec![d]
+            // Simulate mode forces a dry run even with --apply
+            let apply = if apply && config.general.dry_run {
+                println!("Simulate mode is on (general.dry_run); no changes will be made");
+                false
+            } else {
+                apply
+            };
+
+            let dirs: Vec<_> = if !dir.is_empty() {
+                dir.into_iter().map(|d| (d, recursive)).collect()
             } else {
                 config
                     .watches
                     .iter()
-                    .map(|w| hazelnut::expand_path(&w.path))
+                    .map(|w| (hazelnut::expand_path(&w.path), w.recursive || recursive))
                     .collect()
             };
 
-            for dir in dirs {
-                println!("Processing: {}", dir.display());
-                let entries = std::fs::read_dir(&dir)?;
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.is_file() {
-                        let actions = engine.evaluate_all(&path)?;
-                        for action in actions {
-                            if apply {
-                                println!("  Applying: {} -> {:?}", path.display(), action);
-                                action.execute(&path)?;
-                            } else {
-                                println!("  [dry-run] {} -> {:?}", path.display(), action);
-                            }
-                        }
+            let mut summary = hazelnut::cli::RunSummary::default();
+            for (dir, recursive) in dirs {
+                hazelnut::cli::run_dir(&engine, &dir, recursive, apply, &mut summary)?;
+            }
+
+            if apply {
+                println!("{}", summary);
+                if summary.exit_code() != 0 {
+                    std::process::exit(summary.exit_code());
+                }
+            } else {
+                println!(
+                    "{} action(s) would be applied (use --apply to perform them)",
+                    summary.applied
+                );
+            }
+        }
+        Some(Commands::TestRule { rule_name, path }) => {
+            let mut config = hazelnut::Config::load(cli.config.as_deref())?;
+            let Some(rule) = config.rules.iter().find(|r| r.name == rule_name).cloned() else {
+                eprintln!("✗ No rule named '{}'", rule_name);
+                std::process::exit(2);
+            };
+            let disabled = if rule.enabled {
+                ""
+            } else {
+                " (disabled in the config)"
+            };
+
+            // Evaluate the rule on its own, as if it were enabled, with the
+            // config's general settings (e.g. protected extensions)
+            config.rules = vec![hazelnut::Rule {
+                enabled: true,
+                ..rule
+            }];
+            let engine = hazelnut::RuleEngine::from_config(&config);
+            let path = hazelnut::expand_path(&path);
+            match engine.evaluate_first(&path)? {
+                Some(action) => {
+                    println!(
+                        "✓ Rule '{}'{} matches {}",
+                        rule_name,
+                        disabled,
+                        path.display()
+                    );
+                    println!("  then: {}", action.preview());
+                }
+                None => {
+                    println!(
+                        "✗ Rule '{}'{} does not match {}",
+                        rule_name,
+                        disabled,
+                        path.display()
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(Commands::Undo { count, list }) => {
+            let journal = hazelnut::rules::UndoJournal::open_default();
+            if list {
+                let entries = journal.load()?;
+                if entries.is_empty() {
+                    println!("Nothing to undo");
+                }
+                for entry in entries.iter().rev() {
+                    println!(
+                        "{}  [{}] {} {} -> {}",
+                        entry.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                        entry.rule,
+                        entry.op.label(),
+                        entry.source.display(),
+                        entry.destination.display()
+                    );
+                }
+                return Ok(());
+            }
+
+            let undone = journal.undo_last(count)?;
+            if undone.is_empty() {
+                println!("Nothing to undo");
+            }
+            for entry in &undone {
+                println!("↶ {}", entry.describe());
+            }
+        }
+        Some(Commands::Stats { json }) => {
+            let stats = match hazelnut::ipc::DaemonStats::fetch(&hazelnut::ipc::socket_path()) {
+                Ok(stats) => stats,
+                Err(e) => {
+                    eprintln!("✗ Daemon is not reachable: {:#}", e);
+                    std::process::exit(1);
+                }
+            };
+            if json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                println!("Uptime: {}", hazelnut::format_uptime(stats.uptime_seconds));
+                println!("Watches: {}", stats.watches);
+                println!("Rules: {}", stats.rules);
+                println!("Files processed: {}", stats.files_processed);
+                if let Some(error) = stats.last_error {
+                    println!("Last error: {}", error);
+                }
+                if !stats.quarantined.is_empty() {
+                    println!("Quarantined (no longer retried):");
+                    for path in &stats.quarantined {
+                        println!("  {}", path.display());
                     }
                 }
             }
         }
+        Some(Commands::Logs { follow, lines }) => {
+            let path = hazelnut::ipc::daemon_log_path();
+            let (tail, end) = match hazelnut::cli::tail_lines(&path, lines) {
+                Ok(tail) => tail,
+                Err(_) if follow => (Vec::new(), 0),
+                Err(e) => {
+                    eprintln!("✗ {:#}", e);
+                    std::process::exit(1);
+                }
+            };
+            for line in tail {
+                println!("{}", line);
+            }
+            if follow {
+                hazelnut::cli::follow_log(&path, end, &mut std::io::stdout())?;
+            }
+        }
         Some(Commands::Status) => {
             show_daemon_status();
         }
@@ -1,17 +1,26 @@
 //! Desktop notifications for error alerts
 //!
-//! Only notifies on errors to avoid being noisy.
+//! Only notifies on errors to avoid being noisy. With `general.notify_command`
+//! set, notifications go to that command instead (ntfy, Slack, email...).
 
 use notify_rust::{Notification, Timeout};
+use std::sync::RwLock;
 use std::sync::atomic::{AtomicBool, Ordering};
 use tracing::warn;
 
 /// Global flag to enable/disable notifications
 static NOTIFICATIONS_ENABLED: AtomicBool = AtomicBool::new(false);
 
-/// Initialize notifications with the enabled setting
-pub fn init(enabled: bool) {
+/// Command template used instead of desktop notifications, if configured
+static NOTIFY_COMMAND: RwLock<Option<String>> = RwLock::new(None);
+
+/// Initialize notifications with the enabled setting and an optional command
+/// template that replaces desktop notifications
+pub fn init(enabled: bool, command: Option<String>) {
     NOTIFICATIONS_ENABLED.store(enabled, Ordering::SeqCst);
+    if let Ok(mut current) = NOTIFY_COMMAND.write() {
+        *current = command.filter(|c| !c.trim().is_empty());
+    }
 }
 
 /// Check if notifications are enabled
@@ -55,10 +64,25 @@ pub fn notify(kind: NotificationKind, message: &str) {
     if !is_enabled() {
         return;
     }
+    let command = NOTIFY_COMMAND.read().ok().and_then(|c| c.clone());
+    deliver(kind, message, command.as_deref());
+}
+
+/// Show a notification, through the notify command `template` if there is
+/// one. Returns the thread running the command.
+fn deliver(
+    kind: NotificationKind,
+    message: &str,
+    template: Option<&str>,
+) -> Option<std::thread::JoinHandle<()>> {
+    let summary = format!("Hazelnut: {}", kind.prefix());
+    if let Some(template) = template {
+        return Some(run_notify_command(template, &summary, message));
+    }
 
     let result = Notification::new()
         .appname("Hazelnut")
-        .summary(&format!("Hazelnut: {}", kind.prefix()))
+        .summary(&summary)
         .body(message)
         .icon(kind.icon())
         .timeout(Timeout::Milliseconds(5000))
@@ -67,6 +91,40 @@ pub fn notify(kind: NotificationKind, message: &str) {
     if let Err(e) = result {
         warn!("Failed to send notification: {}", e);
     }
+    None
+}
+
+/// Fill in `{summary}` and `{message}` in a notify command template,
+/// shell-escaping both
+pub fn expand_command(template: &str, summary: &str, message: &str) -> String {
+    template
+        .replace("{summary}", &shell_escape::escape(summary.into()))
+        .replace("{message}", &shell_escape::escape(message.into()))
+}
+
+/// Run the notify command through the shell in the background. Failures
+/// are logged, never reported back.
+fn run_notify_command(template: &str, summary: &str, message: &str) -> std::thread::JoinHandle<()> {
+    let command = expand_command(template, summary, message);
+    std::thread::spawn(move || {
+        let (shell, shell_arg) = if cfg!(target_os = "windows") {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        match std::process::Command::new(shell)
+            .arg(shell_arg)
+            .arg(&command)
+            .stdin(std::process::Stdio::null())
+            .status()
+        {
+            Ok(status) if !status.success() => {
+                warn!("Notify command exited with {}: {}", status, command);
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to run notify command: {}", e),
+        }
+    })
 }
 
 /// Convenience function for rule errors
@@ -99,3 +157,55 @@ pub fn notify_command_error(command: &str, error: &str) {
         &format!("Command '{}' failed: {}", cmd_display, error),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_command_escapes_values() {
+        assert_eq!(
+            expand_command(
+                "curl -d {message} -H Title:{summary} https://ntfy.sh/t",
+                "Hazelnut: Rule Error",
+                "Rule 'PDFs' failed"
+            ),
+            "curl -d 'Rule '\\''PDFs'\\'' failed' -H Title:'Hazelnut: Rule Error' https://ntfy.sh/t"
+        );
+        assert_eq!(
+            expand_command("notify {message}", "s", "plain"),
+            "notify plain"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_notify_runs_configured_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("sent.txt");
+        let template = format!("printf '%s|%s' {{summary}} {{message}} > {}", out.display());
+
+        run_notify_command(&template, "Hazelnut: Watch Error", "it's $HOME; gone")
+            .join()
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            "Hazelnut: Watch Error|it's $HOME; gone"
+        );
+
+        // Notifications take the command path once one is configured
+        std::fs::remove_file(&out).unwrap();
+        deliver(
+            NotificationKind::WatchError,
+            "Watch '/mnt/share': missing",
+            Some(&template),
+        )
+        .unwrap()
+        .join()
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            "Hazelnut: Watch Error|Watch '/mnt/share': missing"
+        );
+    }
+}
@@ -14,8 +14,16 @@ use libc;
 static DATE_FORMAT_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"\{date:([^}]+)\}").expect("invalid date format regex"));
 
+/// Pre-compiled regex for `{mtime:FORMAT}` patterns.
+static MTIME_FORMAT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{mtime:([^}]+)\}").expect("invalid mtime format regex"));
+
+/// Pre-compiled regex for `{1}`, `{2}`, … capture-group references.
+static CAPTURE_GROUP_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{(\d+)\}").expect("invalid capture group regex"));
+
 /// Action to perform on a matched file
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum Action {
     /// Move file to a destination folder
@@ -27,6 +35,16 @@ pub enum Action {
         /// Overwrite if file exists
         #[serde(default)]
         overwrite: bool,
+        /// Recreate the file's subfolder, relative to the watch root, under the destination
+        #[serde(default)]
+        preserve_structure: bool,
+        /// Leave the file for a later pass while another process has it open
+        /// (Linux only; ignored elsewhere)
+        #[serde(default)]
+        skip_if_open: bool,
+        /// Do nothing when the destination already holds a file with the same content
+        #[serde(default)]
+        skip_if_identical: bool,
     },
 
     /// Copy file to a destination folder
@@ -36,19 +54,42 @@ pub enum Action {
         create_destination: bool,
         #[serde(default)]
         overwrite: bool,
+        #[serde(default)]
+        preserve_structure: bool,
+        #[serde(default)]
+        skip_if_open: bool,
+        #[serde(default)]
+        skip_if_identical: bool,
+        /// Give the copy the source's modification and access times
+        #[serde(default)]
+        preserve_timestamps: bool,
     },
 
     /// Rename the file
     Rename {
-        /// New name pattern (supports {name}, {ext}, {date}, etc.)
+        /// New name pattern (supports {name}, {ext}, {date}, and {1}, {2}, …
+        /// for `name_regex` capture groups)
         pattern: String,
+        /// Replace a file that already has the new name (otherwise the new
+        /// name gets a `_1`, `_2`, … suffix)
+        #[serde(default)]
+        overwrite: bool,
     },
 
     /// Move to trash
-    Trash,
+    Trash {
+        /// Folder to move the file into instead of the system trash
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        destination: Option<PathBuf>,
+    },
 
     /// Delete permanently (use with caution!)
-    Delete,
+    Delete {
+        /// Must be set for the action to run, so a broad rule can't destroy
+        /// files by accident
+        #[serde(default)]
+        i_understand_delete_is_permanent: bool,
+    },
 
     /// Run a shell command
     Run {
@@ -75,26 +116,189 @@ fn default_true() -> bool {
     true
 }
 
+/// What to do when a rule's action fails
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "policy", rename_all = "lowercase")]
+pub enum OnError {
+    /// Log the error and leave the file alone (the default)
+    Skip,
+    /// Try again up to `attempts` more times, doubling the delay after each failure
+    Retry {
+        attempts: u32,
+        #[serde(default = "default_retry_delay")]
+        delay_secs: f64,
+    },
+    /// Leave the file and run the rule on it again on a later pass
+    Requeue,
+}
+
+fn default_retry_delay() -> f64 {
+    1.0
+}
+
+/// Error for a `skip_if_open` move or copy of a file another process has open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileInUse;
+
+impl std::fmt::Display for FileInUse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("file is open in another process")
+    }
+}
+
+impl std::error::Error for FileInUse {}
+
+/// Settings that apply to a single action execution
+#[derive(Debug, Clone, Default)]
+pub struct ActionContext {
+    /// Log what would happen instead of touching the filesystem
+    pub dry_run: bool,
+    /// Default folder for `trash` actions without their own destination
+    pub trash_dir: Option<PathBuf>,
+    /// Capture groups from the rule's `name_regex`, for `{1}`, `{2}`, … in rename patterns
+    pub captures: Vec<String>,
+    /// Watched directory the file was found under, for `preserve_structure`
+    pub watch_root: Option<PathBuf>,
+    /// Name of the rule being applied, for `{rule}` in destinations
+    pub rule_name: Option<String>,
+    /// Log the action's progress at debug level instead of info (errors are
+    /// still reported by the caller)
+    pub quiet: bool,
+}
+
+/// Log an action's progress at info level, or at debug level for quiet rules
+macro_rules! activity {
+    ($ctx:expr, $($arg:tt)+) => {
+        if $ctx.quiet {
+            debug!($($arg)+)
+        } else {
+            info!($($arg)+)
+        }
+    };
+}
+
 impl Action {
+    /// Short one-line description of the action, e.g. `→ ~/Documents`
+    pub fn preview(&self) -> String {
+        match self {
+            Action::Move { destination, .. } => format!("→ {}", destination.display()),
+            Action::Copy { destination, .. } => format!("⇒ {}", destination.display()),
+            Action::Rename { pattern, .. } => format!("✎ {}", pattern),
+            Action::Trash {
+                destination: Some(destination),
+            } => format!("🗑 {}", destination.display()),
+            Action::Trash { destination: None } => "🗑 Trash".to_string(),
+            Action::Delete {
+                i_understand_delete_is_permanent: true,
+            } => "⚠ Delete".to_string(),
+            Action::Delete {
+                i_understand_delete_is_permanent: false,
+            } => "⚠ Delete (not acknowledged, won't run)".to_string(),
+            Action::Run { command, .. } => format!("$ {}", command),
+            Action::Archive { .. } => "📦 Archive".to_string(),
+            Action::Nothing => "∅ Nothing".to_string(),
+        }
+    }
+
+    /// Whether the file is no longer at its original path after this action runs
+    pub fn removes_source(&self) -> bool {
+        matches!(
+            self,
+            Action::Move { .. }
+                | Action::Rename { .. }
+                | Action::Trash { .. }
+                | Action::Delete { .. }
+        )
+    }
+
+    /// Whether this action takes the file out of its folder, which a budget
+    /// rule needs to ever bring its folder back under budget
+    pub fn leaves_folder(&self) -> bool {
+        matches!(
+            self,
+            Action::Move { .. } | Action::Trash { .. } | Action::Delete { .. }
+        )
+    }
+
+    /// Whether this is a delete action missing `i_understand_delete_is_permanent = true`,
+    /// which refuses to run
+    pub fn is_unacknowledged_delete(&self) -> bool {
+        matches!(
+            self,
+            Action::Delete {
+                i_understand_delete_is_permanent: false
+            }
+        )
+    }
+
+    /// Folder this action puts files into, if it names one
+    pub fn destination_dir(&self) -> Option<&Path> {
+        match self {
+            Action::Move { destination, .. } | Action::Copy { destination, .. } => {
+                Some(destination)
+            }
+            Action::Trash { destination } | Action::Archive { destination, .. } => {
+                destination.as_deref()
+            }
+            _ => None,
+        }
+    }
+
     /// Execute this action on a file
     pub fn execute(&self, path: &Path) -> Result<()> {
+        self.execute_with(path, &ActionContext::default())
+    }
+
+    /// Execute this action on a file with the given execution context
+    pub fn execute_with(&self, path: &Path, ctx: &ActionContext) -> Result<()> {
+        self.apply(path, ctx).map(|_| ())
+    }
+
+    /// Execute this action and return where the file ended up, for the
+    /// actions that can be undone (move, copy and rename)
+    pub fn apply(&self, path: &Path, ctx: &ActionContext) -> Result<Option<PathBuf>> {
+        if ctx.dry_run {
+            info!("[dry-run] {} {}", path.display(), self.preview());
+            return Ok(None);
+        }
+
         match self {
             Action::Move {
                 destination,
                 create_destination,
                 overwrite,
+                preserve_structure,
+                skip_if_open,
+                skip_if_identical,
             } => {
-                let dest = expand_path(destination);
-
-                if *create_destination {
-                    std::fs::create_dir_all(&dest).with_context(|| {
-                        format!("Failed to create directory: {}", dest.display())
-                    })?;
+                if *skip_if_open && is_open_elsewhere(path) {
+                    return Err(FileInUse.into());
                 }
+                let base = expand_path(&expand_mtime(
+                    &expand_rule_name(destination, ctx.rule_name.as_deref()),
+                    path,
+                )?);
+                let dest = if *preserve_structure {
+                    base.join(relative_subdir(path, ctx.watch_root.as_deref()))
+                } else {
+                    base.clone()
+                };
+
+                // Surface a vanished source as NotFound before touching the destination
+                std::fs::symlink_metadata(path)?;
 
                 let filename = path.file_name().context("File has no name")?;
                 let dest_path = dest.join(filename);
 
+                if *skip_if_identical && files_identical(path, &dest_path)? {
+                    activity!(
+                        ctx,
+                        "{} is already at {}, skipping",
+                        path.display(),
+                        dest_path.display()
+                    );
+                    return Ok(None);
+                }
                 if dest_path.exists() && !overwrite {
                     anyhow::bail!(
                         "Destination exists and overwrite is false: {}",
@@ -102,7 +306,14 @@ impl Action {
                     );
                 }
 
-                info!("Moving {} -> {}", path.display(), dest_path.display());
+                prepare_destination_dir(&base, *create_destination)?;
+                if dest != base {
+                    std::fs::create_dir_all(&dest).with_context(|| {
+                        format!("Failed to create directory {}", dest.display())
+                    })?;
+                }
+
+                activity!(ctx, "Moving {} -> {}", path.display(), dest_path.display());
                 if std::fs::rename(path, &dest_path).is_err() {
                     // rename fails across filesystems; fall back to copy + remove
                     if path.is_dir() {
@@ -129,22 +340,45 @@ impl Action {
                         })?;
                     }
                 }
+                return Ok(Some(dest_path));
             }
 
             Action::Copy {
                 destination,
                 create_destination,
                 overwrite,
+                preserve_structure,
+                skip_if_open,
+                skip_if_identical,
+                preserve_timestamps,
             } => {
-                let dest = expand_path(destination);
-
-                if *create_destination {
-                    std::fs::create_dir_all(&dest)?;
+                if *skip_if_open && is_open_elsewhere(path) {
+                    return Err(FileInUse.into());
                 }
+                let base = expand_path(&expand_mtime(
+                    &expand_rule_name(destination, ctx.rule_name.as_deref()),
+                    path,
+                )?);
+                let dest = if *preserve_structure {
+                    base.join(relative_subdir(path, ctx.watch_root.as_deref()))
+                } else {
+                    base.clone()
+                };
+
+                std::fs::symlink_metadata(path)?;
 
                 let filename = path.file_name().context("File has no name")?;
                 let dest_path = dest.join(filename);
 
+                if *skip_if_identical && files_identical(path, &dest_path)? {
+                    activity!(
+                        ctx,
+                        "{} is already at {}, skipping",
+                        path.display(),
+                        dest_path.display()
+                    );
+                    return Ok(None);
+                }
                 if dest_path.exists() && !overwrite {
                     anyhow::bail!(
                         "Destination exists and overwrite is false: {}",
@@ -152,20 +386,62 @@ impl Action {
                     );
                 }
 
-                info!("Copying {} -> {}", path.display(), dest_path.display());
+                prepare_destination_dir(&base, *create_destination)?;
+                if dest != base {
+                    std::fs::create_dir_all(&dest).with_context(|| {
+                        format!("Failed to create directory {}", dest.display())
+                    })?;
+                }
+
+                activity!(ctx, "Copying {} -> {}", path.display(), dest_path.display());
+                // Taken before copying, which may update the source's access time
+                let source_meta = std::fs::metadata(path)?;
                 std::fs::copy(path, &dest_path)?;
+                if *preserve_timestamps {
+                    copy_timestamps(&source_meta, &dest_path).with_context(|| {
+                        format!("Failed to set timestamps on {}", dest_path.display())
+                    })?;
+                }
+                return Ok(Some(dest_path));
             }
 
-            Action::Rename { pattern } => {
-                let new_name = expand_pattern(pattern, path)?;
-                let new_path = path.parent().unwrap_or(Path::new(".")).join(&new_name);
+            Action::Rename { pattern, overwrite } => {
+                let new_name = expand_pattern(&expand_captures(pattern, &ctx.captures), path, ctx)?;
+                let dir = path.parent().unwrap_or(Path::new("."));
+                let mut new_path = dir.join(&new_name);
+
+                // rename(2) silently replaces an existing file
+                if !overwrite && new_path.exists() && !same_file(path, &new_path) {
+                    let free = unique_destination(dir, &new_path, path)?;
+                    activity!(
+                        ctx,
+                        "{} already exists, using {} instead",
+                        new_path.display(),
+                        free.display()
+                    );
+                    new_path = free;
+                }
 
-                info!("Renaming {} -> {}", path.display(), new_path.display());
+                activity!(ctx, "Renaming {} -> {}", path.display(), new_path.display());
                 std::fs::rename(path, &new_path)?;
+                return Ok(Some(new_path));
             }
 
-            Action::Trash => {
-                info!("Trashing {}", path.display());
+            Action::Trash { destination } => {
+                // A custom folder (per action, then global) replaces the system trash
+                if let Some(trash_dir) = destination.as_ref().or(ctx.trash_dir.as_ref()) {
+                    let trash_dir = expand_path(trash_dir);
+                    activity!(
+                        ctx,
+                        "Trashing {} -> {}",
+                        path.display(),
+                        trash_dir.display()
+                    );
+                    move_to_trash_dir(path, &trash_dir)?;
+                    return Ok(None);
+                }
+
+                activity!(ctx, "Trashing {}", path.display());
                 // Use the `trash` crate for proper .trashinfo / Finder integration.
                 // Fall back to a manual move if the crate fails (e.g. headless CI).
                 if let Err(e) = trash::delete(path) {
@@ -175,46 +451,20 @@ impl Action {
                         .or_else(|| dirs::home_dir().map(|h| h.join(".local/share/Trash/files")))
                         .unwrap_or_else(|| PathBuf::from("/tmp/trash"));
 
-                    std::fs::create_dir_all(&trash_dir)?;
-
-                    let filename = path.file_name().context("File has no name")?;
-                    let mut trash_path = trash_dir.join(filename);
-
-                    if trash_path.exists() {
-                        let stem = path
-                            .file_stem()
-                            .unwrap_or_default()
-                            .to_string_lossy()
-                            .to_string();
-                        let ext = path
-                            .extension()
-                            .map(|e| format!(".{}", e.to_string_lossy()))
-                            .unwrap_or_default();
-                        let mut counter = 1u32;
-                        loop {
-                            if counter > 10000 {
-                                anyhow::bail!(
-                                    "Too many duplicate files in trash for: {}",
-                                    path.display()
-                                );
-                            }
-                            trash_path = trash_dir.join(format!("{}_{}{}", stem, counter, ext));
-                            if !trash_path.exists() {
-                                break;
-                            }
-                            counter += 1;
-                        }
-                    }
-
-                    if std::fs::rename(path, &trash_path).is_err() {
-                        std::fs::copy(path, &trash_path)?;
-                        std::fs::remove_file(path)?;
-                    }
+                    move_to_trash_dir(path, &trash_dir)?;
                 }
             }
 
-            Action::Delete => {
-                info!("Deleting {}", path.display());
+            Action::Delete {
+                i_understand_delete_is_permanent,
+            } => {
+                if !i_understand_delete_is_permanent {
+                    anyhow::bail!(
+                        "Refusing to delete {}: the delete action needs i_understand_delete_is_permanent = true",
+                        path.display()
+                    );
+                }
+                activity!(ctx, "Deleting {}", path.display());
                 if path.is_dir() {
                     std::fs::remove_dir_all(path)?;
                 } else {
@@ -245,10 +495,10 @@ impl Action {
                     };
 
                     // Expand {path} patterns in the command, shell-escaping values
-                    let expanded_command = expand_pattern_shell_escaped(command, path)
+                    let expanded_command = expand_pattern_shell_escaped(command, path, ctx)
                         .unwrap_or_else(|_| command.clone());
 
-                    info!("Running (shell): {}", expanded_command);
+                    activity!(ctx, "Running (shell): {}", expanded_command);
 
                     let mut child = std::process::Command::new(shell)
                         .arg(shell_arg)
@@ -311,10 +561,10 @@ impl Action {
                         base_args.iter().map(|s| s.to_string()).collect();
                     expanded_args.extend(
                         args.iter()
-                            .map(|a| expand_pattern(a, path).unwrap_or_else(|_| a.clone())),
+                            .map(|a| expand_pattern(a, path, ctx).unwrap_or_else(|_| a.clone())),
                     );
 
-                    info!("Running: {} {:?}", actual_command, expanded_args);
+                    activity!(ctx, "Running: {} {:?}", actual_command, expanded_args);
 
                     let mut child = std::process::Command::new(actual_command)
                         .args(&expanded_args)
@@ -373,7 +623,12 @@ impl Action {
                 let archive_name = format!("{}.zip", filename.to_string_lossy());
                 let archive_path = dest.join(&archive_name);
 
-                info!("Archiving {} -> {}", path.display(), archive_path.display());
+                activity!(
+                    ctx,
+                    "Archiving {} -> {}",
+                    path.display(),
+                    archive_path.display()
+                );
 
                 // Create the zip archive
                 let zip_file = std::fs::File::create(&archive_path)?;
@@ -424,7 +679,7 @@ impl Action {
                 }
                 zip.finish()?;
 
-                info!("Created archive: {}", archive_path.display());
+                activity!(ctx, "Created archive: {}", archive_path.display());
 
                 if *delete_original {
                     if path.is_dir() {
@@ -440,8 +695,202 @@ impl Action {
             }
         }
 
-        Ok(())
+        Ok(None)
+    }
+}
+
+/// Whether another process of this user has `path` open, going by /proc
+#[cfg(target_os = "linux")]
+fn is_open_elsewhere(path: &Path) -> bool {
+    let Ok(target) = std::fs::canonicalize(path) else {
+        return false;
+    };
+    let Ok(procs) = std::fs::read_dir("/proc") else {
+        return false;
+    };
+    let own_pid = std::process::id().to_string();
+    procs
+        .flatten()
+        .filter(|p| {
+            let name = p.file_name();
+            let name = name.to_string_lossy();
+            name != own_pid && name.bytes().all(|b| b.is_ascii_digit())
+        })
+        .filter_map(|p| std::fs::read_dir(p.path().join("fd")).ok())
+        .flat_map(|fds| fds.flatten())
+        .any(|fd| std::fs::read_link(fd.path()).is_ok_and(|link| link == target))
+}
+
+/// There's no cheap, reliable check here, so files never count as open
+#[cfg(not(target_os = "linux"))]
+fn is_open_elsewhere(_path: &Path) -> bool {
+    false
+}
+
+/// Whether `a` and `b` are both regular files with the same content. Sizes
+/// are compared first; only files of equal size are read, in chunks.
+fn files_identical(a: &Path, b: &Path) -> Result<bool> {
+    use std::io::Read;
+
+    const CHUNK: usize = 64 * 1024;
+
+    let (Ok(meta_a), Ok(meta_b)) = (std::fs::metadata(a), std::fs::metadata(b)) else {
+        return Ok(false);
+    };
+    if !meta_a.is_file() || !meta_b.is_file() || meta_a.len() != meta_b.len() {
+        return Ok(false);
+    }
+
+    let open = |p: &Path| {
+        std::fs::File::open(p).with_context(|| format!("Failed to open {}", p.display()))
+    };
+    let (mut file_a, mut file_b) = (open(a)?, open(b)?);
+    let (mut buf_a, mut buf_b) = (vec![0; CHUNK], vec![0; CHUNK]);
+    loop {
+        let read = file_a.read(&mut buf_a)?;
+        if read == 0 {
+            return Ok(true);
+        }
+        file_b.read_exact(&mut buf_b[..read])?;
+        if buf_a[..read] != buf_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+/// Folder of `path` relative to `watch_root`, or empty when the file sits directly
+/// in the root or isn't under it
+fn relative_subdir(path: &Path, watch_root: Option<&Path>) -> PathBuf {
+    let (Some(root), Some(parent)) = (watch_root, path.parent()) else {
+        return PathBuf::new();
+    };
+    if let Ok(rel) = parent.strip_prefix(root) {
+        return rel.to_path_buf();
+    }
+    // Watch roots are canonical; the event path may not be
+    std::fs::canonicalize(parent)
+        .ok()
+        .and_then(|p| p.strip_prefix(root).ok().map(Path::to_path_buf))
+        .unwrap_or_default()
+}
+
+/// Make sure `dest` exists and can be written to, creating it only when
+/// `create` is set. Called right before the file operation so a doomed or
+/// simulated action never leaves empty directories behind.
+fn prepare_destination_dir(dest: &Path, create: bool) -> Result<()> {
+    if dest.is_dir() {
+        return ensure_writable(dest);
+    }
+    if dest.exists() {
+        anyhow::bail!("Destination is not a directory: {}", dest.display());
+    }
+    if !create {
+        anyhow::bail!(
+            "Destination directory does not exist and create_destination is false: {}",
+            dest.display()
+        );
+    }
+
+    // Check the closest existing ancestor, since that is where create_dir_all writes first
+    if let Some(ancestor) = dest.ancestors().skip(1).find(|a| a.is_dir()) {
+        ensure_writable(ancestor)?;
     }
+    std::fs::create_dir_all(dest)
+        .with_context(|| format!("Failed to create directory: {}", dest.display()))
+}
+
+/// Fail with a clear error if the current user can't create entries in `dir`
+fn ensure_writable(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    let writable = {
+        use std::os::unix::ffi::OsStrExt;
+        let c_path = std::ffi::CString::new(dir.as_os_str().as_bytes())?;
+        // SAFETY: access(2) only reads the NUL-terminated path we pass in.
+        unsafe { libc::access(c_path.as_ptr(), libc::W_OK) == 0 }
+    };
+    #[cfg(not(unix))]
+    let writable = !std::fs::metadata(dir)?.permissions().readonly();
+
+    if !writable {
+        anyhow::bail!("Destination directory is not writable: {}", dir.display());
+    }
+    Ok(())
+}
+
+/// Pick a free path for `path`'s file name inside `dir`, appending `_1`, `_2`, …
+/// to the stem when the name is already taken.
+///
+/// A candidate that is `source` itself counts as free, so re-running a rule on
+/// a file it already suffixed leaves the file where it is.
+fn unique_destination(dir: &Path, path: &Path, source: &Path) -> Result<PathBuf> {
+    let is_free = |candidate: &Path| !candidate.exists() || same_file(candidate, source);
+    let filename = path.file_name().context("File has no name")?;
+    let candidate = dir.join(filename);
+    if is_free(&candidate) {
+        return Ok(candidate);
+    }
+
+    let stem = path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    for counter in 1..=10000u32 {
+        let candidate = dir.join(format!("{}_{}{}", stem, counter, ext));
+        if is_free(&candidate) {
+            return Ok(candidate);
+        }
+    }
+    anyhow::bail!(
+        "Too many duplicate files in {} for: {}",
+        dir.display(),
+        path.display()
+    )
+}
+
+/// Give `dest` the modification and access times from `source`
+fn copy_timestamps(source: &std::fs::Metadata, dest: &Path) -> Result<()> {
+    let mut times = std::fs::FileTimes::new().set_modified(source.modified()?);
+    if let Ok(accessed) = source.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    std::fs::File::options()
+        .write(true)
+        .open(dest)?
+        .set_times(times)?;
+    Ok(())
+}
+
+/// Whether two paths name the same file, e.g. a rename that only changes case
+/// on a case-insensitive filesystem
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Move a file or directory into a trash folder without overwriting earlier entries.
+fn move_to_trash_dir(path: &Path, trash_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(trash_dir)
+        .with_context(|| format!("Failed to create trash directory: {}", trash_dir.display()))?;
+    let trash_path = unique_destination(trash_dir, path, path)?;
+
+    if std::fs::rename(path, &trash_path).is_err() {
+        // rename fails across filesystems; fall back to copy + remove
+        if path.is_dir() {
+            copy_dir_recursive(path, &trash_path)?;
+            std::fs::remove_dir_all(path)?;
+        } else {
+            std::fs::copy(path, &trash_path)?;
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
 }
 
 /// Recursively copy a directory tree from `src` to `dst`.
@@ -464,8 +913,98 @@ fn expand_path(path: &Path) -> PathBuf {
     crate::expand_path(path)
 }
 
+/// Replace `{rule}` in a destination with the rule's name as a single folder
+/// (see `rule_folder_name`)
+pub(crate) fn expand_rule_name(destination: &Path, rule_name: Option<&str>) -> PathBuf {
+    let Some(rule_name) = rule_name else {
+        return destination.to_path_buf();
+    };
+    let folder = rule_folder_name(rule_name);
+    let Ok(expanded) = replace_in_path(destination, "{rule}", |s| {
+        Ok::<_, std::convert::Infallible>(s.replace("{rule}", &folder))
+    });
+    expanded
+}
+
+/// A rule name made safe to use as one path component: separators become `-`,
+/// and a name of only dots (`.`, `..`) becomes dashes so it can't climb out of
+/// the destination
+fn rule_folder_name(rule_name: &str) -> String {
+    let folder = rule_name.replace(['/', '\\'], "-");
+    if folder.chars().all(|c| c == '.') {
+        return "-".repeat(folder.len().max(1));
+    }
+    folder
+}
+
+/// Replace `{mtime}` and `{mtime:FORMAT}` in a destination with the file's
+/// modification time, so files can be sorted by when they were last changed.
+/// The destination is left as written if the file's time can't be read.
+pub(crate) fn expand_mtime(destination: &Path, path: &Path) -> Result<PathBuf> {
+    replace_in_path(destination, "{mtime", |s| replace_mtime(s, path))
+}
+
+/// Run `replace` over a destination that contains `placeholder`, leaving any
+/// other destination untouched. A destination that isn't valid UTF-8 is only
+/// expanded in the components that are, so the rest keeps its exact bytes.
+fn replace_in_path<E>(
+    destination: &Path,
+    placeholder: &str,
+    replace: impl Fn(&str) -> std::result::Result<String, E>,
+) -> std::result::Result<PathBuf, E> {
+    if let Some(destination) = destination.to_str() {
+        if !destination.contains(placeholder) {
+            return Ok(PathBuf::from(destination));
+        }
+        return replace(destination).map(PathBuf::from);
+    }
+    destination
+        .components()
+        .map(|component| match component.as_os_str().to_str() {
+            Some(s) if s.contains(placeholder) => replace(s).map(std::ffi::OsString::from),
+            _ => Ok(component.as_os_str().to_os_string()),
+        })
+        .collect()
+}
+
+/// The first `{mtime:FORMAT}` in `pattern` whose FORMAT chrono can't render
+pub(crate) fn invalid_mtime_format(pattern: &str) -> Option<&str> {
+    MTIME_FORMAT_RE
+        .captures_iter(pattern)
+        .map(|caps| caps.get(1).map_or("", |m| m.as_str()))
+        .find(|format| {
+            chrono::format::StrftimeItems::new(format)
+                .any(|item| matches!(item, chrono::format::Item::Error))
+        })
+}
+
+/// Expand `{mtime}` (YYYY-MM-DD) and `{mtime:FORMAT}` using `path`'s
+/// modification time in local time
+fn replace_mtime(pattern: &str, path: &Path) -> Result<String> {
+    // chrono panics when asked to display an invalid format
+    if let Some(format) = invalid_mtime_format(pattern) {
+        anyhow::bail!("Invalid {{mtime}} format '{}'", format);
+    }
+    let Ok(modified) = std::fs::metadata(path).and_then(|m| m.modified()) else {
+        return Ok(pattern.to_string());
+    };
+    let mtime = chrono::DateTime::<chrono::Local>::from(modified);
+    let result = pattern.replace("{mtime}", &mtime.format("%Y-%m-%d").to_string());
+    Ok(MTIME_FORMAT_RE
+        .replace_all(&result, |caps: &regex::Captures| {
+            mtime.format(&caps[1]).to_string()
+        })
+        .to_string())
+}
+
 /// Internal pattern expansion with optional shell escaping of path-derived values.
-fn expand_pattern_inner(pattern: &str, path: &Path, shell_escape: bool) -> Result<String> {
+/// `ctx` supplies the rule name for `{rule}`.
+fn expand_pattern_inner(
+    pattern: &str,
+    path: &Path,
+    ctx: &ActionContext,
+    shell_escape: bool,
+) -> Result<String> {
     let mut result = pattern.to_string();
 
     let maybe_escape = |s: std::borrow::Cow<'_, str>| -> String {
@@ -501,6 +1040,12 @@ fn expand_pattern_inner(pattern: &str, path: &Path, shell_escape: bool) -> Resul
         result = result.replace("{ext}", "");
     }
 
+    // {rule} - name of the matching rule, made a single component as in destinations
+    if let Some(ref rule_name) = ctx.rule_name {
+        let rule_name = rule_folder_name(rule_name);
+        result = result.replace("{rule}", &maybe_escape(rule_name.into()));
+    }
+
     // {date} - current date
     let now = chrono::Local::now();
     result = result.replace("{date}", &now.format("%Y-%m-%d").to_string());
@@ -514,17 +1059,36 @@ fn expand_pattern_inner(pattern: &str, path: &Path, shell_escape: bool) -> Resul
         })
         .to_string();
 
+    // {mtime}, {mtime:FORMAT} - the file's modification time
+    if result.contains("{mtime") {
+        result = replace_mtime(&result, path)?;
+    }
+
     Ok(result)
 }
 
-/// Expand pattern variables like {name}, {ext}, {date}
-fn expand_pattern(pattern: &str, path: &Path) -> Result<String> {
-    expand_pattern_inner(pattern, path, false)
+/// Replace `{N}` with capture group N; groups that don't exist expand to nothing
+fn expand_captures(pattern: &str, captures: &[String]) -> String {
+    CAPTURE_GROUP_RE
+        .replace_all(pattern, |caps: &regex::Captures| {
+            caps[1]
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| captures.get(i))
+                .cloned()
+                .unwrap_or_default()
+        })
+        .to_string()
+}
+
+/// Expand pattern variables like {name}, {ext}, {date}, {rule}
+fn expand_pattern(pattern: &str, path: &Path, ctx: &ActionContext) -> Result<String> {
+    expand_pattern_inner(pattern, path, ctx, false)
 }
 
 /// Expand pattern variables with shell-escaped values (for use in shell commands)
-fn expand_pattern_shell_escaped(pattern: &str, path: &Path) -> Result<String> {
-    expand_pattern_inner(pattern, path, true)
+fn expand_pattern_shell_escaped(pattern: &str, path: &Path, ctx: &ActionContext) -> Result<String> {
+    expand_pattern_inner(pattern, path, ctx, true)
 }
 
 #[cfg(test)]
@@ -535,10 +1099,18 @@ mod tests {
     fn test_expand_pattern() {
         let path = Path::new("/tmp/test.pdf");
 
-        assert_eq!(expand_pattern("{name}", path).unwrap(), "test");
-        assert_eq!(expand_pattern("{ext}", path).unwrap(), "pdf");
-        assert_eq!(expand_pattern("{filename}", path).unwrap(), "test.pdf");
-        assert_eq!(expand_pattern("{name}.{ext}", path).unwrap(), "test.pdf");
+        let ctx = ActionContext::default();
+
+        assert_eq!(expand_pattern("{name}", path, &ctx).unwrap(), "test");
+        assert_eq!(expand_pattern("{ext}", path, &ctx).unwrap(), "pdf");
+        assert_eq!(
+            expand_pattern("{filename}", path, &ctx).unwrap(),
+            "test.pdf"
+        );
+        assert_eq!(
+            expand_pattern("{name}.{ext}", path, &ctx).unwrap(),
+            "test.pdf"
+        );
     }
 
     #[test]
@@ -548,4 +1120,619 @@ mod tests {
         let expanded = expand_path(path);
         assert!(!expanded.to_string_lossy().contains('~'));
     }
+
+    #[test]
+    fn test_dry_run_does_not_touch_filesystem() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "hello").unwrap();
+        let dest = dir.path().join("archive");
+        let ctx = ActionContext {
+            dry_run: true,
+            ..Default::default()
+        };
+
+        let actions = [
+            Action::Move {
+                destination: dest.clone(),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+            },
+            Action::Copy {
+                destination: dest.clone(),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+                preserve_timestamps: false,
+            },
+            Action::Rename {
+                pattern: "renamed.txt".to_string(),
+                overwrite: false,
+            },
+            Action::Archive {
+                destination: Some(dest.clone()),
+                delete_original: true,
+            },
+            Action::Delete {
+                i_understand_delete_is_permanent: true,
+            },
+        ];
+        for action in &actions {
+            action.execute_with(&file, &ctx).unwrap();
+        }
+
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "hello");
+        assert!(!dest.exists());
+        assert!(!dir.path().join("renamed.txt").exists());
+    }
+
+    #[test]
+    fn test_trash_to_custom_dir_suffixes_collisions() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = dir.path().join("quarantine");
+        let action = Action::Trash {
+            destination: Some(trash_dir.clone()),
+        };
+
+        for content in ["first", "second"] {
+            let file = dir.path().join("report.pdf");
+            std::fs::write(&file, content).unwrap();
+            action.execute(&file).unwrap();
+            assert!(!file.exists());
+        }
+
+        assert_eq!(
+            std::fs::read_to_string(trash_dir.join("report.pdf")).unwrap(),
+            "first"
+        );
+        assert_eq!(
+            std::fs::read_to_string(trash_dir.join("report_1.pdf")).unwrap(),
+            "second"
+        );
+    }
+
+    #[test]
+    fn test_trash_uses_context_trash_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let global = dir.path().join("global-trash");
+        let own = dir.path().join("rule-trash");
+        let ctx = ActionContext {
+            trash_dir: Some(global.clone()),
+            ..Default::default()
+        };
+
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "a").unwrap();
+        Action::Trash { destination: None }
+            .execute_with(&file, &ctx)
+            .unwrap();
+        assert!(global.join("a.txt").exists());
+
+        // A destination on the action wins over the global setting
+        let file = dir.path().join("b.txt");
+        std::fs::write(&file, "b").unwrap();
+        Action::Trash {
+            destination: Some(own.clone()),
+        }
+        .execute_with(&file, &ctx)
+        .unwrap();
+        assert!(own.join("b.txt").exists());
+        assert!(!global.join("b.txt").exists());
+    }
+
+    #[test]
+    fn test_trash_deserializes_with_and_without_destination() {
+        let plain: Action = toml::from_str(r#"type = "trash""#).unwrap();
+        assert!(matches!(plain, Action::Trash { destination: None }));
+
+        let custom: Action =
+            toml::from_str("type = \"trash\"\ndestination = \"~/Quarantine\"").unwrap();
+        assert!(matches!(
+            custom,
+            Action::Trash {
+                destination: Some(ref d)
+            } if d == Path::new("~/Quarantine")
+        ));
+    }
+
+    #[test]
+    fn test_expand_captures() {
+        let captures = vec!["IMG_1234.jpg".to_string(), "1234".to_string()];
+        assert_eq!(
+            expand_captures("2024_vacation_{1}.{ext}", &captures),
+            "2024_vacation_1234.{ext}"
+        );
+        assert_eq!(expand_captures("{0}", &captures), "IMG_1234.jpg");
+        // Missing groups fall back to an empty string
+        assert_eq!(expand_captures("{1}-{2}", &captures), "1234-");
+        assert_eq!(expand_captures("{name}_{1}", &[]), "{name}_");
+    }
+
+    #[test]
+    fn test_rename_with_capture_groups() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("IMG_1234.jpg");
+        std::fs::write(&file, "jpg").unwrap();
+
+        let ctx = ActionContext {
+            captures: vec!["IMG_1234".to_string(), "1234".to_string()],
+            ..Default::default()
+        };
+        Action::Rename {
+            pattern: "2024_vacation_{1}.{ext}".to_string(),
+            overwrite: false,
+        }
+        .execute_with(&file, &ctx)
+        .unwrap();
+
+        assert!(!file.exists());
+        assert!(dir.path().join("2024_vacation_1234.jpg").exists());
+    }
+
+    #[test]
+    fn test_failed_move_does_not_create_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("new").join("nested");
+        let action = Action::Move {
+            destination: dest.clone(),
+            create_destination: true,
+            overwrite: false,
+            preserve_structure: false,
+            skip_if_open: false,
+            skip_if_identical: false,
+        };
+
+        // Source vanished before the move: NotFound, and nothing created
+        let err = action.execute(&dir.path().join("gone.txt")).unwrap_err();
+        assert!(
+            err.downcast_ref::<std::io::Error>()
+                .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+        );
+        assert!(!dir.path().join("new").exists());
+
+        // Dry-run never creates the destination either
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "a").unwrap();
+        let ctx = ActionContext {
+            dry_run: true,
+            ..Default::default()
+        };
+        action.execute_with(&file, &ctx).unwrap();
+        assert!(!dir.path().join("new").exists());
+
+        // A real move creates it just in time
+        action.execute(&file).unwrap();
+        assert!(dest.join("a.txt").exists());
+    }
+
+    #[test]
+    fn test_move_without_create_destination_reports_missing_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "a").unwrap();
+        let dest = dir.path().join("missing");
+
+        let err = Action::Move {
+            destination: dest.clone(),
+            create_destination: false,
+            overwrite: false,
+            preserve_structure: false,
+            skip_if_open: false,
+            skip_if_identical: false,
+        }
+        .execute(&file)
+        .unwrap_err();
+        assert!(err.to_string().contains("does not exist"));
+        assert!(file.exists());
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn test_preserve_structure_keeps_relative_subpath() {
+        let watch = tempfile::tempdir().unwrap();
+        let backup = tempfile::tempdir().unwrap();
+        let nested = watch.path().join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        let root = std::fs::canonicalize(watch.path()).unwrap();
+        let ctx = ActionContext {
+            watch_root: Some(root),
+            ..Default::default()
+        };
+
+        let copied = nested.join("c.pdf");
+        std::fs::write(&copied, "pdf").unwrap();
+        Action::Copy {
+            destination: backup.path().to_path_buf(),
+            create_destination: true,
+            overwrite: false,
+            preserve_structure: true,
+            skip_if_open: false,
+            skip_if_identical: false,
+            preserve_timestamps: false,
+        }
+        .execute_with(&copied, &ctx)
+        .unwrap();
+        assert!(backup.path().join("a/b/c.pdf").exists());
+        assert!(copied.exists());
+
+        let moved = nested.join("d.pdf");
+        std::fs::write(&moved, "pdf").unwrap();
+        let move_action = Action::Move {
+            destination: backup.path().to_path_buf(),
+            create_destination: true,
+            overwrite: false,
+            preserve_structure: true,
+            skip_if_open: false,
+            skip_if_identical: false,
+        };
+        move_action.execute_with(&moved, &ctx).unwrap();
+        assert!(backup.path().join("a/b/d.pdf").exists());
+        assert!(!moved.exists());
+
+        // Files directly in the root land directly in the destination
+        let top = watch.path().join("top.pdf");
+        std::fs::write(&top, "pdf").unwrap();
+        move_action.execute_with(&top, &ctx).unwrap();
+        assert!(backup.path().join("top.pdf").exists());
+    }
+
+    #[test]
+    fn test_preserve_structure_without_watch_root_flattens() {
+        let watch = tempfile::tempdir().unwrap();
+        let backup = tempfile::tempdir().unwrap();
+        let nested = watch.path().join("a");
+        std::fs::create_dir_all(&nested).unwrap();
+        let file = nested.join("c.pdf");
+        std::fs::write(&file, "pdf").unwrap();
+
+        Action::Move {
+            destination: backup.path().to_path_buf(),
+            create_destination: true,
+            overwrite: false,
+            preserve_structure: true,
+            skip_if_open: false,
+            skip_if_identical: false,
+        }
+        .execute(&file)
+        .unwrap();
+        assert!(backup.path().join("c.pdf").exists());
+    }
+
+    #[test]
+    fn test_skip_if_identical() {
+        let dir = tempfile::tempdir().unwrap();
+        let backup = dir.path().join("backup");
+        std::fs::create_dir(&backup).unwrap();
+        let copy = Action::Copy {
+            destination: backup.clone(),
+            create_destination: true,
+            overwrite: true,
+            preserve_structure: false,
+            skip_if_open: false,
+            skip_if_identical: true,
+            preserve_timestamps: false,
+        };
+        let ctx = ActionContext::default();
+
+        // Missing destination: copied
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "v1").unwrap();
+        assert_eq!(
+            copy.apply(&file, &ctx).unwrap(),
+            Some(backup.join("notes.txt"))
+        );
+
+        // Identical destination: nothing written
+        let modified = || {
+            backup
+                .join("notes.txt")
+                .metadata()
+                .unwrap()
+                .modified()
+                .unwrap()
+        };
+        let before = modified();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert_eq!(copy.apply(&file, &ctx).unwrap(), None);
+        assert_eq!(modified(), before);
+
+        // Same size, different content: overwritten
+        std::fs::write(&file, "v2").unwrap();
+        assert!(copy.apply(&file, &ctx).unwrap().is_some());
+        assert_eq!(
+            std::fs::read_to_string(backup.join("notes.txt")).unwrap(),
+            "v2"
+        );
+
+        // A move of an identical file leaves the source where it is
+        let mv = Action::Move {
+            destination: backup.clone(),
+            create_destination: true,
+            overwrite: false,
+            preserve_structure: false,
+            skip_if_open: false,
+            skip_if_identical: true,
+        };
+        assert_eq!(mv.apply(&file, &ctx).unwrap(), None);
+        assert!(file.exists());
+    }
+
+    #[test]
+    fn test_expand_rule_name_in_patterns() {
+        let path = Path::new("/tmp/report.pdf");
+        let ctx = ActionContext {
+            rule_name: Some("Tom's PDFs".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            expand_pattern("{rule}: {filename}", path, &ctx).unwrap(),
+            "Tom's PDFs: report.pdf"
+        );
+        assert_eq!(
+            expand_pattern_shell_escaped("notify {rule} {path}", path, &ctx).unwrap(),
+            "notify 'Tom'\\''s PDFs' /tmp/report.pdf"
+        );
+
+        // Slashes can't sneak a path into a rename
+        let ctx = ActionContext {
+            rule_name: Some("a/b".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            expand_pattern("{rule}-{name}", path, &ctx).unwrap(),
+            "a-b-report"
+        );
+
+        // Without a rule (a bare action), {rule} is left as written
+        let bare = ActionContext::default();
+        assert_eq!(expand_pattern("{rule}", path, &bare).unwrap(), "{rule}");
+    }
+
+    #[test]
+    fn test_expand_rule_name_stays_inside_the_destination() {
+        let sorted = Path::new("/x/Sorted/{rule}");
+        assert_eq!(
+            expand_rule_name(sorted, Some("Images")),
+            PathBuf::from("/x/Sorted/Images")
+        );
+        assert_eq!(
+            expand_rule_name(sorted, Some("..")),
+            PathBuf::from("/x/Sorted/--")
+        );
+        assert_eq!(
+            expand_rule_name(sorted, Some("../../etc")),
+            PathBuf::from("/x/Sorted/..-..-etc")
+        );
+        assert_eq!(
+            expand_rule_name(Path::new("/x/{rule}/{rule}"), Some(".")),
+            PathBuf::from("/x/-/-")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_expand_rule_name_keeps_non_utf8_destinations() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let odd = Path::new(std::ffi::OsStr::from_bytes(b"/x/caf\xe9"));
+        assert_eq!(expand_rule_name(odd, Some("Images")), odd);
+        assert_eq!(
+            expand_rule_name(&odd.join("{rule}"), Some("Images")),
+            odd.join("Images")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_command_receives_rule_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("in.txt");
+        std::fs::write(&file, "x").unwrap();
+        let out = dir.path().join("out.txt");
+        let ctx = ActionContext {
+            rule_name: Some("Notes; rm -rf".to_string()),
+            ..Default::default()
+        };
+
+        let action = Action::Run {
+            command: format!("printf %s {{rule}} > {}", out.display()),
+            args: Vec::new(),
+        };
+        action.apply(&file, &ctx).unwrap();
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "Notes; rm -rf");
+    }
+
+    #[test]
+    fn test_delete_requires_acknowledgment() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("old.log");
+        std::fs::write(&file, "log").unwrap();
+
+        // A config that just says `type = "delete"` doesn't acknowledge
+        let action: Action = toml::from_str("type = \"delete\"").unwrap();
+        assert!(action.is_unacknowledged_delete());
+        let err = action.execute(&file).unwrap_err();
+        assert!(err.to_string().contains("i_understand_delete_is_permanent"));
+        assert!(file.exists());
+
+        let action: Action =
+            toml::from_str("type = \"delete\"\ni_understand_delete_is_permanent = true").unwrap();
+        assert!(!action.is_unacknowledged_delete());
+        action.execute(&file).unwrap();
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn test_mtime_placeholders_use_the_file_time() {
+        use chrono::TimeZone;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("IMG_1.jpg");
+        std::fs::write(&file, "photo").unwrap();
+        let taken = chrono::Local
+            .with_ymd_and_hms(2021, 7, 4, 12, 0, 0)
+            .single()
+            .unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(taken.into())
+            .unwrap();
+
+        let ctx = ActionContext::default();
+        assert_eq!(
+            expand_pattern("{mtime}_{filename}", &file, &ctx).unwrap(),
+            "2021-07-04_IMG_1.jpg"
+        );
+
+        let photos = dir.path().join("Photos");
+        let mv = Action::Move {
+            destination: photos.join("{mtime:%Y}/{mtime:%Y-%m}"),
+            create_destination: true,
+            overwrite: false,
+            preserve_structure: false,
+            skip_if_open: false,
+            skip_if_identical: false,
+        };
+        let moved = mv.apply(&file, &ctx).unwrap().unwrap();
+        assert_eq!(moved, photos.join("2021/2021-07/IMG_1.jpg"));
+        assert!(moved.exists());
+
+        // A file that can't be read leaves the placeholders alone
+        assert_eq!(
+            expand_mtime(Path::new("/x/{mtime:%Y}"), &dir.path().join("missing")).unwrap(),
+            PathBuf::from("/x/{mtime:%Y}")
+        );
+
+        // A bad format is an error instead of a panic
+        assert!(expand_mtime(Path::new("/x/{mtime:%Q}"), &file).is_err());
+        assert!(expand_pattern("{mtime:%Q}_{filename}", &file, &ctx).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_expand_mtime_keeps_non_utf8_destinations() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "a").unwrap();
+        let odd = Path::new(std::ffi::OsStr::from_bytes(b"/x/caf\xe9"));
+        assert_eq!(expand_mtime(odd, &file).unwrap(), odd);
+
+        let year = chrono::DateTime::<chrono::Local>::from(
+            std::fs::metadata(&file).unwrap().modified().unwrap(),
+        )
+        .format("%Y")
+        .to_string();
+        assert_eq!(
+            expand_mtime(&odd.join("{mtime:%Y}"), &file).unwrap(),
+            odd.join(year)
+        );
+    }
+
+    #[test]
+    fn test_rename_never_clobbers_an_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("scan.pdf");
+        let existing = dir.path().join("invoice.pdf");
+        std::fs::write(&file, "new scan").unwrap();
+        std::fs::write(&existing, "old invoice").unwrap();
+
+        let rename = |overwrite| Action::Rename {
+            pattern: "invoice.{ext}".to_string(),
+            overwrite,
+        };
+        let ctx = ActionContext::default();
+
+        let renamed = rename(false).apply(&file, &ctx).unwrap().unwrap();
+        assert_eq!(renamed, dir.path().join("invoice_1.pdf"));
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "old invoice");
+        assert_eq!(std::fs::read_to_string(&renamed).unwrap(), "new scan");
+
+        // With overwrite, the existing file is replaced
+        let renamed = rename(true).apply(&renamed, &ctx).unwrap().unwrap();
+        assert_eq!(renamed, existing);
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "new scan");
+
+        // A file that already has the target name keeps it
+        let renamed = rename(false).apply(&existing, &ctx).unwrap().unwrap();
+        assert_eq!(renamed, existing);
+        assert!(!dir.path().join("invoice_1.pdf").exists());
+    }
+
+    #[test]
+    fn test_rename_rerun_keeps_a_suffixed_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("scan.pdf");
+        std::fs::write(&file, "new scan").unwrap();
+        std::fs::write(dir.path().join("invoice.pdf"), "old invoice").unwrap();
+
+        let rename = Action::Rename {
+            pattern: "invoice.{ext}".to_string(),
+            overwrite: false,
+        };
+        let ctx = ActionContext::default();
+
+        let first = rename.apply(&file, &ctx).unwrap().unwrap();
+        assert_eq!(first, dir.path().join("invoice_1.pdf"));
+
+        // Running the rule again on its own output must not move it to invoice_2
+        let second = rename.apply(&first, &ctx).unwrap().unwrap();
+        assert_eq!(second, first);
+        assert_eq!(std::fs::read_to_string(&second).unwrap(), "new scan");
+        assert!(!dir.path().join("invoice_2.pdf").exists());
+    }
+
+    #[test]
+    fn test_copy_can_preserve_timestamps() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("photo.jpg");
+        std::fs::write(&file, "photo").unwrap();
+        let taken =
+            std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000);
+        std::fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_times(
+                std::fs::FileTimes::new()
+                    .set_modified(taken)
+                    .set_accessed(taken),
+            )
+            .unwrap();
+
+        let copy = |destination: PathBuf, preserve_timestamps| Action::Copy {
+            destination,
+            create_destination: true,
+            overwrite: false,
+            preserve_structure: false,
+            skip_if_open: false,
+            skip_if_identical: false,
+            preserve_timestamps,
+        };
+        let ctx = ActionContext::default();
+        let mtime = |p: &Path| std::fs::metadata(p).unwrap().modified().unwrap();
+
+        let kept = copy(dir.path().join("kept"), true)
+            .apply(&file, &ctx)
+            .unwrap()
+            .unwrap();
+        assert_eq!(mtime(&kept), taken);
+        assert_eq!(std::fs::metadata(&kept).unwrap().accessed().unwrap(), taken);
+
+        // By default the copy is as new as the copying
+        let fresh = copy(dir.path().join("fresh"), false)
+            .apply(&file, &ctx)
+            .unwrap()
+            .unwrap();
+        assert!(mtime(&fresh) > taken);
+    }
 }
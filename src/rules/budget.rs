@@ -0,0 +1,132 @@
+//! Folder budgets - keep a folder under a total size or file count
+//!
+//! A rule with a `budget` doesn't react to single files. Whenever its folder
+//! is over budget, the rule's action is applied to the oldest matching files
+//! until the folder is back under.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Size and/or file-count limit for the files directly inside a folder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FolderBudget {
+    /// Folder to keep under budget (supports `~` and environment variables)
+    pub path: PathBuf,
+
+    /// Largest allowed total size of the files, in bytes
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+
+    /// Largest allowed number of files
+    #[serde(default)]
+    pub max_files: Option<usize>,
+}
+
+impl FolderBudget {
+    /// Whether a folder with `bytes` in `files` files is over this budget
+    pub fn is_exceeded(&self, bytes: u64, files: usize) -> bool {
+        self.max_bytes.is_some_and(|max| bytes > max)
+            || self.max_files.is_some_and(|max| files > max)
+    }
+
+    /// Files that have to go, oldest first by modification time, to bring the
+    /// folder back under budget. Every file counts toward the total, but only
+    /// files accepted by `eligible` are picked. A missing folder is empty.
+    pub fn files_over_budget(&self, eligible: impl Fn(&Path) -> bool) -> Result<Vec<PathBuf>> {
+        let dir = crate::expand_path(&self.path);
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", dir.display()));
+            }
+        };
+
+        let mut files: Vec<(PathBuf, u64, SystemTime)> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                metadata.is_file().then(|| {
+                    let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                    (entry.path(), metadata.len(), modified)
+                })
+            })
+            .collect();
+        let mut bytes: u64 = files.iter().map(|(_, len, _)| len).sum();
+        let mut count = files.len();
+        if !self.is_exceeded(bytes, count) {
+            return Ok(Vec::new());
+        }
+
+        files.sort_by_key(|(_, _, modified)| *modified);
+        let mut selected = Vec::new();
+        for (path, len, _) in files {
+            if !self.is_exceeded(bytes, count) {
+                break;
+            }
+            if eligible(&path) {
+                bytes -= len;
+                count -= 1;
+                selected.push(path);
+            }
+        }
+        Ok(selected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Write a file of `len` bytes modified `age_secs` ago
+    fn write_aged(dir: &Path, name: &str, len: usize, age_secs: u64) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, vec![b'x'; len]).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age_secs);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_files_over_budget_oldest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let newest = write_aged(dir.path(), "newest.bin", 40, 10);
+        let oldest = write_aged(dir.path(), "oldest.bin", 30, 300);
+        let middle = write_aged(dir.path(), "middle.bin", 30, 200);
+        let keep = write_aged(dir.path(), "keep.txt", 30, 400);
+
+        let budget = FolderBudget {
+            path: dir.path().to_path_buf(),
+            max_bytes: Some(100),
+            max_files: None,
+        };
+        // 130 bytes: dropping the oldest eligible file is enough
+        let only_bin = |p: &Path| p.extension().is_some_and(|e| e == "bin");
+        assert_eq!(budget.files_over_budget(only_bin).unwrap(), vec![oldest]);
+
+        let budget = FolderBudget {
+            max_bytes: None,
+            max_files: Some(1),
+            ..budget
+        };
+        assert_eq!(
+            budget.files_over_budget(|_| true).unwrap(),
+            vec![keep.clone(), dir.path().join("oldest.bin"), middle]
+        );
+        assert!(newest.exists() && keep.exists());
+
+        let budget = FolderBudget {
+            max_files: Some(4),
+            ..budget
+        };
+        assert!(budget.files_over_budget(|_| true).unwrap().is_empty());
+    }
+}
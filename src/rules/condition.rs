@@ -4,19 +4,63 @@ use anyhow::Result;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 // Simple thread-local caches for compiled patterns.
 // Capped at 1000 entries; cleared entirely when the cap is exceeded.
 const CACHE_MAX_ENTRIES: usize = 1000;
 
+/// Largest file `contains_text` / `contains_regex` will read by default (1 MiB)
+const DEFAULT_CONTENT_MAX_BYTES: u64 = 1024 * 1024;
+
+/// Bytes inspected for a NUL byte when deciding whether a file is binary
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+/// Extension groups the `category` condition knows without any config
+pub const BUILTIN_CATEGORIES: &[(&str, &[&str])] = &[
+    (
+        "images",
+        &[
+            "jpg", "jpeg", "png", "gif", "webp", "heic", "bmp", "tiff", "svg",
+        ],
+    ),
+    (
+        "documents",
+        &[
+            "pdf", "doc", "docx", "odt", "rtf", "txt", "md", "xls", "xlsx", "ods", "ppt", "pptx",
+            "odp", "csv",
+        ],
+    ),
+    (
+        "audio",
+        &["mp3", "wav", "flac", "aac", "ogg", "m4a", "opus"],
+    ),
+    ("video", &["mp4", "mkv", "mov", "avi", "webm", "m4v"]),
+    (
+        "archives",
+        &["zip", "tar", "gz", "tgz", "bz2", "xz", "7z", "rar"],
+    ),
+    (
+        "installers",
+        &["dmg", "pkg", "msi", "exe", "deb", "rpm", "appimage"],
+    ),
+];
+
+/// Extensions in a built-in category, if there is one by that name
+pub fn builtin_category(name: &str) -> Option<&'static [&'static str]> {
+    BUILTIN_CATEGORIES
+        .iter()
+        .find(|(category, _)| *category == name)
+        .map(|(_, extensions)| *extensions)
+}
+
 std::thread_local! {
     static GLOB_CACHE: std::cell::RefCell<HashMap<String, glob::Pattern>> = std::cell::RefCell::new(HashMap::new());
     static REGEX_CACHE: std::cell::RefCell<HashMap<String, Regex>> = std::cell::RefCell::new(HashMap::new());
 }
 
 /// Conditions for matching files
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct Condition {
     /// Match file extension (without dot, e.g., "pdf")
     #[serde(default)]
@@ -26,22 +70,71 @@ pub struct Condition {
     #[serde(default)]
     pub extensions: Vec<String>,
 
+    /// Extension is in this group: a built-in category (`images`,
+    /// `documents`, ...) or one from the config's `[extension_groups]`
+    #[serde(default)]
+    pub category: Option<String>,
+
+    /// Extensions `category` resolved to from the config's groups, set by
+    /// `resolve_category`. When unset the built-in category is used.
+    #[serde(skip)]
+    pub category_extensions: Option<Vec<String>>,
+
     /// Match filename with glob pattern
     #[serde(default)]
     pub name_matches: Option<String>,
 
+    /// Match filename with glob patterns (any of these)
+    #[serde(default)]
+    pub name_matches_any: Vec<String>,
+
     /// Match filename with regex
     #[serde(default)]
     pub name_regex: Option<String>,
 
-    /// File size greater than (in bytes)
+    /// Match the name of the directory directly containing the file (exact or glob)
+    #[serde(default)]
+    pub parent_name: Option<String>,
+
+    /// File is somewhere inside one of these directories
+    #[serde(default)]
+    pub under_any: Vec<PathBuf>,
+
+    /// File is not inside any of these directories
+    #[serde(default)]
+    pub not_under_any: Vec<PathBuf>,
+
+    /// A different file with the same name already exists in this directory
+    #[serde(default)]
+    pub would_collide_in: Option<PathBuf>,
+
+    /// Path has more than this many components below the watch root (a file
+    /// directly in the watched folder has depth 1). Only matches files
+    /// evaluated for a watch.
+    #[serde(default)]
+    pub depth_greater_than: Option<usize>,
+
+    /// Path has fewer than this many components below the watch root (see
+    /// `depth_greater_than`)
+    #[serde(default)]
+    pub depth_less_than: Option<usize>,
+
+    /// File size strictly greater than (in bytes); a file of exactly this size does not match
     #[serde(default)]
     pub size_greater_than: Option<u64>,
 
-    /// File size less than (in bytes)
+    /// File size strictly less than (in bytes); a file of exactly this size does not match
     #[serde(default)]
     pub size_less_than: Option<u64>,
 
+    /// File size at least (in bytes, inclusive); a file of exactly this size matches
+    #[serde(default)]
+    pub size_at_least: Option<u64>,
+
+    /// File size at most (in bytes, inclusive); a file of exactly this size matches
+    #[serde(default)]
+    pub size_at_most: Option<u64>,
+
     /// File age greater than (in days)
     #[serde(default)]
     pub age_days_greater_than: Option<u64>,
@@ -50,6 +143,33 @@ pub struct Condition {
     #[serde(default)]
     pub age_days_less_than: Option<u64>,
 
+    /// Modified after this reference file (e.g. a backup marker). A relative
+    /// path is looked up next to the file; if the reference doesn't exist,
+    /// every file counts as newer.
+    #[serde(default)]
+    pub newer_than_file: Option<PathBuf>,
+
+    /// Modified before this reference file (see `newer_than_file`). If the
+    /// reference doesn't exist, no file counts as older.
+    #[serde(default)]
+    pub older_than_file: Option<PathBuf>,
+
+    /// Last access more than this many days ago.
+    ///
+    /// Many systems mount with `noatime` or `relatime`, in which case the access
+    /// time is rarely updated and may simply equal the modification time.
+    #[serde(default)]
+    pub accessed_days_greater_than: Option<u64>,
+
+    /// Last access less than this many days ago (see `accessed_days_greater_than`)
+    #[serde(default)]
+    pub accessed_days_less_than: Option<u64>,
+
+    /// Modified on one of these days of the week, in local time
+    /// (e.g. `["sat", "sun"]`; full names like `"saturday"` work too)
+    #[serde(default)]
+    pub mtime_weekday: Option<Vec<String>>,
+
     /// File is a directory
     #[serde(default)]
     pub is_directory: Option<bool>,
@@ -57,11 +177,134 @@ pub struct Condition {
     /// File is hidden (starts with .)
     #[serde(default)]
     pub is_hidden: Option<bool>,
+
+    /// File is a symlink whose target no longer exists
+    #[serde(default)]
+    pub is_broken_symlink: Option<bool>,
+
+    /// File is owned by this user id (Unix only; ignored elsewhere)
+    #[serde(default)]
+    pub owner_uid: Option<u32>,
+
+    /// File is (or isn't) owned by the user Hazelnut runs as (Unix only; ignored elsewhere)
+    #[serde(default)]
+    pub owner_is_me: Option<bool>,
+
+    /// All of these permission bits are set, e.g. `0o002` for world-writable
+    /// (Unix only; ignored elsewhere)
+    #[serde(default)]
+    pub mode_mask: Option<u32>,
+
+    /// Permission bits are exactly this, e.g. `0o644` (Unix only; ignored elsewhere)
+    #[serde(default)]
+    pub mode_equals: Option<u32>,
+
+    /// File has (or lacks) any execute bit (Unix only; ignored elsewhere)
+    #[serde(default)]
+    pub is_executable: Option<bool>,
+
+    /// File is on the filesystem with this device id (Unix only; ignored elsewhere)
+    #[serde(default)]
+    pub device_id: Option<u64>,
+
+    /// File is on the volume mounted at this directory. Doesn't match while
+    /// nothing is mounted there (Unix only; ignored elsewhere)
+    #[serde(default)]
+    pub on_mount: Option<PathBuf>,
+
+    /// Some folder (typically the destination) holds fewer than this many entries
+    #[serde(default)]
+    pub dir_file_count_less_than: Option<DirFileCount>,
+
+    /// File content contains this text (case-insensitive). Only text files up
+    /// to `contains_max_bytes` are read; binary and larger files don't match.
+    #[serde(default)]
+    pub contains_text: Option<String>,
+
+    /// File content matches this regex (same limits as `contains_text`)
+    #[serde(default)]
+    pub contains_regex: Option<String>,
+
+    /// Size cap in bytes for `contains_text` / `contains_regex` (default 1 MiB)
+    #[serde(default)]
+    pub contains_max_bytes: Option<u64>,
+}
+
+/// A folder and an entry count, for `dir_file_count_less_than`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirFileCount {
+    /// Folder to count (supports `~` and environment variables)
+    pub path: PathBuf,
+    /// Threshold on the number of entries directly inside the folder
+    pub count: usize,
 }
 
 impl Condition {
+    /// Rewrite `extension` and `extensions` in canonical form, so `".PDF"`
+    /// is stored as `"pdf"`
+    pub fn normalize_extensions(&mut self) {
+        if let Some(ref mut ext) = self.extension {
+            *ext = normalize_extension(ext);
+        }
+        for ext in &mut self.extensions {
+            *ext = normalize_extension(ext);
+        }
+    }
+
+    /// Look `category` up in user-defined extension groups, which take
+    /// precedence over built-in categories of the same name
+    pub fn resolve_category(&mut self, groups: &HashMap<String, Vec<String>>) {
+        self.category_extensions = self
+            .category
+            .as_ref()
+            .and_then(|category| groups.get(category))
+            .cloned();
+    }
+
+    /// Whether `category` names a group, user-defined (already resolved) or built-in
+    pub fn category_is_known(&self) -> bool {
+        self.category
+            .as_deref()
+            .is_none_or(|c| self.category_extensions.is_some() || builtin_category(c).is_some())
+    }
+
+    /// Capture groups of `name_regex` against the filename, indexed by group
+    /// number (0 is the whole match). Groups that didn't participate are empty;
+    /// returns an empty list when there is no regex or it doesn't match.
+    pub fn name_captures(&self, path: &Path) -> Result<Vec<String>> {
+        let Some(ref pattern) = self.name_regex else {
+            return Ok(Vec::new());
+        };
+        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        Ok(cached_regex(pattern)?
+            .captures(filename)
+            .map(|caps| {
+                caps.iter()
+                    .map(|m| m.map(|m| m.as_str().to_string()).unwrap_or_default())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
     /// Check if a file matches this condition
     pub fn matches(&self, path: &Path) -> Result<bool> {
+        self.matches_in(path, None)
+    }
+
+    /// Check if a file found under the watched directory `watch_root` matches
+    /// this condition. Depth conditions never match without a root.
+    pub fn matches_in(&self, path: &Path, watch_root: Option<&Path>) -> Result<bool> {
+        if self.depth_greater_than.is_some() || self.depth_less_than.is_some() {
+            let Some(depth) = watch_root.and_then(|root| path_depth(path, root)) else {
+                return Ok(false);
+            };
+            if self.depth_greater_than.is_some_and(|min| depth <= min)
+                || self.depth_less_than.is_some_and(|max| depth >= max)
+            {
+                return Ok(false);
+            }
+        }
+
         // Check extension
         if let Some(ref ext) = self.extension
             && !check_extension(path, ext)
@@ -77,6 +320,19 @@ impl Condition {
             }
         }
 
+        // Check extension group; an unknown category matches nothing
+        if let Some(ref category) = self.category {
+            let in_group = match self.category_extensions {
+                Some(ref extensions) => extensions.iter().any(|ext| check_extension(path, ext)),
+                None => builtin_category(category).is_some_and(|extensions| {
+                    extensions.iter().any(|ext| check_extension(path, ext))
+                }),
+            };
+            if !in_group {
+                return Ok(false);
+            }
+        }
+
         // Check name glob pattern
         if let Some(ref pattern) = self.name_matches
             && !check_glob(path, pattern)?
@@ -84,6 +340,20 @@ impl Condition {
             return Ok(false);
         }
 
+        // Check name glob patterns list
+        if !self.name_matches_any.is_empty() {
+            let mut matches_any = false;
+            for pattern in &self.name_matches_any {
+                if check_glob(path, pattern)? {
+                    matches_any = true;
+                    break;
+                }
+            }
+            if !matches_any {
+                return Ok(false);
+            }
+        }
+
         // Check name regex
         if let Some(ref pattern) = self.name_regex
             && !check_regex(path, pattern)?
@@ -91,11 +361,61 @@ impl Condition {
             return Ok(false);
         }
 
+        // Check immediate parent directory name
+        if let Some(ref pattern) = self.parent_name {
+            let parent = path
+                .parent()
+                .and_then(|p| p.file_name())
+                .and_then(|n| n.to_str())
+                .unwrap_or("");
+            if !glob_matches(parent, pattern)? {
+                return Ok(false);
+            }
+        }
+
+        // Check which directories the file is inside, comparing resolved paths
+        if !self.under_any.is_empty() || !self.not_under_any.is_empty() {
+            let file = resolve_file_path(path);
+            let under = |dirs: &[PathBuf]| {
+                dirs.iter().any(|dir| {
+                    let dir = crate::expand_path(dir);
+                    file.starts_with(std::fs::canonicalize(&dir).unwrap_or(dir))
+                })
+            };
+            if !self.under_any.is_empty() && !under(&self.under_any) {
+                return Ok(false);
+            }
+            if under(&self.not_under_any) {
+                return Ok(false);
+            }
+        }
+
+        // Check for a same-named file in the target directory
+        if let Some(ref dir) = self.would_collide_in {
+            let Some(name) = path.file_name() else {
+                return Ok(false);
+            };
+            let existing = crate::expand_path(dir).join(name);
+            // The file itself, when it already lives there, isn't a collision
+            let collides = existing.symlink_metadata().is_ok()
+                && resolve_file_path(&existing) != resolve_file_path(path);
+            if !collides {
+                return Ok(false);
+            }
+        }
+
         // Check file size and age using a single metadata call
         if self.size_greater_than.is_some()
             || self.size_less_than.is_some()
+            || self.size_at_least.is_some()
+            || self.size_at_most.is_some()
             || self.age_days_greater_than.is_some()
             || self.age_days_less_than.is_some()
+            || self.accessed_days_greater_than.is_some()
+            || self.accessed_days_less_than.is_some()
+            || self.mtime_weekday.is_some()
+            || self.newer_than_file.is_some()
+            || self.older_than_file.is_some()
         {
             let metadata = match path.metadata() {
                 Ok(m) => m,
@@ -112,6 +432,16 @@ impl Condition {
             {
                 return Ok(false);
             }
+            if let Some(min) = self.size_at_least
+                && metadata.len() < min
+            {
+                return Ok(false);
+            }
+            if let Some(max) = self.size_at_most
+                && metadata.len() > max
+            {
+                return Ok(false);
+            }
 
             if self.age_days_greater_than.is_some() || self.age_days_less_than.is_some() {
                 match metadata.modified() {
@@ -132,6 +462,52 @@ impl Condition {
                     Err(_) => return Ok(false),
                 }
             }
+
+            if self.accessed_days_greater_than.is_some() || self.accessed_days_less_than.is_some() {
+                match metadata.accessed() {
+                    Ok(accessed) => {
+                        let idle = accessed.elapsed().map(|d| d.as_secs() / 86400).unwrap_or(0);
+
+                        if let Some(min_days) = self.accessed_days_greater_than
+                            && idle <= min_days
+                        {
+                            return Ok(false);
+                        }
+                        if let Some(max_days) = self.accessed_days_less_than
+                            && idle >= max_days
+                        {
+                            return Ok(false);
+                        }
+                    }
+                    Err(_) => return Ok(false),
+                }
+            }
+
+            if let Some(ref days) = self.mtime_weekday {
+                match metadata.modified() {
+                    Ok(modified) if weekday_matches(modified, days) => {}
+                    _ => return Ok(false),
+                }
+            }
+
+            if let Some(ref reference) = self.newer_than_file
+                && let Some(reference_time) = reference_mtime(path, reference)
+                && !metadata
+                    .modified()
+                    .is_ok_and(|modified| modified > reference_time)
+            {
+                return Ok(false);
+            }
+            if let Some(ref reference) = self.older_than_file {
+                let older = reference_mtime(path, reference).is_some_and(|reference_time| {
+                    metadata
+                        .modified()
+                        .is_ok_and(|modified| modified < reference_time)
+                });
+                if !older {
+                    return Ok(false);
+                }
+            }
         }
 
         // Check if directory
@@ -150,19 +526,202 @@ impl Condition {
             }
         }
 
+        // Check for a dangling symlink (`exists` follows the link)
+        if let Some(is_broken) = self.is_broken_symlink {
+            let actually_broken = path
+                .symlink_metadata()
+                .is_ok_and(|m| m.file_type().is_symlink())
+                && !path.exists();
+            if actually_broken != is_broken {
+                return Ok(false);
+            }
+        }
+
+        // Check who owns the file and its permission bits
+        #[cfg(unix)]
+        if self.owner_uid.is_some()
+            || self.owner_is_me.is_some()
+            || self.mode_mask.is_some()
+            || self.mode_equals.is_some()
+            || self.is_executable.is_some()
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            let (uid, mode) = match path.metadata() {
+                Ok(m) => (m.uid(), m.mode() & 0o7777),
+                Err(_) => return Ok(false),
+            };
+            if let Some(mask) = self.mode_mask
+                && mode & mask != mask
+            {
+                return Ok(false);
+            }
+            if let Some(exact) = self.mode_equals
+                && mode != exact
+            {
+                return Ok(false);
+            }
+            if let Some(executable) = self.is_executable
+                && (mode & 0o111 != 0) != executable
+            {
+                return Ok(false);
+            }
+            if let Some(owner) = self.owner_uid
+                && uid != owner
+            {
+                return Ok(false);
+            }
+            if let Some(is_me) = self.owner_is_me
+                && (uid == crate::current_uid()) != is_me
+            {
+                return Ok(false);
+            }
+        }
+
+        // Check which filesystem the file is on
+        #[cfg(unix)]
+        if self.device_id.is_some() || self.on_mount.is_some() {
+            use std::os::unix::fs::MetadataExt;
+
+            let Ok(dev) = path.symlink_metadata().map(|m| m.dev()) else {
+                return Ok(false);
+            };
+            if let Some(id) = self.device_id
+                && dev != id
+            {
+                return Ok(false);
+            }
+            if let Some(ref mount) = self.on_mount {
+                let mount = crate::expand_path(mount);
+                let mounted_dev = std::fs::metadata(&mount).ok().map(|m| m.dev());
+                let parent_dev = mount
+                    .parent()
+                    .and_then(|p| std::fs::metadata(p).ok())
+                    .map(|m| m.dev());
+                // A directory on the same device as its parent has nothing mounted on it
+                let is_mounted = mounted_dev.is_some()
+                    && (mounted_dev != parent_dev || mount.parent().is_none());
+                if !is_mounted
+                    || Some(dev) != mounted_dev
+                    || !resolve_file_path(path)
+                        .starts_with(std::fs::canonicalize(&mount).unwrap_or(mount))
+                {
+                    return Ok(false);
+                }
+            }
+        }
+
+        // Check how full another folder is
+        if let Some(ref limit) = self.dir_file_count_less_than
+            && !dir_has_fewer_entries(&crate::expand_path(&limit.path), limit.count)
+        {
+            return Ok(false);
+        }
+
+        // Check file content last: it's the most expensive check
+        if self.contains_text.is_some() || self.contains_regex.is_some() {
+            let max_bytes = self.contains_max_bytes.unwrap_or(DEFAULT_CONTENT_MAX_BYTES);
+            let Some(content) = read_text_content(path, max_bytes) else {
+                return Ok(false);
+            };
+            if let Some(ref text) = self.contains_text
+                && !content.to_lowercase().contains(&text.to_lowercase())
+            {
+                return Ok(false);
+            }
+            if let Some(ref pattern) = self.contains_regex
+                && !cached_regex(pattern)?.is_match(&content)
+            {
+                return Ok(false);
+            }
+        }
+
         Ok(true)
     }
 }
 
+/// Read a file's content for text searching, or `None` if it is larger than
+/// `max_bytes`, looks binary (has a NUL byte near the start) or can't be read.
+/// The size is checked before reading, so large files are skipped cheaply.
+fn read_text_content(path: &Path, max_bytes: u64) -> Option<String> {
+    let metadata = path.metadata().ok()?;
+    if !metadata.is_file() || metadata.len() > max_bytes {
+        return None;
+    }
+    let bytes = std::fs::read(path).ok()?;
+    if bytes.iter().take(BINARY_SNIFF_BYTES).any(|&b| b == 0) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Modification time of a `newer_than_file` / `older_than_file` reference,
+/// resolved next to `path` when relative; `None` if it can't be read
+fn reference_mtime(path: &Path, reference: &Path) -> Option<std::time::SystemTime> {
+    let reference = crate::expand_path(reference);
+    let reference = match path.parent() {
+        Some(dir) if reference.is_relative() => dir.join(reference),
+        _ => reference,
+    };
+    reference.metadata().and_then(|m| m.modified()).ok()
+}
+
+/// Whether `time` falls, in local time, on one of the named weekdays.
+/// Unrecognized names never match.
+fn weekday_matches(time: std::time::SystemTime, days: &[String]) -> bool {
+    use chrono::Datelike;
+    let weekday = chrono::DateTime::<chrono::Local>::from(time).weekday();
+    days.iter()
+        .any(|day| day.trim().parse::<chrono::Weekday>() == Ok(weekday))
+}
+
+/// Whether `dir` has fewer than `limit` entries. Stops reading once the limit
+/// is reached, so huge folders stay cheap; a missing folder counts as empty.
+fn dir_has_fewer_entries(dir: &Path, limit: usize) -> bool {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => entries.take(limit).count() < limit,
+        Err(_) => !dir.exists(),
+    }
+}
+
+/// Number of path components of `path` below `root`, if it is under `root`
+fn path_depth(path: &Path, root: &Path) -> Option<usize> {
+    path.strip_prefix(root)
+        .ok()
+        .map(|relative| relative.components().count())
+}
+
 fn check_extension(path: &Path, ext: &str) -> bool {
+    let ext = ext.trim().trim_start_matches('.');
     path.extension()
         .and_then(|e| e.to_str())
         .map(|e| e.eq_ignore_ascii_case(ext))
         .unwrap_or(false)
 }
 
+/// `path` with its directory canonicalized. The file itself isn't resolved, so
+/// a symlink counts as being where the link is.
+fn resolve_file_path(path: &Path) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => std::fs::canonicalize(parent)
+            .map(|parent| parent.join(name))
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// A configured extension in canonical form: lowercase, without a leading dot
+pub(crate) fn normalize_extension(ext: &str) -> String {
+    ext.trim().trim_start_matches('.').to_lowercase()
+}
+
 fn check_glob(path: &Path, pattern: &str) -> Result<bool> {
     let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    glob_matches(filename, pattern)
+}
+
+/// Match a single name against a glob, reusing the thread-local cache
+fn glob_matches(name: &str, pattern: &str) -> Result<bool> {
     GLOB_CACHE.with(|cache| {
         let mut cache = cache.borrow_mut();
         if cache.len() >= CACHE_MAX_ENTRIES && !cache.contains_key(pattern) {
@@ -175,28 +734,32 @@ fn check_glob(path: &Path, pattern: &str) -> Result<bool> {
             cache.insert(pattern.to_string(), p.clone());
             p
         };
-        Ok(glob_pattern.matches(filename))
+        Ok(glob_pattern.matches(name))
     })
 }
 
-fn check_regex(path: &Path, pattern: &str) -> Result<bool> {
-    let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+/// Compile a regex, reusing the thread-local cache
+fn cached_regex(pattern: &str) -> Result<Regex> {
     REGEX_CACHE.with(|cache| {
         let mut cache = cache.borrow_mut();
         if cache.len() >= CACHE_MAX_ENTRIES && !cache.contains_key(pattern) {
             cache.clear();
         }
-        let regex = if let Some(r) = cache.get(pattern) {
-            r.clone()
+        if let Some(r) = cache.get(pattern) {
+            Ok(r.clone())
         } else {
             let r = Regex::new(pattern)?;
             cache.insert(pattern.to_string(), r.clone());
-            r
-        };
-        Ok(regex.is_match(filename))
+            Ok(r)
+        }
     })
 }
 
+fn check_regex(path: &Path, pattern: &str) -> Result<bool> {
+    let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    Ok(cached_regex(pattern)?.is_match(filename))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -238,4 +801,583 @@ mod tests {
         assert!(condition.matches(Path::new("/tmp/.hidden")).unwrap());
         assert!(!condition.matches(Path::new("/tmp/visible")).unwrap());
     }
+
+    #[test]
+    fn test_dir_file_count_less_than() {
+        let archive = tempfile::tempdir().unwrap();
+        for i in 0..3 {
+            std::fs::write(archive.path().join(format!("{}.txt", i)), "x").unwrap();
+        }
+        let limit = |count| Condition {
+            dir_file_count_less_than: Some(DirFileCount {
+                path: archive.path().to_path_buf(),
+                count,
+            }),
+            ..Default::default()
+        };
+        let file = Path::new("/tmp/new.pdf");
+
+        // Below the threshold
+        assert!(limit(4).matches(file).unwrap());
+        // At and above it
+        assert!(!limit(3).matches(file).unwrap());
+        assert!(!limit(2).matches(file).unwrap());
+
+        // A folder that doesn't exist yet is empty
+        let missing = Condition {
+            dir_file_count_less_than: Some(DirFileCount {
+                path: archive.path().join("not-yet"),
+                count: 1,
+            }),
+            ..Default::default()
+        };
+        assert!(missing.matches(file).unwrap());
+    }
+
+    #[test]
+    fn test_parent_name_match() {
+        let condition = Condition {
+            parent_name: Some("Invoices".to_string()),
+            ..Default::default()
+        };
+
+        assert!(
+            condition
+                .matches(Path::new("/home/me/Documents/Invoices/x.pdf"))
+                .unwrap()
+        );
+        assert!(
+            !condition
+                .matches(Path::new("/home/me/Documents/Other/x.pdf"))
+                .unwrap()
+        );
+        // Only the immediate parent counts
+        assert!(
+            !condition
+                .matches(Path::new("/home/me/Invoices/2024/x.pdf"))
+                .unwrap()
+        );
+
+        let glob = Condition {
+            parent_name: Some("Invoices*".to_string()),
+            ..Default::default()
+        };
+        assert!(glob.matches(Path::new("/tmp/Invoices 2024/x.pdf")).unwrap());
+        assert!(!glob.matches(Path::new("/tmp/Receipts/x.pdf")).unwrap());
+    }
+
+    #[test]
+    fn test_name_matches_any() {
+        let condition = Condition {
+            name_matches_any: vec!["*.tmp".to_string(), "*.bak".to_string()],
+            ..Default::default()
+        };
+
+        assert!(condition.matches(Path::new("/tmp/cache.tmp")).unwrap());
+        assert!(condition.matches(Path::new("/tmp/config.bak")).unwrap());
+        assert!(!condition.matches(Path::new("/tmp/notes.txt")).unwrap());
+
+        // Combined with name_matches, both must hold
+        let condition = Condition {
+            name_matches: Some("draft*".to_string()),
+            ..condition
+        };
+        assert!(condition.matches(Path::new("/tmp/draft.bak")).unwrap());
+        assert!(!condition.matches(Path::new("/tmp/final.bak")).unwrap());
+    }
+
+    #[test]
+    fn test_size_bounds_at_threshold() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("exact.bin");
+        std::fs::write(&file, vec![0u8; 1024]).unwrap();
+
+        let with = |f: fn(&mut Condition, u64), size: u64| {
+            let mut condition = Condition::default();
+            f(&mut condition, size);
+            condition.matches(&file).unwrap()
+        };
+
+        // Exclusive bounds reject a file of exactly the threshold size
+        assert!(!with(|c, v| c.size_greater_than = Some(v), 1024));
+        assert!(with(|c, v| c.size_greater_than = Some(v), 1023));
+        assert!(!with(|c, v| c.size_less_than = Some(v), 1024));
+        assert!(with(|c, v| c.size_less_than = Some(v), 1025));
+
+        // Inclusive bounds accept it
+        assert!(with(|c, v| c.size_at_least = Some(v), 1024));
+        assert!(!with(|c, v| c.size_at_least = Some(v), 1025));
+        assert!(with(|c, v| c.size_at_most = Some(v), 1024));
+        assert!(!with(|c, v| c.size_at_most = Some(v), 1023));
+    }
+
+    #[test]
+    fn test_accessed_days() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("stale.zip");
+        std::fs::write(&file, "zip").unwrap();
+        let hundred_days_ago =
+            std::time::SystemTime::now() - std::time::Duration::from_secs(100 * 86400);
+        std::fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_times(std::fs::FileTimes::new().set_accessed(hundred_days_ago))
+            .unwrap();
+
+        let not_opened_in_90 = Condition {
+            accessed_days_greater_than: Some(90),
+            ..Default::default()
+        };
+        assert!(not_opened_in_90.matches(&file).unwrap());
+
+        let opened_this_week = Condition {
+            accessed_days_less_than: Some(7),
+            ..Default::default()
+        };
+        assert!(!opened_this_week.matches(&file).unwrap());
+
+        let not_opened_in_200 = Condition {
+            accessed_days_greater_than: Some(200),
+            ..Default::default()
+        };
+        assert!(!not_opened_in_200.matches(&file).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_owner_conditions() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("mine.txt");
+        std::fs::write(&file, "x").unwrap();
+        let me = crate::current_uid();
+
+        let owned_by = |uid| Condition {
+            owner_uid: Some(uid),
+            ..Default::default()
+        };
+        assert!(owned_by(me).matches(&file).unwrap());
+        assert!(!owned_by(me.wrapping_add(1)).matches(&file).unwrap());
+
+        let is_me = |mine| Condition {
+            owner_is_me: Some(mine),
+            ..Default::default()
+        };
+        assert!(is_me(true).matches(&file).unwrap());
+        assert!(!is_me(false).matches(&file).unwrap());
+
+        // A file that can't be inspected doesn't match
+        assert!(!is_me(true).matches(&dir.path().join("gone.txt")).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_mode_conditions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let with_mode = |name: &str, mode: u32| {
+            let file = dir.path().join(name);
+            std::fs::write(&file, "x").unwrap();
+            std::fs::set_permissions(&file, std::fs::Permissions::from_mode(mode)).unwrap();
+            file
+        };
+        let script = with_mode("run.sh", 0o755);
+        let shared = with_mode("shared.txt", 0o666);
+        let private = with_mode("notes.txt", 0o600);
+
+        let world_writable = Condition {
+            mode_mask: Some(0o002),
+            ..Default::default()
+        };
+        assert!(world_writable.matches(&shared).unwrap());
+        assert!(!world_writable.matches(&script).unwrap());
+        assert!(!world_writable.matches(&private).unwrap());
+
+        let exactly_600 = Condition {
+            mode_equals: Some(0o600),
+            ..Default::default()
+        };
+        assert!(exactly_600.matches(&private).unwrap());
+        assert!(!exactly_600.matches(&shared).unwrap());
+
+        let executable = |yes| Condition {
+            is_executable: Some(yes),
+            ..Default::default()
+        };
+        assert!(executable(true).matches(&script).unwrap());
+        assert!(!executable(true).matches(&shared).unwrap());
+        assert!(executable(false).matches(&private).unwrap());
+
+        // Octal values can be written directly in TOML
+        let parsed: Condition = toml::from_str("mode_mask = 0o002").unwrap();
+        assert_eq!(parsed.mode_mask, Some(2));
+    }
+
+    #[test]
+    fn test_contains_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let invoice = dir.path().join("scan1.txt");
+        let receipt = dir.path().join("scan2.txt");
+        let binary = dir.path().join("scan3.bin");
+        let large = dir.path().join("scan4.txt");
+        std::fs::write(&invoice, "ACME Corp\nINVOICE #1042\nTotal: 120.00").unwrap();
+        std::fs::write(&receipt, "ACME Corp\nReceipt\nThank you").unwrap();
+        std::fs::write(&binary, b"INVOICE\0\x01\x02").unwrap();
+        std::fs::write(&large, format!("INVOICE{}", "x".repeat(2048))).unwrap();
+
+        let condition = Condition {
+            contains_text: Some("invoice".to_string()),
+            contains_max_bytes: Some(1024),
+            ..Default::default()
+        };
+        assert!(condition.matches(&invoice).unwrap());
+        assert!(!condition.matches(&receipt).unwrap());
+        // Binary and over-the-cap files are skipped, even though they contain the word
+        assert!(!condition.matches(&binary).unwrap());
+        assert!(!condition.matches(&large).unwrap());
+        assert!(!condition.matches(&dir.path().join("missing.txt")).unwrap());
+
+        let numbered = Condition {
+            contains_regex: Some(r"INVOICE #\d+".to_string()),
+            ..Default::default()
+        };
+        assert!(numbered.matches(&invoice).unwrap());
+        assert!(!numbered.matches(&receipt).unwrap());
+        // The default cap is large enough for the bigger file
+        assert!(
+            Condition {
+                contains_text: Some("INVOICE".to_string()),
+                ..Default::default()
+            }
+            .matches(&large)
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_mtime_weekday() {
+        use chrono::TimeZone;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("holiday.jpg");
+        std::fs::write(&file, "jpg").unwrap();
+        // Saturday, 6 January 2024, at noon local time
+        let saturday = chrono::Local
+            .with_ymd_and_hms(2024, 1, 6, 12, 0, 0)
+            .unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(saturday.into())
+            .unwrap();
+
+        let on = |days: &[&str]| Condition {
+            mtime_weekday: Some(days.iter().map(|d| d.to_string()).collect()),
+            ..Default::default()
+        };
+        assert!(on(&["sat", "sun"]).matches(&file).unwrap());
+        assert!(on(&["Saturday"]).matches(&file).unwrap());
+        assert!(
+            !on(&["mon", "tue", "wed", "thu", "fri"])
+                .matches(&file)
+                .unwrap()
+        );
+        assert!(!on(&["caturday"]).matches(&file).unwrap());
+        assert!(!on(&[]).matches(&file).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_broken_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("target.txt");
+        std::fs::write(&target, "data").unwrap();
+        let valid = dir.path().join("valid");
+        let dangling = dir.path().join("dangling");
+        std::os::unix::fs::symlink(&target, &valid).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("gone.txt"), &dangling).unwrap();
+
+        let broken = Condition {
+            is_broken_symlink: Some(true),
+            ..Default::default()
+        };
+        assert!(broken.matches(&dangling).unwrap());
+        assert!(!broken.matches(&valid).unwrap());
+        assert!(!broken.matches(&target).unwrap());
+
+        let not_broken = Condition {
+            is_broken_symlink: Some(false),
+            ..Default::default()
+        };
+        assert!(!not_broken.matches(&dangling).unwrap());
+        assert!(not_broken.matches(&valid).unwrap());
+        assert!(not_broken.matches(&target).unwrap());
+    }
+
+    #[test]
+    fn test_extension_is_dot_and_case_tolerant() {
+        let path = Path::new("/tmp/file.pdf");
+        for ext in [".PDF", "PDF", "pdf"] {
+            let single = Condition {
+                extension: Some(ext.to_string()),
+                ..Default::default()
+            };
+            assert!(single.matches(path).unwrap(), "extension = {:?}", ext);
+
+            let mut list = Condition {
+                extensions: vec!["doc".to_string(), ext.to_string()],
+                ..Default::default()
+            };
+            assert!(list.matches(path).unwrap(), "extensions = [{:?}]", ext);
+            list.normalize_extensions();
+            assert_eq!(list.extensions, vec!["doc", "pdf"]);
+            assert!(list.matches(path).unwrap());
+        }
+        assert!(
+            !Condition {
+                extension: Some(".pd".to_string()),
+                ..Default::default()
+            }
+            .matches(path)
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_under_any_and_not_under_any() {
+        let dir = tempfile::tempdir().unwrap();
+        let projects = dir.path().join("projects");
+        let builds = projects.join("app").join("target");
+        let scratch = dir.path().join("scratch");
+        for d in [&builds, &scratch] {
+            std::fs::create_dir_all(d).unwrap();
+        }
+        let source = projects.join("app").join("main.rs");
+        let built = builds.join("app.bin");
+        let loose = scratch.join("notes.txt");
+        for f in [&source, &built, &loose] {
+            std::fs::write(f, "x").unwrap();
+        }
+
+        let condition = Condition {
+            under_any: vec![projects.clone(), dir.path().join("missing")],
+            not_under_any: vec![builds.clone()],
+            ..Default::default()
+        };
+        assert!(condition.matches(&source).unwrap());
+        assert!(!condition.matches(&built).unwrap());
+        assert!(!condition.matches(&loose).unwrap());
+
+        // A sibling with a shared name prefix isn't inside the directory
+        let lookalike = dir.path().join("projects-old");
+        std::fs::create_dir(&lookalike).unwrap();
+        std::fs::write(lookalike.join("a.rs"), "x").unwrap();
+        assert!(!condition.matches(&lookalike.join("a.rs")).unwrap());
+
+        // Paths are compared after resolving `..` and symlinked directories
+        let blocked = Condition {
+            not_under_any: vec![projects.join("app").join("..").join("app")],
+            ..Default::default()
+        };
+        assert!(!blocked.matches(&source).unwrap());
+        assert!(blocked.matches(&loose).unwrap());
+        #[cfg(unix)]
+        {
+            let link = dir.path().join("link");
+            std::os::unix::fs::symlink(&projects, &link).unwrap();
+            assert!(!blocked.matches(&link.join("app").join("main.rs")).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_would_collide_in() {
+        let dir = tempfile::tempdir().unwrap();
+        let inbox = dir.path().join("inbox");
+        let target = dir.path().join("target");
+        for d in [&inbox, &target] {
+            std::fs::create_dir(d).unwrap();
+        }
+        let taken = inbox.join("report.pdf");
+        let free = inbox.join("invoice.pdf");
+        for f in [&taken, &free] {
+            std::fs::write(f, "x").unwrap();
+        }
+        std::fs::write(target.join("report.pdf"), "y").unwrap();
+
+        let condition = Condition {
+            would_collide_in: Some(target.clone()),
+            ..Default::default()
+        };
+        assert!(condition.matches(&taken).unwrap());
+        assert!(!condition.matches(&free).unwrap());
+
+        // A file already in the directory doesn't collide with itself
+        assert!(!condition.matches(&target.join("report.pdf")).unwrap());
+
+        // A missing directory has nothing to collide with
+        let missing = Condition {
+            would_collide_in: Some(dir.path().join("missing")),
+            ..Default::default()
+        };
+        assert!(!missing.matches(&taken).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_device_id_and_on_mount() {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("data.bin");
+        std::fs::write(&file, "x").unwrap();
+        let dev = file.metadata().unwrap().dev();
+
+        let on_device = |id| Condition {
+            device_id: Some(id),
+            ..Default::default()
+        };
+        assert!(on_device(dev).matches(&file).unwrap());
+        assert!(!on_device(dev.wrapping_add(1)).matches(&file).unwrap());
+
+        // A file on another mount (tmpfs on most Linux systems) has another id
+        let shm = Path::new("/dev/shm");
+        if let Ok(other) = shm.metadata().map(|m| m.dev())
+            && other != dev
+        {
+            let elsewhere = tempfile::tempdir_in(shm).unwrap();
+            let copy = elsewhere.path().join("data.bin");
+            std::fs::write(&copy, "x").unwrap();
+            assert!(!on_device(dev).matches(&copy).unwrap());
+            assert!(on_device(other).matches(&copy).unwrap());
+        }
+
+        // Walk up from the temp dir to the mount point it lives on
+        let mut mount = std::fs::canonicalize(dir.path()).unwrap();
+        while let Some(parent) = mount.parent()
+            && parent.metadata().unwrap().dev() == dev
+        {
+            mount = parent.to_path_buf();
+        }
+        let on_mount = |path: &Path| Condition {
+            on_mount: Some(path.to_path_buf()),
+            ..Default::default()
+        };
+        assert!(on_mount(&mount).matches(&file).unwrap());
+
+        // A plain directory isn't a mount point, even though the file is inside it
+        assert!(!on_mount(dir.path()).matches(&file).unwrap());
+        assert!(
+            !on_mount(&dir.path().join("missing"))
+                .matches(&file)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_category_uses_builtin_groups() {
+        let condition = Condition {
+            category: Some("archives".to_string()),
+            ..Default::default()
+        };
+        assert!(condition.matches(Path::new("/tmp/backup.ZIP")).unwrap());
+        assert!(!condition.matches(Path::new("/tmp/backup.pdf")).unwrap());
+        assert!(condition.category_is_known());
+
+        let unknown = Condition {
+            category: Some("spreadsheets".to_string()),
+            ..Default::default()
+        };
+        assert!(!unknown.category_is_known());
+        assert!(!unknown.matches(Path::new("/tmp/budget.xlsx")).unwrap());
+    }
+
+    #[test]
+    fn test_depth_relative_to_watch_root() {
+        let root = Path::new("/watched");
+        let at_depth = |n: usize| {
+            let mut path = root.to_path_buf();
+            for i in 1..n {
+                path.push(format!("d{}", i));
+            }
+            path.join("file.txt")
+        };
+        let deep = Condition {
+            depth_greater_than: Some(3),
+            ..Default::default()
+        };
+        let shallow = Condition {
+            depth_less_than: Some(3),
+            ..Default::default()
+        };
+
+        assert!(shallow.matches_in(&at_depth(1), Some(root)).unwrap());
+        assert!(shallow.matches_in(&at_depth(2), Some(root)).unwrap());
+        assert!(!shallow.matches_in(&at_depth(3), Some(root)).unwrap());
+        assert!(!deep.matches_in(&at_depth(3), Some(root)).unwrap());
+        assert!(deep.matches_in(&at_depth(4), Some(root)).unwrap());
+        assert!(deep.matches_in(&at_depth(6), Some(root)).unwrap());
+
+        // Both bounds together leave a window
+        let middle = Condition {
+            depth_greater_than: Some(1),
+            depth_less_than: Some(4),
+            ..Default::default()
+        };
+        let matching: Vec<usize> = (1..=5)
+            .filter(|&n| middle.matches_in(&at_depth(n), Some(root)).unwrap())
+            .collect();
+        assert_eq!(matching, vec![2, 3]);
+
+        // Without a root, or outside it, there's no depth to compare
+        assert!(!deep.matches(&at_depth(5)).unwrap());
+        assert!(
+            !shallow
+                .matches_in(Path::new("/elsewhere/file.txt"), Some(root))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_newer_and_older_than_reference_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = std::time::SystemTime::now();
+        let hours = |h: u64| std::time::Duration::from_secs(h * 3600);
+        let touch = |name: &str, time: std::time::SystemTime| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, name).unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(time)
+                .unwrap();
+            path
+        };
+        touch(".last-backup", now - hours(24));
+        let before = touch("before.txt", now - hours(48));
+        let after = touch("after.txt", now - hours(1));
+
+        // Relative references are looked up next to the file
+        let newer = Condition {
+            newer_than_file: Some(".last-backup".into()),
+            ..Default::default()
+        };
+        let older = Condition {
+            older_than_file: Some(dir.path().join(".last-backup")),
+            ..Default::default()
+        };
+        assert!(newer.matches(&after).unwrap());
+        assert!(!newer.matches(&before).unwrap());
+        assert!(older.matches(&before).unwrap());
+        assert!(!older.matches(&after).unwrap());
+        // The reference isn't newer or older than itself
+        assert!(!newer.matches(&dir.path().join(".last-backup")).unwrap());
+
+        // Without a reference, everything is newer and nothing is older
+        std::fs::remove_file(dir.path().join(".last-backup")).unwrap();
+        assert!(newer.matches(&before).unwrap());
+        assert!(!older.matches(&before).unwrap());
+    }
 }
@@ -1,26 +1,185 @@
 //! Rule engine - evaluates and executes rules
 
-use anyhow::Result;
-use std::path::Path;
-use tracing::{debug, info, trace};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, trace, warn};
 
-use super::{Action, Rule};
+use super::{
+    Action, ActionContext, FileInUse, JournalEntry, JournalOp, OnError, Rule, UndoJournal,
+};
+use crate::config::Config;
+
+/// A rule whose action was executed for a file
+#[derive(Debug, Clone)]
+pub struct RuleMatch {
+    /// Name of the matching rule
+    pub rule_name: String,
+    /// The action that was executed
+    pub action: Action,
+    /// Whether the rule is quiet, so its successes stay out of the activity log
+    pub quiet: bool,
+}
+
+/// A file and the rule (name and action) that would act on it, as listed by
+/// `RuleEngine::dry_evaluate_dir`
+pub type DryEvaluation = (PathBuf, Option<(String, Action)>);
+
+/// Longest total wait across the `on_error` retries of one file
+const MAX_RETRY_WAIT_SECS: f64 = 3600.0;
+
+/// Error context marking a failure whose rule asked, with `on_error = retry`,
+/// for the file to be tried again after a delay
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Retry {
+    /// Retries allowed after the first failure
+    pub attempts: u32,
+    /// Wait before the first retry, doubled after each failure
+    pub delay_secs: f64,
+}
+
+impl Retry {
+    /// Wait before retrying a file that has been retried `attempt` times
+    /// already, or `None` once the attempts are used up or the waits would
+    /// add up to more than an hour
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        if attempt >= self.attempts {
+            return None;
+        }
+        let wait = |n: u32| self.delay_secs.max(0.0) * 2f64.powi(n.min(31) as i32);
+        let total: f64 = (0..=attempt).map(wait).sum();
+        (total <= MAX_RETRY_WAIT_SECS).then(|| Duration::from_secs_f64(wait(attempt)))
+    }
+}
+
+impl std::fmt::Display for Retry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("retrying later")
+    }
+}
+
+/// Error context marking a failure whose rule asked, with `on_error = requeue`,
+/// for the file to be tried again on a later pass
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Requeue;
+
+impl std::fmt::Display for Requeue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("requeued for a later pass")
+    }
+}
 
 /// Engine for evaluating rules against files
 pub struct RuleEngine {
     rules: Vec<Rule>,
+    /// Log matching actions instead of executing them
+    dry_run: bool,
+    /// Default folder for `trash` actions (system trash if None)
+    trash_dir: Option<PathBuf>,
+    /// Extensions no rule may act on, checked before any rule runs
+    protected_extensions: Vec<String>,
+    /// Where reversible operations are recorded for undo (not recorded if None)
+    journal: Option<UndoJournal>,
+    /// When each (rule, path) pair may run again, for rules with `cooldown_secs`
+    cooldowns: Mutex<HashMap<(String, PathBuf), Instant>>,
 }
 
 impl RuleEngine {
     /// Create a new rule engine with the given rules
     pub fn new(rules: Vec<Rule>) -> Self {
-        Self { rules }
+        for rule in rules.iter().filter(|r| r.budget.is_some()) {
+            if !rule.action.leaves_folder() {
+                warn!(
+                    "Ignoring the budget of rule '{}': its action ({}) doesn't take files out of the folder",
+                    rule.name,
+                    rule.action.preview()
+                );
+            }
+        }
+        Self {
+            rules,
+            dry_run: false,
+            trash_dir: None,
+            protected_extensions: Vec::new(),
+            journal: None,
+            cooldowns: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Create a rule engine from the rules and general settings in a config
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.rules.clone())
+            .with_dry_run(config.general.dry_run)
+            .with_trash_dir(config.general.trash_dir.clone())
+            .with_protected_extensions(config.general.protected_extensions.clone())
+    }
+
+    /// Only log what matching actions would do, without executing them
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Send `trash` actions without their own destination to this folder
+    pub fn with_trash_dir(mut self, trash_dir: Option<PathBuf>) -> Self {
+        self.trash_dir = trash_dir;
+        self
+    }
+
+    /// Never match files with any of these extensions (leading dots are ignored)
+    pub fn with_protected_extensions(mut self, extensions: Vec<String>) -> Self {
+        self.protected_extensions = extensions
+            .into_iter()
+            .map(|ext| ext.trim_start_matches('.').to_string())
+            .collect();
+        self
+    }
+
+    /// Record moves, copies and renames in this journal so they can be undone
+    pub fn with_journal(mut self, journal: Option<UndoJournal>) -> Self {
+        self.journal = journal;
+        self
+    }
+
+    /// Whether a file has a protected extension and must be left alone
+    pub fn is_protected(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| {
+                self.protected_extensions
+                    .iter()
+                    .any(|p| p.eq_ignore_ascii_case(ext))
+            })
+    }
+
+    /// Whether actions are only logged, not executed
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Execution context for actions run by this engine
+    fn action_context(&self) -> ActionContext {
+        ActionContext {
+            dry_run: self.dry_run,
+            trash_dir: self.trash_dir.clone(),
+            captures: Vec::new(),
+            watch_root: None,
+            rule_name: None,
+            quiet: false,
+        }
     }
 
     /// Evaluate rules for a file and return the first matching action
     pub fn evaluate_first(&self, path: &Path) -> Result<Option<Action>> {
         debug!("Evaluating first matching rule for: {}", path.display());
 
+        if self.is_protected(path) {
+            debug!("Protected extension, skipping: {}", path.display());
+            return Ok(None);
+        }
+
         for rule in &self.rules {
             if !rule.enabled {
                 trace!("Skipping disabled rule: {}", rule.name);
@@ -28,7 +187,15 @@ impl RuleEngine {
             }
 
             if rule.condition.matches(path)? {
-                info!("Rule '{}' matched: {}", rule.name, path.display());
+                if rule.is_exclusion() {
+                    debug!("Excluded by rule '{}': {}", rule.name, path.display());
+                    return Ok(None);
+                }
+                if rule.quiet {
+                    debug!("Rule '{}' matched: {}", rule.name, path.display());
+                } else {
+                    info!("Rule '{}' matched: {}", rule.name, path.display());
+                }
                 return Ok(Some(rule.action.clone()));
             }
         }
@@ -41,97 +208,348 @@ impl RuleEngine {
     pub fn evaluate_all(&self, path: &Path) -> Result<Vec<Action>> {
         debug!("Evaluating all rules for: {}", path.display());
 
-        let mut actions = Vec::new();
+        Ok(self
+            .matching_rules(path, None, None)?
+            .into_iter()
+            .map(|rule| rule.action.clone())
+            .collect())
+    }
 
-        for rule in &self.rules {
-            if !rule.enabled {
+    /// Evaluate only rules whose names are in the allowed list (or all if None)
+    pub fn evaluate_filtered(
+        &self,
+        path: &Path,
+        allowed_rules: Option<&[String]>,
+    ) -> Result<Vec<Action>> {
+        Ok(self
+            .matching_rules(path, allowed_rules, None)?
+            .into_iter()
+            .map(|rule| rule.action.clone())
+            .collect())
+    }
+
+    /// What the rules would do to the files in `dir` (and its subfolders when
+    /// `recursive`), without running anything.
+    ///
+    /// A file gets one entry per rule that would act on it, in the order the
+    /// rules would run and up to the first `stop_processing` rule or action
+    /// that moves the file away, just like real processing. A file no rule
+    /// matches gets a single `None` entry. Files whose conditions can't be
+    /// checked are logged and listed as unmatched.
+    pub fn dry_evaluate_dir(&self, dir: &Path, recursive: bool) -> Result<Vec<DryEvaluation>> {
+        let entries: Box<dyn Iterator<Item = std::fs::DirEntry>> = if recursive {
+            let walk = crate::watcher::walkdir(dir)
+                .with_context(|| format!("Failed to read directory: {}", dir.display()))?;
+            walk.log_skipped(dir);
+            Box::new(walk.entries.into_iter())
+        } else {
+            Box::new(
+                std::fs::read_dir(dir)
+                    .with_context(|| format!("Failed to read directory: {}", dir.display()))?
+                    .flatten(),
+            )
+        };
+
+        let mut results = Vec::new();
+        for entry in entries {
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            let rules = match self.matching_rules(&path, None, Some(dir)) {
+                Ok(rules) => rules,
+                Err(e) => {
+                    warn!("Can't evaluate rules for {}: {:#}", path.display(), e);
+                    Vec::new()
+                }
+            };
+            if rules.is_empty() {
+                results.push((path, None));
                 continue;
             }
+            for rule in rules {
+                results.push((path.clone(), Some((rule.name.clone(), rule.action.clone()))));
+                if rule.action.removes_source() {
+                    break;
+                }
+            }
+        }
+        Ok(results)
+    }
 
-            if rule.condition.matches(path)? {
-                info!("Rule '{}' matched: {}", rule.name, path.display());
-                actions.push(rule.action.clone());
+    /// Rule filter for the initial scan of a watch: `allowed` without the
+    /// rules that set `on_startup_scan = false`. `None` means every rule; an
+    /// empty list means no rule takes part in the scan.
+    pub fn startup_scan_rules(&self, allowed: Option<&[String]>) -> Option<Vec<String>> {
+        let allowed = allowed.filter(|names| !names.is_empty());
+        if self.rules.iter().all(|rule| rule.on_startup_scan) {
+            return allowed.map(<[String]>::to_vec);
+        }
+        Some(
+            self.rules
+                .iter()
+                .filter(|rule| rule.on_startup_scan)
+                .filter(|rule| allowed.is_none_or(|names| names.contains(&rule.name)))
+                .map(|rule| rule.name.clone())
+                .collect(),
+        )
+    }
+
+    /// Collect the enabled rules matching a file, in order, honoring
+    /// `stop_processing` and the optional allowed-rule filter. `watch_root`
+    /// is the watched directory the file was found under, for depth conditions.
+    pub(crate) fn matching_rules(
+        &self,
+        path: &Path,
+        allowed_rules: Option<&[String]>,
+        watch_root: Option<&Path>,
+    ) -> Result<Vec<&Rule>> {
+        let names = allowed_rules.filter(|names| !names.is_empty());
+        if let Some(names) = names {
+            debug!(
+                "Evaluating filtered rules ({} allowed) for: {}",
+                names.len(),
+                path.display()
+            );
+        }
+
+        let mut matched = Vec::new();
+        if self.is_protected(path) {
+            debug!("Protected extension, skipping: {}", path.display());
+            return Ok(matched);
+        }
+
+        for rule in &self.rules {
+            // Budget rules run from `enforce_budgets`, not per file
+            if !rule.enabled || rule.budget.is_some() {
+                continue;
+            }
+            if let Some(names) = names
+                && !names.iter().any(|n| n == &rule.name)
+            {
+                trace!("Skipping rule '{}' (not in filter)", rule.name);
+                continue;
+            }
+            if rule.condition.matches_in(path, watch_root)? {
+                // Exclusion rules stop evaluation without contributing an action
+                if rule.is_exclusion() {
+                    debug!("Excluded by rule '{}': {}", rule.name, path.display());
+                    break;
+                }
+                if rule.quiet {
+                    debug!("Rule '{}' matched: {}", rule.name, path.display());
+                } else {
+                    info!("Rule '{}' matched: {}", rule.name, path.display());
+                }
+                matched.push(rule);
                 if rule.stop_processing {
                     break;
                 }
             }
         }
 
-        Ok(actions)
+        Ok(matched)
     }
 
-    /// Evaluate only rules whose names are in the allowed list (or all if None)
-    pub fn evaluate_filtered(
+    /// Evaluate filtered rules and execute all matching actions.
+    ///
+    /// Returns the rules whose actions were executed (empty if nothing matched).
+    pub fn process_filtered(
         &self,
         path: &Path,
         allowed_rules: Option<&[String]>,
-    ) -> Result<Vec<Action>> {
-        match allowed_rules {
-            Some(names) if !names.is_empty() => {
+    ) -> Result<Vec<RuleMatch>> {
+        self.process_in_watch(path, allowed_rules, None)
+    }
+
+    /// Like `process_filtered`, for a file found under the watched directory
+    /// `watch_root` (used by `preserve_structure`)
+    pub fn process_in_watch(
+        &self,
+        path: &Path,
+        allowed_rules: Option<&[String]>,
+        watch_root: Option<&Path>,
+    ) -> Result<Vec<RuleMatch>> {
+        self.process_in_watch_at(path, allowed_rules, watch_root, Instant::now())
+    }
+
+    /// `process_in_watch` as of `now`, for rule cooldowns
+    fn process_in_watch_at(
+        &self,
+        path: &Path,
+        allowed_rules: Option<&[String]>,
+        watch_root: Option<&Path>,
+        now: Instant,
+    ) -> Result<Vec<RuleMatch>> {
+        let mut executed = Vec::new();
+        for rule in self.matching_rules(path, allowed_rules, watch_root)? {
+            if self.is_cooling_down(rule, path, now) {
                 debug!(
-                    "Evaluating filtered rules ({} allowed) for: {}",
-                    names.len(),
+                    "Rule '{}' cooling down, skipping: {}",
+                    rule.name,
                     path.display()
                 );
-                let mut actions = Vec::new();
-                for rule in &self.rules {
-                    if !rule.enabled {
-                        continue;
-                    }
-                    if !names.iter().any(|n| n == &rule.name) {
-                        trace!("Skipping rule '{}' (not in filter)", rule.name);
-                        continue;
-                    }
-                    if rule.condition.matches(path)? {
-                        info!("Rule '{}' matched: {}", rule.name, path.display());
-                        actions.push(rule.action.clone());
-                        if rule.stop_processing {
-                            break;
-                        }
-                    }
-                }
-                Ok(actions)
+                continue;
+            }
+            self.execute_rule(rule, path, watch_root)?;
+            self.start_cooldown(rule, path, now);
+            executed.push(RuleMatch {
+                rule_name: rule.name.clone(),
+                action: rule.action.clone(),
+                quiet: rule.quiet,
+            });
+            // After a destructive action, the file is gone — stop processing
+            if rule.action.removes_source() {
+                break;
             }
-            _ => self.evaluate_all(path),
         }
+        Ok(executed)
+    }
+
+    /// Whether `rule` ran on `path` less than its `cooldown_secs` before `now`
+    fn is_cooling_down(&self, rule: &Rule, path: &Path, now: Instant) -> bool {
+        rule.cooldown_secs.is_some()
+            && self
+                .cooldowns
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(&(rule.name.clone(), path.to_path_buf()))
+                .is_some_and(|until| now < *until)
     }
 
-    /// Evaluate filtered rules and execute all matching actions
-    pub fn process_filtered(&self, path: &Path, allowed_rules: Option<&[String]>) -> Result<bool> {
-        let actions = self.evaluate_filtered(path, allowed_rules)?;
-        if actions.is_empty() {
-            return Ok(false);
+    /// Start the rule's cooldown for `path`, if it has one, after a successful run
+    fn start_cooldown(&self, rule: &Rule, path: &Path, now: Instant) {
+        let Some(secs) = rule.cooldown_secs else {
+            return;
+        };
+        let mut cooldowns = self.cooldowns.lock().unwrap_or_else(|e| e.into_inner());
+        // Forget expired entries now and then so the map stays small
+        if cooldowns.len() >= 1000 {
+            cooldowns.retain(|_, until| now < *until);
         }
-        for action in &actions {
-            action.execute(path)?;
-            // After a destructive action, the file is gone — stop processing
-            if matches!(
-                action,
-                Action::Move { .. } | Action::Rename { .. } | Action::Trash | Action::Delete
-            ) {
-                break;
+        cooldowns.insert(
+            (rule.name.clone(), path.to_path_buf()),
+            now + Duration::from_secs(secs),
+        );
+    }
+
+    /// Whether any enabled rule is a budget rule
+    pub fn has_budgets(&self) -> bool {
+        self.budget_rules().next().is_some()
+    }
+
+    /// Enabled rules with a budget they can enforce, with that budget
+    fn budget_rules(&self) -> impl Iterator<Item = (&Rule, &super::FolderBudget)> {
+        self.rules
+            .iter()
+            .filter(|r| r.enabled && r.action.leaves_folder())
+            .filter_map(|r| Some((r, r.budget.as_ref()?)))
+    }
+
+    /// Bring every budget rule's folder back under budget by applying the
+    /// rule to its oldest matching files.
+    ///
+    /// Returns each file the rule was applied to, with the outcome.
+    pub fn enforce_budgets(&self) -> Vec<(PathBuf, Result<RuleMatch>)> {
+        let mut outcomes = Vec::new();
+        for (rule, budget) in self.budget_rules() {
+            let eligible = |path: &Path| {
+                !self.is_protected(path) && rule.condition.matches(path).unwrap_or(false)
+            };
+            let files = match budget.files_over_budget(eligible) {
+                Ok(files) => files,
+                Err(e) => {
+                    outcomes.push((budget.path.clone(), Err(e)));
+                    continue;
+                }
+            };
+            if !files.is_empty() {
+                info!(
+                    "{} is over budget; applying rule '{}' to {} oldest files",
+                    budget.path.display(),
+                    rule.name,
+                    files.len()
+                );
+            }
+            for path in files {
+                let outcome = self.execute_rule(rule, &path, None).map(|()| RuleMatch {
+                    rule_name: rule.name.clone(),
+                    action: rule.action.clone(),
+                    quiet: rule.quiet,
+                });
+                outcomes.push((path, outcome));
             }
         }
-        Ok(true)
+        outcomes
     }
 
-    /// Evaluate rules and execute all matching actions
-    pub fn process(&self, path: &Path) -> Result<bool> {
-        let actions = self.evaluate_all(path)?;
-        if actions.is_empty() {
-            return Ok(false);
+    /// Execute a rule's action on a file, marking a failure with the rule's
+    /// `on_error` policy.
+    ///
+    /// A file that vanished is never retried. With `retry` the error carries a
+    /// `Retry` context and with `requeue` a `Requeue` context, for the caller
+    /// to try the file again later without blocking; a `skip_if_open` action
+    /// on a file that's in use is marked `Requeue` whatever the policy.
+    pub(crate) fn execute_rule(
+        &self,
+        rule: &Rule,
+        path: &Path,
+        watch_root: Option<&Path>,
+    ) -> Result<()> {
+        let Err(e) = self.execute_rule_once(rule, path, watch_root) else {
+            return Ok(());
+        };
+        let gone = e
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|io_err| io_err.kind() == std::io::ErrorKind::NotFound);
+        let in_use = e.downcast_ref::<FileInUse>().is_some();
+        match rule.on_error {
+            _ if gone => Err(e),
+            _ if in_use => Err(e.context(Requeue)),
+            Some(OnError::Retry {
+                attempts,
+                delay_secs,
+            }) => Err(e.context(Retry {
+                attempts,
+                delay_secs,
+            })),
+            Some(OnError::Requeue) => Err(e.context(Requeue)),
+            _ => Err(e),
         }
-        for action in &actions {
-            action.execute(path)?;
-            // After a destructive action, the file is gone — stop processing
-            if matches!(
-                action,
-                Action::Move { .. } | Action::Rename { .. } | Action::Trash | Action::Delete
-            ) {
-                break;
+    }
+
+    /// Execute a rule's action on a file once, with the rule's regex captures
+    /// available to rename patterns
+    fn execute_rule_once(&self, rule: &Rule, path: &Path, watch_root: Option<&Path>) -> Result<()> {
+        let ctx = ActionContext {
+            captures: rule.condition.name_captures(path)?,
+            watch_root: watch_root.map(Path::to_path_buf),
+            rule_name: Some(rule.name.clone()),
+            quiet: rule.quiet,
+            ..self.action_context()
+        };
+        let Some(output) = rule.action.apply(path, &ctx)? else {
+            return Ok(());
+        };
+        let op = match rule.action {
+            Action::Move { .. } => JournalOp::Move,
+            Action::Copy { .. } => JournalOp::Copy,
+            Action::Rename { .. } => JournalOp::Rename,
+            _ => return Ok(()),
+        };
+        if let Some(journal) = &self.journal {
+            let entry = JournalEntry::new(&rule.name, op, path.to_path_buf(), output);
+            // The file operation already happened; a journal failure only loses undo
+            if let Err(e) = journal.append(&entry) {
+                warn!("Failed to record undo entry: {:#}", e);
             }
         }
-        Ok(true)
+        Ok(())
+    }
+
+    /// Evaluate rules and execute all matching actions
+    pub fn process(&self, path: &Path) -> Result<bool> {
+        Ok(!self.process_filtered(path, None)?.is_empty())
     }
 
     /// Get all rules
@@ -139,6 +557,23 @@ impl RuleEngine {
         &self.rules
     }
 
+    /// Folders that enabled rules put files into, paired with the rule.
+    ///
+    /// `trash` actions without their own destination use the configured trash folder.
+    pub fn destinations(&self) -> Vec<(&Rule, PathBuf)> {
+        self.enabled_rules()
+            .filter_map(|rule| {
+                let dir = match &rule.action {
+                    Action::Trash { destination: None } => self.trash_dir.clone(),
+                    action => action
+                        .destination_dir()
+                        .map(|dir| super::action::expand_rule_name(dir, Some(&rule.name))),
+                }?;
+                Some((rule, crate::expand_path(&dir)))
+            })
+            .collect()
+    }
+
     /// Get enabled rules only
     pub fn enabled_rules(&self) -> impl Iterator<Item = &Rule> {
         self.rules.iter().filter(|r| r.enabled)
@@ -163,7 +598,6 @@ impl RuleEngine {
 mod tests {
     use super::*;
     use crate::rules::Condition;
-    use std::path::PathBuf;
 
     #[test]
     fn test_evaluate_matching_rule() {
@@ -177,6 +611,9 @@ mod tests {
                 destination: PathBuf::from("/tmp/pdfs"),
                 create_destination: true,
                 overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
             },
         )];
 
@@ -198,8 +635,15 @@ mod tests {
                 extension: Some("pdf".to_string()),
                 ..Default::default()
             },
-            action: Action::Delete,
+            action: Action::Delete {
+                i_understand_delete_is_permanent: true,
+            },
             stop_processing: false,
+            cooldown_secs: None,
+            on_error: None,
+            on_startup_scan: true,
+            budget: None,
+            quiet: false,
         }];
 
         let engine = RuleEngine::new(rules);
@@ -221,6 +665,9 @@ mod tests {
                     destination: PathBuf::from("/tmp/pdfs"),
                     create_destination: true,
                     overwrite: false,
+                    preserve_structure: false,
+                    skip_if_open: false,
+                    skip_if_identical: false,
                 },
             ),
             Rule::new(
@@ -233,6 +680,9 @@ mod tests {
                     destination: PathBuf::from("/tmp/images"),
                     create_destination: true,
                     overwrite: false,
+                    preserve_structure: false,
+                    skip_if_open: false,
+                    skip_if_identical: false,
                 },
             ),
         ];
@@ -265,4 +715,643 @@ mod tests {
             .unwrap();
         assert!(!result.is_empty());
     }
+
+    #[test]
+    fn test_nothing_with_stop_processing_excludes_later_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("moved");
+        let file = dir.path().join("keep.pdf");
+        std::fs::write(&file, "pdf").unwrap();
+
+        let mut exclusion = Rule::new(
+            "Keep these",
+            Condition {
+                name_matches: Some("keep*".to_string()),
+                ..Default::default()
+            },
+            Action::Nothing,
+        );
+        exclusion.stop_processing = true;
+
+        let engine = RuleEngine::new(vec![
+            exclusion,
+            Rule::new(
+                "PDFs",
+                Condition {
+                    extension: Some("pdf".to_string()),
+                    ..Default::default()
+                },
+                Action::Move {
+                    destination: dest.clone(),
+                    create_destination: true,
+                    overwrite: false,
+                    preserve_structure: false,
+                    skip_if_open: false,
+                    skip_if_identical: false,
+                },
+            ),
+        ]);
+
+        assert!(engine.evaluate_all(&file).unwrap().is_empty());
+        assert!(engine.evaluate_first(&file).unwrap().is_none());
+        assert!(engine.process_filtered(&file, None).unwrap().is_empty());
+        assert!(file.exists());
+        assert!(!dest.exists());
+
+        // Files the exclusion doesn't match still reach the Move rule
+        let other = dir.path().join("report.pdf");
+        std::fs::write(&other, "pdf").unwrap();
+        assert_eq!(engine.evaluate_all(&other).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_dry_run_leaves_files_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("sorted");
+        let moved = dir.path().join("report.pdf");
+        let deleted = dir.path().join("old.tmp");
+        std::fs::write(&moved, "pdf").unwrap();
+        std::fs::write(&deleted, "tmp").unwrap();
+
+        let mut config = Config::default();
+        config.general.dry_run = true;
+        config.rules = vec![
+            Rule::new(
+                "PDFs",
+                Condition {
+                    extension: Some("pdf".to_string()),
+                    ..Default::default()
+                },
+                Action::Move {
+                    destination: dest.clone(),
+                    create_destination: true,
+                    overwrite: false,
+                    preserve_structure: false,
+                    skip_if_open: false,
+                    skip_if_identical: false,
+                },
+            ),
+            Rule::new(
+                "Temp",
+                Condition {
+                    extension: Some("tmp".to_string()),
+                    ..Default::default()
+                },
+                Action::Delete {
+                    i_understand_delete_is_permanent: true,
+                },
+            ),
+        ];
+        let engine = RuleEngine::from_config(&config);
+        assert!(engine.is_dry_run());
+
+        // Matches are still reported so callers can log what would happen
+        assert_eq!(engine.process_filtered(&moved, None).unwrap().len(), 1);
+        assert_eq!(engine.process_filtered(&deleted, None).unwrap().len(), 1);
+
+        assert!(moved.exists());
+        assert!(deleted.exists());
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn test_process_filtered_reports_matched_rule() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "hello").unwrap();
+
+        let engine = RuleEngine::new(vec![
+            Rule::new(
+                "PDFs",
+                Condition {
+                    extension: Some("pdf".to_string()),
+                    ..Default::default()
+                },
+                Action::Delete {
+                    i_understand_delete_is_permanent: true,
+                },
+            ),
+            Rule::new(
+                "Text",
+                Condition {
+                    extension: Some("txt".to_string()),
+                    ..Default::default()
+                },
+                Action::Nothing,
+            ),
+        ]);
+
+        let matches = engine.process_filtered(&file, None).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].rule_name, "Text");
+        assert!(matches!(matches[0].action, Action::Nothing));
+
+        let other = dir.path().join("photo.png");
+        std::fs::write(&other, "png").unwrap();
+        assert!(engine.process_filtered(&other, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rename_uses_name_regex_captures() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("IMG_1234.jpg");
+        std::fs::write(&file, "jpg").unwrap();
+
+        let engine = RuleEngine::new(vec![Rule::new(
+            "Vacation photos",
+            Condition {
+                name_regex: Some(r"^IMG_(\d+)\.jpg$".to_string()),
+                ..Default::default()
+            },
+            Action::Rename {
+                pattern: "2024_vacation_{1}.{ext}".to_string(),
+                overwrite: false,
+            },
+        )]);
+
+        assert_eq!(engine.process_filtered(&file, None).unwrap().len(), 1);
+        assert!(dir.path().join("2024_vacation_1234.jpg").exists());
+    }
+
+    #[test]
+    fn test_protected_extension_never_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("dest");
+        let catch_all = Rule::new(
+            "Everything",
+            Condition {
+                name_matches: Some("*".to_string()),
+                ..Default::default()
+            },
+            Action::Move {
+                destination: dest.clone(),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+            },
+        );
+        let engine = RuleEngine::new(vec![catch_all])
+            .with_protected_extensions(vec!["lnk".to_string(), ".app".to_string()]);
+
+        let shortcut = dir.path().join("Game.LNK");
+        std::fs::write(&shortcut, "lnk").unwrap();
+        let bundle = dir.path().join("Editor.app");
+        std::fs::create_dir(&bundle).unwrap();
+
+        assert!(engine.evaluate_first(&shortcut).unwrap().is_none());
+        assert!(engine.evaluate_all(&bundle).unwrap().is_empty());
+        assert!(engine.process_filtered(&shortcut, None).unwrap().is_empty());
+        assert!(shortcut.exists());
+        assert!(bundle.exists());
+
+        // Anything else is still handled by the broad rule
+        let other = dir.path().join("notes.txt");
+        std::fs::write(&other, "txt").unwrap();
+        assert_eq!(engine.process_filtered(&other, None).unwrap().len(), 1);
+        assert!(dest.join("notes.txt").exists());
+    }
+
+    #[test]
+    fn test_rule_placeholder_in_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let sorted = dir.path().join("Sorted").join("{rule}");
+        let sort_into = |name: &str, ext: &str| {
+            Rule::new(
+                name,
+                Condition {
+                    extension: Some(ext.to_string()),
+                    ..Default::default()
+                },
+                Action::Move {
+                    destination: sorted.clone(),
+                    create_destination: true,
+                    overwrite: false,
+                    preserve_structure: false,
+                    skip_if_open: false,
+                    skip_if_identical: false,
+                },
+            )
+        };
+        let engine = RuleEngine::new(vec![
+            sort_into("Images", "jpg"),
+            sort_into("Docs/PDF", "pdf"),
+        ]);
+
+        let photo = dir.path().join("photo.jpg");
+        let paper = dir.path().join("paper.pdf");
+        std::fs::write(&photo, "jpg").unwrap();
+        std::fs::write(&paper, "pdf").unwrap();
+        engine.process(&photo).unwrap();
+        engine.process(&paper).unwrap();
+
+        assert!(dir.path().join("Sorted/Images/photo.jpg").exists());
+        // Separators in a rule name don't create nested folders
+        assert!(dir.path().join("Sorted/Docs-PDF/paper.pdf").exists());
+        assert_eq!(
+            engine.destinations()[0].1,
+            dir.path().join("Sorted").join("Images")
+        );
+    }
+
+    #[test]
+    fn test_rule_cooldown_skips_repeat_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("copies");
+        let mut rule = Rule::new(
+            "Back up",
+            Condition {
+                extension: Some("txt".to_string()),
+                ..Default::default()
+            },
+            Action::Copy {
+                destination: dest.clone(),
+                create_destination: true,
+                overwrite: true,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+                preserve_timestamps: false,
+            },
+        );
+        rule.cooldown_secs = Some(60);
+        let engine = RuleEngine::new(vec![rule]);
+        let file = dir.path().join("draft.txt");
+        let other = dir.path().join("other.txt");
+        std::fs::write(&file, "v1").unwrap();
+        std::fs::write(&other, "other").unwrap();
+
+        let start = Instant::now();
+        let run = |path: &Path, secs| {
+            engine
+                .process_in_watch_at(path, None, None, start + Duration::from_secs(secs))
+                .unwrap()
+                .len()
+        };
+        assert_eq!(run(&file, 0), 1);
+
+        // The file is rewritten within the cooldown: left alone
+        std::fs::write(&file, "v2").unwrap();
+        assert_eq!(run(&file, 30), 0);
+        assert_eq!(
+            std::fs::read_to_string(dest.join("draft.txt")).unwrap(),
+            "v1"
+        );
+        // Other paths have their own cooldown
+        assert_eq!(run(&other, 30), 1);
+
+        // Once it has passed, the rule runs again
+        assert_eq!(run(&file, 60), 1);
+        assert_eq!(
+            std::fs::read_to_string(dest.join("draft.txt")).unwrap(),
+            "v2"
+        );
+        assert_eq!(run(&file, 90), 0);
+    }
+
+    #[test]
+    fn test_on_error_marks_failures_for_retry() {
+        let dir = tempfile::tempdir().unwrap();
+        // Stands in for a network share that isn't mounted yet
+        let share = dir.path().join("share");
+        let rule = |on_error| Rule {
+            on_error,
+            ..Rule::new(
+                "To share",
+                Condition::default(),
+                Action::Move {
+                    destination: share.clone(),
+                    create_destination: false,
+                    overwrite: false,
+                    preserve_structure: false,
+                    skip_if_open: false,
+                    skip_if_identical: false,
+                },
+            )
+        };
+        let file = dir.path().join("report.pdf");
+        std::fs::write(&file, "pdf").unwrap();
+
+        // Without a policy the first failure is final
+        let engine = RuleEngine::new(vec![rule(None)]);
+        assert!(engine.process(&file).is_err());
+
+        // Retry hands the failure back right away, with the delays to wait
+        let engine = RuleEngine::new(vec![rule(Some(OnError::Retry {
+            attempts: 3,
+            delay_secs: 5.0,
+        }))]);
+        let started = std::time::Instant::now();
+        let err = engine.process(&file).unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        let retry = err.downcast_ref::<Retry>().unwrap();
+        let delays: Vec<_> = (0..4).map(|n| retry.delay(n)).collect();
+        assert_eq!(
+            delays,
+            [
+                Some(Duration::from_secs(5)),
+                Some(Duration::from_secs(10)),
+                Some(Duration::from_secs(20)),
+                None
+            ]
+        );
+
+        // The waits are capped at an hour in total
+        let retry = Retry {
+            attempts: 100,
+            delay_secs: 600.0,
+        };
+        assert_eq!(retry.delay(1), Some(Duration::from_secs(1200)));
+        assert_eq!(retry.delay(2), None);
+        let missing = dir.path().join("other.pdf");
+        std::fs::write(&missing, "pdf").unwrap();
+
+        // Requeue hands the failure back, marked for the caller
+        let engine = RuleEngine::new(vec![rule(Some(OnError::Requeue))]);
+        let err = engine.process(&missing).unwrap_err();
+        assert!(err.downcast_ref::<Requeue>().is_some());
+    }
+
+    #[test]
+    fn test_startup_scan_rules() {
+        let rule = |name: &str, on_startup_scan| Rule {
+            on_startup_scan,
+            ..Rule::new(name, Condition::default(), Action::Nothing)
+        };
+        let names = |list: &[&str]| list.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        let engine = RuleEngine::new(vec![rule("A", true), rule("B", true)]);
+        assert_eq!(engine.startup_scan_rules(None), None);
+        assert_eq!(
+            engine.startup_scan_rules(Some(&names(&["B"]))),
+            Some(names(&["B"]))
+        );
+
+        let engine = RuleEngine::new(vec![rule("A", true), rule("B", false)]);
+        assert_eq!(engine.startup_scan_rules(None), Some(names(&["A"])));
+        assert_eq!(engine.startup_scan_rules(Some(&[])), Some(names(&["A"])));
+        assert_eq!(
+            engine.startup_scan_rules(Some(&names(&["B"]))),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn test_budget_rule_prunes_oldest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let downloads = dir.path().join("Downloads");
+        let archive = dir.path().join("Archive");
+        std::fs::create_dir(&downloads).unwrap();
+        let now = std::time::SystemTime::now();
+        for (name, age_days) in [("new.zip", 1), ("old.zip", 30), ("older.zip", 60)] {
+            let path = downloads.join(name);
+            std::fs::write(&path, vec![0u8; 100]).unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(age_days * 86400))
+                .unwrap();
+        }
+
+        let mut rule = Rule::new(
+            "Downloads budget",
+            Condition::default(),
+            Action::Move {
+                destination: archive.clone(),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+            },
+        );
+        rule.budget = Some(crate::rules::FolderBudget {
+            path: downloads.clone(),
+            max_bytes: Some(150),
+            max_files: None,
+        });
+        let engine = RuleEngine::new(vec![rule]);
+        assert!(engine.has_budgets());
+
+        // Budget rules never match single files
+        assert!(!engine.process(&downloads.join("older.zip")).unwrap());
+
+        let outcomes = engine.enforce_budgets();
+        let moved: Vec<_> = outcomes
+            .iter()
+            .map(|(path, outcome)| {
+                assert!(outcome.is_ok());
+                path.file_name().unwrap().to_str().unwrap()
+            })
+            .collect();
+        assert_eq!(moved, vec!["older.zip", "old.zip"]);
+        assert!(archive.join("older.zip").exists());
+        assert!(archive.join("old.zip").exists());
+        assert!(downloads.join("new.zip").exists());
+
+        // Back under budget: nothing more to do
+        assert!(engine.enforce_budgets().is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_skip_if_open_defers_move() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("download.iso");
+        std::fs::write(&file, "partial").unwrap();
+        let dest = dir.path().join("done");
+
+        let rule = Rule::new(
+            "ISOs",
+            Condition {
+                extension: Some("iso".to_string()),
+                ..Default::default()
+            },
+            Action::Move {
+                destination: dest.clone(),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: true,
+                skip_if_identical: false,
+            },
+        );
+        let engine = RuleEngine::new(vec![rule]);
+
+        // Another process is still writing the file
+        let mut writer = std::process::Command::new("sleep")
+            .arg("30")
+            .stdin(std::fs::File::open(&file).unwrap())
+            .spawn()
+            .unwrap();
+        let deferred = engine.process_filtered(&file, None);
+        writer.kill().unwrap();
+        writer.wait().unwrap();
+
+        let err = deferred.unwrap_err();
+        assert!(err.downcast_ref::<Requeue>().is_some());
+        assert!(err.downcast_ref::<FileInUse>().is_some());
+        assert!(file.exists());
+
+        // Once it's closed, the move goes ahead
+        assert_eq!(engine.process_filtered(&file, None).unwrap().len(), 1);
+        assert!(dest.join("download.iso").exists());
+    }
+
+    #[test]
+    fn test_dry_evaluate_dir_matches_applied_behavior() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let backup = dest.path().join("backup");
+        let docs = dest.path().join("docs");
+        for name in ["a.txt", "b.pdf", "c.md"] {
+            std::fs::write(src.path().join(name), name).unwrap();
+        }
+        let copy_or_move = |copy: bool, destination: &Path| {
+            let destination = destination.to_path_buf();
+            if copy {
+                Action::Copy {
+                    destination,
+                    create_destination: true,
+                    overwrite: false,
+                    preserve_structure: false,
+                    skip_if_open: false,
+                    skip_if_identical: false,
+                    preserve_timestamps: false,
+                }
+            } else {
+                Action::Move {
+                    destination,
+                    create_destination: true,
+                    overwrite: false,
+                    preserve_structure: false,
+                    skip_if_open: false,
+                    skip_if_identical: false,
+                }
+            }
+        };
+        let engine = RuleEngine::new(vec![
+            Rule::new(
+                "Back up text",
+                Condition {
+                    extension: Some("txt".to_string()),
+                    ..Default::default()
+                },
+                copy_or_move(true, &backup),
+            ),
+            Rule::new(
+                "Documents",
+                Condition {
+                    extensions: vec!["txt".to_string(), "pdf".to_string()],
+                    ..Default::default()
+                },
+                copy_or_move(false, &docs),
+            ),
+            // Never runs: the file has been moved away by then
+            Rule::new("Anything else", Condition::default(), Action::Nothing),
+        ]);
+
+        let mut planned: Vec<(PathBuf, Option<String>)> = engine
+            .dry_evaluate_dir(src.path(), false)
+            .unwrap()
+            .into_iter()
+            .map(|(path, matched)| (path, matched.map(|(rule, _)| rule)))
+            .collect();
+        planned.sort();
+        let named =
+            |name: &str, rule: Option<&str>| (src.path().join(name), rule.map(str::to_string));
+        assert_eq!(
+            planned,
+            vec![
+                named("a.txt", Some("Back up text")),
+                named("a.txt", Some("Documents")),
+                named("b.pdf", Some("Documents")),
+                named("c.md", Some("Anything else")),
+            ]
+        );
+        // Nothing was touched
+        assert!(!dest.path().join("docs").exists());
+
+        let mut applied = Vec::new();
+        for name in ["a.txt", "b.pdf", "c.md"] {
+            let path = src.path().join(name);
+            for m in engine.process_filtered(&path, None).unwrap() {
+                applied.push((path.clone(), Some(m.rule_name)));
+            }
+        }
+        applied.sort();
+        assert_eq!(planned, applied);
+
+        // A file no rule matches is listed without a rule
+        let engine = RuleEngine::new(Vec::new());
+        let unmatched = engine.dry_evaluate_dir(src.path(), false).unwrap();
+        assert_eq!(unmatched.len(), 1);
+        assert_eq!(unmatched[0].0, src.path().join("c.md"));
+        assert!(unmatched[0].1.is_none());
+    }
+
+    #[test]
+    fn test_depth_conditions_use_the_watch_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("a").join("b").join("c");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(dir.path().join("top.txt"), "x").unwrap();
+        std::fs::write(nested.join("deep.txt"), "x").unwrap();
+        let engine = RuleEngine::new(vec![Rule::new(
+            "Deeply nested",
+            Condition {
+                depth_greater_than: Some(3),
+                ..Default::default()
+            },
+            Action::Nothing,
+        )]);
+
+        let mut matched: Vec<String> = engine
+            .dry_evaluate_dir(dir.path(), true)
+            .unwrap()
+            .into_iter()
+            .filter(|(_, rule)| rule.is_some())
+            .map(|(path, _)| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        matched.sort();
+        assert_eq!(matched, vec!["deep.txt"]);
+
+        // Evaluated without a watch, the depth is unknown and nothing matches
+        assert!(
+            engine
+                .evaluate_all(&nested.join("deep.txt"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_budget_rule_that_keeps_files_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut rule = Rule::new(
+            "Copy budget",
+            Condition::default(),
+            Action::Copy {
+                destination: dir.path().join("Backup"),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+                preserve_timestamps: false,
+            },
+        );
+        rule.budget = Some(crate::rules::FolderBudget {
+            path: dir.path().to_path_buf(),
+            max_bytes: None,
+            max_files: Some(0),
+        });
+        std::fs::write(dir.path().join("a.txt"), "a").unwrap();
+
+        let engine = RuleEngine::new(vec![rule]);
+        assert!(!engine.has_budgets());
+        assert!(engine.enforce_budgets().is_empty());
+    }
 }
@@ -0,0 +1,516 @@
+//! Undo journal - a persistent record of reversible file operations
+//!
+//! Every move, copy and rename a rule performs is appended to a JSONL file in
+//! the data directory, so it can be reversed later, even after a restart.
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Journal size that triggers trimming to the newest `max_entries` entries
+const MAX_JOURNAL_BYTES: u64 = 1024 * 1024;
+
+/// Entries kept when the journal is trimmed
+const DEFAULT_MAX_ENTRIES: usize = 1000;
+
+/// Kind of reversible operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalOp {
+    Move,
+    Copy,
+    Rename,
+}
+
+impl JournalOp {
+    /// Lowercase name, as written to the journal
+    pub fn label(self) -> &'static str {
+        match self {
+            JournalOp::Move => "move",
+            JournalOp::Copy => "copy",
+            JournalOp::Rename => "rename",
+        }
+    }
+}
+
+/// One reversible operation performed by a rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: DateTime<Local>,
+    /// Rule that performed the operation
+    pub rule: String,
+    pub op: JournalOp,
+    /// Where the file was before the operation
+    pub source: PathBuf,
+    /// Where the operation put the file (or the copy)
+    pub destination: PathBuf,
+    /// What a copy looked like when it was made, so undo only removes it if
+    /// it hasn't been changed since
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copy: Option<CopyStamp>,
+}
+
+/// Size, entry count and newest modification time of a file or folder, to
+/// tell whether a copy is still the one that was made
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CopyStamp {
+    pub bytes: u64,
+    pub entries: u64,
+    pub modified_ns: u64,
+}
+
+impl CopyStamp {
+    /// Stamp of `path` as it is now, including everything inside a folder
+    pub fn of(path: &Path) -> std::io::Result<Self> {
+        let mut stamp = Self {
+            bytes: 0,
+            entries: 0,
+            modified_ns: 0,
+        };
+        stamp.add(path)?;
+        Ok(stamp)
+    }
+
+    fn add(&mut self, path: &Path) -> std::io::Result<()> {
+        let metadata = std::fs::symlink_metadata(path)?;
+        self.entries += 1;
+        if metadata.is_file() {
+            self.bytes += metadata.len();
+        }
+        let modified = metadata
+            .modified()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        self.modified_ns = self.modified_ns.max(modified);
+        if metadata.is_dir() {
+            for entry in std::fs::read_dir(path)? {
+                self.add(&entry?.path())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// File operation that reverses a journal entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UndoStep {
+    /// Move `from` back to `to`
+    MoveBack { from: PathBuf, to: PathBuf },
+    /// Remove a copy that was made, if it still matches `stamp`
+    Remove {
+        path: PathBuf,
+        stamp: Option<CopyStamp>,
+    },
+}
+
+impl JournalEntry {
+    /// Record an operation that just happened. A copy is stamped as it is now.
+    pub fn new(
+        rule: impl Into<String>,
+        op: JournalOp,
+        source: PathBuf,
+        destination: PathBuf,
+    ) -> Self {
+        let copy = match op {
+            JournalOp::Copy => CopyStamp::of(&destination).ok(),
+            _ => None,
+        };
+        Self {
+            timestamp: Local::now(),
+            rule: rule.into(),
+            op,
+            source,
+            destination,
+            copy,
+        }
+    }
+
+    /// The operation that reverses this one
+    pub fn inverse(&self) -> UndoStep {
+        match self.op {
+            JournalOp::Move | JournalOp::Rename => UndoStep::MoveBack {
+                from: self.destination.clone(),
+                to: self.source.clone(),
+            },
+            JournalOp::Copy => UndoStep::Remove {
+                path: self.destination.clone(),
+                stamp: self.copy,
+            },
+        }
+    }
+
+    /// One-line description, e.g. `~/Downloads/a.pdf ← ~/Documents/a.pdf`
+    pub fn describe(&self) -> String {
+        match self.op {
+            JournalOp::Copy => format!("removed copy {}", self.destination.display()),
+            _ => format!("{} ← {}", self.source.display(), self.destination.display()),
+        }
+    }
+}
+
+impl UndoStep {
+    /// Perform the reversal, refusing to overwrite anything or to remove a
+    /// copy that has changed since it was made
+    pub fn apply(&self) -> Result<()> {
+        match self {
+            UndoStep::MoveBack { from, to } => {
+                if std::fs::symlink_metadata(to).is_ok() {
+                    bail!("Original path is taken: {}", to.display());
+                }
+                std::fs::symlink_metadata(from)
+                    .with_context(|| format!("File is gone: {}", from.display()))?;
+                if let Some(parent) = to.parent() {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create directory {}", parent.display())
+                    })?;
+                }
+                info!("Undo: moving {} -> {}", from.display(), to.display());
+                std::fs::rename(from, to).with_context(|| {
+                    format!("Failed to move {} back to {}", from.display(), to.display())
+                })
+            }
+            UndoStep::Remove { path, stamp } => {
+                let Some(stamp) = stamp else {
+                    bail!(
+                        "No record of what the copy {} looked like; remove it by hand",
+                        path.display()
+                    );
+                };
+                let current = CopyStamp::of(path)
+                    .with_context(|| format!("Copy is gone: {}", path.display()))?;
+                if current != *stamp {
+                    bail!(
+                        "{} has changed since it was copied; remove it by hand",
+                        path.display()
+                    );
+                }
+                info!("Undo: removing copy {}", path.display());
+                if path.is_dir() {
+                    std::fs::remove_dir_all(path)
+                } else {
+                    std::fs::remove_file(path)
+                }
+                .with_context(|| format!("Failed to remove {}", path.display()))
+            }
+        }
+    }
+}
+
+/// Append-only JSONL journal of reversible operations
+#[derive(Debug, Clone)]
+pub struct UndoJournal {
+    path: PathBuf,
+    max_entries: usize,
+}
+
+impl UndoJournal {
+    /// Journal stored at `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_entries: DEFAULT_MAX_ENTRIES,
+        }
+    }
+
+    /// Journal in the default location
+    pub fn open_default() -> Self {
+        Self::new(Self::default_path())
+    }
+
+    /// Default journal path in the data directory
+    pub fn default_path() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_else(|| {
+                dirs::home_dir()
+                    .map(|h| h.join(".local").join("share"))
+                    .unwrap_or_else(|| PathBuf::from("/tmp"))
+            })
+            .join("hazelnut")
+            .join("undo.jsonl")
+    }
+
+    /// Keep at most this many entries when the journal is trimmed
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    /// Path of the journal file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry, trimming old entries once the file grows too large
+    pub fn append(&self, entry: &JournalEntry) -> Result<()> {
+        self.locked(|| {
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .with_context(|| format!("Failed to open journal {}", self.path.display()))?;
+            writeln!(file, "{}", serde_json::to_string(entry)?)?;
+
+            if file.metadata()?.len() > MAX_JOURNAL_BYTES {
+                let mut entries = self.load()?;
+                let excess = entries.len().saturating_sub(self.max_entries);
+                entries.drain(..excess);
+                self.save(&entries)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Run `f` holding the journal's lock, so reading, changing and writing
+    /// the journal back can't interleave with another process appending
+    fn locked<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        // The lock file stays; removing it would let two processes lock
+        // different files
+        let lock_path = self.path.with_extension("jsonl.lock");
+        let lock_file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to create lock file {}", lock_path.display()))?;
+
+        use fs2::FileExt;
+        lock_file
+            .lock_exclusive()
+            .context("Failed to lock the undo journal")?;
+        let result = f();
+        let _ = lock_file.unlock();
+        result
+    }
+
+    /// Load every entry, oldest first, skipping lines that don't parse
+    pub fn load(&self) -> Result<Vec<JournalEntry>> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read journal {}", self.path.display()));
+            }
+        };
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("Skipping bad journal line: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Replace the journal with `entries`, through a temporary file so a
+    /// crash never leaves it half written
+    fn save(&self, entries: &[JournalEntry]) -> Result<()> {
+        let mut content = String::new();
+        for entry in entries {
+            content.push_str(&serde_json::to_string(entry)?);
+            content.push('\n');
+        }
+        let temp_path = self.path.with_extension("jsonl.tmp");
+        std::fs::write(&temp_path, content)
+            .and_then(|()| std::fs::rename(&temp_path, &self.path))
+            .with_context(|| format!("Failed to write journal {}", self.path.display()))
+    }
+
+    /// Reverse the last `count` operations, newest first.
+    ///
+    /// Stops at the first operation that can't be reversed; that entry and
+    /// older ones stay in the journal. Returns the entries that were undone.
+    pub fn undo_last(&self, count: usize) -> Result<Vec<JournalEntry>> {
+        self.locked(|| self.undo_last_locked(count))
+    }
+
+    fn undo_last_locked(&self, count: usize) -> Result<Vec<JournalEntry>> {
+        let mut entries = self.load()?;
+        let mut undone = Vec::new();
+        let mut failure = None;
+
+        while undone.len() < count {
+            let Some(entry) = entries.pop() else { break };
+            if let Err(e) = entry.inverse().apply() {
+                failure = Some(e.context(format!("Can't undo {}", entry.describe())));
+                entries.push(entry);
+                break;
+            }
+            undone.push(entry);
+        }
+
+        if !undone.is_empty() {
+            self.save(&entries)?;
+        }
+        match failure {
+            Some(e) if undone.is_empty() => Err(e),
+            Some(e) => {
+                warn!("{:#}", e);
+                Ok(undone)
+            }
+            None => Ok(undone),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_append_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = UndoJournal::new(dir.path().join("data").join("undo.jsonl"));
+        assert!(journal.load().unwrap().is_empty());
+
+        let first = JournalEntry::new(
+            "PDFs",
+            JournalOp::Move,
+            "/a/x.pdf".into(),
+            "/b/x.pdf".into(),
+        );
+        let second = JournalEntry::new("Copies", JournalOp::Copy, "/a/y".into(), "/c/y".into());
+        journal.append(&first).unwrap();
+        journal.append(&second).unwrap();
+
+        // Garbage lines are skipped rather than failing the whole journal
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(journal.path())
+            .unwrap();
+        writeln!(file, "not json").unwrap();
+
+        assert_eq!(journal.load().unwrap(), vec![first, second]);
+    }
+
+    #[test]
+    fn test_inverse_operations() {
+        let moved = JournalEntry::new("R", JournalOp::Move, "/a/x".into(), "/b/x".into());
+        assert_eq!(
+            moved.inverse(),
+            UndoStep::MoveBack {
+                from: "/b/x".into(),
+                to: "/a/x".into()
+            }
+        );
+        let renamed = JournalEntry::new("R", JournalOp::Rename, "/a/x".into(), "/a/y".into());
+        assert_eq!(
+            renamed.inverse(),
+            UndoStep::MoveBack {
+                from: "/a/y".into(),
+                to: "/a/x".into()
+            }
+        );
+        let copied = JournalEntry::new("R", JournalOp::Copy, "/a/x".into(), "/b/x".into());
+        assert_eq!(
+            copied.inverse(),
+            UndoStep::Remove {
+                path: "/b/x".into(),
+                stamp: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_undo_last_replays_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = UndoJournal::new(dir.path().join("undo.jsonl"));
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        let c = dir.path().join("c.txt");
+        std::fs::write(&c, "data").unwrap();
+        // a.txt was renamed to b.txt, then b.txt to c.txt
+        journal
+            .append(&JournalEntry::new(
+                "R",
+                JournalOp::Rename,
+                a.clone(),
+                b.clone(),
+            ))
+            .unwrap();
+        journal
+            .append(&JournalEntry::new(
+                "R",
+                JournalOp::Rename,
+                b.clone(),
+                c.clone(),
+            ))
+            .unwrap();
+
+        assert_eq!(journal.undo_last(1).unwrap().len(), 1);
+        assert!(b.exists() && !c.exists());
+        assert_eq!(journal.load().unwrap().len(), 1);
+
+        assert_eq!(journal.undo_last(5).unwrap().len(), 1);
+        assert!(a.exists() && !b.exists());
+        assert!(journal.load().unwrap().is_empty());
+
+        // An undo that would overwrite a file is refused and kept
+        std::fs::write(&b, "new").unwrap();
+        journal
+            .append(&JournalEntry::new(
+                "R",
+                JournalOp::Rename,
+                b.clone(),
+                a.clone(),
+            ))
+            .unwrap();
+        assert!(journal.undo_last(1).is_err());
+        assert_eq!(journal.load().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_undo_only_removes_unchanged_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = UndoJournal::new(dir.path().join("undo.jsonl"));
+        let source = dir.path().join("photos");
+        let copy = dir.path().join("backup");
+        for folder in [&source, &copy] {
+            std::fs::create_dir(folder).unwrap();
+            std::fs::write(folder.join("a.jpg"), "jpg").unwrap();
+        }
+        let entry = JournalEntry::new("Backup", JournalOp::Copy, source.clone(), copy.clone());
+        assert!(entry.copy.is_some());
+        journal.append(&entry).unwrap();
+
+        // A file added to the copied folder since means it's not just the copy
+        std::fs::write(copy.join("mine.txt"), "keep me").unwrap();
+        let err = journal.undo_last(1).unwrap_err();
+        assert!(format!("{:#}", err).contains("has changed"), "{:#}", err);
+        assert!(copy.join("mine.txt").exists());
+        assert_eq!(journal.load().unwrap().len(), 1);
+
+        // Back as it was copied, it can be removed
+        std::fs::remove_file(copy.join("mine.txt")).unwrap();
+        let stamp = CopyStamp::of(&copy).unwrap();
+        assert_eq!(stamp.entries, 2);
+        let entry = JournalEntry {
+            copy: Some(stamp),
+            ..entry
+        };
+        std::fs::remove_file(journal.path()).unwrap();
+        journal.append(&entry).unwrap();
+        assert_eq!(journal.undo_last(1).unwrap().len(), 1);
+        assert!(!copy.exists() && source.exists());
+
+        // Entries without a stamp are never removed
+        let file = dir.path().join("old.txt");
+        std::fs::write(&file, "old").unwrap();
+        let step = UndoStep::Remove {
+            path: file.clone(),
+            stamp: None,
+        };
+        assert!(step.apply().is_err());
+        assert!(file.exists());
+    }
+}
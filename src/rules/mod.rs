@@ -1,17 +1,23 @@
 //! Rule engine - conditions and actions for file organization
 
 mod action;
+mod budget;
 mod condition;
 mod engine;
+mod journal;
 
-pub use action::Action;
-pub use condition::Condition;
-pub use engine::RuleEngine;
+pub(crate) use action::invalid_mtime_format;
+pub use action::{Action, ActionContext, FileInUse, OnError};
+pub use budget::FolderBudget;
+pub(crate) use condition::normalize_extension;
+pub use condition::{Condition, DirFileCount};
+pub use engine::{DryEvaluation, Requeue, Retry, RuleEngine, RuleMatch};
+pub use journal::{CopyStamp, JournalEntry, JournalOp, UndoJournal, UndoStep};
 
 use serde::{Deserialize, Serialize};
 
 /// A rule that matches files and performs actions
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Rule {
     /// Human-readable name
     pub name: String,
@@ -30,12 +36,40 @@ pub struct Rule {
     /// Stop processing further rules if this matches
     #[serde(default)]
     pub stop_processing: bool,
+
+    /// Don't apply this rule to the same path again within this many seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown_secs: Option<u64>,
+
+    /// What to do when the action fails (skip the file if None)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_error: Option<OnError>,
+
+    /// Apply this rule to files already in a folder when watching starts
+    /// (if false, only files that arrive later are handled)
+    #[serde(default = "default_enabled", skip_serializing_if = "is_true")]
+    pub on_startup_scan: bool,
+
+    /// Makes this a budget rule: instead of matching files as they arrive, it
+    /// is applied to the oldest matching files in a folder while the folder
+    /// is over budget
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<FolderBudget>,
+
+    /// Log this rule's matches and actions at debug level instead of info,
+    /// for trusted rules that would flood the activity log (errors still show)
+    #[serde(default)]
+    pub quiet: bool,
 }
 
 fn default_enabled() -> bool {
     true
 }
 
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
 impl Rule {
     /// Create a new rule
     pub fn new(name: impl Into<String>, condition: Condition, action: Action) -> Self {
@@ -45,6 +79,17 @@ impl Rule {
             condition,
             action,
             stop_processing: false,
+            cooldown_secs: None,
+            on_error: None,
+            on_startup_scan: true,
+            budget: None,
+            quiet: false,
         }
     }
+
+    /// Whether this rule is an exclusion: a `nothing` action with `stop_processing`,
+    /// which blocks any later rule from handling the file
+    pub fn is_exclusion(&self) -> bool {
+        self.stop_processing && matches!(self.action, Action::Nothing)
+    }
 }
@@ -35,15 +35,46 @@ impl Theme {
         self.0.display_name()
     }
 
-    /// Load theme from config or use default
+    /// Load theme from config or use default.
+    ///
+    /// Reads `general.theme` as a slug (see [`Theme::from_slug`]); a missing
+    /// or unknown name falls back to the default theme.
+    /// Text that doesn't stand out enough from the background is logged as
+    /// a warning; the theme is still used.
     pub fn load(config: &crate::config::Config) -> Theme {
-        config
+        let theme = config
             .general
             .theme
-            .as_ref()
-            .and_then(|name| name.parse::<ThemeName>().ok())
-            .map(Theme::from)
-            .unwrap_or_default()
+            .as_deref()
+            .and_then(Theme::from_slug)
+            .unwrap_or_default();
+        if let Some(ratio) = theme.colors().low_contrast() {
+            tracing::warn!(
+                "Theme '{}' has low text contrast ({:.1}:1, want at least {:.1}:1); text may be hard to read",
+                theme.slug(),
+                ratio,
+                MIN_CONTRAST
+            );
+        }
+        theme
+    }
+
+    /// Look up a theme by its config slug, e.g. `"tokyo-night"`.
+    ///
+    /// Case and surrounding whitespace are ignored, and a bare family name
+    /// (`"catppuccin"`, `"gruvbox"`, `"solarized"`) picks its dark variant.
+    #[must_use]
+    pub fn from_slug(slug: &str) -> Option<Theme> {
+        let slug = slug.trim().to_ascii_lowercase();
+        let slug = match slug.as_str() {
+            "catppuccin" => "catppuccin-mocha",
+            "gruvbox" => "gruvbox-dark",
+            "solarized" => "solarized-dark",
+            "monokai" => "monokai-pro",
+            "one-dark" => "one-dark-pro",
+            other => other,
+        };
+        slug.parse::<ThemeName>().ok().map(Theme::from)
     }
 
     /// Get the color palette for this theme
@@ -83,6 +114,40 @@ impl std::fmt::Display for Theme {
     }
 }
 
+/// Lowest foreground/background contrast ratio considered readable.
+///
+/// This is the WCAG minimum for large text; normal body text asks for 4.5.
+pub const MIN_CONTRAST: f64 = 3.0;
+
+/// Relative luminance of an RGB color, from 0.0 (black) to 1.0 (white).
+///
+/// Named and indexed colors depend on the terminal's palette, so they
+/// have no known luminance.
+#[must_use]
+pub fn relative_luminance(color: Color) -> Option<f64> {
+    let Color::Rgb(r, g, b) = color else {
+        return None;
+    };
+    let linear = |c: u8| {
+        let c = f64::from(c) / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    Some(0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b))
+}
+
+/// WCAG contrast ratio between two colors, from 1.0 (identical) to 21.0
+/// (black on white). `None` unless both are RGB colors.
+#[must_use]
+pub fn contrast_ratio(a: Color, b: Color) -> Option<f64> {
+    let (a, b) = (relative_luminance(a)?, relative_luminance(b)?);
+    let (light, dark) = if a > b { (a, b) } else { (b, a) };
+    Some((light + 0.05) / (dark + 0.05))
+}
+
 /// Extended color palette for UI elements.
 ///
 /// This provides pre-built styles and derived colors for Hazelnut's UI,
@@ -152,6 +217,39 @@ impl ThemeColors {
         }
     }
 
+    /// The text/background contrast ratio if it's below [`MIN_CONTRAST`].
+    /// Colors without a known luminance aren't flagged.
+    #[must_use]
+    pub fn low_contrast(&self) -> Option<f64> {
+        contrast_ratio(self.fg, self.bg).filter(|ratio| *ratio < MIN_CONTRAST)
+    }
+
+    /// High-contrast variant: dim text, muted text and borders are blended
+    /// toward the foreground color so they stay readable.
+    #[must_use]
+    pub fn high_contrast(self) -> Self {
+        Self {
+            fg_dim: Self::blend(self.fg_dim, self.fg, 70),
+            fg_muted: Self::blend(self.fg_muted, self.fg, 55),
+            border: Self::blend(self.border, self.fg, 60),
+            ..self
+        }
+    }
+
+    /// Move `color` `percent`% of the way toward `target`.
+    /// Non-RGB colors can't be blended, so they become `target`.
+    fn blend(color: Color, target: Color, percent: u16) -> Color {
+        match (color, target) {
+            (Color::Rgb(r, g, b), Color::Rgb(tr, tg, tb)) => {
+                let mix = |c: u8, t: u8| -> u8 {
+                    ((c as u16 * (100 - percent) + t as u16 * percent) / 100) as u8
+                };
+                Color::Rgb(mix(r, tr), mix(g, tg), mix(b, tb))
+            }
+            _ => target,
+        }
+    }
+
     /// Adjust color brightness
     fn adjust_brightness(color: Color, amount: i16) -> Color {
         if let Color::Rgb(r, g, b) = color {
@@ -285,3 +383,103 @@ impl ThemeColors {
             .add_modifier(Modifier::BOLD)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn config_with_theme(theme: Option<&str>) -> Config {
+        let mut config = Config::default();
+        config.general.theme = theme.map(str::to_string);
+        config
+    }
+
+    #[test]
+    fn test_load_maps_every_slug() {
+        for name in Theme::all() {
+            let theme = Theme::load(&config_with_theme(Some(name.slug())));
+            assert_eq!(theme, Theme::from(*name), "slug {}", name.slug());
+            assert_eq!(theme.slug(), name.slug());
+        }
+    }
+
+    #[test]
+    fn test_load_accepts_family_names_and_any_case() {
+        let by_slug = |slug: &str| {
+            Theme::all()
+                .iter()
+                .find(|t| t.slug() == slug)
+                .map(|t| Theme::from(*t))
+        };
+        assert_eq!(Theme::from_slug("catppuccin"), by_slug("catppuccin-mocha"));
+        assert_eq!(Theme::from_slug("gruvbox"), by_slug("gruvbox-dark"));
+        assert_eq!(Theme::from_slug(" Nord "), by_slug("nord"));
+    }
+
+    #[test]
+    fn test_load_falls_back_to_default() {
+        assert_eq!(Theme::load(&config_with_theme(None)), Theme::default());
+        assert_eq!(
+            Theme::load(&config_with_theme(Some("no-such-theme"))),
+            Theme::default()
+        );
+        assert!(Theme::from_slug("").is_none());
+    }
+
+    #[test]
+    fn test_high_contrast_overrides_muted_styles() {
+        for name in Theme::all() {
+            let normal = Theme::from(*name).colors();
+            let contrast = normal.clone().high_contrast();
+
+            assert_ne!(
+                contrast.text_muted(),
+                normal.text_muted(),
+                "{}",
+                name.slug()
+            );
+            assert_ne!(contrast.text_dim(), normal.text_dim(), "{}", name.slug());
+            assert_ne!(contrast.block(), normal.block(), "{}", name.slug());
+            // Everything else is left as the theme defines it
+            assert_eq!(contrast.text(), normal.text());
+            assert_eq!(contrast.block_focus(), normal.block_focus());
+            assert_eq!(contrast.selected(), normal.selected());
+        }
+    }
+
+    #[test]
+    fn test_blend_moves_toward_target() {
+        let blended = ThemeColors::blend(Color::Rgb(0, 100, 200), Color::Rgb(200, 200, 200), 50);
+        assert_eq!(blended, Color::Rgb(100, 150, 200));
+        assert_eq!(
+            ThemeColors::blend(Color::DarkGray, Color::White, 50),
+            Color::White
+        );
+    }
+
+    #[test]
+    fn test_contrast_ratio() {
+        let black = Color::Rgb(0, 0, 0);
+        let white = Color::Rgb(255, 255, 255);
+        assert!((contrast_ratio(black, white).unwrap() - 21.0).abs() < 0.01);
+        assert_eq!(contrast_ratio(white, black), contrast_ratio(black, white));
+        assert_eq!(contrast_ratio(white, white), Some(1.0));
+        assert_eq!(contrast_ratio(Color::White, black), None);
+    }
+
+    #[test]
+    fn test_low_contrast_themes_are_flagged() {
+        let mut colors = Theme::default().colors();
+        colors.fg = Color::Rgb(40, 40, 40);
+        colors.bg = Color::Rgb(40, 40, 40);
+        assert_eq!(colors.low_contrast(), Some(1.0));
+
+        colors.fg = Color::Rgb(230, 230, 230);
+        assert_eq!(colors.low_contrast(), None);
+
+        // Terminal palette colors can't be judged
+        colors.fg = Color::Gray;
+        assert_eq!(colors.low_contrast(), None);
+    }
+}
@@ -0,0 +1,138 @@
+//! Coalescing of handled files into activity log summaries
+
+use std::path::PathBuf;
+
+use crate::rules::{Action, RuleMatch};
+
+/// Minimum number of files in a group before it is logged as one summary entry
+pub const COALESCE_MIN_FILES: usize = 3;
+
+/// Files handled by the same rule and action during one processing pass
+#[derive(Debug, Clone)]
+pub struct ActivityGroup {
+    /// Name of the rule that matched
+    pub rule_name: String,
+    /// The action that was executed
+    pub action: Action,
+    /// Files the action was applied to, in the order they were handled
+    pub files: Vec<PathBuf>,
+}
+
+impl ActivityGroup {
+    /// Whether this group is a burst worth summarizing instead of logging per file
+    pub fn is_burst(&self) -> bool {
+        self.files.len() >= COALESCE_MIN_FILES
+    }
+
+    /// One-line summary, e.g. "Moved 500 files to ~/Documents"
+    pub fn summary(&self) -> String {
+        let count = self.files.len();
+        let files = if count == 1 { "file" } else { "files" };
+        match &self.action {
+            Action::Move { destination, .. } => {
+                format!("Moved {} {} to {}", count, files, destination.display())
+            }
+            Action::Copy { destination, .. } => {
+                format!("Copied {} {} to {}", count, files, destination.display())
+            }
+            Action::Rename { .. } => format!("Renamed {} {}", count, files),
+            Action::Trash { .. } => format!("Trashed {} {}", count, files),
+            Action::Delete { .. } => format!("Deleted {} {}", count, files),
+            Action::Run { command, .. } => format!("Ran {} on {} {}", command, count, files),
+            Action::Archive { .. } => format!("Archived {} {}", count, files),
+            Action::Nothing => format!("Matched {} {}", count, files),
+        }
+    }
+}
+
+/// Whether two handled files belong in the same group: same rule, same action target
+fn same_batch(a: &RuleMatch, b: &RuleMatch) -> bool {
+    a.rule_name == b.rule_name && a.action.preview() == b.action.preview()
+}
+
+/// Group handled files by rule and action, keeping the order groups first appear in.
+///
+/// Every file is still listed; this only changes how the activity is reported.
+pub fn coalesce_activity(activity: Vec<(PathBuf, RuleMatch)>) -> Vec<ActivityGroup> {
+    let mut groups: Vec<(RuleMatch, Vec<PathBuf>)> = Vec::new();
+    for (path, matched) in activity {
+        match groups.iter_mut().find(|(m, _)| same_batch(m, &matched)) {
+            Some((_, files)) => files.push(path),
+            None => groups.push((matched, vec![path])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(matched, files)| ActivityGroup {
+            rule_name: matched.rule_name,
+            action: matched.action,
+            files,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moved_to(rule: &str, dest: &str) -> RuleMatch {
+        RuleMatch {
+            rule_name: rule.to_string(),
+            action: Action::Move {
+                destination: PathBuf::from(dest),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+            },
+            quiet: false,
+        }
+    }
+
+    #[test]
+    fn test_same_batch_requires_same_rule_and_target() {
+        let docs = moved_to("PDFs", "/tmp/docs");
+        assert!(same_batch(&docs, &moved_to("PDFs", "/tmp/docs")));
+        assert!(!same_batch(&docs, &moved_to("PDFs", "/tmp/other")));
+        assert!(!same_batch(&docs, &moved_to("Invoices", "/tmp/docs")));
+        assert!(!same_batch(
+            &docs,
+            &RuleMatch {
+                rule_name: "PDFs".to_string(),
+                action: Action::Delete {
+                    i_understand_delete_is_permanent: true,
+                },
+                quiet: false,
+            }
+        ));
+    }
+
+    #[test]
+    fn test_coalesce_activity_groups_bursts() {
+        let mut activity: Vec<(PathBuf, RuleMatch)> = (0..500)
+            .map(|i| {
+                (
+                    PathBuf::from(format!("/tmp/in/{}.pdf", i)),
+                    moved_to("PDFs", "/tmp/docs"),
+                )
+            })
+            .collect();
+        activity.insert(
+            10,
+            (
+                PathBuf::from("/tmp/in/a.png"),
+                moved_to("Images", "/tmp/pics"),
+            ),
+        );
+
+        let groups = coalesce_activity(activity);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].files.len(), 500);
+        assert!(groups[0].is_burst());
+        assert_eq!(groups[0].summary(), "Moved 500 files to /tmp/docs");
+        assert_eq!(groups[1].rule_name, "Images");
+        assert!(!groups[1].is_burst());
+    }
+}
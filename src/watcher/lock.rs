@@ -0,0 +1,116 @@
+//! Lock that keeps a single Hazelnut instance processing files
+//!
+//! The daemon and the TUI's embedded watcher both apply rules. Whichever one
+//! holds the processor lock is the active processor; the other stays idle
+//! until the lock is released.
+
+use anyhow::{Context, Result};
+use fs2::FileExt;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How often `acquire_timeout` retries a contended lock
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Exclusive lock on the processor lock file, released when dropped
+#[derive(Debug)]
+pub struct ProcessorLock {
+    file: File,
+}
+
+impl ProcessorLock {
+    /// Default lock file path, next to the daemon's PID file
+    pub fn default_path() -> PathBuf {
+        dirs::state_dir()
+            .unwrap_or_else(|| {
+                dirs::home_dir()
+                    .map(|h| h.join(".local").join("state"))
+                    .unwrap_or_else(|| PathBuf::from("/tmp"))
+            })
+            .join("hazelnut")
+            .join("processor.lock")
+    }
+
+    /// Take the lock if no other instance holds it.
+    ///
+    /// Returns `Ok(None)` when the lock is held elsewhere.
+    pub fn try_acquire(path: &Path) -> Result<Option<Self>> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create lock directory: {}", parent.display())
+            })?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .with_context(|| format!("Failed to open lock file: {}", path.display()))?;
+
+        match file.try_lock_exclusive() {
+            Ok(()) => Ok(Some(Self { file })),
+            Err(e) if e.raw_os_error() == fs2::lock_contended_error().raw_os_error() => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to lock {}", path.display())),
+        }
+    }
+
+    /// Take the lock, waiting up to `timeout` for the current holder to let go
+    pub fn acquire_timeout(path: &Path, timeout: Duration) -> Result<Option<Self>> {
+        let start = Instant::now();
+        loop {
+            if let Some(lock) = Self::try_acquire(path)? {
+                return Ok(Some(lock));
+            }
+            if start.elapsed() >= timeout {
+                return Ok(None);
+            }
+            std::thread::sleep(RETRY_INTERVAL);
+        }
+    }
+}
+
+impl Drop for ProcessorLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("processor.lock");
+
+        let lock = ProcessorLock::try_acquire(&path).unwrap();
+        assert!(lock.is_some());
+        assert!(ProcessorLock::try_acquire(&path).unwrap().is_none());
+
+        drop(lock);
+        assert!(ProcessorLock::try_acquire(&path).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_acquire_timeout_waits_for_holder() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("processor.lock");
+
+        let held = ProcessorLock::try_acquire(&path).unwrap().unwrap();
+        assert!(
+            ProcessorLock::acquire_timeout(&path, Duration::from_millis(100))
+                .unwrap()
+                .is_none()
+        );
+
+        let releaser = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            drop(held);
+        });
+        let lock = ProcessorLock::acquire_timeout(&path, Duration::from_secs(5)).unwrap();
+        assert!(lock.is_some());
+        releaser.join().unwrap();
+    }
+}
@@ -1,25 +1,126 @@
 //! File system watcher
 
+mod activity;
 mod handler;
+mod lock;
+mod schedule;
 
+pub use activity::{ActivityGroup, coalesce_activity};
 pub use handler::EventHandler;
+pub use lock::ProcessorLock;
+pub use schedule::{ScanReport, ScheduledScan, parse_schedule};
 
 use anyhow::Result;
 use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher as NotifyWatcher};
 use std::path::Path;
 use std::sync::mpsc;
 use std::time::Duration;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 
-use crate::rules::{Rule, RuleEngine};
+use crate::rules::{Requeue, Retry, RuleEngine, RuleMatch};
+
+/// Maximum number of handled files kept until `take_activity` is called
+const MAX_ACTIVITY_ENTRIES: usize = 500;
+
+/// How long a requeued file waits before its rules run again
+const REQUEUE_DELAY: Duration = Duration::from_secs(30);
+
+/// Times a file is requeued before its failure is reported like any other
+const MAX_REQUEUES: u32 = 10;
+
+/// A file whose rule failed with `on_error = retry` or `requeue`, waiting for
+/// another pass
+struct Requeued {
+    path: std::path::PathBuf,
+    due: std::time::Instant,
+    /// Times this file has been requeued so far
+    count: u32,
+}
+
+/// How long to wait before trying a file again after `e`, when it has been
+/// requeued `requeues` times before; `None` if the failure is final
+fn requeue_after(e: &anyhow::Error, requeues: u32, requeue_delay: Duration) -> Option<Duration> {
+    if let Some(retry) = e.downcast_ref::<Retry>() {
+        return retry.delay(requeues);
+    }
+    (e.downcast_ref::<Requeue>().is_some() && requeues < MAX_REQUEUES).then_some(requeue_delay)
+}
+
+/// Change in whether a watched directory can be reached, reported by `check_watches`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchAvailability {
+    /// The directory disappeared or can no longer be read
+    Lost {
+        path: std::path::PathBuf,
+        error: String,
+    },
+    /// The directory is back and has been watched again
+    Restored { path: std::path::PathBuf },
+}
+
+/// Files handled in one watch folder, as reported by `Watcher::watch_stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WatchStats {
+    /// Files a rule was applied to
+    pub files_processed: u64,
+    /// Unix timestamp of the last file a rule was applied to
+    pub last_event_at: Option<u64>,
+}
+
+/// Per-watch counters, shared with the watch's initial scan thread
+#[derive(Debug, Default)]
+struct WatchCounters {
+    files_processed: AtomicU64,
+    /// Unix timestamp, 0 until a rule is first applied
+    last_event_at: AtomicU64,
+}
+
+impl WatchCounters {
+    /// Count `files` more files handled just now
+    fn record(&self, files: u64) {
+        if files > 0 {
+            self.files_processed.fetch_add(files, Ordering::Relaxed);
+            self.last_event_at.store(unix_now(), Ordering::Relaxed);
+        }
+    }
+
+    fn stats(&self) -> WatchStats {
+        let last_event_at = self.last_event_at.load(Ordering::Relaxed);
+        WatchStats {
+            files_processed: self.files_processed.load(Ordering::Relaxed),
+            last_event_at: (last_event_at > 0).then_some(last_event_at),
+        }
+    }
+}
+
+/// How `Watcher::watch_with_options` watches a directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchOptions {
+    /// Also watch subfolders
+    pub recursive: bool,
+    /// Only these rules apply to the directory (empty = all rules)
+    pub rules: Vec<String>,
+    /// Let the initial scan of a recursive watch descend into symlinked folders
+    pub follow_symlinks: bool,
+}
+
+/// A directory registered with `watch_with_options`, kept so it can be re-registered
+struct WatchedPath {
+    /// Canonical form of the path, the key into the per-watch maps
+    canonical: std::path::PathBuf,
+    recursive: bool,
+    rules: Vec<String>,
+    follow_symlinks: bool,
+    available: bool,
+}
 
 /// File system watcher that monitors directories and applies rules
 pub struct Watcher {
     watcher: RecommendedWatcher,
-    engine: RuleEngine,
+    engine: Arc<RuleEngine>,
     rx: mpsc::Receiver<Result<notify::Event, notify::Error>>,
     event_handler: EventHandler,
     files_processed: Arc<AtomicU64>,
@@ -27,6 +128,43 @@ pub struct Watcher {
     watch_rules: std::collections::HashMap<std::path::PathBuf, Vec<String>>,
     /// Cache of canonical paths for watched directories
     canonical_cache: std::collections::HashMap<std::path::PathBuf, std::path::PathBuf>,
+    /// Files handled since the last call to `take_activity`, with the rule that matched
+    activity: Vec<(std::path::PathBuf, RuleMatch)>,
+    /// Most recent rule processing error, if any
+    last_error: Option<String>,
+    /// Unix timestamp of the last file a rule was applied to
+    last_event_at: Option<u64>,
+    /// Files handled per canonical watch directory
+    watch_counters: std::collections::HashMap<std::path::PathBuf, Arc<WatchCounters>>,
+    /// Registered watch directories as given, with whether they were last reachable
+    watched: std::collections::HashMap<std::path::PathBuf, WatchedPath>,
+    /// Rule destinations inside each recursive watch, whose files are never processed
+    loop_guards: std::collections::HashMap<std::path::PathBuf, Vec<std::path::PathBuf>>,
+    /// Files to process again once they're due
+    requeued: Vec<Requeued>,
+    /// Files the initial scans want processed again, picked up on the next pass
+    scan_requeued: Arc<std::sync::Mutex<Vec<Requeued>>>,
+    /// Initial scans that may still be running in the background
+    scans: Vec<std::thread::JoinHandle<()>>,
+    /// Wait before a requeued file is processed again
+    requeue_delay: Duration,
+    /// Whether budget rules should be checked on the next pass (set on start
+    /// and whenever files change)
+    budgets_due: bool,
+    /// Whether a new watch scans the files already in its folder
+    scan_on_start: bool,
+    /// How many folder levels the initial scan of a recursive watch goes into
+    scan_max_depth: Option<usize>,
+    /// Most events `poll` takes from the queue at once (0 = no limit)
+    max_events_per_poll: usize,
+    /// Failures in a row per file, cleared when a rule succeeds on it
+    failures: std::collections::HashMap<std::path::PathBuf, u32>,
+    /// Files that failed too often and are no longer retried, oldest first
+    quarantined: Vec<std::path::PathBuf>,
+    /// Failures in a row before a file is quarantined (0 = never)
+    quarantine_after: u32,
+    /// Folder quarantined files are moved into
+    quarantine_dir: Option<std::path::PathBuf>,
 }
 
 impl Watcher {
@@ -49,15 +187,63 @@ impl Watcher {
 
         Ok(Self {
             watcher,
-            engine,
+            engine: Arc::new(engine),
             rx,
             event_handler: EventHandler::new(debounce_seconds),
             files_processed: Arc::new(AtomicU64::new(0)),
             watch_rules: std::collections::HashMap::new(),
             canonical_cache: std::collections::HashMap::new(),
+            activity: Vec::new(),
+            last_error: None,
+            last_event_at: None,
+            watch_counters: std::collections::HashMap::new(),
+            watched: std::collections::HashMap::new(),
+            loop_guards: std::collections::HashMap::new(),
+            requeued: Vec::new(),
+            scan_requeued: Arc::default(),
+            scans: Vec::new(),
+            requeue_delay: REQUEUE_DELAY,
+            budgets_due: true,
+            scan_on_start: true,
+            scan_max_depth: None,
+            max_events_per_poll: 0,
+            failures: std::collections::HashMap::new(),
+            quarantined: Vec::new(),
+            quarantine_after: 0,
+            quarantine_dir: None,
         })
     }
 
+    /// Whether new watches scan the files already in their folder (default:
+    /// true). Without the scan, only files that change afterwards are handled.
+    pub fn with_scan_on_start(mut self, scan_on_start: bool) -> Self {
+        self.scan_on_start = scan_on_start;
+        self
+    }
+
+    /// Limit the initial scan of recursive watches to this many folder levels
+    /// (1 = only files directly in the watch folder). Live events are still
+    /// handled at any depth.
+    pub fn with_scan_max_depth(mut self, scan_max_depth: Option<usize>) -> Self {
+        self.scan_max_depth = scan_max_depth;
+        self
+    }
+
+    /// Take at most `max` events per `poll` (0 = no limit), leaving the rest
+    /// queued for later calls so a burst of files is handled in slices
+    pub fn with_max_events_per_poll(mut self, max: usize) -> Self {
+        self.max_events_per_poll = max;
+        self
+    }
+
+    /// Stop retrying a file once its rules have failed `after` times in a row
+    /// (0 = never), moving it into `dir` if given. See `quarantined`.
+    pub fn with_quarantine(mut self, after: u32, dir: Option<std::path::PathBuf>) -> Self {
+        self.quarantine_after = after;
+        self.quarantine_dir = dir;
+        self
+    }
+
     /// Start watching a directory
     pub fn watch(&mut self, path: &Path, recursive: bool) -> Result<()> {
         self.watch_with_rules(path, recursive, Vec::new())
@@ -70,42 +256,163 @@ impl Watcher {
         recursive: bool,
         rules: Vec<String>,
     ) -> Result<()> {
+        self.watch_with_options(
+            path,
+            WatchOptions {
+                recursive,
+                rules,
+                follow_symlinks: false,
+            },
+        )
+    }
+
+    /// Start watching a directory with all of a watch's settings
+    pub fn watch_with_options(&mut self, path: &Path, options: WatchOptions) -> Result<()> {
+        let WatchOptions {
+            recursive,
+            rules,
+            follow_symlinks,
+        } = options;
         let mode = if recursive {
             RecursiveMode::Recursive
         } else {
             RecursiveMode::NonRecursive
         };
 
-        self.watcher.watch(path, mode)?;
+        // Remember the watch even if it fails now, so it's picked up once the path appears
+        let result = self.watcher.watch(path, mode);
         let canonical = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
-        self.watch_rules.insert(canonical.clone(), rules);
+        self.watched.insert(
+            path.to_path_buf(),
+            WatchedPath {
+                canonical: canonical.clone(),
+                recursive,
+                rules: rules.clone(),
+                follow_symlinks,
+                available: result.is_ok(),
+            },
+        );
+        result?;
+        self.watch_rules.insert(canonical.clone(), rules.clone());
         self.canonical_cache
             .insert(canonical.clone(), canonical.clone());
+        let counters = Arc::clone(self.watch_counters.entry(canonical.clone()).or_default());
         info!("Watching: {} (recursive: {})", path.display(), recursive);
 
+        // Files a rule moves into a subfolder of this watch would come straight back
+        let mut guards = Vec::new();
+        let mut scan_excluded = Vec::new();
+        if recursive {
+            for (rule_name, subfolder) in looping_subfolders(&self.engine, path, &rules) {
+                warn!(
+                    "Not processing {}: it is inside the watch and is where rule '{}' puts files",
+                    path.join(&subfolder).display(),
+                    rule_name
+                );
+                guards.push(canonical.join(&subfolder));
+                guards.push(path.join(&subfolder));
+                scan_excluded.push(path.join(&subfolder));
+            }
+        }
+        self.loop_guards.insert(path.to_path_buf(), guards);
+
+        if !self.scan_on_start {
+            debug!("Initial scan disabled, skipping: {}", path.display());
+            return Ok(());
+        }
+
         // Initial scan — run in a background thread so TUI startup isn't blocked.
         let scan_path = path.to_path_buf();
-        let scan_rules: Arc<Vec<Rule>> = Arc::new(self.engine.rules().to_vec());
+        let scan_engine = Arc::clone(&self.engine);
         let allowed_rules: Option<Vec<String>> = self
             .watch_rules
             .get(&canonical)
             .filter(|r| !r.is_empty())
             .cloned();
         let counter = Arc::clone(&self.files_processed);
-        std::thread::spawn(move || {
-            scan_existing_background(&scan_path, recursive, &scan_rules, allowed_rules, counter);
-        });
+        let options = WalkOptions {
+            max_depth: if recursive {
+                self.scan_max_depth
+            } else {
+                Some(1)
+            },
+            follow_symlinks,
+        };
+        let requeue_delay = self.requeue_delay;
+        let scan_requeued = Arc::clone(&self.scan_requeued);
+        self.scans.retain(|scan| !scan.is_finished());
+        self.scans.push(std::thread::spawn(move || {
+            let retries = scan_existing_background(
+                &scan_path,
+                options,
+                &scan_engine,
+                allowed_rules,
+                &scan_excluded,
+                counter,
+                &counters,
+            );
+            scan_requeued
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .extend(requeued_for(retries, requeue_delay));
+        }));
 
         Ok(())
     }
 
     /// Stop watching a directory
     pub fn unwatch(&mut self, path: &Path) -> Result<()> {
+        self.watched.remove(path);
+        self.loop_guards.remove(path);
         self.watcher.unwatch(path)?;
         info!("Stopped watching: {}", path.display());
         Ok(())
     }
 
+    /// Check that every watched directory still exists and is readable.
+    ///
+    /// A directory that went away (e.g. an unmounted network share) is reported
+    /// once as `Lost`; when it comes back it is watched again, including the
+    /// initial scan, and reported as `Restored`.
+    pub fn check_watches(&mut self) -> Vec<WatchAvailability> {
+        let mut changes = Vec::new();
+        let mut restored = Vec::new();
+
+        for (path, watched) in &mut self.watched {
+            match (watched.available, std::fs::read_dir(path)) {
+                (true, Err(e)) => {
+                    watched.available = false;
+                    // The backend may already have dropped it along with the directory
+                    let _ = self.watcher.unwatch(path);
+                    changes.push(WatchAvailability::Lost {
+                        path: path.clone(),
+                        error: e.to_string(),
+                    });
+                }
+                (false, Ok(_)) => {
+                    restored.push((
+                        path.clone(),
+                        WatchOptions {
+                            recursive: watched.recursive,
+                            rules: watched.rules.clone(),
+                            follow_symlinks: watched.follow_symlinks,
+                        },
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        for (path, options) in restored {
+            match self.watch_with_options(&path, options) {
+                Ok(()) => changes.push(WatchAvailability::Restored { path }),
+                Err(e) => debug!("Watch not restored yet for {}: {}", path.display(), e),
+            }
+        }
+
+        changes
+    }
+
     /// Process pending events (non-blocking)
     pub fn poll(&self) -> Result<Vec<notify::Event>> {
         let mut events = Vec::new();
@@ -115,15 +422,40 @@ impl Watcher {
                 Ok(event) => events.push(event),
                 Err(e) => error!("Watch error: {}", e),
             }
+            if self.max_events_per_poll > 0 && events.len() >= self.max_events_per_poll {
+                info!(
+                    "Throttling: handling {} events now, any others on the next pass",
+                    events.len()
+                );
+                break;
+            }
         }
 
         Ok(events)
     }
 
-    /// Process already-polled events and apply rules (with debouncing)
+    /// Process already-polled events and apply rules (with debouncing).
+    ///
+    /// Requeued files that are due are processed first, even without events.
     pub fn process_polled_events(&mut self, events: Vec<notify::Event>) -> Result<usize> {
         let mut processed = 0;
+        // Overlapping watches can report the same file more than once per batch
+        let mut seen = std::collections::HashSet::new();
+
+        let now = std::time::Instant::now();
+        self.requeued
+            .append(&mut self.scan_requeued.lock().unwrap_or_else(|e| e.into_inner()));
+        let (due, waiting) = std::mem::take(&mut self.requeued)
+            .into_iter()
+            .partition::<Vec<_>, _>(|r| r.due <= now);
+        self.requeued = waiting;
+        for requeued in due {
+            if self.process_path(requeued.path, requeued.count, &mut seen) {
+                processed += 1;
+            }
+        }
 
+        self.budgets_due |= !events.is_empty();
         for event in events {
             debug!("Event: {:?}", event.kind);
 
@@ -134,26 +466,8 @@ impl Watcher {
                     let paths_to_process = self.event_handler.should_process(&event);
 
                     for path in paths_to_process {
-                        info!("File event detected: {}", path.display());
-                        let allowed = self.allowed_rules_for(&path);
-                        match self.engine.process_filtered(&path, allowed) {
-                            Ok(true) => processed += 1,
-                            Ok(false) => {} // No matching rule
-                            Err(e) => {
-                                // Skip NotFound errors (file gone between event and processing)
-                                if e.downcast_ref::<std::io::Error>().is_some_and(|io_err| {
-                                    io_err.kind() == std::io::ErrorKind::NotFound
-                                }) {
-                                    debug!(
-                                        "File disappeared before processing: {}",
-                                        path.display()
-                                    );
-                                    continue;
-                                }
-                                error!("Rule processing failed for {}: {}", path.display(), e);
-                                let rule_name = self.find_matching_rule_name(&path);
-                                crate::notifications::notify_rule_error(&rule_name, &e.to_string());
-                            }
+                        if self.process_path(path, 0, &mut seen) {
+                            processed += 1;
                         }
                     }
                 }
@@ -163,6 +477,10 @@ impl Watcher {
             }
         }
 
+        if std::mem::take(&mut self.budgets_due) {
+            processed += self.enforce_budgets();
+        }
+
         // Periodically clean up old entries
         self.event_handler.cleanup();
 
@@ -171,21 +489,295 @@ impl Watcher {
         Ok(processed)
     }
 
+    /// Apply the rules to one file from an event or the requeue, skipping files
+    /// already in `seen`. `requeues` is how often the file was requeued before.
+    ///
+    /// Returns whether a rule was applied.
+    fn process_path(
+        &mut self,
+        path: std::path::PathBuf,
+        requeues: u32,
+        seen: &mut std::collections::HashSet<std::path::PathBuf>,
+    ) -> bool {
+        let canonical = std::fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if self.is_loop_guarded(&path) || self.is_loop_guarded(&canonical) {
+            debug!("Skipping file in a rule destination: {}", path.display());
+            return false;
+        }
+        if !seen.insert(canonical) {
+            debug!("Already handled in this batch: {}", path.display());
+            return false;
+        }
+        if self.quarantined.contains(&path) {
+            debug!("Skipping quarantined file: {}", path.display());
+            return false;
+        }
+
+        debug!("File event detected: {}", path.display());
+        let allowed = self.allowed_rules_for(&path);
+        let root = self.watch_for(&path).map(|(root, _)| root.clone());
+        match self
+            .engine
+            .process_in_watch(&path, allowed, root.as_deref())
+        {
+            Ok(matches) if matches.is_empty() => {
+                self.failures.remove(&path);
+                false // No matching rule
+            }
+            Ok(matches) => {
+                self.failures.remove(&path);
+                self.last_event_at = Some(unix_now());
+                if let Some(counters) = root.and_then(|root| self.watch_counters.get(&root)) {
+                    counters.record(1);
+                }
+                self.record_activity(matches.into_iter().map(|m| (path.clone(), m)));
+                true
+            }
+            Err(e) => {
+                // Skip NotFound errors (file gone between event and processing)
+                if e.downcast_ref::<std::io::Error>()
+                    .is_some_and(|io_err| io_err.kind() == std::io::ErrorKind::NotFound)
+                {
+                    debug!("File disappeared before processing: {}", path.display());
+                    self.failures.remove(&path);
+                    return false;
+                }
+                if let Some(delay) = requeue_after(&e, requeues, self.requeue_delay) {
+                    info!(
+                        "Will retry {} in {:.1}s: {:#}",
+                        path.display(),
+                        delay.as_secs_f64(),
+                        e
+                    );
+                    self.requeued.push(Requeued {
+                        path,
+                        due: std::time::Instant::now() + delay,
+                        count: requeues + 1,
+                    });
+                    return false;
+                }
+                error!("Rule processing failed for {}: {}", path.display(), e);
+                self.last_error = Some(format!("{}: {}", path.display(), e));
+                let rule_name = self.find_matching_rule_name(&path);
+                crate::notifications::notify_rule_error(&rule_name, &e.to_string());
+                self.record_failure(path);
+                false
+            }
+        }
+    }
+
+    /// Count a failed attempt on `path`, quarantining it once it has failed
+    /// `quarantine_after` times in a row
+    fn record_failure(&mut self, path: std::path::PathBuf) {
+        if self.quarantine_after == 0 {
+            return;
+        }
+        let failures = self.failures.entry(path.clone()).or_default();
+        *failures += 1;
+        if *failures < self.quarantine_after {
+            return;
+        }
+        self.failures.remove(&path);
+
+        warn!(
+            "Quarantined {} after {} failures in a row; it won't be retried",
+            path.display(),
+            self.quarantine_after
+        );
+        if let Some(ref dir) = self.quarantine_dir {
+            let quarantine = crate::rules::Action::Trash {
+                destination: Some(dir.clone()),
+            };
+            match quarantine.execute(&path) {
+                Ok(()) => info!("Moved {} to {}", path.display(), dir.display()),
+                Err(e) => error!("Failed to move {} to quarantine: {:#}", path.display(), e),
+            }
+        }
+        self.quarantined.push(path);
+    }
+
+    /// Files that failed `quarantine_after` times in a row and are no longer
+    /// retried, by their original path, oldest first
+    pub fn quarantined(&self) -> &[std::path::PathBuf] {
+        &self.quarantined
+    }
+
+    /// Apply budget rules to folders that are over budget, recording the
+    /// results like processed events. Returns the number of files handled.
+    fn enforce_budgets(&mut self) -> usize {
+        if !self.engine.has_budgets() {
+            return 0;
+        }
+        let mut handled = 0;
+        for (path, outcome) in self.engine.enforce_budgets() {
+            match outcome {
+                Ok(m) => {
+                    self.last_event_at = Some(unix_now());
+                    self.record_activity([(path, m)]);
+                    handled += 1;
+                }
+                Err(e) => {
+                    error!("Budget rule failed for {}: {}", path.display(), e);
+                    self.last_error = Some(format!("{}: {}", path.display(), e));
+                }
+            }
+        }
+        handled
+    }
+
+    /// Whether `process_polled_events` has work even without new events:
+    /// requeued files that are due, or budget rules to check
+    pub fn has_pending_work(&self) -> bool {
+        self.has_due_requeues() || (self.budgets_due && self.engine.has_budgets())
+    }
+
+    /// Whether a requeued file is due, so a caller idling on an empty event
+    /// queue should still call `process_polled_events`
+    pub fn has_due_requeues(&self) -> bool {
+        let now = std::time::Instant::now();
+        self.requeued.iter().any(|r| r.due <= now)
+            || self
+                .scan_requeued
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .any(|r| r.due <= now)
+    }
+
     /// Get total number of files processed
     pub fn files_processed(&self) -> u64 {
         self.files_processed.load(Ordering::Relaxed)
     }
 
+    /// Files handled in each watch folder, keyed by the path as passed to
+    /// `watch_with_options`
+    pub fn watch_stats(&self) -> std::collections::HashMap<std::path::PathBuf, WatchStats> {
+        self.watched
+            .iter()
+            .map(|(path, watched)| {
+                let stats = self
+                    .watch_counters
+                    .get(&watched.canonical)
+                    .map(|counters| counters.stats())
+                    .unwrap_or_default();
+                (path.clone(), stats)
+            })
+            .collect()
+    }
+
+    /// Most recent rule processing error, if any
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Unix timestamp of the last file a rule was applied to
+    pub fn last_event_at(&self) -> Option<u64> {
+        self.last_event_at
+    }
+
+    /// Take the files handled since the last call, with the rule that matched each
+    pub fn take_activity(&mut self) -> Vec<(std::path::PathBuf, RuleMatch)> {
+        std::mem::take(&mut self.activity)
+    }
+
+    /// Add handled files to the activity, leaving out quiet rules so they
+    /// never reach the daemon or TUI log
+    fn record_activity(
+        &mut self,
+        handled: impl IntoIterator<Item = (std::path::PathBuf, RuleMatch)>,
+    ) {
+        self.activity
+            .extend(handled.into_iter().filter(|(_, m)| !m.quiet));
+        // Bound the backlog for callers that never take it
+        if self.activity.len() > MAX_ACTIVITY_ENTRIES {
+            let excess = self.activity.len() - MAX_ACTIVITY_ENTRIES;
+            self.activity.drain(..excess);
+        }
+    }
+
+    /// Count a scheduled scan's results in the stats, activity and health info.
+    ///
+    /// Returns the number of files a rule was applied to.
+    pub fn record_scan(&mut self, report: ScanReport) -> usize {
+        let processed = report.files_processed();
+        self.requeued
+            .extend(requeued_for(report.retries, self.requeue_delay));
+        if let Some(e) = report.errors.last() {
+            self.last_error = Some(e.clone());
+        }
+        if processed > 0 {
+            self.last_event_at = Some(unix_now());
+        }
+        let mut files: Vec<_> = report.activity.iter().map(|(path, _)| path).collect();
+        files.dedup();
+        for file in files {
+            if let Some((root, _)) = self.watch_for(file)
+                && let Some(counters) = self.watch_counters.get(root)
+            {
+                counters.record(1);
+            }
+        }
+        self.record_activity(report.activity);
+        self.files_processed
+            .fetch_add(processed as u64, Ordering::Relaxed);
+        processed
+    }
+
     /// Process events and apply rules (polls + processes, convenience method)
     pub fn process_events(&mut self) -> Result<usize> {
         let events = self.poll()?;
         self.process_polled_events(events)
     }
 
-    /// Carry over files_processed count from a previous watcher (e.g. on config reload)
+    /// Process whatever is still queued before shutting down.
+    ///
+    /// Keeps polling until the queue is empty or `timeout` elapses, so a
+    /// file dropped just before SIGTERM isn't left half-handled.
+    pub fn drain(&mut self, timeout: Duration) -> Result<usize> {
+        let deadline = std::time::Instant::now() + timeout;
+        let mut processed = 0;
+
+        loop {
+            let events = self.poll()?;
+            if events.is_empty() {
+                break;
+            }
+            processed += self.process_polled_events(events)?;
+            if std::time::Instant::now() >= deadline {
+                info!("Drain timed out, remaining events dropped");
+                break;
+            }
+        }
+
+        Ok(processed)
+    }
+
+    /// Carry over files_processed counts (overall and per watch) and health info from a previous watcher (e.g. on config reload)
     pub fn carry_over_files_processed(&mut self, old: &Watcher) {
         self.files_processed
             .store(old.files_processed(), Ordering::Relaxed);
+        self.last_error = old.last_error.clone();
+        self.last_event_at = old.last_event_at;
+        for (root, old_counters) in &old.watch_counters {
+            if let Some(counters) = self.watch_counters.get(root) {
+                let old_stats = old_counters.stats();
+                counters
+                    .files_processed
+                    .fetch_add(old_stats.files_processed, Ordering::Relaxed);
+                counters
+                    .last_event_at
+                    .fetch_max(old_stats.last_event_at.unwrap_or(0), Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Whether `path` is inside a rule destination that lies within a recursive
+    /// watch; such files are left alone so a rule can't keep re-handling them
+    pub fn is_loop_guarded(&self, path: &Path) -> bool {
+        self.loop_guards
+            .values()
+            .flatten()
+            .any(|guard| path.starts_with(guard))
     }
 
     /// Find the name of the first matching rule for a path
@@ -205,6 +797,17 @@ impl Watcher {
 
     /// Find the allowed rules filter for a file path based on which watch directory it belongs to
     fn allowed_rules_for(&self, file_path: &Path) -> Option<&[String]> {
+        match self.watch_for(file_path) {
+            Some((_, rules)) if !rules.is_empty() => Some(rules.as_slice()),
+            _ => None,
+        }
+    }
+
+    /// Find the (canonical) watch directory a file path belongs to, with its rule list.
+    ///
+    /// When watches overlap, the most specific (longest) watch path wins, so a
+    /// nested watch's rule list takes precedence over its parent's.
+    fn watch_for(&self, file_path: &Path) -> Option<(&std::path::PathBuf, &Vec<String>)> {
         // Try matching with the raw event path first to avoid a syscall per event.
         // Watch paths are already canonicalized at registration time.
         let mut best_match: Option<(&std::path::PathBuf, &Vec<String>)> = None;
@@ -248,30 +851,129 @@ impl Watcher {
             }
         }
 
-        match best_match {
-            Some((_, rules)) if !rules.is_empty() => Some(rules.as_slice()),
-            _ => None,
+        best_match
+    }
+}
+
+/// Seconds since the Unix epoch
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Rule destinations inside the watch directory `root`, as subfolders relative
+/// to it, with the name of the rule that puts files there.
+///
+/// Only rules allowed for the watch (`rules`, empty = all) are considered.
+pub(crate) fn looping_subfolders(
+    engine: &RuleEngine,
+    root: &Path,
+    rules: &[String],
+) -> Vec<(String, std::path::PathBuf)> {
+    let root = std::fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    engine
+        .destinations()
+        .into_iter()
+        .filter(|(rule, _)| rules.is_empty() || rules.contains(&rule.name))
+        .filter_map(|(rule, dest)| {
+            let dest = canonicalize_existing(&dest);
+            let subfolder = dest.strip_prefix(&root).ok()?;
+            // A destination equal to the watch itself isn't a subfolder
+            (!subfolder.as_os_str().is_empty())
+                .then(|| (rule.name.clone(), subfolder.to_path_buf()))
+        })
+        .collect()
+}
+
+/// Canonicalize the longest existing ancestor of `path` and re-append the rest,
+/// so destinations that don't exist yet still compare against watch paths
+fn canonicalize_existing(path: &Path) -> std::path::PathBuf {
+    let mut rest = Vec::new();
+    let mut current = path;
+    loop {
+        if let Ok(canonical) = std::fs::canonicalize(current) {
+            return rest
+                .iter()
+                .rev()
+                .fold(canonical, |acc, part| acc.join(part));
+        }
+        match (current.parent(), current.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                current = parent;
+            }
+            _ => return path.to_path_buf(),
         }
     }
 }
 
+/// Queue files a scan failed on for another pass, if their rules asked for it
+fn requeued_for(
+    retries: Vec<(std::path::PathBuf, anyhow::Error)>,
+    requeue_delay: Duration,
+) -> Vec<Requeued> {
+    let now = std::time::Instant::now();
+    retries
+        .into_iter()
+        .filter_map(|(path, e)| {
+            let delay = requeue_after(&e, 0, requeue_delay)?;
+            info!(
+                "Will retry {} in {:.1}s: {:#}",
+                path.display(),
+                delay.as_secs_f64(),
+                e
+            );
+            Some(Requeued {
+                path,
+                due: now + delay,
+                count: 1,
+            })
+        })
+        .collect()
+}
+
+/// Whether the rules asked for a file that failed with `e` to be tried again
+fn wants_retry(e: &anyhow::Error) -> bool {
+    requeue_after(e, 0, Duration::ZERO).is_some()
+}
+
 /// Run the initial scan in a background thread so TUI startup isn't blocked.
+///
+/// `options.max_depth` is how many folder levels to scan; `Some(1)` lists
+/// only the folder itself, as for a non-recursive watch. Returns the files
+/// whose rules failed and asked to be tried again.
 fn scan_existing_background(
     path: &Path,
-    recursive: bool,
-    rules: &[Rule],
+    options: WalkOptions,
+    engine: &RuleEngine,
     allowed_rules: Option<Vec<String>>,
+    excluded: &[std::path::PathBuf],
     counter: Arc<AtomicU64>,
-) {
-    let engine = RuleEngine::new(rules.to_vec());
-    let allowed = allowed_rules.as_deref();
+    watch_counters: &WatchCounters,
+) -> Vec<(std::path::PathBuf, anyhow::Error)> {
+    let mut retries = Vec::new();
+    let startup_rules = engine.startup_scan_rules(allowed_rules.as_deref());
+    if startup_rules.as_ref().is_some_and(Vec::is_empty) {
+        debug!(
+            "No rules take part in the initial scan of {}",
+            path.display()
+        );
+        return retries;
+    }
+    let allowed = startup_rules.as_deref();
 
-    let entries: Box<dyn Iterator<Item = std::fs::DirEntry>> = if recursive {
-        match walkdir(path) {
-            Ok(entries) => entries,
+    let mut skipped = 0;
+    let entries: Box<dyn Iterator<Item = std::fs::DirEntry>> = if options.max_depth != Some(1) {
+        match walkdir_with(path, options) {
+            Ok(walk) => {
+                skipped = walk.log_skipped(path);
+                Box::new(walk.entries.into_iter())
+            }
             Err(e) => {
                 error!("Failed to scan directory {}: {}", path.display(), e);
-                return;
+                return retries;
             }
         }
     } else {
@@ -279,7 +981,7 @@ fn scan_existing_background(
             Ok(rd) => Box::new(rd.filter_map(|e| e.ok())),
             Err(e) => {
                 error!("Failed to scan directory {}: {}", path.display(), e);
-                return;
+                return retries;
             }
         }
     };
@@ -289,13 +991,16 @@ fn scan_existing_background(
 
     for entry in entries {
         let file_path = entry.path();
+        if excluded.iter().any(|dir| file_path.starts_with(dir)) {
+            continue;
+        }
         {
             scanned += 1;
-            match engine.process_filtered(&file_path, allowed) {
-                Ok(true) => {
+            match engine.process_in_watch(&file_path, allowed, Some(path)) {
+                Ok(matches) if matches.is_empty() => {}
+                Ok(_) => {
                     matched += 1;
                 }
-                Ok(false) => {}
                 Err(e) => {
                     if e.downcast_ref::<std::io::Error>()
                         .is_some_and(|io_err| io_err.kind() == std::io::ErrorKind::NotFound)
@@ -306,6 +1011,10 @@ fn scan_existing_background(
                         );
                         continue;
                     }
+                    if wants_retry(&e) {
+                        retries.push((file_path, e));
+                        continue;
+                    }
                     error!("Rule processing failed for {}: {}", file_path.display(), e);
                 }
             }
@@ -320,35 +1029,956 @@ fn scan_existing_background(
             matched
         );
         counter.fetch_add(matched, Ordering::Relaxed);
+        watch_counters.record(matched);
+    }
+    if skipped > 0 {
+        crate::notifications::notify_watch_error(
+            &path.display().to_string(),
+            &format!("initial scan skipped {} unreadable path(s)", skipped),
+        );
     }
+    retries
 }
 
-/// Recursively iterate all file entries from a directory tree.
-/// Returns a boxed iterator to avoid collecting into a Vec.
-fn walkdir(path: &Path) -> Result<Box<dyn Iterator<Item = std::fs::DirEntry>>> {
-    let mut stack = vec![path.to_path_buf()];
-    let mut entries = Vec::new();
-
-    // NOTE: We still collect into a Vec internally because returning a true
-    // lazy iterator over a recursive directory walk requires either unsafe
-    // self-referential types or a crate like `walkdir`. The allocation is
-    // bounded by the number of files on disk, which is unavoidable for a
-    // full-tree scan. The Box<dyn Iterator> signature keeps the public API
-    // ready for a zero-alloc implementation in the future.
-    while let Some(dir) = stack.pop() {
-        for entry in std::fs::read_dir(&dir)? {
-            let entry = entry?;
-            let ft = entry.file_type()?;
+/// Everything a recursive walk found, and what it couldn't read
+pub(crate) struct Walk {
+    pub entries: Vec<std::fs::DirEntry>,
+    /// Paths that couldn't be read (e.g. permission denied), with the error
+    pub skipped: Vec<(std::path::PathBuf, std::io::Error)>,
+}
+
+impl Walk {
+    /// Log the paths the walk of `root` skipped, returning how many there were
+    pub fn log_skipped(&self, root: &Path) -> usize {
+        for (path, e) in &self.skipped {
+            debug!("Skipped unreadable {}: {}", path.display(), e);
+        }
+        if !self.skipped.is_empty() {
+            warn!(
+                "Skipped {} unreadable path(s) while scanning {}",
+                self.skipped.len(),
+                root.display()
+            );
+        }
+        self.skipped.len()
+    }
+}
+
+/// How far a recursive walk goes
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct WalkOptions {
+    /// List at most this many levels: 1 is only the entries directly in the
+    /// root, 2 adds those of its subfolders, and so on
+    pub max_depth: Option<usize>,
+    /// Descend into symlinked directories, visiting each real directory once
+    pub follow_symlinks: bool,
+}
+
+/// Recursively list all entries under a directory tree.
+///
+/// Only `path` itself has to be readable: a subdirectory or entry that can't
+/// be read is recorded in `Walk::skipped` and the walk carries on with its
+/// siblings. Symlinks are skipped, not followed.
+pub(crate) fn walkdir(path: &Path) -> Result<Walk> {
+    walkdir_with(path, WalkOptions::default())
+}
+
+/// `walkdir` limited or extended by `options`
+pub(crate) fn walkdir_with(path: &Path, options: WalkOptions) -> Result<Walk> {
+    walk_with(path, options, |dir| std::fs::read_dir(dir))
+}
+
+/// `walkdir_with` with the directory listing supplied by the caller, so
+/// tests can simulate unreadable directories
+fn walk_with(
+    path: &Path,
+    options: WalkOptions,
+    read_dir: impl Fn(&Path) -> std::io::Result<std::fs::ReadDir>,
+) -> Result<Walk> {
+    let mut walk = Walk {
+        entries: Vec::new(),
+        skipped: Vec::new(),
+    };
+    // Each folder with the depth of the entries listed in it
+    let mut stack = vec![(path.to_path_buf(), 1)];
+    // Real directories already queued, so symlinks that point back up the
+    // tree (or at a folder seen elsewhere) don't loop or repeat
+    let mut visited = std::collections::HashSet::new();
+    if options.follow_symlinks {
+        visited.insert(std::fs::canonicalize(path)?);
+    }
+    let descend = |depth: usize| options.max_depth.is_none_or(|max| depth < max);
+
+    while let Some((dir, depth)) = stack.pop() {
+        let listing = match read_dir(&dir) {
+            Ok(listing) => listing,
+            Err(e) if dir == path => return Err(e.into()),
+            Err(e) => {
+                walk.skipped.push((dir, e));
+                continue;
+            }
+        };
+        for entry in listing {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    walk.skipped.push((dir.clone(), e));
+                    continue;
+                }
+            };
+            let ft = match entry.file_type() {
+                Ok(ft) => ft,
+                Err(e) => {
+                    walk.skipped.push((entry.path(), e));
+                    continue;
+                }
+            };
             if ft.is_symlink() {
+                // Only links to directories are followed; the link itself isn't listed
+                if options.follow_symlinks
+                    && descend(depth)
+                    && entry.path().is_dir()
+                    && let Ok(target) = std::fs::canonicalize(entry.path())
+                    && visited.insert(target)
+                {
+                    stack.push((entry.path(), depth + 1));
+                }
                 continue;
             }
-            if ft.is_dir() {
-                stack.push(entry.path());
-                entries.push(entry);
+            if ft.is_dir() && descend(depth) {
+                let first_visit = !options.follow_symlinks
+                    || std::fs::canonicalize(entry.path())
+                        .map_or(true, |real| visited.insert(real));
+                if first_visit {
+                    stack.push((entry.path(), depth + 1));
+                }
+            }
+            walk.entries.push(entry);
+        }
+    }
+    Ok(walk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{Action, Condition, Rule};
+
+    fn move_txt_rule(destination: &Path) -> Rule {
+        Rule::new(
+            "Text files",
+            Condition {
+                extension: Some("txt".to_string()),
+                ..Default::default()
+            },
+            Action::Move {
+                destination: destination.to_path_buf(),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+            },
+        )
+    }
+
+    /// Wait for the watcher's initial scans to finish
+    fn finish_scans(watcher: &mut Watcher) {
+        for scan in watcher.scans.drain(..) {
+            scan.join().unwrap();
+        }
+    }
+
+    /// Process watch events until a file is handled or a few seconds pass,
+    /// returning how many files were handled
+    fn process_next(watcher: &mut Watcher) -> usize {
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        let mut processed = 0;
+        while processed == 0 && std::time::Instant::now() < deadline {
+            processed += watcher.process_events().unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        processed
+    }
+
+    #[test]
+    fn test_drain_processes_queued_event() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+
+        let engine = RuleEngine::new(vec![move_txt_rule(dest.path())]);
+        let mut watcher = Watcher::new(engine, 1, 0).unwrap();
+        watcher.watch(src.path(), false).unwrap();
+        finish_scans(&mut watcher);
+
+        // Drain again until the event has arrived, rather than guessing how long that takes
+        std::fs::write(src.path().join("late.txt"), "queued").unwrap();
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        let mut processed = 0;
+        while !dest.path().join("late.txt").exists() && std::time::Instant::now() < deadline {
+            processed += watcher.drain(Duration::from_secs(2)).unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(processed >= 1);
+        assert!(dest.path().join("late.txt").exists());
+        assert!(!src.path().join("late.txt").exists());
+    }
+
+    #[test]
+    fn test_overlapping_watches_process_file_once_per_batch() {
+        let root = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let sub = root.path().join("sub");
+        std::fs::create_dir(&sub).unwrap();
+
+        let engine = RuleEngine::new(vec![Rule::new(
+            "Copy text",
+            Condition {
+                extension: Some("txt".to_string()),
+                ..Default::default()
+            },
+            Action::Copy {
+                destination: dest.path().to_path_buf(),
+                create_destination: true,
+                overwrite: true,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+                preserve_timestamps: false,
+            },
+        )]);
+        let mut watcher = Watcher::new(engine, 1, 0).unwrap();
+        watcher.watch(root.path(), true).unwrap();
+        watcher.watch(&sub, true).unwrap();
+
+        let file = sub.join("notes.txt");
+        std::fs::write(&file, "hello").unwrap();
+
+        // The same file reported through both watches, spelled differently
+        let event = |path: std::path::PathBuf| {
+            notify::Event::new(notify::EventKind::Create(notify::event::CreateKind::File))
+                .add_path(path)
+        };
+        let events = vec![
+            event(file.clone()),
+            event(sub.join("..").join("sub").join("notes.txt")),
+        ];
+
+        assert_eq!(watcher.process_polled_events(events).unwrap(), 1);
+        assert!(dest.path().join("notes.txt").exists());
+    }
+
+    #[test]
+    fn test_processing_error_and_activity_are_recorded() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+
+        let engine = RuleEngine::new(vec![move_txt_rule(dest.path())]);
+        let mut watcher = Watcher::new(engine, 1, 0).unwrap();
+        assert!(watcher.last_error().is_none());
+        assert!(watcher.last_event_at().is_none());
+
+        let event = |path: std::path::PathBuf| {
+            notify::Event::new(notify::EventKind::Create(notify::event::CreateKind::File))
+                .add_path(path)
+        };
+
+        // A name clash at the destination makes the move fail
+        let clash = src.path().join("clash.txt");
+        std::fs::write(&clash, "new").unwrap();
+        std::fs::write(dest.path().join("clash.txt"), "old").unwrap();
+        assert_eq!(
+            watcher.process_polled_events(vec![event(clash)]).unwrap(),
+            0
+        );
+        let error = watcher.last_error().expect("error should be recorded");
+        assert!(error.contains("clash.txt"));
+        assert!(watcher.last_event_at().is_none());
+
+        let ok = src.path().join("fine.txt");
+        std::fs::write(&ok, "fine").unwrap();
+        assert_eq!(watcher.process_polled_events(vec![event(ok)]).unwrap(), 1);
+        assert!(watcher.last_event_at().is_some());
+
+        // Health info survives a config reload
+        let mut reloaded =
+            Watcher::new(RuleEngine::new(vec![move_txt_rule(dest.path())]), 1, 0).unwrap();
+        reloaded.carry_over_files_processed(&watcher);
+        assert_eq!(reloaded.last_error(), watcher.last_error());
+        assert_eq!(reloaded.last_event_at(), watcher.last_event_at());
+    }
+
+    #[test]
+    fn test_watch_availability_transitions() {
+        let root = tempfile::tempdir().unwrap();
+        let share = root.path().join("share");
+        std::fs::create_dir(&share).unwrap();
+
+        let mut watcher = Watcher::new(RuleEngine::new(vec![]), 1, 0).unwrap();
+        watcher.watch(&share, false).unwrap();
+        assert!(watcher.check_watches().is_empty());
+
+        std::fs::remove_dir(&share).unwrap();
+        match watcher.check_watches().as_slice() {
+            [WatchAvailability::Lost { path, .. }] => assert_eq!(path, &share),
+            other => panic!("expected the watch to be lost, got {:?}", other),
+        }
+        // Reported once, not on every check
+        assert!(watcher.check_watches().is_empty());
+
+        std::fs::create_dir(&share).unwrap();
+        assert_eq!(
+            watcher.check_watches(),
+            vec![WatchAvailability::Restored {
+                path: share.clone()
+            }]
+        );
+        assert!(watcher.check_watches().is_empty());
+    }
+
+    #[test]
+    fn test_missing_watch_is_restored_once_it_appears() {
+        let root = tempfile::tempdir().unwrap();
+        let mount = root.path().join("mount");
+
+        let mut watcher = Watcher::new(RuleEngine::new(vec![]), 1, 0).unwrap();
+        assert!(watcher.watch(&mount, false).is_err());
+        assert!(watcher.check_watches().is_empty());
+
+        std::fs::create_dir(&mount).unwrap();
+        assert_eq!(
+            watcher.check_watches(),
+            vec![WatchAvailability::Restored { path: mount }]
+        );
+    }
+
+    #[test]
+    fn test_recursive_watch_preserves_structure() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let nested = src.path().join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let engine = RuleEngine::new(vec![Rule::new(
+            "Mirror",
+            Condition {
+                extension: Some("pdf".to_string()),
+                ..Default::default()
+            },
+            Action::Move {
+                destination: dest.path().to_path_buf(),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: true,
+                skip_if_open: false,
+                skip_if_identical: false,
+            },
+        )]);
+        let mut watcher = Watcher::new(engine, 1, 0).unwrap();
+        watcher.watch(src.path(), true).unwrap();
+        // Let the initial scan finish before the file appears
+        finish_scans(&mut watcher);
+
+        let file = nested.join("c.pdf");
+        std::fs::write(&file, "pdf").unwrap();
+        let event = notify::Event::new(notify::EventKind::Create(notify::event::CreateKind::File))
+            .add_path(file);
+
+        assert_eq!(watcher.process_polled_events(vec![event]).unwrap(), 1);
+        assert!(dest.path().join("a/b/c.pdf").exists());
+    }
+
+    #[test]
+    fn test_destination_inside_recursive_watch_does_not_loop() {
+        let root = tempfile::tempdir().unwrap();
+        let sorted = root.path().join("Sorted");
+        let engine = RuleEngine::new(vec![Rule::new(
+            "Copy text",
+            Condition {
+                extension: Some("txt".to_string()),
+                ..Default::default()
+            },
+            Action::Copy {
+                destination: sorted.clone(),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+                preserve_timestamps: false,
+            },
+        )]);
+
+        // The destination doesn't exist yet and is still recognised
+        let loops = looping_subfolders(&engine, root.path(), &[]);
+        assert_eq!(
+            loops,
+            vec![("Copy text".to_string(), std::path::PathBuf::from("Sorted"))]
+        );
+
+        let mut watcher = Watcher::new(engine, 1, 0).unwrap();
+        watcher.watch(root.path(), true).unwrap();
+        assert!(watcher.is_loop_guarded(&sorted.join("a.txt")));
+        assert!(!watcher.is_loop_guarded(&root.path().join("a.txt")));
+        finish_scans(&mut watcher);
+
+        let created = |path: std::path::PathBuf| {
+            notify::Event::new(notify::EventKind::Create(notify::event::CreateKind::File))
+                .add_path(path)
+        };
+        std::fs::write(root.path().join("a.txt"), "a").unwrap();
+        let event = created(root.path().join("a.txt"));
+        assert_eq!(watcher.process_polled_events(vec![event]).unwrap(), 1);
+        assert!(sorted.join("a.txt").exists());
+
+        // The copy's own events are ignored instead of being copied onto itself
+        let event = created(sorted.join("a.txt"));
+        assert_eq!(watcher.process_polled_events(vec![event]).unwrap(), 0);
+        assert!(watcher.last_error().is_none());
+
+        // A non-recursive watch never sees the subfolder, so nothing is guarded
+        let other = tempfile::tempdir().unwrap();
+        let engine = RuleEngine::new(vec![move_txt_rule(&other.path().join("Sorted"))]);
+        let mut flat = Watcher::new(engine, 1, 0).unwrap();
+        flat.watch(other.path(), false).unwrap();
+        assert!(!flat.is_loop_guarded(&other.path().join("Sorted").join("a.txt")));
+    }
+
+    #[test]
+    fn test_requeued_file_is_processed_on_a_later_pass() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let share = dest.path().join("share");
+        let mut rule = move_txt_rule(&share);
+        if let crate::rules::Action::Move {
+            create_destination, ..
+        } = &mut rule.action
+        {
+            *create_destination = false;
+        }
+        rule.on_error = Some(crate::rules::OnError::Requeue);
+        let mut watcher = Watcher::new(RuleEngine::new(vec![rule]), 1, 0).unwrap();
+        watcher.requeue_delay = Duration::ZERO;
+
+        let file = src.path().join("notes.txt");
+        std::fs::write(&file, "notes").unwrap();
+        let event = notify::Event::new(notify::EventKind::Create(notify::event::CreateKind::File))
+            .add_path(file.clone());
+
+        // The share isn't there yet: the file waits instead of failing
+        assert_eq!(watcher.process_polled_events(vec![event]).unwrap(), 0);
+        assert!(watcher.last_error().is_none());
+        assert!(watcher.has_due_requeues());
+
+        // Next pass, with no new events, picks it up once the share is back
+        std::fs::create_dir(&share).unwrap();
+        assert_eq!(watcher.process_polled_events(Vec::new()).unwrap(), 1);
+        assert!(share.join("notes.txt").exists());
+        assert!(!watcher.has_due_requeues());
+    }
+
+    #[test]
+    fn test_retried_files_wait_in_the_queue() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let share = dest.path().join("share");
+        let mut rule = move_txt_rule(&share);
+        if let crate::rules::Action::Move {
+            create_destination, ..
+        } = &mut rule.action
+        {
+            *create_destination = false;
+        }
+        rule.on_error = Some(crate::rules::OnError::Retry {
+            attempts: 2,
+            delay_secs: 0.0,
+        });
+        let config = crate::config::Config {
+            rules: vec![rule],
+            watches: vec![crate::config::WatchConfig {
+                path: src.path().to_path_buf(),
+                recursive: false,
+                rules: Vec::new(),
+                follow_symlinks: false,
+            }],
+            ..Default::default()
+        };
+        let mut watcher = Watcher::new(RuleEngine::from_config(&config), 1, 0).unwrap();
+        std::fs::write(src.path().join("notes.txt"), "notes").unwrap();
+
+        // A scheduled scan hands the failure to the watcher's queue instead
+        // of waiting for the share
+        let report =
+            ScheduledScan::new(&config.watches, Duration::from_secs(60)).scan(watcher.engine());
+        assert!(report.errors.is_empty());
+        assert_eq!(watcher.record_scan(report), 0);
+        assert!(watcher.has_due_requeues());
+
+        // Still failing, it's queued once more, then given up on
+        assert_eq!(watcher.process_polled_events(Vec::new()).unwrap(), 0);
+        assert!(watcher.has_due_requeues());
+        assert!(watcher.last_error().is_none());
+        assert_eq!(watcher.process_polled_events(Vec::new()).unwrap(), 0);
+        assert!(!watcher.has_due_requeues());
+        assert!(watcher.last_error().is_some());
+
+        // A new event starts over and succeeds once the share is back
+        let file = src.path().join("notes.txt");
+        let event = notify::Event::new(notify::EventKind::Create(notify::event::CreateKind::File))
+            .add_path(file);
+        assert_eq!(watcher.process_polled_events(vec![event]).unwrap(), 0);
+        std::fs::create_dir(&share).unwrap();
+        assert_eq!(watcher.process_polled_events(Vec::new()).unwrap(), 1);
+        assert!(share.join("notes.txt").exists());
+    }
+
+    #[test]
+    fn test_dry_run_daemon_paths_change_nothing() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let mut config = crate::config::Config::default();
+        config.general.dry_run = true;
+        config.rules.push(move_txt_rule(dest.path()));
+        config.watches.push(crate::config::WatchConfig {
+            path: src.path().to_path_buf(),
+            recursive: false,
+            rules: Vec::new(),
+            follow_symlinks: false,
+        });
+        let journal = crate::rules::UndoJournal::new(dest.path().join("undo.jsonl"));
+        let engine = RuleEngine::from_config(&config).with_journal(Some(journal.clone()));
+
+        // Scheduled scans report what they would do but leave files alone
+        std::fs::write(src.path().join("scanned.txt"), "a").unwrap();
+        let report = ScheduledScan::new(&config.watches, Duration::from_secs(60)).scan(&engine);
+        assert_eq!(report.files_processed(), 1);
+        assert!(src.path().join("scanned.txt").exists());
+
+        // So do watch events
+        let mut watcher = Watcher::new(engine, 1, 0).unwrap();
+        watcher.watch(src.path(), false).unwrap();
+        finish_scans(&mut watcher);
+        std::fs::write(src.path().join("event.txt"), "b").unwrap();
+        assert!(process_next(&mut watcher) >= 1);
+
+        assert!(src.path().join("scanned.txt").exists());
+        assert!(src.path().join("event.txt").exists());
+        assert!(!dest.path().join("scanned.txt").exists());
+        assert!(!dest.path().join("event.txt").exists());
+        assert!(journal.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_startup_scan_skips_opted_out_rules() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let mut archive = move_txt_rule(dest.path());
+        archive.on_startup_scan = false;
+        let logs = Rule::new(
+            "Logs",
+            Condition {
+                extension: Some("log".to_string()),
+                ..Default::default()
+            },
+            Action::Move {
+                destination: dest.path().to_path_buf(),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+            },
+        );
+        std::fs::write(src.path().join("existing.txt"), "old").unwrap();
+        std::fs::write(src.path().join("existing.log"), "old").unwrap();
+
+        let mut watcher = Watcher::new(RuleEngine::new(vec![archive, logs]), 1, 0).unwrap();
+        watcher.watch(src.path(), false).unwrap();
+        finish_scans(&mut watcher);
+
+        // The initial scan only used the rule that takes part in it
+        assert!(src.path().join("existing.txt").exists());
+        assert!(dest.path().join("existing.log").exists());
+
+        // Live events still use the opted-out rule
+        std::fs::write(src.path().join("new.txt"), "new").unwrap();
+        assert!(process_next(&mut watcher) >= 1);
+        assert!(dest.path().join("new.txt").exists());
+        assert!(src.path().join("existing.txt").exists());
+    }
+
+    #[test]
+    fn test_scan_on_start_disabled_leaves_existing_files() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        std::fs::write(src.path().join("old.txt"), "already here").unwrap();
+
+        let engine = RuleEngine::new(vec![move_txt_rule(dest.path())]);
+        let mut watcher = Watcher::new(engine, 1, 0)
+            .unwrap()
+            .with_scan_on_start(false);
+        watcher.watch(src.path(), false).unwrap();
+
+        // No scan thread was started
+        assert!(watcher.scans.is_empty());
+        assert!(src.path().join("old.txt").exists());
+        assert_eq!(watcher.files_processed(), 0);
+
+        // Live events are still handled
+        std::fs::write(src.path().join("new.txt"), "fresh").unwrap();
+        assert_eq!(process_next(&mut watcher), 1);
+        assert!(dest.path().join("new.txt").exists());
+        assert!(src.path().join("old.txt").exists());
+    }
+
+    #[test]
+    fn test_files_are_counted_on_their_own_watch() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+
+        let engine = RuleEngine::new(vec![move_txt_rule(dest.path())]);
+        let mut watcher = Watcher::new(engine, 1, 0)
+            .unwrap()
+            .with_scan_on_start(false);
+        watcher.watch(first.path(), false).unwrap();
+        watcher.watch(second.path(), false).unwrap();
+
+        let events: Vec<_> = ["a.txt", "b.txt"]
+            .into_iter()
+            .map(|name| {
+                let file = first.path().join(name);
+                std::fs::write(&file, name).unwrap();
+                notify::Event::new(notify::EventKind::Create(notify::event::CreateKind::File))
+                    .add_path(file)
+            })
+            .collect();
+        assert_eq!(watcher.process_polled_events(events).unwrap(), 2);
+
+        let stats = watcher.watch_stats();
+        assert_eq!(stats[first.path()].files_processed, 2);
+        assert!(stats[first.path()].last_event_at.is_some());
+        assert_eq!(stats[second.path()], WatchStats::default());
+        assert_eq!(watcher.files_processed(), 2);
+    }
+
+    #[test]
+    fn test_walk_skips_unreadable_directories_and_continues() {
+        let root = tempfile::tempdir().unwrap();
+        for dir in ["a", "locked", "locked/inner", "z"] {
+            std::fs::create_dir(root.path().join(dir)).unwrap();
+        }
+        for file in ["top.txt", "a/one.txt", "locked/secret.txt", "z/two.txt"] {
+            std::fs::write(root.path().join(file), "x").unwrap();
+        }
+
+        // Simulate a directory we aren't allowed to list
+        let walk = walk_with(root.path(), WalkOptions::default(), |dir| {
+            if dir.ends_with("locked") {
+                Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
             } else {
-                entries.push(entry);
+                std::fs::read_dir(dir)
+            }
+        })
+        .unwrap();
+
+        let mut found: Vec<_> = walk
+            .entries
+            .iter()
+            .map(|e| {
+                e.path()
+                    .strip_prefix(root.path())
+                    .unwrap()
+                    .display()
+                    .to_string()
+            })
+            .collect();
+        found.sort();
+        // Siblings on both sides of the locked folder are still walked
+        assert_eq!(
+            found,
+            ["a", "a/one.txt", "locked", "top.txt", "z", "z/two.txt"]
+        );
+        assert_eq!(walk.skipped.len(), 1);
+        assert_eq!(walk.skipped[0].0, root.path().join("locked"));
+        assert_eq!(
+            walk.skipped[0].1.kind(),
+            std::io::ErrorKind::PermissionDenied
+        );
+        assert_eq!(walk.log_skipped(root.path()), 1);
+
+        // An unreadable root is still an error
+        let missing = root.path().join("missing");
+        assert!(walkdir(&missing).is_err());
+    }
+
+    #[test]
+    fn test_scan_max_depth_limits_only_the_initial_scan() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(src.path().join("a/b")).unwrap();
+        std::fs::write(src.path().join("top.txt"), "top").unwrap();
+        std::fs::write(src.path().join("a/deep.txt"), "deep").unwrap();
+
+        let engine = RuleEngine::new(vec![move_txt_rule(dest.path())]);
+        let mut watcher = Watcher::new(engine, 1, 0)
+            .unwrap()
+            .with_scan_max_depth(Some(1));
+        watcher.watch(src.path(), true).unwrap();
+
+        finish_scans(&mut watcher);
+        assert!(dest.path().join("top.txt").exists());
+        // The scan never reached deeper folders
+        assert!(src.path().join("a/deep.txt").exists());
+
+        // Live events below the scan depth are still handled
+        let fresh = src.path().join("a/b/fresh.txt");
+        std::fs::write(&fresh, "fresh").unwrap();
+        let event = notify::Event::new(notify::EventKind::Create(notify::event::CreateKind::File))
+            .add_path(fresh.clone());
+        assert_eq!(watcher.process_polled_events(vec![event]).unwrap(), 1);
+        assert!(dest.path().join("fresh.txt").exists());
+    }
+
+    #[test]
+    fn test_walk_stops_at_max_depth() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("a/b")).unwrap();
+        std::fs::write(root.path().join("a/b/c.txt"), "x").unwrap();
+
+        let names = |max_depth| {
+            let options = WalkOptions {
+                max_depth,
+                ..Default::default()
+            };
+            let mut found: Vec<_> = walkdir_with(root.path(), options)
+                .unwrap()
+                .entries
+                .iter()
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect();
+            found.sort();
+            found
+        };
+        assert_eq!(names(Some(1)), ["a"]);
+        assert_eq!(names(Some(2)), ["a", "b"]);
+        assert_eq!(names(None), ["a", "b", "c.txt"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_follows_symlinked_folders_once() {
+        let root = tempfile::tempdir().unwrap();
+        let media = tempfile::tempdir().unwrap();
+        std::fs::create_dir(media.path().join("album")).unwrap();
+        std::fs::write(media.path().join("album/song.mp3"), "x").unwrap();
+        std::fs::write(root.path().join("top.txt"), "x").unwrap();
+        std::os::unix::fs::symlink(media.path(), root.path().join("media")).unwrap();
+        // Links back up the tree and a second link to the same folder
+        std::os::unix::fs::symlink(root.path(), media.path().join("album/up")).unwrap();
+        std::os::unix::fs::symlink(media.path(), root.path().join("media-again")).unwrap();
+
+        let relative = |options: WalkOptions| {
+            let mut found: Vec<_> = walkdir_with(root.path(), options)
+                .unwrap()
+                .entries
+                .iter()
+                .map(|e| {
+                    e.path()
+                        .strip_prefix(root.path())
+                        .unwrap()
+                        .display()
+                        .to_string()
+                })
+                .collect();
+            found.sort();
+            found
+        };
+
+        assert_eq!(relative(WalkOptions::default()), ["top.txt"]);
+        let followed = relative(WalkOptions {
+            follow_symlinks: true,
+            ..Default::default()
+        });
+        // Whichever link is reached first is walked; the other is skipped
+        assert_eq!(followed.len(), 3, "{:?}", followed);
+        assert!(followed.contains(&"top.txt".to_string()));
+        assert!(followed.iter().any(|p| p.ends_with("album/song.mp3")));
+        assert!(followed.iter().any(|p| p.ends_with("album")));
+    }
+
+    #[test]
+    fn test_file_is_quarantined_after_repeated_failures() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        // The destination never exists, so every attempt fails
+        let mut rule = move_txt_rule(&dest.path().join("missing"));
+        if let crate::rules::Action::Move {
+            create_destination, ..
+        } = &mut rule.action
+        {
+            *create_destination = false;
+        }
+        let quarantine = dest.path().join("quarantine");
+        let mut watcher = Watcher::new(RuleEngine::new(vec![rule]), 1, 0)
+            .unwrap()
+            .with_quarantine(3, Some(quarantine.clone()));
+
+        let file = src.path().join("locked.txt");
+        std::fs::write(&file, "locked").unwrap();
+        let event = || {
+            notify::Event::new(notify::EventKind::Modify(notify::event::ModifyKind::Any))
+                .add_path(file.clone())
+        };
+
+        for _ in 0..2 {
+            watcher.process_polled_events(vec![event()]).unwrap();
+            assert!(watcher.quarantined().is_empty());
+        }
+        watcher.process_polled_events(vec![event()]).unwrap();
+        assert_eq!(watcher.quarantined(), [file.clone()]);
+        assert!(!file.exists());
+        assert!(quarantine.join("locked.txt").exists());
+
+        // A quarantined path isn't tried again, even if a file shows up there
+        std::fs::write(&file, "again").unwrap();
+        watcher.last_error = None;
+        assert_eq!(watcher.process_polled_events(vec![event()]).unwrap(), 0);
+        assert!(watcher.last_error().is_none());
+        assert_eq!(watcher.quarantined().len(), 1);
+    }
+
+    #[test]
+    fn test_success_resets_the_failure_count() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let share = dest.path().join("share");
+        let rule = Rule::new(
+            "Copy notes",
+            Condition::default(),
+            Action::Copy {
+                destination: share.clone(),
+                create_destination: false,
+                overwrite: true,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+                preserve_timestamps: false,
+            },
+        );
+        let mut watcher = Watcher::new(RuleEngine::new(vec![rule]), 1, 0)
+            .unwrap()
+            .with_quarantine(2, None);
+
+        let file = src.path().join("notes.txt");
+        std::fs::write(&file, "notes").unwrap();
+        let event = || {
+            notify::Event::new(notify::EventKind::Modify(notify::event::ModifyKind::Any))
+                .add_path(file.clone())
+        };
+
+        watcher.process_polled_events(vec![event()]).unwrap();
+        std::fs::create_dir(&share).unwrap();
+        assert_eq!(watcher.process_polled_events(vec![event()]).unwrap(), 1);
+        std::fs::remove_dir_all(&share).unwrap();
+        watcher.process_polled_events(vec![event()]).unwrap();
+        // Two failures, but not in a row
+        assert!(watcher.quarantined().is_empty());
+        assert!(file.exists());
+    }
+
+    #[test]
+    fn test_large_batches_are_split_across_polls() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let mut watcher = Watcher::new(RuleEngine::new(vec![move_txt_rule(dest.path())]), 1, 0)
+            .unwrap()
+            .with_max_events_per_poll(2);
+        // Feed the queue directly instead of waiting on the filesystem
+        let (tx, rx) = mpsc::channel();
+        watcher.rx = rx;
+        for i in 0..5 {
+            let file = src.path().join(format!("{}.txt", i));
+            std::fs::write(&file, "x").unwrap();
+            let event =
+                notify::Event::new(notify::EventKind::Create(notify::event::CreateKind::File))
+                    .add_path(file);
+            tx.send(Ok(event)).unwrap();
+        }
+
+        let mut sizes = Vec::new();
+        loop {
+            let events = watcher.poll().unwrap();
+            if events.is_empty() {
+                break;
             }
+            sizes.push(events.len());
+            watcher.process_polled_events(events).unwrap();
         }
+        assert_eq!(sizes, [2, 2, 1]);
+        assert_eq!(watcher.files_processed(), 5);
+        assert!(dest.path().join("4.txt").exists());
+    }
+
+    /// Writer that appends log output to a shared buffer
+    #[derive(Clone, Default)]
+    struct LogBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Run `f` and return what it logged at info level and above
+    fn capture_logs<T>(f: impl FnOnce() -> T) -> (T, String) {
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::INFO)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let result = tracing::subscriber::with_default(subscriber, f);
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        (result, output)
+    }
+
+    #[test]
+    fn test_quiet_rules_only_log_errors() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let mut rule = move_txt_rule(dest.path());
+        rule.quiet = true;
+        let mut watcher = Watcher::new(RuleEngine::new(vec![rule]), 1, 0).unwrap();
+        let event = |path: std::path::PathBuf| {
+            notify::Event::new(notify::EventKind::Modify(notify::event::ModifyKind::Any))
+                .add_path(path)
+        };
+
+        let file = src.path().join("a.txt");
+        std::fs::write(&file, "a").unwrap();
+        let (processed, output) =
+            capture_logs(|| watcher.process_polled_events(vec![event(file)]).unwrap());
+        assert_eq!(processed, 1);
+        assert!(dest.path().join("a.txt").exists());
+        assert!(!output.contains("INFO"), "{}", output);
+        // Nor does it reach the daemon or TUI activity log
+        assert!(watcher.take_activity().is_empty());
+
+        // A failure is still reported
+        let file = src.path().join("b.txt");
+        std::fs::write(&file, "b").unwrap();
+        std::fs::write(dest.path().join("b.txt"), "taken").unwrap();
+        let (processed, output) =
+            capture_logs(|| watcher.process_polled_events(vec![event(file)]).unwrap());
+        assert_eq!(processed, 0);
+        assert!(!output.contains("INFO"), "{}", output);
+        assert!(output.contains("ERROR"), "{}", output);
     }
-    Ok(Box::new(entries.into_iter()))
 }
@@ -0,0 +1,330 @@
+//! Scheduled scanning: organize every watch folder on a fixed interval
+//!
+//! Instead of holding file system watches, the daemon can scan all watch
+//! folders in one pass, then sleep until the next run.
+
+use anyhow::{Context, Result, bail};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info};
+
+use crate::config::WatchConfig;
+use crate::rules::{RuleEngine, RuleMatch};
+
+/// Parse a `schedule` value into the time between scans.
+///
+/// Accepts `hourly`, `daily` and `weekly` (with or without a leading `@`), or
+/// an interval made of `<number><unit>` parts with units `s`, `m`, `h`, `d`,
+/// e.g. `30m`, `1h30m` or `every 2h`.
+pub fn parse_schedule(spec: &str) -> Result<Duration> {
+    let spec = spec.trim().to_lowercase();
+    let spec = spec.strip_prefix('@').unwrap_or(&spec);
+    let spec = spec.strip_prefix("every").unwrap_or(spec).trim();
+
+    match spec {
+        "hourly" => return Ok(Duration::from_secs(60 * 60)),
+        "daily" => return Ok(Duration::from_secs(24 * 60 * 60)),
+        "weekly" => return Ok(Duration::from_secs(7 * 24 * 60 * 60)),
+        _ => {}
+    }
+
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in spec.chars().filter(|c| !c.is_whitespace()) {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => bail!("Unknown unit '{}' in schedule '{}'", c, spec),
+        };
+        if digits.is_empty() {
+            bail!("Missing number before '{}' in schedule '{}'", c, spec);
+        }
+        let amount: u64 = digits
+            .parse()
+            .with_context(|| format!("Invalid number in schedule '{}'", spec))?;
+        total = total.saturating_add(amount.saturating_mul(unit));
+        digits.clear();
+    }
+
+    if !digits.is_empty() {
+        bail!(
+            "Missing unit after '{}' in schedule '{}' (use s, m, h or d)",
+            digits,
+            spec
+        );
+    }
+    if total == 0 {
+        bail!("Schedule '{}' must be at least one second", spec);
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// Result of one scan over every watch folder
+#[derive(Debug, Default)]
+pub struct ScanReport {
+    /// Files a rule was applied to, with the rules that matched
+    pub activity: Vec<(PathBuf, RuleMatch)>,
+    /// Files that failed to process, as `path: error`
+    pub errors: Vec<String>,
+    /// Files whose rules failed and asked, with `on_error`, to be tried again
+    pub retries: Vec<(PathBuf, anyhow::Error)>,
+}
+
+impl ScanReport {
+    /// Number of files a rule was applied to
+    pub fn files_processed(&self) -> usize {
+        let mut files: Vec<_> = self.activity.iter().map(|(path, _)| path).collect();
+        files.dedup();
+        files.len()
+    }
+}
+
+/// A watch folder to scan, with its path already expanded
+#[derive(Debug, Clone)]
+struct ScanTarget {
+    path: PathBuf,
+    recursive: bool,
+    rules: Vec<String>,
+    follow_symlinks: bool,
+}
+
+/// Runs a full scan of the watch folders whenever the interval has elapsed
+#[derive(Debug)]
+pub struct ScheduledScan {
+    targets: Vec<ScanTarget>,
+    interval: Duration,
+    next_run: Option<Instant>,
+}
+
+impl ScheduledScan {
+    /// Scan `watches` every `interval`, starting with the first call to `run_if_due`
+    pub fn new(watches: &[WatchConfig], interval: Duration) -> Self {
+        Self {
+            targets: watches
+                .iter()
+                .map(|w| ScanTarget {
+                    path: crate::expand_path(&w.path),
+                    recursive: w.recursive,
+                    rules: w.rules.clone(),
+                    follow_symlinks: w.follow_symlinks,
+                })
+                .collect(),
+            interval,
+            next_run: None,
+        }
+    }
+
+    /// Time between scans
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// When the next scan is due, or `None` if none has run yet
+    pub fn next_run(&self) -> Option<Instant> {
+        self.next_run
+    }
+
+    /// Scan now if the interval has elapsed since the last scan, then sleep
+    /// until the next one. Returns `None` while sleeping.
+    pub fn run_if_due(&mut self, engine: &RuleEngine, now: Instant) -> Option<ScanReport> {
+        if self.next_run.is_some_and(|next| now < next) {
+            return None;
+        }
+        self.next_run = Some(now + self.interval);
+        Some(self.scan(engine))
+    }
+
+    /// Apply the rules to every file in the watch folders once
+    pub fn scan(&self, engine: &RuleEngine) -> ScanReport {
+        let mut report = ScanReport::default();
+
+        for target in &self.targets {
+            let allowed = (!target.rules.is_empty()).then_some(target.rules.as_slice());
+            let entries: Box<dyn Iterator<Item = std::fs::DirEntry>> = if target.recursive {
+                let options = super::WalkOptions {
+                    follow_symlinks: target.follow_symlinks,
+                    ..Default::default()
+                };
+                match super::walkdir_with(&target.path, options) {
+                    Ok(walk) => {
+                        let skipped = walk.log_skipped(&target.path);
+                        if skipped > 0 {
+                            crate::notifications::notify_watch_error(
+                                &target.path.display().to_string(),
+                                &format!("scan skipped {} unreadable path(s)", skipped),
+                            );
+                        }
+                        Box::new(walk.entries.into_iter())
+                    }
+                    Err(e) => {
+                        error!("Failed to scan directory {}: {}", target.path.display(), e);
+                        report
+                            .errors
+                            .push(format!("{}: {}", target.path.display(), e));
+                        continue;
+                    }
+                }
+            } else {
+                match std::fs::read_dir(&target.path) {
+                    Ok(rd) => Box::new(rd.filter_map(|e| e.ok())),
+                    Err(e) => {
+                        error!("Failed to scan directory {}: {}", target.path.display(), e);
+                        report
+                            .errors
+                            .push(format!("{}: {}", target.path.display(), e));
+                        continue;
+                    }
+                }
+            };
+
+            // Leave rule destinations inside a recursive watch alone
+            let excluded: Vec<PathBuf> = if target.recursive {
+                super::looping_subfolders(engine, &target.path, &target.rules)
+                    .into_iter()
+                    .map(|(_, subfolder)| target.path.join(subfolder))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            for entry in entries {
+                let path = entry.path();
+                if excluded.iter().any(|dir| path.starts_with(dir)) {
+                    continue;
+                }
+                match engine.process_in_watch(&path, allowed, Some(&target.path)) {
+                    Ok(matches) => {
+                        report
+                            .activity
+                            .extend(matches.into_iter().map(|m| (path.clone(), m)));
+                    }
+                    Err(e) => {
+                        if e.downcast_ref::<std::io::Error>()
+                            .is_some_and(|io_err| io_err.kind() == std::io::ErrorKind::NotFound)
+                        {
+                            debug!("File disappeared before processing: {}", path.display());
+                            continue;
+                        }
+                        if super::wants_retry(&e) {
+                            report.retries.push((path, e));
+                            continue;
+                        }
+                        error!("Rule processing failed for {}: {}", path.display(), e);
+                        report.errors.push(format!("{}: {}", path.display(), e));
+                    }
+                }
+            }
+        }
+
+        for (path, outcome) in engine.enforce_budgets() {
+            match outcome {
+                Ok(m) => report.activity.push((path, m)),
+                Err(e) => {
+                    error!("Budget rule failed for {}: {}", path.display(), e);
+                    report.errors.push(format!("{}: {}", path.display(), e));
+                }
+            }
+        }
+
+        info!(
+            "Scheduled scan: {} files processed, {} errors",
+            report.files_processed(),
+            report.errors.len()
+        );
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{Action, Condition, Rule};
+
+    #[test]
+    fn test_parse_schedule() {
+        assert_eq!(parse_schedule("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_schedule("15m").unwrap(), Duration::from_secs(15 * 60));
+        assert_eq!(
+            parse_schedule("1h30m").unwrap(),
+            Duration::from_secs(90 * 60)
+        );
+        assert_eq!(
+            parse_schedule("every 2h").unwrap(),
+            Duration::from_secs(7200)
+        );
+        assert_eq!(parse_schedule("1d").unwrap(), Duration::from_secs(86400));
+        assert_eq!(
+            parse_schedule("@hourly").unwrap(),
+            Duration::from_secs(3600)
+        );
+        assert_eq!(
+            parse_schedule(" Daily ").unwrap(),
+            Duration::from_secs(86400)
+        );
+
+        assert!(parse_schedule("").is_err());
+        assert!(parse_schedule("0m").is_err());
+        assert!(parse_schedule("10").is_err());
+        assert!(parse_schedule("h").is_err());
+        assert!(parse_schedule("5 minutes").is_err());
+        assert!(parse_schedule("*/5 * * * *").is_err());
+    }
+
+    #[test]
+    fn test_scan_then_sleep_until_next_run() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let engine = RuleEngine::new(vec![Rule::new(
+            "Text files",
+            Condition {
+                extension: Some("txt".to_string()),
+                ..Default::default()
+            },
+            Action::Move {
+                destination: dest.path().to_path_buf(),
+                create_destination: true,
+                overwrite: false,
+                preserve_structure: false,
+                skip_if_open: false,
+                skip_if_identical: false,
+            },
+        )]);
+        let watches = vec![WatchConfig {
+            path: src.path().to_path_buf(),
+            recursive: false,
+            rules: Vec::new(),
+            follow_symlinks: false,
+        }];
+        let mut schedule = ScheduledScan::new(&watches, Duration::from_secs(3600));
+        std::fs::write(src.path().join("a.txt"), "a").unwrap();
+
+        // The first call scans straight away
+        let start = Instant::now();
+        let report = schedule.run_if_due(&engine, start).unwrap();
+        assert_eq!(report.files_processed(), 1);
+        assert!(report.errors.is_empty());
+        assert!(dest.path().join("a.txt").exists());
+        assert_eq!(schedule.next_run(), Some(start + Duration::from_secs(3600)));
+
+        // Files arriving in between wait for the next run
+        std::fs::write(src.path().join("b.txt"), "b").unwrap();
+        assert!(
+            schedule
+                .run_if_due(&engine, start + Duration::from_secs(60))
+                .is_none()
+        );
+        assert!(src.path().join("b.txt").exists());
+
+        let later = start + Duration::from_secs(3600);
+        let report = schedule.run_if_due(&engine, later).unwrap();
+        assert_eq!(report.files_processed(), 1);
+        assert!(dest.path().join("b.txt").exists());
+        assert_eq!(schedule.next_run(), Some(later + Duration::from_secs(3600)));
+    }
+}
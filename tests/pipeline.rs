@@ -0,0 +1,387 @@
+//! End-to-end runs: load a config file, run the rules over a folder the way
+//! `hazelnut run --apply` does, and check what ended up where.
+
+use std::path::{Path, PathBuf};
+
+use hazelnut::cli::{RunSummary, run_dir};
+use hazelnut::{Config, RuleEngine};
+
+/// A temporary home with a Downloads folder and room for destinations
+struct Sandbox {
+    dir: tempfile::TempDir,
+}
+
+impl Sandbox {
+    fn new() -> Self {
+        let sandbox = Self {
+            dir: tempfile::tempdir().unwrap(),
+        };
+        std::fs::create_dir(sandbox.downloads()).unwrap();
+        sandbox
+    }
+
+    fn path(&self, relative: &str) -> PathBuf {
+        self.dir.path().join(relative)
+    }
+
+    fn downloads(&self) -> PathBuf {
+        self.path("Downloads")
+    }
+
+    /// Create a file in Downloads with its name as content
+    fn download(&self, name: &str) -> PathBuf {
+        let path = self.downloads().join(name);
+        std::fs::write(&path, name).unwrap();
+        path
+    }
+
+    /// Write `toml` (with `$ROOT` standing for the sandbox) and load it
+    fn load_config(&self, toml: &str) -> Config {
+        let path = self.path("config.toml");
+        let root = self.dir.path().display().to_string();
+        std::fs::write(&path, toml.replace("$ROOT", &root)).unwrap();
+        Config::load(Some(&path)).unwrap()
+    }
+
+    /// Apply the config's rules to everything in Downloads
+    fn run(&self, config: &Config) -> RunSummary {
+        let engine = RuleEngine::from_config(config);
+        let mut summary = RunSummary::default();
+        run_dir(&engine, &self.downloads(), false, true, &mut summary).unwrap();
+        summary
+    }
+}
+
+/// Sorted file names directly inside `dir` (empty if it doesn't exist)
+fn names_in(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+const CONFIG: &str = r#"
+[general]
+trash_dir = "$ROOT/Trash"
+
+[[watch]]
+path = "$ROOT/Downloads"
+
+# Drafts are left alone, whatever later rules say
+[[rule]]
+name = "Keep drafts"
+stop_processing = true
+
+[rule.condition]
+name_matches = "draft-*"
+
+[rule.action]
+type = "nothing"
+
+[[rule]]
+name = "Installers to trash"
+
+[rule.condition]
+extensions = ["dmg", "pkg"]
+
+[rule.action]
+type = "trash"
+
+[[rule]]
+name = "Tidy screenshots"
+
+[rule.condition]
+name_matches = "Screenshot *"
+
+[rule.action]
+type = "rename"
+pattern = "screenshot-{ext}.{ext}"
+
+# Copies leave the file in place for the next rule
+[[rule]]
+name = "Back up notes"
+
+[rule.condition]
+extension = "txt"
+
+[rule.action]
+type = "copy"
+destination = "$ROOT/Backup"
+
+[[rule]]
+name = "Documents"
+
+[rule.condition]
+extensions = ["pdf", "txt"]
+
+[rule.action]
+type = "move"
+destination = "$ROOT/Documents"
+"#;
+
+#[test]
+fn test_rules_run_end_to_end() {
+    let sandbox = Sandbox::new();
+    for name in [
+        "report.pdf",
+        "notes.txt",
+        "draft-plan.txt",
+        "setup.dmg",
+        "Screenshot 1.png",
+        "archive.zip",
+    ] {
+        sandbox.download(name);
+    }
+    let config = sandbox.load_config(CONFIG);
+
+    let summary = sandbox.run(&config);
+    assert_eq!(summary.failed(), 0);
+    // Trash, rename, copy + move for the notes, move for the report. The
+    // draft rule is an exclusion (nothing + stop_processing) and isn't counted
+    assert_eq!(summary.applied, 5);
+
+    assert_eq!(
+        names_in(&sandbox.downloads()),
+        vec!["archive.zip", "draft-plan.txt", "screenshot-png.png"]
+    );
+    assert_eq!(
+        names_in(&sandbox.path("Documents")),
+        vec!["notes.txt", "report.pdf"]
+    );
+    assert_eq!(names_in(&sandbox.path("Backup")), vec!["notes.txt"]);
+    assert_eq!(names_in(&sandbox.path("Trash")), vec!["setup.dmg"]);
+
+    // Contents travel with the files
+    assert_eq!(
+        std::fs::read_to_string(sandbox.path("Documents/report.pdf")).unwrap(),
+        "report.pdf"
+    );
+    assert_eq!(
+        std::fs::read_to_string(sandbox.downloads().join("screenshot-png.png")).unwrap(),
+        "Screenshot 1.png"
+    );
+
+    // A second run finds nothing left to do but the draft and the renamed file
+    let summary = sandbox.run(&config);
+    assert_eq!(summary.failed(), 0);
+    assert_eq!(
+        names_in(&sandbox.downloads()),
+        vec!["archive.zip", "draft-plan.txt", "screenshot-png.png"]
+    );
+}
+
+#[test]
+fn test_dry_run_config_changes_nothing() {
+    let sandbox = Sandbox::new();
+    sandbox.download("report.pdf");
+    sandbox.download("setup.dmg");
+    let config = sandbox.load_config(&CONFIG.replace("[general]", "[general]\ndry_run = true"));
+
+    let summary = sandbox.run(&config);
+    assert_eq!(summary.failed(), 0);
+    assert_eq!(
+        names_in(&sandbox.downloads()),
+        vec!["report.pdf", "setup.dmg"]
+    );
+    assert!(!sandbox.path("Documents").exists());
+    assert!(!sandbox.path("Trash").exists());
+}
+
+/// Moving between filesystems can't rename; the file is copied and removed
+#[cfg(target_os = "linux")]
+#[test]
+fn test_move_across_filesystems() {
+    use std::os::unix::fs::MetadataExt;
+
+    let sandbox = Sandbox::new();
+    // /dev/shm is a separate tmpfs on most Linux systems
+    let shm = Path::new("/dev/shm");
+    let Ok(shm_dev) = shm.metadata().map(|m| m.dev()) else {
+        return;
+    };
+    if shm_dev == sandbox.downloads().metadata().unwrap().dev() {
+        return;
+    }
+    let elsewhere = tempfile::tempdir_in(shm).unwrap();
+    let config = sandbox.load_config(&CONFIG.replace(
+        "$ROOT/Documents",
+        &elsewhere.path().join("Documents").display().to_string(),
+    ));
+    sandbox.download("report.pdf");
+
+    let summary = sandbox.run(&config);
+    assert_eq!(summary.failed(), 0);
+    assert!(names_in(&sandbox.downloads()).is_empty());
+    let moved = elsewhere.path().join("Documents").join("report.pdf");
+    assert_eq!(std::fs::read_to_string(moved).unwrap(), "report.pdf");
+}
+
+/// `hazelnut run --apply` with `--dir` given twice processes both folders
+#[test]
+fn test_run_command_with_several_dirs() {
+    let sandbox = Sandbox::new();
+    let desktop = sandbox.path("Desktop");
+    std::fs::create_dir(&desktop).unwrap();
+    sandbox.download("report.pdf");
+    std::fs::write(desktop.join("notes.txt"), "notes.txt").unwrap();
+    // Left alone: not one of the given folders
+    std::fs::create_dir(sandbox.path("Other")).unwrap();
+    std::fs::write(sandbox.path("Other/old.pdf"), "old.pdf").unwrap();
+    sandbox.load_config(CONFIG);
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_hazelnut"))
+        .arg("--config")
+        .arg(sandbox.path("config.toml"))
+        .args(["run", "--apply", "--dir"])
+        .arg(sandbox.downloads())
+        .arg("--dir")
+        .arg(&desktop)
+        // Keep the undo journal inside the sandbox
+        .env("HOME", sandbox.dir.path())
+        .env("XDG_DATA_HOME", sandbox.path("data"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    assert_eq!(
+        names_in(&sandbox.path("Documents")),
+        vec!["notes.txt", "report.pdf"]
+    );
+    assert!(names_in(&sandbox.downloads()).is_empty());
+    assert!(names_in(&desktop).is_empty());
+    assert_eq!(names_in(&sandbox.path("Other")), vec!["old.pdf"]);
+}
+
+/// `hazelnut run --apply` only reaches into subfolders with `--recursive`, or
+/// for watches marked recursive when no `--dir` is given
+#[test]
+fn test_run_command_recursive() {
+    let sandbox = Sandbox::new();
+    let nested = sandbox.downloads().join("2024/receipts");
+    std::fs::create_dir_all(&nested).unwrap();
+    sandbox.download("report.pdf");
+    std::fs::write(nested.join("receipt.pdf"), "receipt.pdf").unwrap();
+    sandbox.load_config(CONFIG);
+
+    let run = |args: &[&str]| {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_hazelnut"))
+            .arg("--config")
+            .arg(sandbox.path("config.toml"))
+            .args(["run", "--apply"])
+            .args(args)
+            .env("HOME", sandbox.dir.path())
+            .env("XDG_DATA_HOME", sandbox.path("data"))
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    };
+    let downloads = sandbox.downloads().display().to_string();
+
+    run(&["--dir", &downloads]);
+    assert_eq!(names_in(&sandbox.path("Documents")), vec!["report.pdf"]);
+    assert_eq!(names_in(&nested), vec!["receipt.pdf"]);
+
+    run(&["--dir", &downloads, "--recursive"]);
+    assert_eq!(
+        names_in(&sandbox.path("Documents")),
+        vec!["receipt.pdf", "report.pdf"]
+    );
+    assert!(names_in(&nested).is_empty());
+
+    // Without --dir, the watch's own recursive setting decides
+    std::fs::write(nested.join("invoice.pdf"), "invoice.pdf").unwrap();
+    run(&[]);
+    assert_eq!(names_in(&nested), vec!["invoice.pdf"]);
+    sandbox.load_config(&CONFIG.replace(
+        "path = \"$ROOT/Downloads\"",
+        "path = \"$ROOT/Downloads\"\nrecursive = true",
+    ));
+    run(&[]);
+    assert!(names_in(&nested).is_empty());
+    assert_eq!(
+        names_in(&sandbox.path("Documents")),
+        vec!["invoice.pdf", "receipt.pdf", "report.pdf"]
+    );
+}
+
+/// `hazelnut run --apply` lists what failed and exits non-zero so scripts notice
+#[test]
+fn test_run_command_reports_failures() {
+    let sandbox = Sandbox::new();
+    sandbox.download("report.pdf");
+    sandbox.download("notes.pdf");
+    // notes.pdf collides at the destination, so its move fails
+    std::fs::create_dir(sandbox.path("Documents")).unwrap();
+    std::fs::write(sandbox.path("Documents/notes.pdf"), "older notes").unwrap();
+    sandbox.load_config(CONFIG);
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_hazelnut"))
+        .arg("--config")
+        .arg(sandbox.path("config.toml"))
+        .args(["run", "--apply", "--dir"])
+        .arg(sandbox.downloads())
+        .env("HOME", sandbox.dir.path())
+        .env("XDG_DATA_HOME", sandbox.path("data"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Applied 1 action, 0 skipped, 1 failed"),
+        "{}",
+        stdout
+    );
+    let failure = format!("✗ {}: ", sandbox.downloads().join("notes.pdf").display());
+    assert!(stdout.contains(&failure), "{}", stdout);
+    assert_eq!(names_in(&sandbox.downloads()), vec!["notes.pdf"]);
+}
+
+/// `hazelnut test-rule` reports whether one rule matches and exits accordingly
+#[test]
+fn test_test_rule_command() {
+    let sandbox = Sandbox::new();
+    sandbox.load_config(CONFIG);
+    let report = sandbox.download("report.pdf");
+    let archive = sandbox.download("archive.zip");
+
+    let test_rule = |path: &Path| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_hazelnut"))
+            .arg("--config")
+            .arg(sandbox.path("config.toml"))
+            .args(["test-rule", "--rule-name", "Documents", "--path"])
+            .arg(path)
+            .output()
+            .unwrap()
+    };
+
+    let matched = test_rule(&report);
+    assert_eq!(matched.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&matched.stdout);
+    assert!(stdout.contains("Rule 'Documents' matches"), "{}", stdout);
+    assert!(stdout.contains("then: → "), "{}", stdout);
+
+    let unmatched = test_rule(&archive);
+    assert_eq!(unmatched.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&unmatched.stdout).contains("does not match"));
+
+    // Testing changes nothing
+    assert_eq!(
+        names_in(&sandbox.downloads()),
+        vec!["archive.zip", "report.pdf"]
+    );
+}